@@ -0,0 +1,339 @@
+use std::collections::{HashMap, VecDeque};
+
+use nalgebra::{point, Point2, Vector2};
+use serde::{Deserialize, Serialize};
+use types::ycbcr422_image::YCbCr422Image;
+
+const BLUR_RADIUS: usize = 2;
+const DERIVATIVE_DISTANCE: usize = 2;
+const NON_MAXIMUM_RADIUS: usize = 4;
+const MINIMUM_RESPONSE_RATIO: f32 = 0.15;
+const RING_RADIUS: f32 = 5.0;
+const RING_SAMPLES: usize = 24;
+/// Maximum distance of a corner to its predicted position relative to the grid spacing
+const MAXIMUM_PREDICTION_ERROR: f32 = 0.35;
+
+/// Checkerboard pattern described by its inner corners
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+pub struct Checkerboard {
+    pub rows: usize,
+    pub columns: usize,
+    /// Edge length of a square in meters
+    pub square_size: f32,
+}
+
+impl Checkerboard {
+    /// Positions of the inner corners on the board plane, row by row
+    pub fn model_points(&self) -> Vec<Point2<f64>> {
+        (0..self.rows)
+            .flat_map(|row| {
+                (0..self.columns).map(move |column| {
+                    point![
+                        column as f64 * self.square_size as f64,
+                        row as f64 * self.square_size as f64
+                    ]
+                })
+            })
+            .collect()
+    }
+}
+
+/// Detects the inner corners of the checkerboard in the image
+///
+/// Corners are saddle points of the blurred luminance. Starting at the corner closest to the
+/// center of all candidates, the grid is grown to neighboring corners. The detection fails unless
+/// exactly the expected number of rows and columns is found. Corners are returned in the order of
+/// [`Checkerboard::model_points`], up to a symmetry of the grid which does not matter for the
+/// intrinsic calibration.
+pub fn detect_corners(image: &YCbCr422Image, board: &Checkerboard) -> Option<Vec<Point2<f32>>> {
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+    let luminance: Vec<f32> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| image.at(x as u32, y as u32).y as f32))
+        .collect();
+    let blurred = box_blur(&box_blur(&luminance, width, height), width, height);
+    let response = saddle_response(&blurred, width, height);
+    let candidates: Vec<_> = find_candidates(&response, width, height)
+        .into_iter()
+        .filter(|candidate| is_x_junction(&blurred, width, height, *candidate))
+        .collect();
+    let grid = grow_grid(&candidates)?;
+    order_corners(&grid, &candidates, board)
+}
+
+fn box_blur(values: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let horizontal: Vec<f32> = (0..height)
+        .flat_map(|y| {
+            (0..width).map(move |x| {
+                let range = x.saturating_sub(BLUR_RADIUS)..(x + BLUR_RADIUS + 1).min(width);
+                let count = range.len() as f32;
+                range.map(|x| values[y * width + x]).sum::<f32>() / count
+            })
+        })
+        .collect();
+    (0..height)
+        .flat_map(|y| {
+            let horizontal = &horizontal;
+            (0..width).map(move |x| {
+                let range = y.saturating_sub(BLUR_RADIUS)..(y + BLUR_RADIUS + 1).min(height);
+                let count = range.len() as f32;
+                range.map(|y| horizontal[y * width + x]).sum::<f32>() / count
+            })
+        })
+        .collect()
+}
+
+/// Negative determinant of the hessian, which is large at saddle points
+fn saddle_response(values: &[f32], width: usize, height: usize) -> Vec<f32> {
+    let distance = DERIVATIVE_DISTANCE;
+    let mut response = vec![0.0; width * height];
+    for y in distance..height.saturating_sub(distance) {
+        for x in distance..width.saturating_sub(distance) {
+            let at = |x: usize, y: usize| values[y * width + x];
+            let xx = at(x + distance, y) - 2.0 * at(x, y) + at(x - distance, y);
+            let yy = at(x, y + distance) - 2.0 * at(x, y) + at(x, y - distance);
+            let xy = (at(x + distance, y + distance)
+                - at(x + distance, y - distance)
+                - at(x - distance, y + distance)
+                + at(x - distance, y - distance))
+                / 4.0;
+            response[y * width + x] = (xy * xy - xx * yy).max(0.0);
+        }
+    }
+    response
+}
+
+fn find_candidates(response: &[f32], width: usize, height: usize) -> Vec<Point2<f32>> {
+    let maximum = response.iter().copied().fold(0.0, f32::max);
+    let threshold = MINIMUM_RESPONSE_RATIO * maximum;
+    let radius = NON_MAXIMUM_RADIUS;
+    let mut candidates = Vec::new();
+    for y in radius..height.saturating_sub(radius) {
+        for x in radius..width.saturating_sub(radius) {
+            let value = response[y * width + x];
+            if value <= threshold {
+                continue;
+            }
+            let is_maximum = (y - radius..=y + radius).all(|other_y| {
+                (x - radius..=x + radius).all(|other_x| {
+                    let other = response[other_y * width + other_x];
+                    // ties are broken towards the first pixel in scan order
+                    other < value || (other == value && (other_y, other_x) >= (y, x))
+                })
+            });
+            if is_maximum {
+                let at = |x: usize, y: usize| response[y * width + x];
+                let offset = |before: f32, after: f32| {
+                    let curvature = before - 2.0 * value + after;
+                    if curvature < 0.0 {
+                        ((before - after) / (2.0 * curvature)).clamp(-0.5, 0.5)
+                    } else {
+                        0.0
+                    }
+                };
+                candidates.push(point![
+                    x as f32 + offset(at(x - 1, y), at(x + 1, y)),
+                    y as f32 + offset(at(x, y - 1), at(x, y + 1))
+                ]);
+            }
+        }
+    }
+    candidates
+}
+
+/// Checks for four alternating dark and bright sectors around the candidate
+///
+/// This rejects corners at the outline of the board, where a single dark square meets the bright
+/// background.
+fn is_x_junction(values: &[f32], width: usize, height: usize, candidate: Point2<f32>) -> bool {
+    let samples: Option<Vec<f32>> = (0..RING_SAMPLES)
+        .map(|index| {
+            let angle = index as f32 / RING_SAMPLES as f32 * std::f32::consts::TAU;
+            let x = (candidate.x + RING_RADIUS * angle.cos()).round();
+            let y = (candidate.y + RING_RADIUS * angle.sin()).round();
+            let inside = x >= 0.0 && y >= 0.0 && (x as usize) < width && (y as usize) < height;
+            inside.then(|| values[y as usize * width + x as usize])
+        })
+        .collect();
+    let Some(samples) = samples else {
+        return false;
+    };
+    let mean = samples.iter().sum::<f32>() / samples.len() as f32;
+    let transitions = (0..samples.len())
+        .filter(|index| (samples[*index] > mean) != (samples[(index + 1) % samples.len()] > mean))
+        .count();
+    transitions == 4
+}
+
+/// Assigns grid coordinates to candidates connected to the most central candidate
+fn grow_grid(candidates: &[Point2<f32>]) -> Option<HashMap<(i32, i32), usize>> {
+    let center = candidates
+        .iter()
+        .fold(Vector2::zeros(), |sum, candidate| sum + candidate.coords)
+        / candidates.len().max(1) as f32;
+    let seed = nearest(candidates, center.into(), |_| true)?;
+    let mut neighbors: Vec<Vector2<f32>> = candidates
+        .iter()
+        .filter(|candidate| **candidate != candidates[seed])
+        .map(|candidate| candidate - candidates[seed])
+        .collect();
+    neighbors.sort_by(|left, right| left.norm().total_cmp(&right.norm()));
+    let first_axis = *neighbors.first()?;
+    let second_axis = *neighbors.iter().take(4).skip(1).max_by(|left, right| {
+        sine_between(first_axis, **left).total_cmp(&sine_between(first_axis, **right))
+    })?;
+
+    let mut grid = HashMap::from([((0, 0), seed)]);
+    let mut assigned = vec![false; candidates.len()];
+    assigned[seed] = true;
+    let mut queue = VecDeque::from([(0, 0)]);
+    while let Some((i, j)) = queue.pop_front() {
+        let position = candidates[grid[&(i, j)]];
+        for (di, dj) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let cell = (i + di, j + dj);
+            if grid.contains_key(&cell) {
+                continue;
+            }
+            let step = match grid.get(&(i - di, j - dj)) {
+                Some(&opposite) => position - candidates[opposite],
+                None => first_axis * di as f32 + second_axis * dj as f32,
+            };
+            let prediction = position + step;
+            let Some(index) = nearest(candidates, prediction, |index| !assigned[index]) else {
+                continue;
+            };
+            if (candidates[index] - prediction).norm() > MAXIMUM_PREDICTION_ERROR * step.norm() {
+                continue;
+            }
+            assigned[index] = true;
+            grid.insert(cell, index);
+            queue.push_back(cell);
+        }
+    }
+    Some(grid)
+}
+
+fn order_corners(
+    grid: &HashMap<(i32, i32), usize>,
+    candidates: &[Point2<f32>],
+    board: &Checkerboard,
+) -> Option<Vec<Point2<f32>>> {
+    let minimum_i = grid.keys().map(|(i, _)| *i).min()?;
+    let maximum_i = grid.keys().map(|(i, _)| *i).max()?;
+    let minimum_j = grid.keys().map(|(_, j)| *j).min()?;
+    let maximum_j = grid.keys().map(|(_, j)| *j).max()?;
+    let extent_i = (maximum_i - minimum_i + 1) as usize;
+    let extent_j = (maximum_j - minimum_j + 1) as usize;
+    if grid.len() != board.rows * board.columns {
+        return None;
+    }
+    let cell = |row: usize, column: usize| {
+        if extent_i == board.columns && extent_j == board.rows {
+            Some((minimum_i + column as i32, minimum_j + row as i32))
+        } else if extent_i == board.rows && extent_j == board.columns {
+            Some((minimum_i + row as i32, minimum_j + column as i32))
+        } else {
+            None
+        }
+    };
+    (0..board.rows)
+        .flat_map(|row| (0..board.columns).map(move |column| (row, column)))
+        .map(|(row, column)| Some(candidates[*grid.get(&cell(row, column)?)?]))
+        .collect()
+}
+
+fn nearest(
+    candidates: &[Point2<f32>],
+    target: Point2<f32>,
+    filter: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    (0..candidates.len())
+        .filter(|index| filter(*index))
+        .min_by(|left, right| {
+            (candidates[*left] - target)
+                .norm_squared()
+                .total_cmp(&(candidates[*right] - target).norm_squared())
+        })
+}
+
+fn sine_between(first: Vector2<f32>, second: Vector2<f32>) -> f32 {
+    (first.perp(&second) / (first.norm() * second.norm())).abs()
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::vector;
+    use types::{color::YCbCr422, ycbcr422_image::YCbCr422Image};
+
+    use super::*;
+
+    fn render_checkerboard(
+        width: u32,
+        height: u32,
+        origin: Vector2<f32>,
+        square_size: f32,
+        squares: (usize, usize),
+    ) -> YCbCr422Image {
+        let pixels = (0..height)
+            .flat_map(|y| {
+                (0..width / 2).map(move |x| {
+                    let value = |x: u32| {
+                        let column = (x as f32 - origin.x) / square_size;
+                        let row = (y as f32 - origin.y) / square_size;
+                        let inside = column >= 0.0
+                            && row >= 0.0
+                            && column < squares.0 as f32
+                            && row < squares.1 as f32;
+                        if inside && (column.floor() + row.floor()).rem_euclid(2.0) < 1.0 {
+                            20
+                        } else {
+                            230
+                        }
+                    };
+                    YCbCr422 {
+                        y1: value(2 * x),
+                        cb: 128,
+                        y2: value(2 * x + 1),
+                        cr: 128,
+                    }
+                })
+            })
+            .collect();
+        YCbCr422Image::from_ycbcr_buffer(width / 2, height, pixels)
+    }
+
+    #[test]
+    fn corners_of_rendered_checkerboard_are_detected() {
+        let board = Checkerboard {
+            rows: 4,
+            columns: 5,
+            square_size: 0.05,
+        };
+        let image = render_checkerboard(320, 240, vector![70.5, 50.5], 30.0, (6, 5));
+
+        let corners = detect_corners(&image, &board).unwrap();
+
+        assert_eq!(corners.len(), 20);
+        for corner in &corners {
+            let column = (corner.x - 70.5) / 30.0;
+            let row = (corner.y - 50.5) / 30.0;
+            assert!((column - column.round()).abs() < 0.05, "{corner:?}");
+            assert!((row - row.round()).abs() < 0.05, "{corner:?}");
+        }
+        let spacing = (corners[1] - corners[0]).norm();
+        assert!((spacing - 30.0).abs() < 1.0);
+        assert!(((corners[5] - corners[0]).norm() - 30.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn incomplete_checkerboard_is_rejected() {
+        let board = Checkerboard {
+            rows: 6,
+            columns: 7,
+            square_size: 0.05,
+        };
+        let image = render_checkerboard(320, 240, vector![70.5, 50.5], 30.0, (6, 5));
+
+        assert!(detect_corners(&image, &board).is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use nalgebra::{
+    matrix, vector, DMatrix, DVector, Matrix3, Point2, Rotation3, SymmetricEigen, Vector3, Vector6,
+};
+
+/// Estimates the homography mapping board points onto image points by the normalized direct
+/// linear transformation
+pub fn estimate_homography(
+    model_points: &[Point2<f64>],
+    image_points: &[Point2<f64>],
+) -> Option<Matrix3<f64>> {
+    if model_points.len() != image_points.len() || model_points.len() < 4 {
+        return None;
+    }
+    let model_normalization = normalization(model_points)?;
+    let image_normalization = normalization(image_points)?;
+    let rows: Vec<f64> = model_points
+        .iter()
+        .zip(image_points)
+        .flat_map(|(model_point, image_point)| {
+            let model = model_normalization * model_point.to_homogeneous();
+            let image = image_normalization * image_point.to_homogeneous();
+            let (x, y) = (model.x, model.y);
+            let (u, v) = (image.x, image.y);
+            [
+                [-x, -y, -1.0, 0.0, 0.0, 0.0, u * x, u * y, u],
+                [0.0, 0.0, 0.0, -x, -y, -1.0, v * x, v * y, v],
+            ]
+        })
+        .flatten()
+        .collect();
+    let system = DMatrix::from_row_slice(2 * model_points.len(), 9, &rows);
+    let null_vector = smallest_eigenvector(system.transpose() * system);
+    let normalized_homography = Matrix3::from_row_slice(null_vector.as_slice());
+    let homography =
+        image_normalization.try_inverse()? * normalized_homography * model_normalization;
+    Some(homography / homography[(2, 2)])
+}
+
+/// Closed form solution of Zhang's method for focal lengths and optical center
+///
+/// Each homography constrains the image of the absolute conic by two equations. The skew is
+/// assumed to be zero, which adds a third equation and allows the solution from two views.
+pub fn estimate_camera_matrix(homographies: &[Matrix3<f64>]) -> Option<Matrix3<f64>> {
+    let constraint = |homography: &Matrix3<f64>, i: usize, j: usize| {
+        let (first, second) = (homography.column(i), homography.column(j));
+        Vector6::new(
+            first[0] * second[0],
+            first[0] * second[1] + first[1] * second[0],
+            first[1] * second[1],
+            first[2] * second[0] + first[0] * second[2],
+            first[2] * second[1] + first[1] * second[2],
+            first[2] * second[2],
+        )
+    };
+    let mut system = DMatrix::zeros(2 * homographies.len() + 1, 6);
+    for (index, homography) in homographies.iter().enumerate() {
+        system
+            .row_mut(2 * index)
+            .copy_from(&constraint(homography, 0, 1).transpose());
+        system
+            .row_mut(2 * index + 1)
+            .copy_from(&(constraint(homography, 0, 0) - constraint(homography, 1, 1)).transpose());
+    }
+    system[(2 * homographies.len(), 1)] = 1.0;
+
+    let mut b = smallest_eigenvector(system.transpose() * system);
+    if b[0] < 0.0 {
+        b = -b;
+    }
+    let (b11, b12, b22, b13, b23, b33) = (b[0], b[1], b[2], b[3], b[4], b[5]);
+    let denominator = b11 * b22 - b12 * b12;
+    let optical_center_y = (b12 * b13 - b11 * b23) / denominator;
+    let scale = b33 - (b13 * b13 + optical_center_y * (b12 * b13 - b11 * b23)) / b11;
+    let focal_length_x = (scale / b11).sqrt();
+    let focal_length_y = (scale * b11 / denominator).sqrt();
+    let skew = -b12 * focal_length_x * focal_length_x * focal_length_y / scale;
+    let optical_center_x =
+        skew * optical_center_y / focal_length_y - b13 * focal_length_x * focal_length_x / scale;
+    let camera_matrix = matrix![
+        focal_length_x, 0.0, optical_center_x;
+        0.0, focal_length_y, optical_center_y;
+        0.0, 0.0, 1.0;
+    ];
+    camera_matrix
+        .iter()
+        .all(|value| value.is_finite())
+        .then_some(camera_matrix)
+}
+
+/// Decomposes the homography of a view into the pose of the board in the camera
+pub fn estimate_pose(
+    camera_matrix: &Matrix3<f64>,
+    homography: &Matrix3<f64>,
+) -> Option<(Rotation3<f64>, Vector3<f64>)> {
+    let columns = camera_matrix.try_inverse()? * homography;
+    let scale = 1.0 / columns.column(0).norm();
+    let sign = if columns[(2, 2)] < 0.0 { -1.0 } else { 1.0 };
+    let first_axis = columns.column(0) * scale * sign;
+    let second_axis = columns.column(1) * scale * sign;
+    let translation = columns.column(2) * scale * sign;
+    let rotation = Rotation3::from_matrix(&Matrix3::from_columns(&[
+        first_axis,
+        second_axis,
+        first_axis.cross(&second_axis),
+    ]));
+    Some((
+        rotation,
+        vector![translation.x, translation.y, translation.z],
+    ))
+}
+
+/// Similarity transformation moving the centroid to the origin with an average distance of √2
+fn normalization(points: &[Point2<f64>]) -> Option<Matrix3<f64>> {
+    let centroid = points
+        .iter()
+        .fold(nalgebra::Vector2::zeros(), |sum, point| sum + point.coords)
+        / points.len() as f64;
+    let mean_distance = points
+        .iter()
+        .map(|point| (point.coords - centroid).norm())
+        .sum::<f64>()
+        / points.len() as f64;
+    if mean_distance <= f64::EPSILON {
+        return None;
+    }
+    let scale = std::f64::consts::SQRT_2 / mean_distance;
+    Some(matrix![
+        scale, 0.0, -scale * centroid.x;
+        0.0, scale, -scale * centroid.y;
+        0.0, 0.0, 1.0;
+    ])
+}
+
+fn smallest_eigenvector(matrix: DMatrix<f64>) -> DVector<f64> {
+    let eigen = SymmetricEigen::new(matrix);
+    let smallest = eigen.eigenvalues.imin();
+    eigen.eigenvectors.column(smallest).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::point;
+
+    use super::*;
+
+    fn camera_matrix() -> Matrix3<f64> {
+        matrix![
+            560.0, 0.0, 315.0;
+            0.0, 555.0, 245.0;
+            0.0, 0.0, 1.0;
+        ]
+    }
+
+    fn poses() -> Vec<(Rotation3<f64>, Vector3<f64>)> {
+        vec![
+            (
+                Rotation3::from_euler_angles(0.3, -0.2, 0.1),
+                vector![-0.1, -0.05, 0.6],
+            ),
+            (
+                Rotation3::from_euler_angles(-0.25, 0.3, -0.05),
+                vector![-0.15, -0.1, 0.7],
+            ),
+            (
+                Rotation3::from_euler_angles(0.1, 0.35, 0.2),
+                vector![-0.05, -0.1, 0.5],
+            ),
+        ]
+    }
+
+    fn project(rotation: &Rotation3<f64>, translation: &Vector3<f64>) -> Vec<Point2<f64>> {
+        model_points()
+            .iter()
+            .map(|point| {
+                let camera = rotation * vector![point.x, point.y, 0.0] + translation;
+                let pixel = camera_matrix() * (camera / camera.z);
+                point![pixel.x, pixel.y]
+            })
+            .collect()
+    }
+
+    fn model_points() -> Vec<Point2<f64>> {
+        (0..5)
+            .flat_map(|row| (0..6).map(move |column| point![column as f64, row as f64] * 0.04))
+            .collect()
+    }
+
+    #[test]
+    fn homography_maps_model_onto_image() {
+        let (rotation, translation) = &poses()[0];
+        let image_points = project(rotation, translation);
+
+        let homography = estimate_homography(&model_points(), &image_points).unwrap();
+
+        for (model_point, image_point) in model_points().iter().zip(&image_points) {
+            let mapped = homography * model_point.to_homogeneous();
+            assert_relative_eq!(
+                point![mapped.x / mapped.z, mapped.y / mapped.z],
+                image_point,
+                epsilon = 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn camera_matrix_and_poses_are_recovered_from_homographies() {
+        let homographies: Vec<_> = poses()
+            .iter()
+            .map(|(rotation, translation)| {
+                estimate_homography(&model_points(), &project(rotation, translation)).unwrap()
+            })
+            .collect();
+
+        let estimated = estimate_camera_matrix(&homographies).unwrap();
+        assert_relative_eq!(estimated, camera_matrix(), epsilon = 1e-3);
+
+        for ((rotation, translation), homography) in poses().iter().zip(&homographies) {
+            let (estimated_rotation, estimated_translation) =
+                estimate_pose(&estimated, homography).unwrap();
+            assert_relative_eq!(estimated_rotation, rotation, epsilon = 1e-6);
+            assert_relative_eq!(estimated_translation, translation, epsilon = 1e-6);
+        }
+    }
+}
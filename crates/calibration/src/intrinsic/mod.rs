@@ -0,0 +1,184 @@
+use levenberg_marquardt::{LevenbergMarquardt, MinimizationReport};
+use nalgebra::{vector, DVector, Point2, Vector2};
+use projection::distortion::Distortion;
+use serde::{Deserialize, Serialize};
+
+use checkerboard::Checkerboard;
+use homography::{estimate_camera_matrix, estimate_homography, estimate_pose};
+use problem::{IntrinsicProblem, AMOUNT_OF_INTRINSIC_PARAMETERS, AMOUNT_OF_VIEW_PARAMETERS};
+
+pub mod checkerboard;
+pub mod homography;
+pub mod problem;
+
+/// Views required to estimate the intrinsics, two suffice in theory but are poorly conditioned
+pub const MINIMUM_AMOUNT_OF_VIEWS: usize = 3;
+
+/// Intrinsic parameters in pixels of the calibrated image resolution
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IntrinsicCalibration {
+    pub focal_lengths: Vector2<f32>,
+    pub optical_center: Point2<f32>,
+    pub distortion: Distortion,
+    /// Root mean square distance between projected and detected corners in pixels
+    pub reprojection_error: f32,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum IntrinsicCalibrationError {
+    #[error("{0} views are not enough, at least {MINIMUM_AMOUNT_OF_VIEWS} are required")]
+    NotEnoughViews(usize),
+    #[error("view {0} does not contain every corner of the checkerboard")]
+    IncompleteView(usize),
+    #[error("failed to estimate the homography of view {0}")]
+    DegenerateView(usize),
+    #[error("failed to estimate the camera matrix, the views are too similar")]
+    DegenerateCameraMatrix,
+}
+
+/// Calibrates focal lengths, optical center and lens distortion with Zhang's method
+///
+/// The closed form solution for a distortion free pinhole camera initializes a
+/// Levenberg-Marquardt refinement of all intrinsics and board poses.
+pub fn calibrate(
+    board: &Checkerboard,
+    views: &[Vec<Point2<f32>>],
+) -> Result<(IntrinsicCalibration, MinimizationReport<f64>), IntrinsicCalibrationError> {
+    if views.len() < MINIMUM_AMOUNT_OF_VIEWS {
+        return Err(IntrinsicCalibrationError::NotEnoughViews(views.len()));
+    }
+    let model_points = board.model_points();
+    let views: Vec<Vec<Point2<f64>>> = views
+        .iter()
+        .map(|corners| corners.iter().map(|corner| corner.cast()).collect())
+        .collect();
+    if let Some(index) = views
+        .iter()
+        .position(|corners| corners.len() != model_points.len())
+    {
+        return Err(IntrinsicCalibrationError::IncompleteView(index));
+    }
+
+    let homographies = views
+        .iter()
+        .enumerate()
+        .map(|(index, corners)| {
+            estimate_homography(&model_points, corners)
+                .ok_or(IntrinsicCalibrationError::DegenerateView(index))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let camera_matrix = estimate_camera_matrix(&homographies)
+        .ok_or(IntrinsicCalibrationError::DegenerateCameraMatrix)?;
+
+    let mut parameters = vec![
+        camera_matrix[(0, 0)],
+        camera_matrix[(1, 1)],
+        camera_matrix[(0, 2)],
+        camera_matrix[(1, 2)],
+    ];
+    parameters.resize(AMOUNT_OF_INTRINSIC_PARAMETERS, 0.0);
+    for (index, homography) in homographies.iter().enumerate() {
+        let (rotation, translation) = estimate_pose(&camera_matrix, homography)
+            .ok_or(IntrinsicCalibrationError::DegenerateView(index))?;
+        parameters.extend(rotation.scaled_axis().iter());
+        parameters.extend(translation.iter());
+    }
+    debug_assert_eq!(
+        parameters.len(),
+        AMOUNT_OF_INTRINSIC_PARAMETERS + views.len() * AMOUNT_OF_VIEW_PARAMETERS
+    );
+
+    let problem = IntrinsicProblem::new(DVector::from_vec(parameters), model_points, views);
+    let (problem, report) = LevenbergMarquardt::new().minimize(problem);
+    let parameters = problem.get_parameters().map(|parameter| parameter as f32);
+    let calibration = IntrinsicCalibration {
+        focal_lengths: vector![parameters[0], parameters[1]],
+        optical_center: Point2::new(parameters[2], parameters[3]),
+        distortion: Distortion::new(
+            vector![parameters[4], parameters[5]],
+            vector![parameters[6], parameters[7]],
+        ),
+        reprojection_error: problem.reprojection_error() as f32,
+    };
+    Ok((calibration, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use nalgebra::{point, Rotation3};
+
+    use super::*;
+
+    #[test]
+    fn undistorted_views_are_calibrated_exactly() {
+        let board = Checkerboard {
+            rows: 5,
+            columns: 6,
+            square_size: 0.04,
+        };
+        let poses = [
+            (
+                Rotation3::from_euler_angles(0.3, -0.2, 0.1),
+                vector![-0.1, -0.05, 0.6],
+            ),
+            (
+                Rotation3::from_euler_angles(-0.25, 0.3, -0.05),
+                vector![-0.15, -0.1, 0.7],
+            ),
+            (
+                Rotation3::from_euler_angles(0.1, 0.35, 0.2),
+                vector![-0.05, -0.1, 0.5],
+            ),
+        ];
+        let views: Vec<Vec<Point2<f32>>> = poses
+            .iter()
+            .map(|(rotation, translation)| {
+                board
+                    .model_points()
+                    .iter()
+                    .map(|point| {
+                        let camera = rotation * vector![point.x, point.y, 0.0] + translation;
+                        point![
+                            (560.0 * camera.x / camera.z + 315.0) as f32,
+                            (555.0 * camera.y / camera.z + 245.0) as f32
+                        ]
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let (calibration, _) = calibrate(&board, &views).unwrap();
+
+        assert_relative_eq!(
+            calibration.focal_lengths,
+            vector![560.0, 555.0],
+            epsilon = 0.5
+        );
+        assert_relative_eq!(
+            calibration.optical_center,
+            point![315.0, 245.0],
+            epsilon = 0.5
+        );
+        assert_relative_eq!(
+            calibration.distortion.radial,
+            Vector2::zeros(),
+            epsilon = 1e-3
+        );
+        assert!(calibration.reprojection_error < 0.01);
+    }
+
+    #[test]
+    fn too_few_views_are_rejected() {
+        let board = Checkerboard {
+            rows: 5,
+            columns: 6,
+            square_size: 0.04,
+        };
+
+        assert!(matches!(
+            calibrate(&board, &[Vec::new(), Vec::new()]),
+            Err(IntrinsicCalibrationError::NotEnoughViews(2))
+        ));
+    }
+}
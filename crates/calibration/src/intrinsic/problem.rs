@@ -0,0 +1,119 @@
+use levenberg_marquardt::LeastSquaresProblem;
+use nalgebra::{vector, DMatrix, DVector, Dyn, Owned, Point2, Rotation3, Vector2, Vector3};
+
+/// Focal lengths, optical center, two radial and two tangential distortion coefficients
+pub const AMOUNT_OF_INTRINSIC_PARAMETERS: usize = 8;
+/// Rotation vector and translation of the board in the camera
+pub const AMOUNT_OF_VIEW_PARAMETERS: usize = 6;
+
+const EPSILON: f64 = 0.000001;
+
+/// Refines intrinsics and board poses by minimizing the reprojection error of all corners
+pub struct IntrinsicProblem {
+    parameters: DVector<f64>,
+    model_points: Vec<Point2<f64>>,
+    views: Vec<Vec<Point2<f64>>>,
+}
+
+impl IntrinsicProblem {
+    pub fn new(
+        parameters: DVector<f64>,
+        model_points: Vec<Point2<f64>>,
+        views: Vec<Vec<Point2<f64>>>,
+    ) -> Self {
+        Self {
+            parameters,
+            model_points,
+            views,
+        }
+    }
+
+    pub fn get_parameters(&self) -> &DVector<f64> {
+        &self.parameters
+    }
+
+    /// Root mean square of the pixel distances between projected and detected corners
+    pub fn reprojection_error(&self) -> f64 {
+        let residuals = calculate_residuals(&self.parameters, &self.model_points, &self.views);
+        (residuals.norm_squared() / (residuals.len() / 2).max(1) as f64).sqrt()
+    }
+}
+
+impl LeastSquaresProblem<f64, Dyn, Dyn> for IntrinsicProblem {
+    type ResidualStorage = Owned<f64, Dyn>;
+    type JacobianStorage = Owned<f64, Dyn, Dyn>;
+    type ParameterStorage = Owned<f64, Dyn>;
+
+    fn set_params(&mut self, parameters: &DVector<f64>) {
+        self.parameters.copy_from(parameters);
+    }
+
+    fn params(&self) -> DVector<f64> {
+        self.parameters.clone()
+    }
+
+    fn residuals(&self) -> Option<DVector<f64>> {
+        Some(calculate_residuals(
+            &self.parameters,
+            &self.model_points,
+            &self.views,
+        ))
+    }
+
+    fn jacobian(&self) -> Option<DMatrix<f64>> {
+        let columns: Vec<_> = (0..self.parameters.len())
+            .map(|index| {
+                let mut upper = self.parameters.clone();
+                let mut lower = self.parameters.clone();
+                upper[index] += EPSILON;
+                lower[index] -= EPSILON;
+                (calculate_residuals(&upper, &self.model_points, &self.views)
+                    - calculate_residuals(&lower, &self.model_points, &self.views))
+                    / (2.0 * EPSILON)
+            })
+            .collect();
+        Some(DMatrix::from_columns(&columns))
+    }
+}
+
+pub fn calculate_residuals(
+    parameters: &DVector<f64>,
+    model_points: &[Point2<f64>],
+    views: &[Vec<Point2<f64>>],
+) -> DVector<f64> {
+    let residuals = views.iter().enumerate().flat_map(|(index, image_points)| {
+        let offset = AMOUNT_OF_INTRINSIC_PARAMETERS + index * AMOUNT_OF_VIEW_PARAMETERS;
+        let view = parameters.fixed_rows::<AMOUNT_OF_VIEW_PARAMETERS>(offset);
+        let rotation = Rotation3::new(vector![view[0], view[1], view[2]]);
+        let translation = vector![view[3], view[4], view[5]];
+        model_points
+            .iter()
+            .zip(image_points)
+            .flat_map(move |(model_point, image_point)| {
+                let camera = rotation * vector![model_point.x, model_point.y, 0.0] + translation;
+                let projected = project(parameters, camera);
+                [projected.x - image_point.x, projected.y - image_point.y]
+            })
+    });
+    DVector::from_iterator(
+        views.len() * model_points.len() * 2,
+        residuals.collect::<Vec<_>>(),
+    )
+}
+
+/// Projects a point in camera coordinates with the distortion model of
+/// [`projection::distortion::Distortion`]
+fn project(parameters: &DVector<f64>, camera: Vector3<f64>) -> Vector2<f64> {
+    let (focal_length_x, focal_length_y) = (parameters[0], parameters[1]);
+    let (optical_center_x, optical_center_y) = (parameters[2], parameters[3]);
+    let (k1, k2, p1, p2) = (parameters[4], parameters[5], parameters[6], parameters[7]);
+    let (x, y) = (camera.x / camera.z, camera.y / camera.z);
+    let squared_radius = x * x + y * y;
+    let radial_factor = 1.0 + k1 * squared_radius + k2 * squared_radius * squared_radius;
+    let distorted_x = x * radial_factor + 2.0 * p1 * x * y + p2 * (squared_radius + 2.0 * x * x);
+    let distorted_y = y * radial_factor + p1 * (squared_radius + 2.0 * y * y) + 2.0 * p2 * x * y;
+    vector![
+        focal_length_x * distorted_x + optical_center_x,
+        focal_length_y * distorted_y + optical_center_y
+    ]
+}
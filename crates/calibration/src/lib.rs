@@ -1,10 +1,11 @@
 use corrections::Corrections;
-use levenberg_marquardt::LevenbergMarquardt;
+use levenberg_marquardt::{LevenbergMarquardt, MinimizationReport};
 use measurement::Measurement;
 use problem::CalibrationProblem;
 use types::field_dimensions::FieldDimensions;
 
 pub mod corrections;
+pub mod intrinsic;
 pub mod jacobian;
 pub mod lines;
 pub mod measurement;
@@ -15,11 +16,8 @@ pub fn solve(
     initial_corrections: Corrections,
     measurements: Vec<Measurement>,
     field_dimensions: FieldDimensions,
-) -> Corrections {
+) -> (Corrections, MinimizationReport<f32>) {
     let problem = CalibrationProblem::new(initial_corrections, measurements, field_dimensions);
     let (result, report) = LevenbergMarquardt::new().minimize(problem);
-    println!("Report: {report:?}");
-    let corrections = result.get_corrections();
-    println!("Corrections: {corrections:?}");
-    corrections
+    (result.get_corrections(), report)
 }
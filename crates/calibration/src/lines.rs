@@ -1,12 +1,21 @@
 use coordinate_systems::{Ground, Pixel};
 use geometry::line::{Line, Line2};
 use linear_algebra::Point2;
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use projection::{camera_matrix::CameraMatrix, Projection};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+/// The lines of a goal box as seen by a camera
+///
+/// The border line is the goal line, the goal box line is the parallel front line of the goal box
+/// and the connecting line is one of the orthogonal goal box lines connecting them.
+#[derive(Clone, Debug, Deserialize, PathDeserialize, PathIntrospect, PathSerialize, Serialize)]
 pub struct Lines<Frame> {
+    #[path_serde(leaf)]
     pub border_line: Line2<Frame>,
+    #[path_serde(leaf)]
     pub goal_box_line: Line2<Frame>,
+    #[path_serde(leaf)]
     pub connecting_line: Line2<Frame>,
 }
 
@@ -1,10 +1,12 @@
 use coordinate_systems::Pixel;
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use projection::camera_matrix::CameraMatrix;
+use serde::{Deserialize, Serialize};
 use types::camera_position::CameraPosition;
 
 use crate::lines::Lines;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Deserialize, PathDeserialize, PathIntrospect, PathSerialize, Serialize)]
 pub struct Measurement {
     pub position: CameraPosition,
     pub matrix: CameraMatrix,
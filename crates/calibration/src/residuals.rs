@@ -62,7 +62,7 @@ impl Residuals {
             .border_line
             .signed_acute_angle_to_orthogonal(projected_lines.connecting_line);
         let connecting_to_goal_box_angle = projected_lines
-            .border_line
+            .goal_box_line
             .signed_acute_angle_to_orthogonal(projected_lines.connecting_line);
         let distance_between_parallel_line_start_points = projected_lines
             .border_line
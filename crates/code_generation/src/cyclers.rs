@@ -3,7 +3,8 @@ use std::{collections::BTreeSet, iter::once};
 use convert_case::{Case, Casing};
 use itertools::Itertools;
 use proc_macro2::{Ident, TokenStream};
-use quote::{format_ident, quote};
+use quote::{format_ident, quote, ToTokens};
+use sha2::{Digest, Sha256};
 use source_analyzer::{
     contexts::Field,
     cyclers::{Cycler, CyclerKind, Cyclers},
@@ -14,6 +15,7 @@ use syn::{Path as SynPath, Type, TypePath};
 
 use crate::{
     accessor::{path_to_accessor_token_stream, ReferenceKind},
+    recording_schema::{module_of_node, TypeDefinitions},
     CyclerMode,
 };
 
@@ -26,6 +28,7 @@ pub fn generate_cyclers(cyclers: &Cyclers, mode: CyclerMode) -> TokenStream {
                     timestamp: std::time::SystemTime,
                     duration: std::time::Duration,
                     data: std::vec::Vec<u8>,
+                    is_in_recording_interval: bool,
                 },
             }
         });
@@ -37,10 +40,11 @@ pub fn generate_cyclers(cyclers: &Cyclers, mode: CyclerMode) -> TokenStream {
     } else {
         Default::default()
     };
+    let type_definitions = TypeDefinitions::from_cyclers(cyclers);
     let cyclers: Vec<_> = cyclers
         .cyclers
         .iter()
-        .map(|cycler| generate_module(cycler, cyclers, mode))
+        .map(|cycler| generate_module(cycler, cyclers, mode, &type_definitions))
         .collect();
 
     quote! {
@@ -50,12 +54,22 @@ pub fn generate_cyclers(cyclers: &Cyclers, mode: CyclerMode) -> TokenStream {
     }
 }
 
-fn generate_module(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) -> TokenStream {
+fn generate_module(
+    cycler: &Cycler,
+    cyclers: &Cyclers,
+    mode: CyclerMode,
+    type_definitions: &TypeDefinitions,
+) -> TokenStream {
     let module_name = format_ident!("{}", cycler.name.to_case(Case::Snake));
     let cycler_instance = generate_cycler_instance(cycler);
+    let recording_schema_hash = generate_recording_schema_hash(cycler, type_definitions);
     let database_struct = generate_database_struct();
     let cycler_struct = generate_struct(cycler, cyclers, mode);
     let cycler_implementation = generate_implementation(cycler, cyclers, mode);
+    let validate_injection = match mode {
+        CyclerMode::Run => generate_validate_injection(cycler),
+        CyclerMode::Replay => Default::default(),
+    };
 
     quote! {
         #[allow(dead_code, unused_mut, unused_variables, clippy::too_many_arguments, clippy::needless_question_mark, clippy::borrow_deref_ref)]
@@ -64,9 +78,11 @@ fn generate_module(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) -> Toke
             use crate::structs::#module_name::{MainOutputs, AdditionalOutputs};
 
             #cycler_instance
+            pub(crate) const RECORDING_SCHEMA_HASH: u64 = #recording_schema_hash;
             #database_struct
             #cycler_struct
             #cycler_implementation
+            #validate_injection
         }
     }
 }
@@ -84,6 +100,149 @@ fn generate_cycler_instance(cycler: &Cycler) -> TokenStream {
     }
 }
 
+/// Hashes everything that determines the layout of a recording frame: main outputs of setup
+/// nodes, inputs from other cyclers and the states of cycle nodes, each including the
+/// definitions of all types they consist of
+fn generate_recording_schema_hash(cycler: &Cycler, type_definitions: &TypeDefinitions) -> u64 {
+    let mut hasher = Sha256::new();
+    let mut visited_types = BTreeSet::new();
+    for node in &cycler.setup_nodes {
+        hasher.update(&node.name);
+        for field in &node.contexts.main_outputs {
+            hasher.update(field_signature(field));
+            type_definitions.hash_referenced_types(
+                &mut hasher,
+                &module_of_node(node),
+                field_data_type(field).to_token_stream(),
+                &mut visited_types,
+            );
+        }
+    }
+    for (field, node) in get_cross_input_fields_with_nodes(cycler) {
+        hasher.update(field_signature(&field));
+        type_definitions.hash_referenced_types(
+            &mut hasher,
+            &module_of_node(node),
+            field_data_type(&field).to_token_stream(),
+            &mut visited_types,
+        );
+    }
+    for node in &cycler.cycle_nodes {
+        hasher.update(&node.name);
+        let node_name = format_ident!("{}", node.name);
+        type_definitions.hash_referenced_types(
+            &mut hasher,
+            &module_of_node(node),
+            node_name.to_token_stream(),
+            &mut visited_types,
+        );
+    }
+    let digest = hasher.finalize();
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+/// Types whose definitions determine the recording schema, each with the module of the node
+/// they are written in
+pub(crate) fn recorded_types(cycler: &Cycler) -> Vec<(String, TokenStream)> {
+    let main_outputs = cycler.setup_nodes.iter().flat_map(|node| {
+        node.contexts.main_outputs.iter().map(move |field| {
+            (
+                module_of_node(node),
+                field_data_type(field).to_token_stream(),
+            )
+        })
+    });
+    let cross_inputs =
+        get_cross_input_fields_with_nodes(cycler)
+            .into_iter()
+            .map(|(field, node)| {
+                (
+                    module_of_node(node),
+                    field_data_type(&field).to_token_stream(),
+                )
+            });
+    let cycle_nodes = cycler.cycle_nodes.iter().map(|node| {
+        let node_name = format_ident!("{}", node.name);
+        (module_of_node(node), node_name.to_token_stream())
+    });
+    main_outputs
+        .chain(cross_inputs)
+        .chain(cycle_nodes)
+        .collect()
+}
+
+/// Cross inputs with the first node that reads them
+fn get_cross_input_fields_with_nodes(cycler: &Cycler) -> Vec<(Field, &Node)> {
+    get_cross_input_fields(cycler)
+        .into_iter()
+        .filter_map(|field| {
+            let node = cycler
+                .setup_nodes
+                .iter()
+                .chain(cycler.cycle_nodes.iter())
+                .find(|node| node.contexts.cycle_context.contains(&field))?;
+            Some((field, node))
+        })
+        .collect()
+}
+
+fn field_signature(field: &Field) -> String {
+    match field {
+        Field::CyclerState {
+            data_type, path, ..
+        }
+        | Field::HistoricInput {
+            data_type, path, ..
+        } => format!(
+            "{}: {}",
+            path.to_segments().join("."),
+            data_type.to_token_stream()
+        ),
+        Field::Input {
+            cycler_instance,
+            data_type,
+            path,
+            ..
+        }
+        | Field::RequiredInput {
+            cycler_instance,
+            data_type,
+            path,
+            ..
+        } => format!(
+            "{cycler_instance:?} {}: {}",
+            path.to_segments().join("."),
+            data_type.to_token_stream()
+        ),
+        Field::PerceptionInput {
+            cycler_instance,
+            data_type,
+            path,
+            ..
+        } => format!(
+            "{cycler_instance} {}: {}",
+            path.to_segments().join("."),
+            data_type.to_token_stream()
+        ),
+        Field::MainOutput { data_type, name } => {
+            format!("{name}: {}", data_type.to_token_stream())
+        }
+        _ => panic!("unexpected field {field:?}"),
+    }
+}
+
+fn field_data_type(field: &Field) -> &Type {
+    match field {
+        Field::CyclerState { data_type, .. }
+        | Field::HistoricInput { data_type, .. }
+        | Field::Input { data_type, .. }
+        | Field::RequiredInput { data_type, .. }
+        | Field::PerceptionInput { data_type, .. }
+        | Field::MainOutput { data_type, .. } => data_type,
+        _ => panic!("unexpected field {field:?}"),
+    }
+}
+
 fn generate_database_struct() -> TokenStream {
     quote! {
         #[derive(
@@ -113,6 +272,13 @@ fn generate_struct(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) -> Toke
         }
     };
     let node_fields = generate_node_fields(cycler);
+    let injections_field = if mode == CyclerMode::Run {
+        quote! {
+            own_injections_reader: framework::Reader<std::collections::BTreeMap<String, serde_json::Value>>,
+        }
+    } else {
+        Default::default()
+    };
     let recording_fields = if mode == CyclerMode::Run {
         quote! {
             recording_sender: std::sync::mpsc::SyncSender<crate::cyclers::RecordingFrame>,
@@ -129,6 +295,7 @@ fn generate_struct(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) -> Toke
             own_writer: framework::Writer<Database>,
             own_changed: std::sync::Arc<tokio::sync::Notify>,
             own_subscribed_outputs_reader: framework::Reader<std::collections::HashSet<String>>,
+            #injections_field
             parameters_reader: framework::Reader<crate::structs::Parameters>,
             cycler_state: crate::structs::#module_name::CyclerState,
             #realtime_inputs
@@ -230,6 +397,20 @@ fn generate_new_method(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) ->
         .iter_nodes()
         .map(|node| format_ident!("{}", node.name.to_case(Case::Snake)));
     let input_output_identifiers = generate_input_output_identifiers(cycler, cyclers);
+    let injections_parameter_field = if mode == CyclerMode::Run {
+        quote! {
+            own_injections_reader: framework::Reader<std::collections::BTreeMap<String, serde_json::Value>>,
+        }
+    } else {
+        Default::default()
+    };
+    let injections_initializer_field = if mode == CyclerMode::Run {
+        quote! {
+            own_injections_reader,
+        }
+    } else {
+        Default::default()
+    };
     let recording_parameter_fields = if mode == CyclerMode::Run {
         quote! {
             recording_sender: std::sync::mpsc::SyncSender<crate::cyclers::RecordingFrame>,
@@ -254,6 +435,7 @@ fn generate_new_method(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) ->
             own_writer: framework::Writer<Database>,
             own_changed: std::sync::Arc<tokio::sync::Notify>,
             own_subscribed_outputs_reader: framework::Reader<std::collections::HashSet<String>>,
+            #injections_parameter_field
             parameters_reader: framework::Reader<crate::structs::Parameters>,
             #input_output_fields
             #recording_parameter_fields
@@ -267,6 +449,7 @@ fn generate_new_method(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) ->
                 own_writer,
                 own_changed,
                 own_subscribed_outputs_reader,
+                #injections_initializer_field
                 parameters_reader,
                 cycler_state,
                 #input_output_identifiers
@@ -456,9 +639,16 @@ fn generate_cycle_method(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) -
         CyclerMode::Replay => generate_cross_inputs_extraction(cross_input_fields),
     };
 
+    let lock_injections = match mode {
+        CyclerMode::Run => quote! {
+            let own_injections = self.own_injections_reader.next();
+        },
+        CyclerMode::Replay => Default::default(),
+    };
     let pre_setup = match mode {
         CyclerMode::Run => quote! {
             let enable_recording = self.recording_trigger.should_record() && self.hardware_interface.should_record();
+            let is_in_recording_interval = self.recording_trigger.is_in_recording_interval();
             self.recording_trigger.update();
             let mut recording_frame = Vec::new(); // TODO: possible optimization: cache capacity
         },
@@ -522,6 +712,7 @@ fn generate_cycle_method(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) -
                         timestamp: recording_timestamp,
                         duration: recording_duration,
                         data: recording_frame,
+                        is_in_recording_interval,
                     },
                 }
             });
@@ -558,6 +749,7 @@ fn generate_cycle_method(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) -
 
                 {
                     let own_subscribed_outputs = self.own_subscribed_outputs_reader.next();
+                    #lock_injections
                     let parameters = self.parameters_reader.next();
                     #(#setup_node_executions)*
                 }
@@ -566,6 +758,7 @@ fn generate_cycle_method(cycler: &Cycler, cyclers: &Cyclers, mode: CyclerMode) -
 
                 {
                     let own_subscribed_outputs = self.own_subscribed_outputs_reader.next();
+                    #lock_injections
                     let parameters = self.parameters_reader.next();
                     #lock_readers
                     #cross_inputs
@@ -900,6 +1093,10 @@ fn generate_execute_node_and_write_main_outputs(
     let cycle_error_message = format!("failed to execute cycle of `{}`", node.name);
     let write_main_outputs = generate_write_main_outputs(node);
     let write_main_outputs_from_defaults = generate_write_main_outputs_from_defaults(node);
+    let write_main_outputs_from_injections = match mode {
+        CyclerMode::Run => generate_write_main_outputs_from_injections(node),
+        CyclerMode::Replay => Default::default(),
+    };
 
     quote! {
         {
@@ -919,6 +1116,7 @@ fn generate_execute_node_and_write_main_outputs(
             else {
                 #write_main_outputs_from_defaults
             }
+            #write_main_outputs_from_injections
         }
     }
 }
@@ -1392,3 +1590,50 @@ fn generate_write_main_outputs_from_defaults(node: &Node) -> TokenStream {
         })
         .collect()
 }
+
+fn generate_validate_injection(cycler: &Cycler) -> TokenStream {
+    let match_arms = cycler
+        .iter_nodes()
+        .flat_map(|node| &node.contexts.main_outputs)
+        .filter_map(|field| match field {
+            Field::MainOutput { data_type, name } => {
+                let path = format!("main_outputs.{name}");
+                Some(quote! {
+                    #path => serde_json::from_value::<#data_type>(value.clone())
+                        .map(|_| ())
+                        .map_err(|error| format!("failed to deserialize: {error}")),
+                })
+            }
+            _ => None,
+        });
+    quote! {
+        /// Checks whether the value can be injected as the main output at the path
+        pub(crate) fn validate_injection(path: &str, value: &serde_json::Value) -> Result<(), String> {
+            match path {
+                #(#match_arms)*
+                _ => Err(format!("path {path:?} cannot be injected")),
+            }
+        }
+    }
+}
+
+fn generate_write_main_outputs_from_injections(node: &Node) -> TokenStream {
+    node.contexts
+        .main_outputs
+        .iter()
+        .filter_map(|field| match field {
+            Field::MainOutput { name, .. } => {
+                let path = format!("main_outputs.{name}");
+                Some(quote! {
+                    if let Some(injected_value) = own_injections.get(#path) {
+                        // values are validated when injected, see `validate_injection`
+                        if let Ok(value) = serde_json::from_value(injected_value.clone()) {
+                            own_database_reference.main_outputs.#name = value;
+                        }
+                    }
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
@@ -16,6 +16,7 @@ pub fn generate_run_function(cyclers: &Cyclers) -> TokenStream {
     let recording_thread = generate_recording_thread(cyclers);
     let construct_cyclers = generate_cycler_constructors(cyclers, CyclerMode::Run);
     let communication_registrations = generate_communication_registrations(cyclers);
+    let injections_registrations = generate_injections_registrations(cyclers);
     let start_cyclers = generate_cycler_starts(cyclers);
     let join_cyclers = generate_cycler_joins(cyclers);
 
@@ -25,12 +26,15 @@ pub fn generate_run_function(cyclers: &Cyclers) -> TokenStream {
         pub fn run(
             hardware_interface: std::sync::Arc<impl crate::HardwareInterface + Send + Sync + 'static>,
             addresses: Option<impl tokio::net::ToSocketAddrs + std::marker::Send + std::marker::Sync + 'static>,
+            authentication: Option<framework::CommunicationAuthentication>,
             parameters_directory: impl std::convert::AsRef<std::path::Path> + std::marker::Send + std::marker::Sync + 'static,
             log_path: impl std::convert::AsRef<std::path::Path> + std::marker::Send + std::marker::Sync + 'static,
             body_id: String,
             head_id: String,
             keep_running: tokio_util::sync::CancellationToken,
             recording_intervals: std::collections::HashMap<String, usize>,
+            recording_compression: framework::RecordingCompression,
+            recording_event_window: Option<framework::RecordingEventWindow>,
         ) -> color_eyre::Result<()>
         {
             use color_eyre::eyre::WrapErr;
@@ -53,6 +57,7 @@ pub fn generate_run_function(cyclers: &Cyclers) -> TokenStream {
 
             #construct_cyclers
             #communication_registrations
+            #injections_registrations
             // Drop sender to cause channel to close once all cyclers exit,
             // otherwise the recording thread waits forever
             drop(recording_sender);
@@ -114,6 +119,7 @@ pub fn generate_replayer_struct(cyclers: &Cyclers, with_communication: bool) ->
         (
             quote! {
                 addresses: Option<impl tokio::net::ToSocketAddrs + std::marker::Send + std::marker::Sync + 'static>,
+                authentication: Option<framework::CommunicationAuthentication>,
                 keep_running: tokio_util::sync::CancellationToken,
             },
             generate_communication_registrations(cyclers),
@@ -217,6 +223,15 @@ fn generate_replayer_token_streams(
                 )
             })
             .collect();
+        let subscribed_outputs_writer_identifiers: Vec<_> = cyclers
+            .instances()
+            .map(|(_cycler, instance)| {
+                format_ident!(
+                    "{}_subscribed_outputs_writer",
+                    instance.to_case(Case::Snake)
+                )
+            })
+            .collect();
 
         let reader_identifiers = reader_tokens.iter().map(|(reader, _cycler)| reader);
         let reader_fields = reader_tokens.iter().map(|(reader, cycler)| {
@@ -232,18 +247,95 @@ fn generate_replayer_token_streams(
                 }
             }
         });
+        let subscribed_outputs_writer_fields =
+            subscribed_outputs_writer_identifiers.iter().map(|writer| {
+                quote! {
+                    #writer: framework::Writer<std::collections::HashSet<String>>,
+                }
+            });
+        let subscribed_outputs_writes = cyclers
+            .instances()
+            .zip(subscribed_outputs_writer_identifiers.iter())
+            .map(|((_cycler, instance), writer)| {
+                quote! {
+                    #instance => *self.#writer.next() = subscribed_outputs,
+                }
+            });
+        let output_serializations =
+            cyclers
+                .instances()
+                .zip(reader_tokens.iter())
+                .map(|((_cycler, instance), (reader, _cycler_module))| {
+                    let error_message = format!("failed to serialize output of {instance}");
+                    quote! {
+                        #instance => path_serde::PathSerialize::serialize_path(&*self.#reader.next(), path, serializer)
+                            .wrap_err(#error_message),
+                    }
+                });
+
+        let output_introspections = cyclers.instances().map(|(cycler, instance)| {
+            let cycler_module = format_ident!("{}", cycler.name.to_case(Case::Snake));
+            quote! {
+                #instance => Ok(<crate::cyclers::#cycler_module::Database as path_serde::PathIntrospect>::get_fields()),
+            }
+        });
 
         ReplayerTokenStreams {
             fields: quote! {
                 _parameters_writer: framework::Writer<crate::structs::Parameters>,
                 #(#reader_fields)*
+                #(#subscribed_outputs_writer_fields)*
             },
             parameters: quote! {
                 _parameters_writer: parameters_writer,
                 #(#reader_identifiers,)*
+                #(#subscribed_outputs_writer_identifiers,)*
             },
             accessors: quote! {
                 #(#reader_accessors)*
+
+                #[allow(unused)]
+                pub(crate) fn set_subscribed_outputs(
+                    &self,
+                    cycler_instance_name: &str,
+                    subscribed_outputs: std::collections::HashSet<String>,
+                ) -> color_eyre::Result<()> {
+                    match cycler_instance_name {
+                        #(#subscribed_outputs_writes)*
+                        _ => color_eyre::eyre::bail!("unexpected cycler instance name {cycler_instance_name}"),
+                    }
+                    Ok(())
+                }
+
+                #[allow(unused)]
+                pub(crate) fn serialize_output<S>(
+                    &self,
+                    cycler_instance_name: &str,
+                    path: &str,
+                    serializer: S,
+                ) -> color_eyre::Result<S::Ok>
+                where
+                    S: serde::Serializer,
+                    S::Error: Send + Sync + 'static,
+                {
+                    use color_eyre::eyre::WrapErr;
+
+                    match cycler_instance_name {
+                        #(#output_serializations)*
+                        _ => color_eyre::eyre::bail!("unexpected cycler instance name {cycler_instance_name}"),
+                    }
+                }
+
+                #[allow(unused)]
+                pub(crate) fn output_fields(
+                    &self,
+                    cycler_instance_name: &str,
+                ) -> color_eyre::Result<std::collections::BTreeSet<String>> {
+                    match cycler_instance_name {
+                        #(#output_introspections)*
+                        _ => color_eyre::eyre::bail!("unexpected cycler instance name {cycler_instance_name}"),
+                    }
+                }
             },
         }
     }
@@ -256,7 +348,7 @@ fn generate_parameters_reader(
     if with_communication {
         quote! {
             let communication_server = communication::server::Runtime::start(
-                addresses, parameters_directory, body_id, head_id, #number_of_parameter_slots, keep_running.clone())
+                addresses, authentication, parameters_directory, body_id, head_id, #number_of_parameter_slots, keep_running.clone())
                 .wrap_err("failed to start communication server")?;
             let parameters_reader = communication_server.get_parameters_reader();
         }
@@ -331,8 +423,10 @@ fn generate_future_queues(cyclers: &Cyclers) -> TokenStream {
 }
 
 fn generate_recording_thread(cyclers: &Cyclers) -> TokenStream {
-    let file_creations = cyclers.instances().map(|(_cycler, instance)| {
+    let file_creations = cyclers.instances().map(|(cycler, instance)| {
         let instance_name_snake_case = format_ident!("{}", instance.to_case(Case::Snake));
+        let event_buffer = format_ident!("{}_event_buffer", instance.to_case(Case::Snake));
+        let cycler_module_name = format_ident!("{}", cycler.name.to_case(Case::Snake));
         let recording_file_name = format!("{instance}.bincode");
         let error_message_file = format!("failed to create recording file for {instance}");
 
@@ -343,21 +437,41 @@ fn generate_recording_thread(cyclers: &Cyclers) -> TokenStream {
                     .expect("recording file path has no parent directory")
             ).wrap_err("failed to create logs folder")?;
 
-            let mut #instance_name_snake_case = std::io::BufWriter::new(std::fs::File::create(recording_file_path).wrap_err(#error_message_file)?); // TODO: possible optimization: buffer size
+            let mut #instance_name_snake_case = framework::RecordingWriter::create(
+                recording_file_path,
+                framework::RecordingHeader {
+                    cycler_instance: #instance.to_string(),
+                    schema_hash: crate::cyclers::#cycler_module_name::RECORDING_SCHEMA_HASH,
+                    parameters_hash,
+                    compression: recording_compression,
+                },
+            ).wrap_err(#error_message_file)?;
+            let mut #event_buffer = recording_event_window.map(framework::RecordingEventBuffer::new);
         }
     });
     let frame_writes = cyclers.instances().map(|(_cycler, instance)| {
         let instance_name = format_ident!("{}", instance);
         let instance_name_snake_case = format_ident!("{}", instance.to_case(Case::Snake));
+        let event_buffer = format_ident!("{}_event_buffer", instance.to_case(Case::Snake));
         let error_message = format!("failed to write into recording file for {instance}");
         quote! {
-            crate::cyclers::RecordingFrame::#instance_name { timestamp, duration, data } => {
-                let mut recording_header = Vec::new();
-                bincode::serialize_into(&mut recording_header, &timestamp).wrap_err("failed to serialize timestamp")?;
-                bincode::serialize_into(&mut recording_header, &duration).wrap_err("failed to serialize duration")?;
-                bincode::serialize_into(&mut recording_header, &data.len()).wrap_err("failed to serialize data length")?;
-                #instance_name_snake_case.write_all(recording_header.as_slice()).wrap_err(#error_message)?;
-                #instance_name_snake_case.write_all(data.as_slice()).wrap_err(#error_message)?;
+            crate::cyclers::RecordingFrame::#instance_name { timestamp, duration, data, is_in_recording_interval } => {
+                match &mut #event_buffer {
+                    Some(event_buffer) => {
+                        let latest_event = hardware::RecordingInterface::latest_recording_event(&*hardware_interface);
+                        let frame = framework::RecordingFrame {
+                            timing: framework::Timing { timestamp, duration },
+                            data,
+                        };
+                        for frame in event_buffer.push(frame, is_in_recording_interval, latest_event) {
+                            #instance_name_snake_case.write_frame(frame.timing.timestamp, frame.timing.duration, &frame.data).wrap_err(#error_message)?;
+                        }
+                    },
+                    None if is_in_recording_interval => {
+                        #instance_name_snake_case.write_frame(timestamp, duration, &data).wrap_err(#error_message)?;
+                    },
+                    None => {},
+                }
             },
         }
     });
@@ -366,12 +480,13 @@ fn generate_recording_thread(cyclers: &Cyclers) -> TokenStream {
         {
             let keep_running = keep_running.clone();
             let parameters_reader = parameters_reader.clone();
+            let hardware_interface = hardware_interface.clone();
             std::thread::Builder::new()
                 .name("Recording".to_string())
                 .spawn(move || -> color_eyre::Result<()> {
                     let result = (|| {
-                        use std::io::Write;
                         std::fs::write(log_path.as_ref().join("default.json"), serde_json::to_string_pretty(&*parameters_reader.next())?)?;
+                        let parameters_hash = framework::parameters_hash(&*parameters_reader.next())?;
                         #(#file_creations)*
                         for recording_frame in recording_receiver {
                             match recording_frame {
@@ -401,10 +516,29 @@ fn generate_cycler_constructors(cyclers: &Cyclers, mode: CyclerMode) -> TokenStr
         let own_writer_identifier = format_ident!("{instance_name_snake_case}_writer");
         let own_subscribed_outputs_writer_identifier = format_ident!("{instance_name_snake_case}_subscribed_outputs_writer");
         let own_subscribed_outputs_reader_identifier = format_ident!("{instance_name_snake_case}_subscribed_outputs_reader");
+        let own_injections_writer_identifier = format_ident!("{instance_name_snake_case}_injections_writer");
+        let own_injections_reader_identifier = format_ident!("{instance_name_snake_case}_injections_reader");
+        let injections = if mode == CyclerMode::Run {
+            quote! {
+                let (#own_injections_writer_identifier, #own_injections_reader_identifier) = framework::multiple_buffer_with_slots([
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                ]);
+            }
+        } else {
+            Default::default()
+        };
+        let injections_parameter = if mode == CyclerMode::Run {
+            quote! { #own_injections_reader_identifier, }
+        } else {
+            Default::default()
+        };
         let recording_trigger = if mode == CyclerMode::Run {
             quote! {
                 let recording_trigger = framework::RecordingTrigger::new(
-                    recording_intervals.get(#cycler_instance_name).copied().unwrap_or(0)
+                    recording_intervals.get(#cycler_instance_name).copied().unwrap_or(0),
+                    recording_event_window.is_some(),
                 );
             }
         } else {
@@ -412,10 +546,19 @@ fn generate_cycler_constructors(cyclers: &Cyclers, mode: CyclerMode) -> TokenStr
         };
         let recording_index = if mode == CyclerMode::Replay {
             let recording_file_name = format!("{instance}.bincode");
+            let parameters_mismatch_message =
+                format!("{instance}: replaying with different parameters than recorded");
             quote! {
                 let #cycler_index_identifier = framework::RecordingIndex::read_from(
-                    recordings_file_path.as_ref().join(#recording_file_name)
+                    recordings_file_path.as_ref().join(#recording_file_name),
+                    #cycler_instance_name,
+                    crate::cyclers::#cycler_module_name::RECORDING_SCHEMA_HASH,
                 ).wrap_err("failed to read recording index")?;
+                if let Some(header) = #cycler_index_identifier.header() {
+                    if header.parameters_hash != framework::parameters_hash(&*parameters_reader.next())? {
+                        eprintln!(#parameters_mismatch_message);
+                    }
+                }
             }
         } else {
             Default::default()
@@ -460,6 +603,7 @@ fn generate_cycler_constructors(cyclers: &Cyclers, mode: CyclerMode) -> TokenStr
                 Default::default(),
             ]);
 
+            #injections
             #recording_trigger
             #recording_index
             let #cycler_variable_identifier = crate::cyclers::#cycler_module_name::Cycler::new(
@@ -468,6 +612,7 @@ fn generate_cycler_constructors(cyclers: &Cyclers, mode: CyclerMode) -> TokenStr
                 #own_writer_identifier,
                 #cycler_database_changed_identifier.clone(),
                 #own_subscribed_outputs_reader_identifier,
+                #injections_parameter
                 parameters_reader.clone(),
                 #own_producer_identifier
                 #(#other_cycler_inputs,)*
@@ -502,6 +647,26 @@ fn generate_communication_registrations(cyclers: &Cyclers) -> TokenStream {
         .collect()
 }
 
+fn generate_injections_registrations(cyclers: &Cyclers) -> TokenStream {
+    cyclers
+        .instances()
+        .map(|(cycler, instance)| {
+            let instance_name_snake_case = instance.to_case(Case::Snake);
+            let cycler_module_name = format_ident!("{}", cycler.name.to_case(Case::Snake));
+            let cycler_instance_name = &instance;
+            let own_injections_writer_identifier =
+                format_ident!("{instance_name_snake_case}_injections_writer");
+            quote! {
+                communication_server.register_injections(
+                    #cycler_instance_name,
+                    crate::cyclers::#cycler_module_name::validate_injection,
+                    #own_injections_writer_identifier,
+                );
+            }
+        })
+        .collect()
+}
+
 fn generate_cycler_starts(cyclers: &Cyclers) -> TokenStream {
     cyclers
         .instances()
@@ -10,6 +10,7 @@ mod accessor;
 pub mod cyclers;
 pub mod execution;
 pub mod perception_databases;
+pub mod recording_schema;
 pub mod structs;
 pub mod write_to_file;
 
@@ -0,0 +1,694 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::read_dir,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+use proc_macro2::{Spacing, TokenStream, TokenTree};
+use quote::ToTokens;
+use sha2::{Digest, Sha256};
+use source_analyzer::{
+    cyclers::Cyclers,
+    node::{parse_rust_file, Node},
+};
+use syn::{File, Item, Type, UseTree};
+
+use crate::cyclers::recorded_types;
+
+// re-exports are followed at most this deep to not loop on cyclic uses
+const MAXIMUM_RESOLUTION_DEPTH: usize = 8;
+
+/// Definitions of all structs, enums and type aliases the recorded types consist of, keyed by
+/// their full path, e.g. `types::ball_position::BallPosition`
+///
+/// Besides the node files, only crates next to the crates containing the nodes are parsed, and
+/// only if a recorded type references them. Types defined elsewhere (e.g. in `nalgebra`) only
+/// contribute their path to the schema.
+#[derive(Debug, Default)]
+pub struct TypeDefinitions {
+    definitions: BTreeMap<String, Vec<Definition>>,
+    modules: BTreeMap<String, Module>,
+    parsed_crates: BTreeSet<String>,
+    resolved_paths: BTreeMap<(String, String), Option<String>>,
+}
+
+#[derive(Debug)]
+struct Definition {
+    item: TokenStream,
+    module: String,
+}
+
+/// Names brought into scope of a module by `use` items, relative to the crate root
+#[derive(Debug, Default)]
+struct Module {
+    uses: BTreeMap<String, Vec<String>>,
+    globs: Vec<Vec<String>>,
+}
+
+impl TypeDefinitions {
+    pub fn from_cyclers(cyclers: &Cyclers) -> Self {
+        let mut definitions = Self::default();
+        for node in cyclers
+            .cyclers
+            .iter()
+            .flat_map(|cycler| cycler.setup_nodes.iter().chain(&cycler.cycle_nodes))
+        {
+            let module = module_of_node(node);
+            if definitions.modules.contains_key(&module) {
+                continue;
+            }
+            let file = parse_rust_file(&node.file_path).unwrap_or_else(|error| {
+                panic!("failed to parse {}: {error}", node.file_path.display())
+            });
+            definitions.insert_items(&module, &file.items);
+        }
+        let crates_root = crates_root(cyclers);
+        definitions.collect(
+            cyclers.cyclers.iter().flat_map(recorded_types),
+            |crate_name| match crates_root {
+                Some(crates_root) => crate_files(crates_root, crate_name),
+                None => Vec::new(),
+            },
+        )
+    }
+
+    /// Collects the definitions of the types, given with the module they are written in, and of
+    /// all types they consist of, `crate_files` returns the parsed files of a crate with their
+    /// module paths
+    pub fn from_types(
+        types: impl IntoIterator<Item = (String, TokenStream)>,
+        crate_files: impl FnMut(&str) -> Vec<(String, File)>,
+    ) -> Self {
+        Self::default().collect(types, crate_files)
+    }
+
+    fn collect(
+        mut self,
+        types: impl IntoIterator<Item = (String, TokenStream)>,
+        mut crate_files: impl FnMut(&str) -> Vec<(String, File)>,
+    ) -> Self {
+        let mut visited = BTreeSet::new();
+        for (module, data_type) in types {
+            for path in referenced_paths(data_type) {
+                self.collect_path(&path, &module, &mut crate_files, &mut visited);
+            }
+        }
+        for items in self.definitions.values_mut() {
+            items.sort_by_cached_key(|definition| definition.item.to_string());
+        }
+        self
+    }
+
+    fn collect_path(
+        &mut self,
+        path: &[String],
+        module: &str,
+        crate_files: &mut impl FnMut(&str) -> Vec<(String, File)>,
+        visited: &mut BTreeSet<String>,
+    ) {
+        let key = (module.to_string(), path.join("::"));
+        if self.resolved_paths.contains_key(&key) {
+            return;
+        }
+        let resolved_path = self.resolve(path, module, crate_files, MAXIMUM_RESOLUTION_DEPTH);
+        self.resolved_paths.insert(key, resolved_path.clone());
+        let Some(resolved_path) = resolved_path else {
+            return;
+        };
+        if !visited.insert(resolved_path.clone()) {
+            return;
+        }
+        let references: Vec<_> = self.definitions[&resolved_path]
+            .iter()
+            .flat_map(|definition| {
+                referenced_paths(definition.item.clone())
+                    .into_iter()
+                    .map(|path| (path, definition.module.clone()))
+            })
+            .collect();
+        for (path, module) in references {
+            self.collect_path(&path, &module, crate_files, visited);
+        }
+    }
+
+    /// Resolves the path as written in the module to the full path of a definition
+    fn resolve(
+        &mut self,
+        path: &[String],
+        module: &str,
+        crate_files: &mut impl FnMut(&str) -> Vec<(String, File)>,
+        depth: usize,
+    ) -> Option<String> {
+        for candidate in self.candidates(path, module) {
+            let Some((name, candidate_module)) = candidate.split_last() else {
+                continue;
+            };
+            let candidate_module = candidate_module.join("::");
+            // modules of node files are known without parsing their whole crate
+            if !self.modules.contains_key(&candidate_module) {
+                self.parse_crate(&candidate[0], crate_files);
+            }
+            let full_path = candidate.join("::");
+            if self.definitions.contains_key(&full_path) {
+                return Some(full_path);
+            }
+            // follow re-exports, e.g. `pub use inner::Ball;` in the module of the candidate
+            let re_exported_path = self
+                .modules
+                .get(&candidate_module)
+                .and_then(|module| module.uses.get(name))
+                .cloned();
+            if let (Some(re_exported_path), Some(depth)) = (re_exported_path, depth.checked_sub(1))
+            {
+                if let Some(full_path) =
+                    self.resolve(&re_exported_path, &candidate_module, crate_files, depth)
+                {
+                    return Some(full_path);
+                }
+            }
+        }
+        None
+    }
+
+    /// Full paths the path may refer to in the module, in order of precedence
+    fn candidates(&self, path: &[String], module: &str) -> Vec<Vec<String>> {
+        let module_path: Vec<_> = module
+            .split("::")
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        let Some((first, rest)) = path.split_first() else {
+            return Vec::new();
+        };
+        if matches!(first.as_str(), "crate" | "self" | "super") {
+            return vec![to_crate_relative(path, &module_path)];
+        }
+        let mut candidates = Vec::new();
+        let scope = self.modules.get(module);
+        if let Some(used_path) = scope.and_then(|scope| scope.uses.get(first)) {
+            candidates.push(used_path.iter().chain(rest).cloned().collect());
+        }
+        if !module_path.is_empty() {
+            candidates.push(module_path.iter().chain(path).cloned().collect());
+        }
+        for glob in scope.iter().flat_map(|scope| &scope.globs) {
+            candidates.push(glob.iter().chain(path).cloned().collect());
+        }
+        candidates.push(path.to_vec());
+        candidates
+    }
+
+    fn parse_crate(
+        &mut self,
+        crate_name: &str,
+        crate_files: &mut impl FnMut(&str) -> Vec<(String, File)>,
+    ) {
+        if !self.parsed_crates.insert(crate_name.to_string()) {
+            return;
+        }
+        for (module, file) in crate_files(crate_name) {
+            if !self.modules.contains_key(&module) {
+                self.insert_items(&module, &file.items);
+            }
+        }
+    }
+
+    fn insert_items(&mut self, module: &str, items: &[Item]) {
+        let module_path: Vec<_> = module.split("::").map(str::to_string).collect();
+        let scope = self.modules.entry(module.to_string()).or_default();
+        for item in items {
+            if let Item::Use(item) = item {
+                insert_uses(scope, &item.tree, Vec::new(), &module_path);
+            }
+        }
+        for item in items {
+            let name = match item {
+                Item::Struct(item) => item.ident.to_string(),
+                Item::Enum(item) => item.ident.to_string(),
+                Item::Type(item) => item.ident.to_string(),
+                Item::Impl(item) if is_serde_implementation(item) => match &*item.self_ty {
+                    Type::Path(path) => match path.path.segments.last() {
+                        Some(segment) => segment.ident.to_string(),
+                        None => continue,
+                    },
+                    _ => continue,
+                },
+                Item::Mod(item) => {
+                    if let Some((_, items)) = &item.content {
+                        self.insert_items(&format!("{module}::{}", item.ident), items);
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            self.definitions
+                .entry(format!("{module}::{name}"))
+                .or_default()
+                .push(Definition {
+                    item: item.to_token_stream(),
+                    module: module.to_string(),
+                });
+        }
+    }
+
+    /// Source directories of all parsed crates, the schema hash has to be regenerated if any of
+    /// them changes
+    pub fn source_directories(&self, crates_root: &Path) -> Vec<PathBuf> {
+        self.parsed_crates
+            .iter()
+            .map(|crate_name| crates_root.join(crate_name).join("src"))
+            .filter(|source_directory| source_directory.is_dir())
+            .collect()
+    }
+
+    /// Hashes the definitions of all types referenced in the tokens, recursing into the types of
+    /// their fields
+    pub fn hash_referenced_types(
+        &self,
+        hasher: &mut Sha256,
+        module: &str,
+        tokens: TokenStream,
+        visited: &mut BTreeSet<String>,
+    ) {
+        for path in referenced_paths(tokens) {
+            self.hash_path(hasher, &path, module, visited);
+        }
+    }
+
+    fn hash_path(
+        &self,
+        hasher: &mut Sha256,
+        path: &[String],
+        module: &str,
+        visited: &mut BTreeSet<String>,
+    ) {
+        let Some(Some(resolved_path)) = self
+            .resolved_paths
+            .get(&(module.to_string(), path.join("::")))
+        else {
+            return;
+        };
+        if !visited.insert(resolved_path.clone()) {
+            return;
+        }
+        for definition in &self.definitions[resolved_path] {
+            hasher.update(definition.item.to_string());
+            for path in referenced_paths(definition.item.clone()) {
+                self.hash_path(hasher, &path, &definition.module, visited);
+            }
+        }
+    }
+}
+
+/// Source directories of the crates defining the recorded types
+pub fn watch_paths(cyclers: &Cyclers) -> Vec<PathBuf> {
+    let Some(crates_root) = crates_root(cyclers) else {
+        return Vec::new();
+    };
+    TypeDefinitions::from_cyclers(cyclers).source_directories(crates_root)
+}
+
+fn crates_root(cyclers: &Cyclers) -> Option<&Path> {
+    let file_path = cyclers.watch_paths().next()?;
+    let source_directory = file_path
+        .ancestors()
+        .find(|ancestor| ancestor.file_name().is_some_and(|name| name == "src"))?;
+    source_directory.parent()?.parent()
+}
+
+fn crate_files(crates_root: &Path, crate_name: &str) -> Vec<(String, File)> {
+    let source_directory = crates_root.join(crate_name).join("src");
+    let mut file_paths = Vec::new();
+    collect_rust_files(&source_directory, &mut file_paths);
+    file_paths
+        .into_iter()
+        .map(|file_path| {
+            let file = parse_rust_file(&file_path)
+                .unwrap_or_else(|error| panic!("failed to parse {}: {error}", file_path.display()));
+            (
+                module_of_file(crate_name, &source_directory, &file_path),
+                file,
+            )
+        })
+        .collect()
+}
+
+pub(crate) fn module_of_node(node: &Node) -> String {
+    node.module
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .join("::")
+}
+
+/// Module path of a source file, e.g. `types::ball` for `types/src/ball.rs` or `ball/mod.rs`
+fn module_of_file(crate_name: &str, source_directory: &Path, file_path: &Path) -> String {
+    let mut module_path = vec![crate_name.to_string()];
+    let relative_path = file_path
+        .strip_prefix(source_directory)
+        .unwrap_or(file_path)
+        .with_extension("");
+    module_path.extend(
+        relative_path
+            .iter()
+            .map(|segment| segment.to_string_lossy().into_owned()),
+    );
+    if module_path.len() == 2 && matches!(module_path[1].as_str(), "lib" | "main") {
+        module_path.pop();
+    }
+    if module_path.last().is_some_and(|segment| segment == "mod") {
+        module_path.pop();
+    }
+    module_path.join("::")
+}
+
+fn collect_rust_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = read_dir(directory) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_rust_files(&path, files);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            files.push(path);
+        }
+    }
+}
+
+fn insert_uses(scope: &mut Module, tree: &UseTree, mut prefix: Vec<String>, module: &[String]) {
+    match tree {
+        UseTree::Path(path) => {
+            prefix.push(path.ident.to_string());
+            insert_uses(scope, &path.tree, prefix, module);
+        }
+        UseTree::Name(name) if name.ident == "self" => {
+            if let Some(last) = prefix.last().cloned() {
+                scope.uses.insert(last, to_crate_relative(&prefix, module));
+            }
+        }
+        UseTree::Name(name) => {
+            prefix.push(name.ident.to_string());
+            scope
+                .uses
+                .insert(name.ident.to_string(), to_crate_relative(&prefix, module));
+        }
+        UseTree::Rename(rename) => {
+            prefix.push(rename.ident.to_string());
+            scope.uses.insert(
+                rename.rename.to_string(),
+                to_crate_relative(&prefix, module),
+            );
+        }
+        UseTree::Glob(_) => scope.globs.push(to_crate_relative(&prefix, module)),
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                insert_uses(scope, tree, prefix.clone(), module);
+            }
+        }
+    }
+}
+
+/// Replaces leading `crate`, `self` and `super` segments by the path of the module
+fn to_crate_relative(path: &[String], module: &[String]) -> Vec<String> {
+    match path.first().map(String::as_str) {
+        Some("crate") if !module.is_empty() => {
+            module[..1].iter().chain(&path[1..]).cloned().collect()
+        }
+        Some("self") => module.iter().chain(&path[1..]).cloned().collect(),
+        Some("super") => {
+            let number_of_supers = path
+                .iter()
+                .take_while(|segment| *segment == "super")
+                .count();
+            let parent = &module[..module.len().saturating_sub(number_of_supers)];
+            parent
+                .iter()
+                .chain(&path[number_of_supers..])
+                .cloned()
+                .collect()
+        }
+        _ => path.to_vec(),
+    }
+}
+
+/// Paths occurring in the tokens, e.g. `Option` and `types::ball::Ball` in
+/// `Option<types::ball::Ball>`
+fn referenced_paths(tokens: TokenStream) -> Vec<Vec<String>> {
+    let mut paths = Vec::new();
+    collect_paths(tokens, &mut paths);
+    paths
+}
+
+fn collect_paths(tokens: TokenStream, paths: &mut Vec<Vec<String>>) {
+    let mut path = Vec::new();
+    let mut expects_segment = false;
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident) => {
+                if !expects_segment && !path.is_empty() {
+                    paths.push(std::mem::take(&mut path));
+                }
+                path.push(ident.to_string());
+                expects_segment = false;
+            }
+            TokenTree::Punct(punct)
+                if punct.as_char() == ':'
+                    && punct.spacing() == Spacing::Joint
+                    && matches!(tokens.peek(), Some(TokenTree::Punct(next)) if next.as_char() == ':') =>
+            {
+                tokens.next();
+                expects_segment = true;
+            }
+            token => {
+                if !path.is_empty() {
+                    paths.push(std::mem::take(&mut path));
+                }
+                expects_segment = false;
+                if let TokenTree::Group(group) = token {
+                    collect_paths(group.stream(), paths);
+                }
+            }
+        }
+    }
+    if !path.is_empty() {
+        paths.push(path);
+    }
+}
+
+fn is_serde_implementation(item: &syn::ItemImpl) -> bool {
+    item.trait_.as_ref().is_some_and(|(_, path, _)| {
+        path.segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Serialize" || segment.ident == "Deserialize")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::quote;
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn hash(crates: &[(&str, File)], tokens: TokenStream) -> [u8; 32] {
+        let definitions =
+            TypeDefinitions::from_types([(String::new(), tokens.clone())], |crate_name| {
+                crates
+                    .iter()
+                    .filter(|(module, _file)| module.split("::").next() == Some(crate_name))
+                    .map(|(module, file)| (module.to_string(), file.clone()))
+                    .collect()
+            });
+        let mut hasher = Sha256::new();
+        definitions.hash_referenced_types(&mut hasher, "", tokens, &mut BTreeSet::new());
+        hasher.finalize().into()
+    }
+
+    #[test]
+    fn changing_a_nested_field_changes_the_hash() {
+        let original: File = parse_quote! {
+            pub struct BallPosition { pub position: Point2<f32>, pub velocity: Vector2<f32> }
+            pub struct Ball { pub position: BallPosition }
+        };
+        let added_field: File = parse_quote! {
+            pub struct BallPosition {
+                pub position: Point2<f32>,
+                pub velocity: Vector2<f32>,
+                pub last_seen: SystemTime,
+            }
+            pub struct Ball { pub position: BallPosition }
+        };
+        let reordered_fields: File = parse_quote! {
+            pub struct BallPosition { pub velocity: Vector2<f32>, pub position: Point2<f32> }
+            pub struct Ball { pub position: BallPosition }
+        };
+        let recorded_type = quote! { Option<Vec<types::Ball>> };
+
+        let original_hash = hash(&[("types", original.clone())], recorded_type.clone());
+
+        assert_eq!(
+            original_hash,
+            hash(&[("types", original)], recorded_type.clone())
+        );
+        assert_ne!(
+            original_hash,
+            hash(&[("types", added_field)], recorded_type.clone())
+        );
+        assert_ne!(
+            original_hash,
+            hash(&[("types", reordered_fields)], recorded_type)
+        );
+    }
+
+    #[test]
+    fn unrelated_types_do_not_change_the_hash() {
+        let original: File = parse_quote! {
+            pub struct Ball { pub position: Point2<f32> }
+        };
+        let with_unrelated_type: File = parse_quote! {
+            pub struct Ball { pub position: Point2<f32> }
+            pub struct Robot { pub position: Point2<f32> }
+        };
+        let recorded_type = quote! { types::Ball };
+
+        assert_eq!(
+            hash(&[("types", original)], recorded_type.clone()),
+            hash(&[("types", with_unrelated_type)], recorded_type)
+        );
+    }
+
+    #[test]
+    fn definitions_in_inline_modules_and_serde_implementations_are_hashed() {
+        let original: File = parse_quote! {
+            mod inner {
+                pub enum Side { Left, Right }
+            }
+            pub struct Foot { pub side: inner::Side }
+        };
+        let added_variant: File = parse_quote! {
+            mod inner {
+                pub enum Side { Left, Right, Both }
+            }
+            pub struct Foot { pub side: inner::Side }
+        };
+        let manual_implementation: File = parse_quote! {
+            mod inner {
+                pub enum Side { Left, Right }
+                impl serde::Serialize for Side {
+                    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                        serializer.serialize_bool(matches!(self, Side::Left))
+                    }
+                }
+            }
+            pub struct Foot { pub side: inner::Side }
+        };
+        let recorded_type = quote! { types::Foot };
+
+        let original_hash = hash(&[("types", original)], recorded_type.clone());
+
+        assert_ne!(
+            original_hash,
+            hash(&[("types", added_variant)], recorded_type.clone())
+        );
+        assert_ne!(
+            original_hash,
+            hash(&[("types", manual_implementation)], recorded_type)
+        );
+    }
+
+    #[test]
+    fn types_with_the_same_name_are_resolved_by_their_path() {
+        let ball: File = parse_quote! {
+            use crate::filtered::Position;
+            pub struct Ball { pub position: Position }
+        };
+        let filtered: File = parse_quote! {
+            pub struct Position { pub x: f32 }
+        };
+        let unused_position: File = parse_quote! {
+            pub struct Position { pub x: f32 }
+        };
+        let changed_unused_position: File = parse_quote! {
+            pub struct Position { pub x: f64 }
+        };
+        let changed_filtered: File = parse_quote! {
+            pub struct Position { pub x: f64 }
+        };
+        let recorded_type = quote! { types::ball::Ball };
+
+        let original_hash = hash(
+            &[
+                ("types::ball", ball.clone()),
+                ("types::filtered", filtered.clone()),
+                ("geometry", unused_position),
+            ],
+            recorded_type.clone(),
+        );
+
+        assert_eq!(
+            original_hash,
+            hash(
+                &[
+                    ("types::ball", ball.clone()),
+                    ("types::filtered", filtered),
+                    ("geometry", changed_unused_position),
+                ],
+                recorded_type.clone()
+            )
+        );
+        assert_ne!(
+            original_hash,
+            hash(
+                &[("types::ball", ball), ("types::filtered", changed_filtered),],
+                recorded_type
+            )
+        );
+    }
+
+    #[test]
+    fn only_crates_of_referenced_types_are_parsed() {
+        let types: File = parse_quote! {
+            pub struct Ball { pub position: linear_algebra::Point2<coordinate_systems::Ground> }
+        };
+        let linear_algebra: File = parse_quote! {
+            pub type Point2<Frame> = Framed<Frame, nalgebra::Point2<f32>>;
+        };
+        let mut requested_crates = Vec::new();
+        TypeDefinitions::from_types(
+            [(String::new(), quote! { Option<types::Ball> })],
+            |crate_name| {
+                requested_crates.push(crate_name.to_string());
+                match crate_name {
+                    "types" => vec![("types".to_string(), types.clone())],
+                    "linear_algebra" => {
+                        vec![("linear_algebra".to_string(), linear_algebra.clone())]
+                    }
+                    _ => Vec::new(),
+                }
+            },
+        );
+
+        assert!(requested_crates.contains(&"linear_algebra".to_string()));
+        assert!(requested_crates.contains(&"coordinate_systems".to_string()));
+        assert!(!requested_crates.contains(&"control".to_string()));
+    }
+
+    #[test]
+    fn module_paths_are_derived_from_file_paths() {
+        let source_directory = Path::new("crates/types/src");
+        let module_of = |file_path: &str| {
+            module_of_file("types", source_directory, &source_directory.join(file_path))
+        };
+
+        assert_eq!(module_of("lib.rs"), "types");
+        assert_eq!(module_of("ball.rs"), "types::ball");
+        assert_eq!(module_of("ball/mod.rs"), "types::ball");
+        assert_eq!(module_of("ball/position.rs"), "types::ball::position");
+    }
+}
@@ -17,7 +17,7 @@ use crate::{
         parameter_subscription_manager::{self, parameter_subscription_manager},
         SubscriberMessage,
     },
-    messages::{Fields, Format, Path},
+    messages::{Fields, Format, Path, SubscriptionOptions},
 };
 
 use super::{
@@ -115,6 +115,7 @@ impl Communication {
         &self,
         output: CyclerOutput,
         format: Format,
+        options: SubscriptionOptions,
     ) -> (Uuid, Receiver<SubscriberMessage>) {
         let (subscriber_sender, subscriber_receiver) = mpsc::channel(10);
         let (response_sender, response_receiver) = oneshot::channel();
@@ -122,6 +123,7 @@ impl Communication {
             .send(output_subscription_manager::Message::Subscribe {
                 output,
                 format,
+                options,
                 subscriber: subscriber_sender,
                 response_sender,
             })
@@ -169,6 +171,20 @@ impl Communication {
         response_receiver.await.unwrap()
     }
 
+    pub async fn set_output_injection(&self, output: CyclerOutput, value: Value) {
+        self.output_subscription_manager
+            .send(output_subscription_manager::Message::SetInjection { output, value })
+            .await
+            .unwrap();
+    }
+
+    pub async fn unset_output_injection(&self, output: CyclerOutput) {
+        self.output_subscription_manager
+            .send(output_subscription_manager::Message::UnsetInjection { output })
+            .await
+            .unwrap();
+    }
+
     pub async fn get_parameter_fields(&self) -> Option<BTreeSet<Path>> {
         let (response_sender, response_receiver) = oneshot::channel();
         self.parameter_subscription_manager
@@ -1,7 +1,8 @@
 use std::collections::{hash_map::Entry, HashMap};
 
-use color_eyre::Result;
+use color_eyre::{eyre::bail, Result};
 use log::{error, info, warn};
+use serde_json::Value;
 use tokio::{
     spawn,
     sync::{broadcast, mpsc, oneshot},
@@ -14,7 +15,7 @@ use crate::{
         responder, Output, SubscriberMessage,
     },
     messages::{
-        Fields, Format, OutputsRequest, Request,
+        Fields, Format, InjectionsRequest, OutputsRequest, Request, SubscriptionOptions,
         TextualDataOrBinaryReference::{self, BinaryReference, TextualData},
     },
 };
@@ -30,6 +31,7 @@ pub enum Message {
     Subscribe {
         output: CyclerOutput,
         format: Format,
+        options: SubscriptionOptions,
         subscriber: mpsc::Sender<SubscriberMessage>,
         response_sender: oneshot::Sender<Uuid>,
     },
@@ -48,13 +50,22 @@ pub enum Message {
     GetOutputFields {
         response_sender: oneshot::Sender<Option<Fields>>,
     },
+    SetInjection {
+        output: CyclerOutput,
+        value: Value,
+    },
+    UnsetInjection {
+        output: CyclerOutput,
+    },
 }
 
+type SubscriptionKey = (CyclerOutput, Format, SubscriptionOptions);
+
 #[derive(Default)]
 struct SubscriptionManager {
-    ids_to_outputs: HashMap<usize, (CyclerOutput, Format)>,
+    ids_to_outputs: HashMap<usize, SubscriptionKey>,
     outputs_to_subscribers:
-        HashMap<(CyclerOutput, Format), HashMap<Uuid, mpsc::Sender<SubscriberMessage>>>,
+        HashMap<SubscriptionKey, HashMap<Uuid, mpsc::Sender<SubscriberMessage>>>,
 }
 
 pub async fn output_subscription_manager(
@@ -68,7 +79,8 @@ pub async fn output_subscription_manager(
     let mut requester = None;
     let mut fields = None;
     let mut binary_data_waiting_for_references: HashMap<usize, Vec<u8>> = HashMap::new();
-    let mut binary_references_waiting_for_data: HashMap<usize, CyclerOutput> = HashMap::new();
+    let mut binary_references_waiting_for_data: HashMap<usize, SubscriptionKey> = HashMap::new();
+    let mut injections: HashMap<CyclerOutput, Value> = HashMap::new();
 
     while let Some(message) = receiver.recv().await {
         match message {
@@ -76,11 +88,12 @@ pub async fn output_subscription_manager(
                 requester: new_requester,
             } => {
                 assert!(manager.ids_to_outputs.is_empty());
-                for ((output, format), subscribers) in &manager.outputs_to_subscribers {
+                for ((output, format, options), subscribers) in &manager.outputs_to_subscribers {
                     let subscribers = subscribers.values().cloned().collect();
                     if let Some(subscription_id) = subscribe(
                         output.clone(),
                         *format,
+                        *options,
                         subscribers,
                         &id_tracker,
                         &responder,
@@ -90,7 +103,21 @@ pub async fn output_subscription_manager(
                     {
                         manager
                             .ids_to_outputs
-                            .insert(subscription_id, (output.clone(), *format));
+                            .insert(subscription_id, (output.clone(), *format, *options));
+                    }
+                }
+                // the server forgets injections of disconnected clients
+                for (output, value) in &injections {
+                    if let Err(error) = set_injection(
+                        output.clone(),
+                        value.clone(),
+                        &id_tracker,
+                        &responder,
+                        &new_requester,
+                    )
+                    .await
+                    {
+                        error!("{error}");
                     }
                 }
                 match query_output_fields(sender.clone(), &id_tracker, &responder, &new_requester)
@@ -109,6 +136,7 @@ pub async fn output_subscription_manager(
             Message::Subscribe {
                 output,
                 format,
+                options,
                 subscriber: output_sender,
                 response_sender,
             } => {
@@ -120,6 +148,7 @@ pub async fn output_subscription_manager(
                             uuid,
                             output,
                             format,
+                            options,
                             output_sender,
                             &id_tracker,
                             &responder,
@@ -134,7 +163,7 @@ pub async fn output_subscription_manager(
                 let mut subscriptions_to_remove = Vec::new();
                 manager
                     .outputs_to_subscribers
-                    .retain(|output_format, clients| {
+                    .retain(|subscription_key, clients| {
                         if clients.remove(&uuid).is_none() {
                             return true;
                         }
@@ -145,7 +174,7 @@ pub async fn output_subscription_manager(
                                     .ids_to_outputs
                                     .iter()
                                     .find_map(|(id, other_output)| {
-                                        (output_format == other_output).then_some(*id)
+                                        (subscription_key == other_output).then_some(*id)
                                     });
                             if let Some(id) = maybe_subscription_id {
                                 subscriptions_to_remove.push(id);
@@ -196,7 +225,7 @@ pub async fn output_subscription_manager(
                                     }
                                 } else {
                                     binary_references_waiting_for_data
-                                        .insert(reference_id, output.0.clone());
+                                        .insert(reference_id, output.clone());
                                 }
                             }
                         }
@@ -216,10 +245,7 @@ pub async fn output_subscription_manager(
             Message::UpdateBinary { referenced_items } => {
                 for (reference_id, data) in referenced_items {
                     if let Some(output) = binary_references_waiting_for_data.get(&reference_id) {
-                        let subscribers = manager
-                            .outputs_to_subscribers
-                            .get(&(output.clone(), Format::Binary));
-                        if let Some(senders) = subscribers {
+                        if let Some(senders) = manager.outputs_to_subscribers.get(output) {
                             for sender in senders.values() {
                                 if let Err(error) = sender
                                     .send(SubscriberMessage::UpdateBinary { data: data.clone() })
@@ -235,6 +261,28 @@ pub async fn output_subscription_manager(
                 }
                 let _ = update_sender.send(());
             }
+            Message::SetInjection { output, value } => {
+                injections.insert(output.clone(), value.clone());
+                if let Some(requester) = &requester {
+                    if let Err(error) =
+                        set_injection(output, value, &id_tracker, &responder, requester).await
+                    {
+                        error!("{error}");
+                    }
+                }
+            }
+            Message::UnsetInjection { output } => {
+                if injections.remove(&output).is_none() {
+                    continue;
+                }
+                if let Some(requester) = &requester {
+                    if let Err(error) =
+                        unset_injection(output, &id_tracker, &responder, requester).await
+                    {
+                        error!("{error}");
+                    }
+                }
+            }
         }
     }
     info!("Finished manager");
@@ -276,6 +324,7 @@ async fn add_subscription(
     uuid: Uuid,
     output: CyclerOutput,
     format: Format,
+    options: SubscriptionOptions,
     output_sender: mpsc::Sender<SubscriberMessage>,
     id_tracker: &mpsc::Sender<id_tracker::Message>,
     responder: &mpsc::Sender<responder::Message>,
@@ -283,7 +332,7 @@ async fn add_subscription(
 ) {
     match manager
         .outputs_to_subscribers
-        .entry((output.clone(), format))
+        .entry((output.clone(), format, options))
     {
         Entry::Occupied(mut entry) => {
             entry.get_mut().insert(uuid, output_sender);
@@ -293,6 +342,7 @@ async fn add_subscription(
                 if let Some(subscription_id) = subscribe(
                     output.clone(),
                     format,
+                    options,
                     vec![output_sender.clone()],
                     id_tracker,
                     responder,
@@ -302,7 +352,7 @@ async fn add_subscription(
                 {
                     manager
                         .ids_to_outputs
-                        .insert(subscription_id, (output, format));
+                        .insert(subscription_id, (output, format, options));
                 }
             };
             entry.insert(HashMap::new()).insert(uuid, output_sender);
@@ -313,6 +363,7 @@ async fn add_subscription(
 async fn subscribe(
     output: CyclerOutput,
     format: Format,
+    options: SubscriptionOptions,
     subscribers: Vec<mpsc::Sender<SubscriberMessage>>,
     id_tracker: &mpsc::Sender<id_tracker::Message>,
     responder: &mpsc::Sender<responder::Message>,
@@ -339,6 +390,7 @@ async fn subscribe(
         cycler_instance: output.cycler.to_string(),
         path,
         format,
+        options,
     });
     if let Err(error) = requester.send(request).await {
         error!("{error}");
@@ -399,3 +451,75 @@ async fn unsubscribe(
         };
     });
 }
+
+async fn set_injection(
+    output: CyclerOutput,
+    value: Value,
+    id_tracker: &mpsc::Sender<id_tracker::Message>,
+    responder: &mpsc::Sender<responder::Message>,
+    requester: &mpsc::Sender<Request>,
+) -> Result<()> {
+    let Output::Main { path } = output.output else {
+        bail!("only main outputs can be injected");
+    };
+    let message_id = get_message_id(id_tracker).await;
+    let (response_sender, response_receiver) = oneshot::channel();
+    responder
+        .send(responder::Message::Await {
+            id: message_id,
+            response_sender,
+        })
+        .await?;
+    requester
+        .send(Request::Injections(InjectionsRequest::Set {
+            id: message_id,
+            cycler_instance: output.cycler.to_string(),
+            path: format!("main_outputs.{path}"),
+            data: value,
+        }))
+        .await?;
+    spawn(async move {
+        let response = response_receiver.await.unwrap();
+        match response {
+            Response::Injection(Ok(())) => {}
+            Response::Injection(Err(error)) => error!("Failed to inject: {error}"),
+            response => error!("unexpected response: {response:?}"),
+        };
+    });
+    Ok(())
+}
+
+async fn unset_injection(
+    output: CyclerOutput,
+    id_tracker: &mpsc::Sender<id_tracker::Message>,
+    responder: &mpsc::Sender<responder::Message>,
+    requester: &mpsc::Sender<Request>,
+) -> Result<()> {
+    let Output::Main { path } = output.output else {
+        bail!("only main outputs can be injected");
+    };
+    let message_id = get_message_id(id_tracker).await;
+    let (response_sender, response_receiver) = oneshot::channel();
+    responder
+        .send(responder::Message::Await {
+            id: message_id,
+            response_sender,
+        })
+        .await?;
+    requester
+        .send(Request::Injections(InjectionsRequest::Unset {
+            id: message_id,
+            cycler_instance: output.cycler.to_string(),
+            path: format!("main_outputs.{path}"),
+        }))
+        .await?;
+    spawn(async move {
+        let response = response_receiver.await.unwrap();
+        match response {
+            Response::Injection(Ok(())) => {}
+            Response::Injection(Err(error)) => error!("Failed to unset injection: {error}"),
+            response => error!("unexpected response: {response:?}"),
+        };
+    });
+    Ok(())
+}
@@ -11,8 +11,8 @@ use crate::{
         responder::{Message, Response},
     },
     messages::{
-        BinaryOutputsResponse, BinaryResponse, ParametersResponse, TextualOutputsResponse,
-        TextualResponse,
+        BinaryOutputsResponse, BinaryResponse, InjectionsResponse, ParametersResponse,
+        TextualOutputsResponse, TextualResponse,
     },
 };
 
@@ -90,7 +90,10 @@ pub async fn receiver(
                             ParametersResponse::LoadFromDisk { id: _, result: _ } => todo!(),
                             ParametersResponse::StoreToDisk { id: _, result: _ } => todo!(),
                         },
-                        message => todo!("unimplemented message {message:?}"),
+                        TextualResponse::Injections(
+                            InjectionsResponse::Set { id, result }
+                            | InjectionsResponse::Unset { id, result },
+                        ) => respond(&responder, id, Response::Injection(result)).await,
                     }
                 }
                 tungstenite::Message::Close(close_frame) => {
@@ -20,6 +20,7 @@ pub enum Message {
 #[derive(Debug)]
 pub enum Response {
     Fields(Fields),
+    Injection(Result<(), Reason>),
     ParameterFields(BTreeSet<Path>),
     Subscribe(Result<(), Reason>),
     Unsubscribe(Result<(), Reason>),
@@ -1,4 +1,8 @@
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    num::{NonZeroU32, NonZeroUsize},
+    time::Duration,
+};
 
 use parameters::directory::Scope;
 use serde::{Deserialize, Serialize};
@@ -81,6 +85,8 @@ pub enum OutputsRequest {
         cycler_instance: CyclerInstance,
         path: Path,
         format: Format,
+        #[serde(default)]
+        options: SubscriptionOptions,
     },
     Unsubscribe {
         id: usize,
@@ -89,6 +95,22 @@ pub enum OutputsRequest {
     UnsubscribeEverything,
 }
 
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct SubscriptionOptions {
+    pub minimum_interval: Option<Duration>,
+    pub decimation: Option<NonZeroUsize>,
+    pub send_on_change: bool,
+    pub jpeg_preview: Option<JpegPreview>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct JpegPreview {
+    /// Divisor of the image width and height
+    pub scale: NonZeroU32,
+    /// Between 1 and 100
+    pub quality: u8,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum TextualOutputsResponse {
     GetFields {
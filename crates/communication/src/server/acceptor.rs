@@ -3,6 +3,7 @@ use std::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
+use framework::CommunicationAuthentication;
 use log::error;
 use tokio::{
     net::{TcpListener, ToSocketAddrs},
@@ -17,7 +18,7 @@ use crate::messages::ParametersRequest;
 use super::{
     client_request::ClientRequest,
     connection::{connection, ConnectionError},
-    outputs,
+    injections, outputs,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -32,8 +33,10 @@ pub enum AcceptError {
 
 pub fn acceptor(
     addresses: impl ToSocketAddrs + Send + Sync + 'static,
+    authentication: Option<CommunicationAuthentication>,
     keep_running: CancellationToken,
     outputs_sender: Sender<outputs::Request>,
+    injections_sender: Sender<injections::Request>,
     parameters_sender: Sender<ClientRequest<ParametersRequest>>,
 ) -> JoinHandle<Result<(), AcceptError>> {
     let next_client_id = AtomicUsize::default();
@@ -53,9 +56,11 @@ pub fn acceptor(
             let client_id = next_client_id.fetch_add(1, Ordering::SeqCst);
             connection(
                 stream,
+                authentication.clone(),
                 keep_running.clone(),
                 error_sender.clone(),
                 outputs_sender.clone(),
+                injections_sender.clone(),
                 parameters_sender.clone(),
                 client_id,
             );
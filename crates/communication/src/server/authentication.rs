@@ -0,0 +1,201 @@
+use framework::{CommunicationAuthentication, CommunicationRole};
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{
+        Callback, ErrorResponse, Request as HandshakeRequest, Response as HandshakeResponse,
+    },
+    http::StatusCode,
+};
+
+use crate::messages::{
+    InjectionsRequest, InjectionsResponse, ParametersRequest, ParametersResponse, Request,
+    TextualResponse,
+};
+
+pub struct Authenticator<'a> {
+    pub authentication: Option<&'a CommunicationAuthentication>,
+    pub role: &'a mut Option<CommunicationRole>,
+}
+
+impl Callback for Authenticator<'_> {
+    fn on_request(
+        self,
+        request: &HandshakeRequest,
+        response: HandshakeResponse,
+    ) -> Result<HandshakeResponse, ErrorResponse> {
+        let Some(role) = authenticate(self.authentication, request) else {
+            let mut response = ErrorResponse::new(Some("invalid or missing token".to_string()));
+            *response.status_mut() = StatusCode::UNAUTHORIZED;
+            return Err(response);
+        };
+        *self.role = Some(role);
+        Ok(response)
+    }
+}
+
+fn authenticate(
+    authentication: Option<&CommunicationAuthentication>,
+    request: &HandshakeRequest,
+) -> Option<CommunicationRole> {
+    match authentication {
+        Some(authentication) => authentication.role_of(token_of(request)),
+        None => Some(CommunicationRole::Write),
+    }
+}
+
+/// Token from the query of the WebSocket URL, e.g. `ws://10.1.24.42:1337/?token=secret`
+fn token_of(request: &HandshakeRequest) -> Option<&str> {
+    request
+        .uri()
+        .query()?
+        .split('&')
+        .find_map(|pair| match pair.split_once('=') {
+            Some(("token", token)) => Some(token),
+            _ => None,
+        })
+}
+
+pub fn rejection(role: CommunicationRole, request: &Request) -> Option<TextualResponse> {
+    if role == CommunicationRole::Write {
+        return None;
+    }
+    let reason = || "client is not permitted to write".to_string();
+    match request {
+        Request::Injections(InjectionsRequest::Set { id, .. }) => {
+            Some(TextualResponse::Injections(InjectionsResponse::Set {
+                id: *id,
+                result: Err(reason()),
+            }))
+        }
+        Request::Injections(InjectionsRequest::Unset { id, .. }) => {
+            Some(TextualResponse::Injections(InjectionsResponse::Unset {
+                id: *id,
+                result: Err(reason()),
+            }))
+        }
+        Request::Parameters(ParametersRequest::Update { id, .. }) => {
+            Some(TextualResponse::Parameters(ParametersResponse::Update {
+                id: *id,
+                result: Err(reason()),
+            }))
+        }
+        Request::Parameters(ParametersRequest::LoadFromDisk { id }) => Some(
+            TextualResponse::Parameters(ParametersResponse::LoadFromDisk {
+                id: *id,
+                result: Err(reason()),
+            }),
+        ),
+        Request::Parameters(ParametersRequest::StoreToDisk { id, .. }) => Some(
+            TextualResponse::Parameters(ParametersResponse::StoreToDisk {
+                id: *id,
+                result: Err(reason()),
+            }),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use parameters::directory::{Id, Location, Scope};
+
+    use super::*;
+
+    fn authentication() -> CommunicationAuthentication {
+        CommunicationAuthentication {
+            tokens: HashMap::from([
+                ("coach".to_string(), CommunicationRole::Write),
+                ("spectator".to_string(), CommunicationRole::ReadOnly),
+            ]),
+            anonymous_role: None,
+        }
+    }
+
+    fn request(uri: &str) -> HandshakeRequest {
+        HandshakeRequest::builder().uri(uri).body(()).unwrap()
+    }
+
+    #[test]
+    fn clients_may_write_without_authentication() {
+        assert_eq!(
+            authenticate(None, &request("/")),
+            Some(CommunicationRole::Write)
+        );
+    }
+
+    #[test]
+    fn tokens_grant_their_roles() {
+        let authentication = authentication();
+
+        assert_eq!(
+            authenticate(Some(&authentication), &request("/?token=coach")),
+            Some(CommunicationRole::Write)
+        );
+        assert_eq!(
+            authenticate(Some(&authentication), &request("/?other=1&token=spectator")),
+            Some(CommunicationRole::ReadOnly)
+        );
+    }
+
+    #[test]
+    fn unknown_or_missing_tokens_are_rejected() {
+        let authentication = authentication();
+
+        let mut role = None;
+        let response = Authenticator {
+            authentication: Some(&authentication),
+            role: &mut role,
+        }
+        .on_request(&request("/?token=guess"), HandshakeResponse::default())
+        .unwrap_err();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(role, None);
+        assert_eq!(authenticate(Some(&authentication), &request("/")), None);
+    }
+
+    #[test]
+    fn anonymous_clients_get_anonymous_role() {
+        let authentication = CommunicationAuthentication {
+            anonymous_role: Some(CommunicationRole::ReadOnly),
+            ..authentication()
+        };
+
+        assert_eq!(
+            authenticate(Some(&authentication), &request("/")),
+            Some(CommunicationRole::ReadOnly)
+        );
+        assert_eq!(
+            authenticate(Some(&authentication), &request("/?token=guess")),
+            None
+        );
+    }
+
+    #[test]
+    fn read_only_clients_are_rejected_when_writing() {
+        let store_to_disk = Request::Parameters(ParametersRequest::StoreToDisk {
+            id: 42,
+            scope: Scope {
+                location: Location::All,
+                id: Id::Head,
+            },
+            path: "a.b".to_string(),
+        });
+        let get_current = Request::Parameters(ParametersRequest::GetCurrent {
+            id: 42,
+            path: "a.b".to_string(),
+        });
+
+        assert_eq!(
+            rejection(CommunicationRole::ReadOnly, &store_to_disk),
+            Some(TextualResponse::Parameters(
+                ParametersResponse::StoreToDisk {
+                    id: 42,
+                    result: Err("client is not permitted to write".to_string()),
+                }
+            ))
+        );
+        assert_eq!(rejection(CommunicationRole::ReadOnly, &get_current), None);
+        assert_eq!(rejection(CommunicationRole::Write, &store_to_disk), None);
+    }
+}
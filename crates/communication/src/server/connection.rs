@@ -1,5 +1,6 @@
 use std::{io, net::SocketAddr};
 
+use framework::CommunicationAuthentication;
 use futures_util::StreamExt;
 use log::error;
 use tokio::{
@@ -7,12 +8,15 @@ use tokio::{
     select, spawn,
     sync::mpsc::{channel, Sender, UnboundedSender},
 };
-use tokio_tungstenite::accept_async;
+use tokio_tungstenite::accept_hdr_async;
 use tokio_util::sync::CancellationToken;
 
 use crate::messages::ParametersRequest;
 
-use super::{client_request::ClientRequest, outputs, receiver::receiver, sender::sender};
+use super::{
+    authentication::Authenticator, client_request::ClientRequest, injections, outputs,
+    receiver::receiver, sender::sender,
+};
 
 #[derive(Debug, thiserror::Error)]
 pub enum ConnectionError {
@@ -46,11 +50,14 @@ pub enum ReceiverOrSenderError {
     WebSocketMessageNotWritten(tokio_tungstenite::tungstenite::Error),
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn connection(
     stream: TcpStream,
+    authentication: Option<CommunicationAuthentication>,
     keep_running: CancellationToken,
     connection_error_sender: UnboundedSender<ConnectionError>,
     outputs_sender: Sender<outputs::Request>,
+    injections_sender: Sender<injections::Request>,
     parameters_sender: Sender<ClientRequest<ParametersRequest>>,
     client_id: usize,
 ) {
@@ -65,8 +72,13 @@ pub fn connection(
             }
         };
 
+        let mut role = None;
+        let authenticator = Authenticator {
+            authentication: authentication.as_ref(),
+            role: &mut role,
+        };
         let websocket_stream = select! {
-            result = accept_async(stream) => match result {
+            result = accept_hdr_async(stream, authenticator) => match result {
                 Ok(websocket_stream) => websocket_stream,
                 Err(source) => {
                     connection_error_sender
@@ -78,6 +90,7 @@ pub fn connection(
             _ = keep_running.cancelled() => return,
         };
 
+        let role = role.expect("role should be set by successful handshake");
         let (writer, reader) = websocket_stream.split();
 
         let (receiver_or_sender_error_sender, mut receiver_or_sender_error_receiver) = channel(1);
@@ -90,8 +103,10 @@ pub fn connection(
             keep_running,
             keep_only_self_running.clone(),
             client_id,
+            role,
             response_sender,
             outputs_sender,
+            injections_sender,
             parameters_sender,
         ));
 
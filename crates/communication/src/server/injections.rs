@@ -0,0 +1,385 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use framework::Writer;
+use serde_json::Value;
+use tokio::{spawn, sync::mpsc::Receiver, task::JoinHandle};
+
+use crate::{
+    messages::{InjectionsRequest, InjectionsResponse, Path, Response, TextualResponse},
+    server::{client::Client, client_request::ClientRequest},
+};
+
+pub enum Request {
+    ClientRequest(ClientRequest<InjectionsRequest>),
+    RegisterCycler {
+        cycler_instance: String,
+        validate_injection: ValidateInjection,
+        injections_writer: Writer<BTreeMap<Path, Value>>,
+    },
+}
+
+pub type ValidateInjection = fn(&str, &Value) -> Result<(), String>;
+
+type Injections = HashMap<(String, Path), (Client, Value)>;
+
+type Cyclers = HashMap<String, (ValidateInjection, Writer<BTreeMap<Path, Value>>)>;
+
+pub fn injections(mut request_receiver: Receiver<Request>) -> JoinHandle<()> {
+    spawn(async move {
+        let mut cyclers = Cyclers::new();
+        let mut injections = Injections::new();
+
+        while let Some(request) = request_receiver.recv().await {
+            match request {
+                Request::ClientRequest(request) => {
+                    handle_request(request, &cyclers, &mut injections).await
+                }
+                Request::RegisterCycler {
+                    cycler_instance,
+                    validate_injection,
+                    injections_writer,
+                } => {
+                    cyclers.insert(cycler_instance, (validate_injection, injections_writer));
+                }
+            }
+        }
+    })
+}
+
+async fn handle_request(
+    request: ClientRequest<InjectionsRequest>,
+    cyclers: &Cyclers,
+    injections: &mut Injections,
+) {
+    match &request.request {
+        InjectionsRequest::Set {
+            id,
+            cycler_instance,
+            path,
+            data,
+        } => {
+            let result = match cyclers.get(cycler_instance) {
+                Some((validate_injection, injections_writer)) => validate_injection(path, data)
+                    .map(|()| {
+                        injections.insert(
+                            (cycler_instance.clone(), path.clone()),
+                            (request.client.clone(), data.clone()),
+                        );
+                        write_injections(cycler_instance, injections, injections_writer);
+                    }),
+                None => Err(format!("unknown cycler_instance {cycler_instance:?}")),
+            };
+            respond(&request, InjectionsResponse::Set { id: *id, result }).await;
+        }
+        InjectionsRequest::Unset {
+            id,
+            cycler_instance,
+            path,
+        } => {
+            let result = match cyclers.get(cycler_instance) {
+                Some((_validate_injection, injections_writer)) => {
+                    match injections.remove(&(cycler_instance.clone(), path.clone())) {
+                        Some(_) => {
+                            write_injections(cycler_instance, injections, injections_writer);
+                            Ok(())
+                        }
+                        None => Err(format!("path {path:?} is not injected")),
+                    }
+                }
+                None => Err(format!("unknown cycler_instance {cycler_instance:?}")),
+            };
+            respond(&request, InjectionsResponse::Unset { id: *id, result }).await;
+        }
+        InjectionsRequest::UnsetEverything => {
+            let mut changed_cycler_instances = BTreeSet::new();
+            injections.retain(|(cycler_instance, _path), (client, _value)| {
+                if client == &request.client {
+                    changed_cycler_instances.insert(cycler_instance.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            for cycler_instance in changed_cycler_instances {
+                if let Some((_validate_injection, injections_writer)) =
+                    cyclers.get(&cycler_instance)
+                {
+                    write_injections(&cycler_instance, injections, injections_writer);
+                }
+            }
+        }
+    }
+}
+
+fn write_injections(
+    cycler_instance: &str,
+    injections: &Injections,
+    injections_writer: &Writer<BTreeMap<Path, Value>>,
+) {
+    let injections_of_cycler = injections
+        .iter()
+        .filter(|((other_cycler_instance, _path), _)| other_cycler_instance == cycler_instance)
+        .map(|((_cycler_instance, path), (_client, value))| (path.clone(), value.clone()))
+        .collect();
+    let mut injections_slot = injections_writer.next();
+    *injections_slot = injections_of_cycler;
+}
+
+async fn respond(request: &ClientRequest<InjectionsRequest>, response: InjectionsResponse) {
+    request
+        .client
+        .response_sender
+        .send(Response::Textual(TextualResponse::Injections(response)))
+        .await
+        .expect("receiver should always wait for all senders");
+}
+
+#[cfg(test)]
+mod tests {
+    use framework::{multiple_buffer_with_slots, Reader};
+    use tokio::sync::mpsc::{channel, Sender};
+
+    use super::*;
+
+    async fn register_cycler(
+        request_sender: &Sender<Request>,
+        cycler_instance: &str,
+    ) -> Reader<BTreeMap<Path, Value>> {
+        let (injections_writer, injections_reader) = multiple_buffer_with_slots([
+            Default::default(),
+            Default::default(),
+            Default::default(),
+        ]);
+        request_sender
+            .send(Request::RegisterCycler {
+                cycler_instance: cycler_instance.to_string(),
+                validate_injection,
+                injections_writer,
+            })
+            .await
+            .unwrap();
+        injections_reader
+    }
+
+    fn validate_injection(path: &str, value: &Value) -> Result<(), String> {
+        match path {
+            "main_outputs.a" => serde_json::from_value::<u32>(value.clone())
+                .map(|_| ())
+                .map_err(|error| format!("failed to deserialize: {error}")),
+            _ => Err(format!("path {path:?} cannot be injected")),
+        }
+    }
+
+    async fn send(request_sender: &Sender<Request>, request: InjectionsRequest, client: &Client) {
+        request_sender
+            .send(Request::ClientRequest(ClientRequest {
+                request,
+                client: client.clone(),
+            }))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn terminates_on_request_sender_drop() {
+        let (request_sender, request_receiver) = channel(1);
+        let injections_task = injections(request_receiver);
+
+        drop(request_sender);
+        injections_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn injected_values_are_written_until_unset() {
+        let (request_sender, request_receiver) = channel(1);
+        let injections_task = injections(request_receiver);
+        let injections_reader = register_cycler(&request_sender, "CyclerInstance").await;
+
+        let (response_sender, mut response_receiver) = channel(1);
+        let client = Client {
+            id: 1337,
+            response_sender,
+        };
+        send(
+            &request_sender,
+            InjectionsRequest::Set {
+                id: 42,
+                cycler_instance: "CyclerInstance".to_string(),
+                path: "main_outputs.a".to_string(),
+                data: Value::from(7),
+            },
+            &client,
+        )
+        .await;
+        assert_eq!(
+            response_receiver.recv().await.unwrap(),
+            Response::Textual(TextualResponse::Injections(InjectionsResponse::Set {
+                id: 42,
+                result: Ok(()),
+            })),
+        );
+        assert_eq!(
+            *injections_reader.next(),
+            [("main_outputs.a".to_string(), Value::from(7))].into(),
+        );
+
+        send(
+            &request_sender,
+            InjectionsRequest::Unset {
+                id: 43,
+                cycler_instance: "CyclerInstance".to_string(),
+                path: "main_outputs.a".to_string(),
+            },
+            &client,
+        )
+        .await;
+        assert_eq!(
+            response_receiver.recv().await.unwrap(),
+            Response::Textual(TextualResponse::Injections(InjectionsResponse::Unset {
+                id: 43,
+                result: Ok(()),
+            })),
+        );
+        assert!(injections_reader.next().is_empty());
+
+        drop(request_sender);
+        injections_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unknown_cycler_instances_paths_and_mistyped_values_result_in_error() {
+        let (request_sender, request_receiver) = channel(1);
+        let injections_task = injections(request_receiver);
+        let injections_reader = register_cycler(&request_sender, "CyclerInstance").await;
+
+        let (response_sender, mut response_receiver) = channel(1);
+        let client = Client {
+            id: 1337,
+            response_sender,
+        };
+        send(
+            &request_sender,
+            InjectionsRequest::Set {
+                id: 42,
+                cycler_instance: "UnknownCyclerInstance".to_string(),
+                path: "main_outputs.a".to_string(),
+                data: Value::from(7),
+            },
+            &client,
+        )
+        .await;
+        assert_eq!(
+            response_receiver.recv().await.unwrap(),
+            Response::Textual(TextualResponse::Injections(InjectionsResponse::Set {
+                id: 42,
+                result: Err("unknown cycler_instance \"UnknownCyclerInstance\"".to_string()),
+            })),
+        );
+
+        send(
+            &request_sender,
+            InjectionsRequest::Set {
+                id: 43,
+                cycler_instance: "CyclerInstance".to_string(),
+                path: "additional_outputs.b".to_string(),
+                data: Value::from(7),
+            },
+            &client,
+        )
+        .await;
+        assert_eq!(
+            response_receiver.recv().await.unwrap(),
+            Response::Textual(TextualResponse::Injections(InjectionsResponse::Set {
+                id: 43,
+                result: Err("path \"additional_outputs.b\" cannot be injected".to_string()),
+            })),
+        );
+
+        send(
+            &request_sender,
+            InjectionsRequest::Set {
+                id: 44,
+                cycler_instance: "CyclerInstance".to_string(),
+                path: "main_outputs.a".to_string(),
+                data: Value::from("seven"),
+            },
+            &client,
+        )
+        .await;
+        assert_eq!(
+            response_receiver.recv().await.unwrap(),
+            Response::Textual(TextualResponse::Injections(InjectionsResponse::Set {
+                id: 44,
+                result: Err(
+                    "failed to deserialize: invalid type: string \"seven\", expected u32"
+                        .to_string()
+                ),
+            })),
+        );
+
+        send(
+            &request_sender,
+            InjectionsRequest::Unset {
+                id: 45,
+                cycler_instance: "CyclerInstance".to_string(),
+                path: "main_outputs.a".to_string(),
+            },
+            &client,
+        )
+        .await;
+        assert_eq!(
+            response_receiver.recv().await.unwrap(),
+            Response::Textual(TextualResponse::Injections(InjectionsResponse::Unset {
+                id: 45,
+                result: Err("path \"main_outputs.a\" is not injected".to_string()),
+            })),
+        );
+        assert!(injections_reader.next().is_empty());
+
+        drop(request_sender);
+        injections_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn unset_everything_only_removes_injections_of_client() {
+        let (request_sender, request_receiver) = channel(1);
+        let injections_task = injections(request_receiver);
+        let first_reader = register_cycler(&request_sender, "First").await;
+        let second_reader = register_cycler(&request_sender, "Second").await;
+
+        let (response_sender, mut response_receiver) = channel(2);
+        let client = Client {
+            id: 1337,
+            response_sender: response_sender.clone(),
+        };
+        let other_client = Client {
+            id: 1338,
+            response_sender,
+        };
+        for (id, cycler_instance, client) in [(42, "First", &client), (43, "Second", &other_client)]
+        {
+            send(
+                &request_sender,
+                InjectionsRequest::Set {
+                    id,
+                    cycler_instance: cycler_instance.to_string(),
+                    path: "main_outputs.a".to_string(),
+                    data: Value::from(id),
+                },
+                client,
+            )
+            .await;
+            response_receiver.recv().await.unwrap();
+        }
+
+        send(&request_sender, InjectionsRequest::UnsetEverything, &client).await;
+        drop(request_sender);
+        injections_task.await.unwrap();
+
+        assert!(first_reader.next().is_empty());
+        assert_eq!(
+            *second_reader.next(),
+            [("main_outputs.a".to_string(), Value::from(43))].into(),
+        );
+    }
+}
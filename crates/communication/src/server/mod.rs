@@ -1,7 +1,9 @@
 mod acceptor;
+mod authentication;
 mod client;
 mod client_request;
 mod connection;
+mod injections;
 mod outputs;
 pub mod parameters; // TODO: revert to private visibility after behavior simulator is refactored to not access private functionality anymore
 mod receiver;
@@ -1,12 +1,17 @@
-use std::collections::BTreeSet;
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeSet},
+    hash::{Hash, Hasher},
+    time::Instant,
+};
 
 use tokio::sync::mpsc::Sender;
 
-use crate::messages::{Format, OutputsRequest, Path};
+use crate::messages::{Format, OutputsRequest, Path, SubscriptionOptions};
 
 use super::client_request::ClientRequest;
 
 pub mod provider;
+mod rgb_image_capture;
 pub mod router;
 
 #[derive(Debug)]
@@ -24,4 +29,52 @@ struct Subscription {
     pub path: Path,
     pub format: Format,
     pub once: bool,
+    pub options: SubscriptionOptions,
+    pub remaining_decimated_cycles: usize,
+    pub last_sent_time: Option<Instant>,
+    pub last_sent_hash: Option<u64>,
+}
+
+impl Subscription {
+    fn new(path: Path, format: Format, once: bool, options: SubscriptionOptions) -> Self {
+        Self {
+            path,
+            format,
+            once,
+            options,
+            remaining_decimated_cycles: 0,
+            last_sent_time: None,
+            last_sent_hash: None,
+        }
+    }
+
+    fn is_due(&mut self, now: Instant) -> bool {
+        let is_decimated = self.remaining_decimated_cycles > 0;
+        self.remaining_decimated_cycles = if is_decimated {
+            self.remaining_decimated_cycles - 1
+        } else {
+            self.options
+                .decimation
+                .map_or(0, |decimation| decimation.get() - 1)
+        };
+        let is_too_early = match (self.options.minimum_interval, self.last_sent_time) {
+            (Some(minimum_interval), Some(last_sent_time)) => {
+                now.duration_since(last_sent_time) < minimum_interval
+            }
+            _ => false,
+        };
+        !is_decimated && !is_too_early
+    }
+
+    fn is_unchanged<T: Hash>(&mut self, data: impl FnOnce() -> T) -> bool {
+        if !self.options.send_on_change {
+            return false;
+        }
+        let mut hasher = DefaultHasher::new();
+        data().hash(&mut hasher);
+        let hash = hasher.finish();
+        let is_unchanged = self.last_sent_hash == Some(hash);
+        self.last_sent_hash = Some(hash);
+        is_unchanged
+    }
 }
@@ -2,13 +2,15 @@ use std::{
     collections::{hash_map::Entry, BTreeSet, HashMap, HashSet},
     num::Wrapping,
     sync::Arc,
+    time::Instant,
 };
 
 use bincode::{DefaultOptions, Options};
 use framework::{Reader, Writer};
 use futures_util::{stream::FuturesUnordered, StreamExt};
 use log::error;
-use path_serde::{PathIntrospect, PathSerialize};
+use path_serde::{serialize, PathIntrospect, PathSerialize};
+use serde::{ser::Error as _, Serialize, Serializer};
 use tokio::{
     select, spawn,
     sync::{
@@ -17,16 +19,17 @@ use tokio::{
     },
     task::JoinHandle,
 };
+use types::jpeg::JpegImage;
 
 use crate::{
     messages::{
-        BinaryOutputsResponse, BinaryResponse, Format, OutputsRequest, Response,
-        TextualDataOrBinaryReference, TextualOutputsResponse, TextualResponse,
+        BinaryOutputsResponse, BinaryResponse, Format, JpegPreview, OutputsRequest, Response,
+        SubscriptionOptions, TextualDataOrBinaryReference, TextualOutputsResponse, TextualResponse,
     },
     server::{client::Client, client_request::ClientRequest},
 };
 
-use super::{Request, Subscription};
+use super::{rgb_image_capture::RgbImageCapture, Request, Subscription};
 
 pub fn provider<Outputs>(
     outputs_sender: Sender<Request>,
@@ -96,6 +99,10 @@ async fn handle_client_request(
     fields: &BTreeSet<String>,
 ) -> SubscriptionsState {
     let is_get_next = matches!(request.request, OutputsRequest::GetNext { .. });
+    let options = match request.request {
+        OutputsRequest::Subscribe { options, .. } => options,
+        _ => SubscriptionOptions::default(),
+    };
     match request.request {
         OutputsRequest::GetFields { .. } => {
             panic!("GetFields should be answered by output router");
@@ -111,6 +118,7 @@ async fn handle_client_request(
             cycler_instance: received_cycler_instance,
             path,
             format,
+            ..
         } => {
             assert_eq!(cycler_instance, received_cycler_instance);
             if !fields.contains(&path) {
@@ -127,6 +135,24 @@ async fn handle_client_request(
                     .expect("receiver should always wait for all senders");
                 return SubscriptionsState::Unchanged;
             }
+            if let Some(JpegPreview { quality, .. }) = options.jpeg_preview {
+                if !(1..=100).contains(&quality) {
+                    request
+                        .client
+                        .response_sender
+                        .send(Response::Textual(TextualResponse::Outputs(
+                            TextualOutputsResponse::Subscribe {
+                                id,
+                                result: Err(format!(
+                                    "JPEG quality {quality} is not between 1 and 100"
+                                )),
+                            },
+                        )))
+                        .await
+                        .expect("receiver should always wait for all senders");
+                    return SubscriptionsState::Unchanged;
+                }
+            }
             match subscriptions.entry((request.client.clone(), id)) {
                 Entry::Occupied(_) => {
                     let error_message = format!("already subscribed with id {id}");
@@ -151,11 +177,7 @@ async fn handle_client_request(
                     SubscriptionsState::Unchanged
                 }
                 Entry::Vacant(entry) => {
-                    entry.insert(Subscription {
-                        path,
-                        format,
-                        once: is_get_next,
-                    });
+                    entry.insert(Subscription::new(path, format, is_get_next, options));
                     if !is_get_next {
                         request
                             .client
@@ -244,18 +266,28 @@ async fn handle_notified_output(
     let mut subscriptions_state = SubscriptionsState::Unchanged;
     {
         let output = outputs_reader.next();
+        let now = Instant::now();
         subscriptions.retain(|(client, subscription_id), subscription| {
+            if !subscription.is_due(now) {
+                return true;
+            }
             let data = match subscription.format {
                 Format::Textual => {
-                    let data = match output
-                        .serialize_path(&subscription.path, serde_json::value::Serializer)
-                    {
+                    let data = match serialize_subscribed_path(
+                        &*output,
+                        &subscription.path,
+                        subscription.options,
+                        serde_json::value::Serializer,
+                    ) {
                         Ok(data) => data,
                         Err(error) => {
                             error!("failed to serialize {:?}: {error:?}", subscription.path);
                             return true;
                         }
                     };
+                    if subscription.is_unchanged(|| data.to_string()) {
+                        return true;
+                    }
                     TextualDataOrBinaryReference::TextualData { data }
                 }
                 Format::Binary => {
@@ -264,10 +296,18 @@ async fn handle_notified_output(
                         .with_fixint_encoding()
                         .allow_trailing_bytes();
                     let mut serializer = bincode::Serializer::new(&mut data, options);
-                    if let Err(error) = output.serialize_path(&subscription.path, &mut serializer) {
+                    if let Err(error) = serialize_subscribed_path(
+                        &*output,
+                        &subscription.path,
+                        subscription.options,
+                        &mut serializer,
+                    ) {
                         error!("failed to serialize {:?}: {error:?}", subscription.path);
                         return true;
                     }
+                    if subscription.is_unchanged(|| &data) {
+                        return true;
+                    }
                     let reference_id = next_binary_reference_id.0;
                     *next_binary_reference_id += 1;
                     if subscription.once {
@@ -284,6 +324,7 @@ async fn handle_notified_output(
                     TextualDataOrBinaryReference::BinaryReference { reference_id }
                 }
             };
+            subscription.last_sent_time = Some(now);
             if subscription.once {
                 textual_get_next_items.insert((client.clone(), *subscription_id), data);
                 subscriptions_state = SubscriptionsState::Changed;
@@ -347,16 +388,71 @@ async fn handle_notified_output(
     subscriptions_state
 }
 
+fn serialize_subscribed_path<S>(
+    output: &impl PathSerialize,
+    path: &str,
+    options: SubscriptionOptions,
+    serializer: S,
+) -> Result<S::Ok, serialize::Error<S::Error>>
+where
+    S: Serializer,
+{
+    match options.jpeg_preview {
+        Some(jpeg_preview) => serialize_jpeg_preview(output, path, jpeg_preview, serializer),
+        None => output.serialize_path(path, serializer),
+    }
+}
+
+fn serialize_jpeg_preview<S>(
+    output: &impl PathSerialize,
+    path: &str,
+    jpeg_preview: JpegPreview,
+    serializer: S,
+) -> Result<S::Ok, serialize::Error<S::Error>>
+where
+    S: Serializer,
+{
+    let mut capture = RgbImageCapture::default();
+    output
+        .serialize_path(path, &mut capture)
+        .map_err(|error| match error {
+            serialize::Error::SerializationFailed(error) => {
+                serialize::Error::SerializationFailed(S::Error::custom(error))
+            }
+            serialize::Error::PathDoesNotExist { path } => {
+                serialize::Error::PathDoesNotExist { path }
+            }
+        })?;
+    let image = capture
+        .finish()
+        .map_err(|error| serialize::Error::SerializationFailed(S::Error::custom(error)))?;
+    JpegImage::downscaled(&image, jpeg_preview.scale.get(), jpeg_preview.quality)
+        .map_err(|error| serialize::Error::SerializationFailed(S::Error::custom(error)))?
+        .serialize(serializer)
+        .map_err(serialize::Error::SerializationFailed)
+}
+
 #[cfg(test)]
 mod tests {
-    use std::{collections::BTreeSet, time::Duration};
+    use std::{
+        collections::BTreeSet,
+        num::{NonZeroU32, NonZeroUsize},
+        time::Duration,
+    };
 
     use bincode::serialize;
     use framework::multiple_buffer_with_slots;
+    use image::RgbImage;
     use path_serde::serialize;
     use serde::{Serialize, Serializer};
     use serde_json::Value;
-    use tokio::{sync::mpsc::error::TryRecvError, task::yield_now, time::timeout};
+    use tokio::{
+        sync::mpsc::{error::TryRecvError, Receiver},
+        task::yield_now,
+        time::timeout,
+    };
+
+    use types::{color::YCbCr422, ycbcr422_image::YCbCr422Image};
 
     use crate::messages::Format;
 
@@ -522,6 +618,7 @@ mod tests {
                     cycler_instance: cycler_instance.clone(),
                     path: path.clone(),
                     format,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -559,6 +656,7 @@ mod tests {
                     cycler_instance,
                     path: path.clone(),
                     format,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -623,6 +721,7 @@ mod tests {
                     cycler_instance: cycler_instance.clone(),
                     path: path.clone(),
                     format,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: 1337,
@@ -660,6 +759,7 @@ mod tests {
                     cycler_instance,
                     path: path.clone(),
                     format,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: 7331,
@@ -724,6 +824,7 @@ mod tests {
                     cycler_instance: cycler_instance.clone(),
                     path: path.clone(),
                     format,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -761,6 +862,7 @@ mod tests {
                     cycler_instance,
                     path: path.clone(),
                     format,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -878,6 +980,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -1002,6 +1105,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: 1337,
@@ -1118,6 +1222,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -1245,6 +1350,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Binary,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -1380,6 +1486,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -1418,6 +1525,7 @@ mod tests {
                     cycler_instance: cycler_instance.to_string(),
                     path: path.clone(),
                     format: Format::Textual,
+                    options: SubscriptionOptions::default(),
                 },
                 client: Client {
                     id: client_id,
@@ -1734,4 +1842,308 @@ mod tests {
         drop(request_sender);
         provider_task.await.unwrap();
     }
+
+    async fn subscribe_with_options(
+        request_sender: &Sender<ClientRequest<OutputsRequest>>,
+        response_sender: &Sender<Response>,
+        response_receiver: &mut Receiver<Response>,
+        path: &str,
+        options: SubscriptionOptions,
+    ) {
+        const SUBSCRIPTION_ID: usize = 42;
+        request_sender
+            .send(ClientRequest {
+                request: OutputsRequest::Subscribe {
+                    id: SUBSCRIPTION_ID,
+                    cycler_instance: "CyclerInstance".to_string(),
+                    path: path.to_string(),
+                    format: Format::Textual,
+                    options,
+                },
+                client: Client {
+                    id: 1337,
+                    response_sender: response_sender.clone(),
+                },
+            })
+            .await
+            .unwrap();
+        let response = response_receiver.recv().await.unwrap();
+        assert!(
+            matches!(
+                response,
+                Response::Textual(TextualResponse::Outputs(
+                    TextualOutputsResponse::Subscribe {
+                        id: SUBSCRIPTION_ID,
+                        result: Ok(()),
+                    }
+                ))
+            ),
+            "unexpected {response:?}",
+        );
+    }
+
+    async fn notify_and_try_receive(
+        outputs_changed: &Notify,
+        response_receiver: &mut Receiver<Response>,
+    ) -> Option<Response> {
+        outputs_changed.notify_one();
+        // let the provider handle the notification before the next one is coalesced into it
+        for _ in 0..10 {
+            yield_now().await;
+        }
+        match response_receiver.try_recv() {
+            Ok(response) => Some(response),
+            Err(TryRecvError::Empty) => None,
+            response => panic!("unexpected result from try_recv(): {response:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn decimated_subscription_sends_every_nth_cycle() {
+        let path = "a.b.c".to_string();
+        let outputs_changed = Arc::new(Notify::new());
+        let (_output_writer, outputs_reader) = multiple_buffer_with_slots([OutputsFake {
+            existing_fields: [(path.clone(), 42)].into(),
+        }]);
+        let (provider_task, _fields, request_sender, _subscribed_outputs_reader) =
+            get_registered_request_sender_from_provider(
+                "CyclerInstance",
+                outputs_changed.clone(),
+                outputs_reader,
+            )
+            .await;
+
+        let (response_sender, mut response_receiver) = channel(1);
+        subscribe_with_options(
+            &request_sender,
+            &response_sender,
+            &mut response_receiver,
+            &path,
+            SubscriptionOptions {
+                decimation: NonZeroUsize::new(3),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let mut sent_cycles = Vec::new();
+        for _ in 0..7 {
+            let response = notify_and_try_receive(&outputs_changed, &mut response_receiver).await;
+            sent_cycles.push(response.is_some());
+        }
+        assert_eq!(sent_cycles, [true, false, false, true, false, false, true]);
+
+        drop(request_sender);
+        provider_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn subscription_with_minimum_interval_skips_early_cycles() {
+        let path = "a.b.c".to_string();
+        let outputs_changed = Arc::new(Notify::new());
+        let (_output_writer, outputs_reader) = multiple_buffer_with_slots([OutputsFake {
+            existing_fields: [(path.clone(), 42)].into(),
+        }]);
+        let (provider_task, _fields, request_sender, _subscribed_outputs_reader) =
+            get_registered_request_sender_from_provider(
+                "CyclerInstance",
+                outputs_changed.clone(),
+                outputs_reader,
+            )
+            .await;
+
+        let (response_sender, mut response_receiver) = channel(1);
+        subscribe_with_options(
+            &request_sender,
+            &response_sender,
+            &mut response_receiver,
+            &path,
+            SubscriptionOptions {
+                minimum_interval: Some(Duration::from_secs(3600)),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let first = notify_and_try_receive(&outputs_changed, &mut response_receiver).await;
+        assert!(first.is_some());
+        let second = notify_and_try_receive(&outputs_changed, &mut response_receiver).await;
+        assert_eq!(second, None);
+
+        drop(request_sender);
+        provider_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn subscription_sending_on_change_skips_unchanged_data() {
+        let path = "a.b.c".to_string();
+        let outputs_changed = Arc::new(Notify::new());
+        let (_output_writer, outputs_reader) = multiple_buffer_with_slots([OutputsFake {
+            existing_fields: [(path.clone(), 42)].into(),
+        }]);
+        let (provider_task, _fields, request_sender, _subscribed_outputs_reader) =
+            get_registered_request_sender_from_provider(
+                "CyclerInstance",
+                outputs_changed.clone(),
+                outputs_reader,
+            )
+            .await;
+
+        let (response_sender, mut response_receiver) = channel(1);
+        subscribe_with_options(
+            &request_sender,
+            &response_sender,
+            &mut response_receiver,
+            &path,
+            SubscriptionOptions {
+                send_on_change: true,
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let first = notify_and_try_receive(&outputs_changed, &mut response_receiver).await;
+        assert_eq!(
+            first,
+            Some(Response::Textual(TextualResponse::Outputs(
+                TextualOutputsResponse::SubscribedData {
+                    items: [(
+                        42,
+                        TextualDataOrBinaryReference::TextualData {
+                            data: Value::from(42)
+                        }
+                    )]
+                    .into()
+                }
+            ))),
+        );
+        let second = notify_and_try_receive(&outputs_changed, &mut response_receiver).await;
+        assert_eq!(second, None);
+
+        drop(request_sender);
+        provider_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn jpeg_preview_subscription_sends_downscaled_jpeg() {
+        let path = "a.b.c".to_string();
+        let image = YCbCr422Image::from_ycbcr_buffer(
+            32,
+            48,
+            (0..32 * 48)
+                .map(|index| YCbCr422 {
+                    y1: index as u8,
+                    cb: (index / 32) as u8,
+                    y2: 255 - index as u8,
+                    cr: 128,
+                })
+                .collect(),
+        );
+        let outputs_changed = Arc::new(Notify::new());
+        let (_output_writer, outputs_reader) = multiple_buffer_with_slots([OutputsFake {
+            existing_fields: [(path.clone(), image.clone())].into(),
+        }]);
+        let (provider_task, _fields, request_sender, _subscribed_outputs_reader) =
+            get_registered_request_sender_from_provider(
+                "CyclerInstance",
+                outputs_changed.clone(),
+                outputs_reader,
+            )
+            .await;
+
+        let (response_sender, mut response_receiver) = channel(1);
+        subscribe_with_options(
+            &request_sender,
+            &response_sender,
+            &mut response_receiver,
+            &path,
+            SubscriptionOptions {
+                jpeg_preview: Some(JpegPreview {
+                    scale: NonZeroU32::new(2).unwrap(),
+                    quality: 20,
+                }),
+                ..Default::default()
+            },
+        )
+        .await;
+
+        let response = notify_and_try_receive(&outputs_changed, &mut response_receiver).await;
+        let preview =
+            serde_json::to_value(JpegImage::downscaled(&RgbImage::from(&image), 2, 20).unwrap())
+                .unwrap();
+        assert_eq!(
+            response,
+            Some(Response::Textual(TextualResponse::Outputs(
+                TextualOutputsResponse::SubscribedData {
+                    items: [(
+                        42,
+                        TextualDataOrBinaryReference::TextualData { data: preview }
+                    )]
+                    .into()
+                }
+            ))),
+        );
+
+        drop(request_sender);
+        provider_task.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn jpeg_preview_subscription_with_invalid_quality_results_in_error() {
+        let path = "a.b.c".to_string();
+        let outputs_changed = Arc::new(Notify::new());
+        let (_output_writer, outputs_reader) = multiple_buffer_with_slots([OutputsFake {
+            existing_fields: [(path.clone(), YCbCr422Image::zero(64, 48))].into(),
+        }]);
+        let (provider_task, _fields, request_sender, subscribed_outputs_reader) =
+            get_registered_request_sender_from_provider(
+                "CyclerInstance",
+                outputs_changed,
+                outputs_reader,
+            )
+            .await;
+
+        let (response_sender, mut response_receiver) = channel(1);
+        for quality in [0, 101] {
+            request_sender
+                .send(ClientRequest {
+                    request: OutputsRequest::Subscribe {
+                        id: 42,
+                        cycler_instance: "CyclerInstance".to_string(),
+                        path: path.clone(),
+                        format: Format::Textual,
+                        options: SubscriptionOptions {
+                            jpeg_preview: Some(JpegPreview {
+                                scale: NonZeroU32::new(2).unwrap(),
+                                quality,
+                            }),
+                            ..Default::default()
+                        },
+                    },
+                    client: Client {
+                        id: 1337,
+                        response_sender: response_sender.clone(),
+                    },
+                })
+                .await
+                .unwrap();
+            let response = response_receiver.recv().await.unwrap();
+            assert!(
+                matches!(
+                    response,
+                    Response::Textual(TextualResponse::Outputs(
+                        TextualOutputsResponse::Subscribe {
+                            id: 42,
+                            result: Err(_),
+                        }
+                    ))
+                ),
+                "unexpected {response:?}",
+            );
+        }
+        assert!(subscribed_outputs_reader.next().is_empty());
+
+        drop(request_sender);
+        provider_task.await.unwrap();
+    }
 }
@@ -0,0 +1,207 @@
+use std::fmt::Display;
+
+use image::RgbImage;
+use serde::{
+    ser::{self, Impossible, SerializeSeq, SerializeStruct},
+    Serialize, Serializer,
+};
+use thiserror::Error;
+use types::color::{Rgb, YCbCr422, YCbCr444};
+
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct Error(String);
+
+impl ser::Error for Error {
+    fn custom<T: Display>(message: T) -> Self {
+        Self(message.to_string())
+    }
+}
+
+fn not_an_image() -> Error {
+    Error("value is not a YCbCr422Image".to_string())
+}
+
+#[derive(Default)]
+pub struct RgbImageCapture {
+    field: &'static str,
+    width_422: u32,
+    height: u32,
+    image: Option<RgbImage>,
+    pixel: Option<YCbCr422>,
+    number_of_pixels: u32,
+}
+
+impl RgbImageCapture {
+    pub fn finish(self) -> Result<RgbImage, Error> {
+        self.image
+            .filter(|_| {
+                u64::from(self.number_of_pixels)
+                    == u64::from(self.width_422) * u64::from(self.height)
+            })
+            .ok_or_else(not_an_image)
+    }
+
+    fn put_pixel(&mut self, pixel: YCbCr422) -> Result<(), Error> {
+        let image = self.image.as_mut().ok_or_else(not_an_image)?;
+        let x = self
+            .number_of_pixels
+            .checked_rem(self.width_422)
+            .ok_or_else(not_an_image)?;
+        let y = self.number_of_pixels / self.width_422;
+        if y >= self.height {
+            return Err(not_an_image());
+        }
+        for (offset, luminance) in [(0, pixel.y1), (1, pixel.y2)] {
+            let color: Rgb = YCbCr444 {
+                y: luminance,
+                cb: pixel.cb,
+                cr: pixel.cr,
+            }
+            .into();
+            image.put_pixel(2 * x + offset, y, image::Rgb([color.r, color.g, color.b]));
+        }
+        self.number_of_pixels += 1;
+        Ok(())
+    }
+}
+
+macro_rules! unsupported {
+    ($($method:ident($($argument:ty),*) -> $ok:ty;)*) => {
+        $(
+            fn $method(self, $(_: $argument),*) -> Result<$ok, Self::Error> {
+                Err(not_an_image())
+            }
+        )*
+    };
+}
+
+impl Serializer for &mut RgbImageCapture {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Self;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = Impossible<(), Error>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    fn serialize_u8(self, value: u8) -> Result<(), Error> {
+        let pixel = self.pixel.as_mut().ok_or_else(not_an_image)?;
+        match self.field {
+            "y1" => pixel.y1 = value,
+            "cb" => pixel.cb = value,
+            "y2" => pixel.y2 = value,
+            "cr" => pixel.cr = value,
+            _ => return Err(not_an_image()),
+        }
+        Ok(())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<(), Error> {
+        match self.field {
+            "width_422" => self.width_422 = value,
+            "height" => self.height = value,
+            _ => return Err(not_an_image()),
+        }
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<(), Error> {
+        Err(not_an_image())
+    }
+
+    fn serialize_seq(self, _length: Option<usize>) -> Result<Self, Error> {
+        if self.field != "buffer" || self.image.is_some() {
+            return Err(not_an_image());
+        }
+        self.image = Some(RgbImage::new(2 * self.width_422, self.height));
+        Ok(self)
+    }
+
+    fn serialize_struct(self, name: &'static str, _length: usize) -> Result<Self, Error> {
+        match name {
+            "YCbCr422Image" if self.image.is_none() => {}
+            "YCbCr422" if self.image.is_some() => self.pixel = Some(YCbCr422::default()),
+            _ => return Err(not_an_image()),
+        }
+        Ok(self)
+    }
+
+    unsupported! {
+        serialize_bool(bool) -> ();
+        serialize_i8(i8) -> ();
+        serialize_i16(i16) -> ();
+        serialize_i32(i32) -> ();
+        serialize_i64(i64) -> ();
+        serialize_u16(u16) -> ();
+        serialize_u64(u64) -> ();
+        serialize_f32(f32) -> ();
+        serialize_f64(f64) -> ();
+        serialize_char(char) -> ();
+        serialize_str(&str) -> ();
+        serialize_bytes(&[u8]) -> ();
+        serialize_none() -> ();
+        serialize_unit() -> ();
+        serialize_unit_struct(&'static str) -> ();
+        serialize_unit_variant(&'static str, u32, &'static str) -> ();
+        serialize_tuple(usize) -> Self::SerializeTuple;
+        serialize_tuple_struct(&'static str, usize) -> Self::SerializeTupleStruct;
+        serialize_tuple_variant(&'static str, u32, &'static str, usize) -> Self::SerializeTupleVariant;
+        serialize_map(Option<usize>) -> Self::SerializeMap;
+        serialize_struct_variant(&'static str, u32, &'static str, usize) -> Self::SerializeStructVariant;
+    }
+}
+
+impl SerializeSeq for &mut RgbImageCapture {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl SerializeStruct for &mut RgbImageCapture {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field = key;
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        match self.pixel.take() {
+            Some(pixel) => self.put_pixel(pixel),
+            None => Ok(()),
+        }
+    }
+}
@@ -175,7 +175,7 @@ async fn handle_request(
 mod tests {
     use tokio::sync::mpsc::{channel, error::TryRecvError};
 
-    use crate::messages::Format;
+    use crate::messages::{Format, SubscriptionOptions};
 
     use super::*;
 
@@ -343,6 +343,7 @@ mod tests {
                 cycler_instance: "CyclerInstance".to_string(),
                 path: "a.b.c".to_string(),
                 format: Format::Textual,
+                options: SubscriptionOptions::default(),
             },
             client: client.clone(),
         };
@@ -1,3 +1,4 @@
+use framework::CommunicationRole;
 use futures_util::{stream::SplitStream, StreamExt};
 use serde_json::from_str;
 use tokio::{net::TcpStream, select, sync::mpsc::Sender};
@@ -8,11 +9,14 @@ use tokio_tungstenite::{
 use tokio_util::sync::CancellationToken;
 
 use crate::{
-    messages::{OutputsRequest, ParametersRequest, Request, Response},
+    messages::{InjectionsRequest, OutputsRequest, ParametersRequest, Request, Response},
     server::client_request::ClientRequest,
 };
 
-use super::{client::Client, connection::ReceiverOrSenderError, outputs};
+use super::{
+    authentication::rejection, client::Client, connection::ReceiverOrSenderError, injections,
+    outputs,
+};
 
 #[allow(clippy::too_many_arguments)]
 pub async fn receiver(
@@ -21,8 +25,10 @@ pub async fn receiver(
     keep_running: CancellationToken,
     keep_only_self_running: CancellationToken,
     client_id: usize,
+    role: CommunicationRole,
     response_sender: Sender<Response>,
     outputs_sender: Sender<outputs::Request>,
+    injections_sender: Sender<injections::Request>,
     parameters_sender: Sender<ClientRequest<ParametersRequest>>,
 ) {
     select! {
@@ -33,8 +39,10 @@ pub async fn receiver(
                     &error_sender,
                     &keep_only_self_running,
                     client_id,
+                    role,
                     &response_sender,
                     &outputs_sender,
+                    &injections_sender,
                     &parameters_sender,
                 ).await;
             }
@@ -53,6 +61,16 @@ pub async fn receiver(
         }))
         .await
         .expect("receiver should always wait for all senders");
+    injections_sender
+        .send(injections::Request::ClientRequest(ClientRequest {
+            request: InjectionsRequest::UnsetEverything,
+            client: Client {
+                id: client_id,
+                response_sender: response_sender.clone(),
+            },
+        }))
+        .await
+        .expect("receiver should always wait for all senders");
     parameters_sender
         .send(ClientRequest {
             request: ParametersRequest::UnsubscribeEverything,
@@ -65,13 +83,16 @@ pub async fn receiver(
         .expect("receiver should always wait for all senders");
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn handle_message(
     message: Result<Message, tokio_tungstenite::tungstenite::Error>,
     error_sender: &Sender<ReceiverOrSenderError>,
     keep_only_self_running: &CancellationToken,
     client_id: usize,
+    role: CommunicationRole,
     response_sender: &Sender<Response>,
     outputs_sender: &Sender<outputs::Request>,
+    injections_sender: &Sender<injections::Request>,
     parameters_sender: &Sender<ClientRequest<ParametersRequest>>,
 ) {
     let message = match message {
@@ -104,6 +125,14 @@ async fn handle_message(
                 }
             };
 
+            if let Some(response) = rejection(role, &request) {
+                response_sender
+                    .send(Response::Textual(response))
+                    .await
+                    .expect("receiver should always wait for all senders");
+                return;
+            }
+
             let client = Client {
                 id: client_id,
                 response_sender: response_sender.clone(),
@@ -118,7 +147,15 @@ async fn handle_message(
                         .await
                         .expect("receiver should always wait for all senders");
                 }
-                Request::Injections(_) => todo!(),
+                Request::Injections(request) => {
+                    injections_sender
+                        .send(injections::Request::ClientRequest(ClientRequest {
+                            request,
+                            client,
+                        }))
+                        .await
+                        .expect("receiver should always wait for all senders");
+                }
                 Request::Parameters(request) => {
                     parameters_sender
                         .send(ClientRequest { request, client })
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     fmt::Debug,
     io,
     iter::repeat_with,
@@ -8,13 +8,15 @@ use std::{
     thread::{self, JoinHandle},
 };
 
-use framework::{multiple_buffer_with_slots, Reader, Writer};
+use framework::{multiple_buffer_with_slots, CommunicationAuthentication, Reader, Writer};
 use parameters::directory::{deserialize, DirectoryError};
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
 use tokio::{
     net::ToSocketAddrs,
     runtime::{self, Runtime as TokioRuntime},
+    spawn,
     sync::{
         mpsc::{channel, Sender},
         oneshot, Notify,
@@ -26,6 +28,7 @@ use crate::server::outputs::router::router;
 
 use super::{
     acceptor::{acceptor, AcceptError},
+    injections::{self, injections, ValidateInjection},
     outputs::{provider::provider, Request},
     parameters::{storage::storage, subscriptions::subscriptions},
 };
@@ -48,6 +51,7 @@ pub struct Runtime<Parameters> {
     join_handle: JoinHandle<Result<(), StartError>>,
     runtime: Arc<TokioRuntime>,
     outputs_sender: Sender<Request>,
+    injections_sender: Sender<injections::Request>,
     parameters_reader: Reader<Parameters>,
     parameters_changed: Arc<Notify>,
 }
@@ -66,6 +70,7 @@ where
 {
     pub fn start(
         addresses: Option<impl ToSocketAddrs + Send + Sync + 'static>,
+        authentication: Option<CommunicationAuthentication>,
         parameters_directory: impl AsRef<Path> + Send + Sync + 'static,
         body_id: String,
         head_id: String,
@@ -101,6 +106,7 @@ where
                         };
 
                     let (outputs_sender, outputs_receiver) = channel(1);
+                    let (injections_sender, injections_receiver) = channel(1);
 
                     let parameters_changed = Arc::new(Notify::new());
                     let (parameters_writer, parameters_reader) = multiple_buffer_with_slots(
@@ -115,6 +121,7 @@ where
                         .send(Some((
                             inner_runtime,
                             outputs_sender.clone(),
+                            injections_sender.clone(),
                             parameters_reader.clone(),
                             parameters_changed.clone(),
                         )))
@@ -125,12 +132,15 @@ where
                     let acceptor_task = addresses.map(|addresses| {
                         acceptor(
                             addresses,
+                            authentication,
                             keep_running.clone(),
                             outputs_sender,
+                            injections_sender,
                             parameters_sender,
                         )
                     });
                     let outputs_task = router(outputs_receiver);
+                    let injections_task = injections(injections_receiver);
                     let parameters_subscriptions_task = subscriptions(
                         parameters_receiver,
                         parameters_reader,
@@ -153,6 +163,7 @@ where
                         None => None,
                     };
                     let outputs_task_result = outputs_task.await;
+                    let injections_task_result = injections_task.await;
                     let parameters_subscriptions_task_result = parameters_subscriptions_task.await;
                     let parameters_storage_task_result = parameters_storage_task.await;
 
@@ -165,6 +176,7 @@ where
                         }
                     }
                     outputs_task_result.expect("failed to join outputs task");
+                    injections_task_result.expect("failed to join injections task");
                     parameters_subscriptions_task_result.expect("failed to join outputs task");
                     parameters_storage_task_result.expect("failed to join outputs task");
 
@@ -177,7 +189,7 @@ where
             })
             .map_err(StartError::ThreadNotStarted)?;
 
-        let (runtime, outputs_sender, parameters_reader, parameters_changed) =
+        let (runtime, outputs_sender, injections_sender, parameters_reader, parameters_changed) =
             match runtime_receiver
                 .blocking_recv()
                 .expect("successful thread creation should always send into runtime_sender")
@@ -195,6 +207,7 @@ where
             join_handle,
             runtime,
             outputs_sender,
+            injections_sender,
             parameters_reader,
             parameters_changed,
         })
@@ -202,6 +215,7 @@ where
 
     pub fn join(self) -> thread::Result<Result<(), StartError>> {
         drop(self.outputs_sender);
+        drop(self.injections_sender);
         self.join_handle.join()
     }
 
@@ -224,6 +238,26 @@ where
         );
     }
 
+    pub fn register_injections(
+        &self,
+        cycler_instance: &'static str,
+        validate_injection: ValidateInjection,
+        injections_writer: Writer<BTreeMap<String, Value>>,
+    ) {
+        let injections_sender = self.injections_sender.clone();
+        let _guard = self.runtime.enter();
+        spawn(async move {
+            injections_sender
+                .send(injections::Request::RegisterCycler {
+                    cycler_instance: cycler_instance.to_string(),
+                    validate_injection,
+                    injections_writer,
+                })
+                .await
+                .expect("receiver should always wait for all senders");
+        });
+    }
+
     pub fn get_parameters_reader(&self) -> Reader<Parameters> {
         self.parameters_reader.clone()
     }
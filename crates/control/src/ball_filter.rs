@@ -60,6 +60,7 @@ pub struct CycleContext {
 #[derive(Default)]
 pub struct MainOutputs {
     pub ball_position: MainOutput<Option<BallPosition<Ground>>>,
+    pub ball_position_distribution: MainOutput<Option<MultivariateNormalDistribution<2>>>,
     pub removed_ball_positions: MainOutput<Vec<Point2<Ground>>>,
     pub hypothetical_ball_positions: MainOutput<Vec<HypotheticalBallPosition<Ground>>>,
 }
@@ -174,14 +175,23 @@ impl BallFilter {
                 .map(|hypothesis| hypothesis.selected_state(context.ball_filter_configuration))
         });
 
-        let ball_position = self
-            .find_best_hypothesis(context.ball_filter_configuration)
-            .map(|hypothesis| {
-                context.chooses_resting_model.fill_if_subscribed(|| {
-                    hypothesis.is_resting(context.ball_filter_configuration)
-                });
-                hypothesis.selected_ball_position(context.ball_filter_configuration)
-            });
+        let best_hypothesis = self.find_best_hypothesis(context.ball_filter_configuration);
+        let ball_position = best_hypothesis.map(|hypothesis| {
+            context
+                .chooses_resting_model
+                .fill_if_subscribed(|| hypothesis.is_resting(context.ball_filter_configuration));
+            hypothesis.selected_ball_position(context.ball_filter_configuration)
+        });
+        let ball_position_distribution = best_hypothesis.map(|hypothesis| {
+            let selected_state = hypothesis.selected_state(context.ball_filter_configuration);
+            MultivariateNormalDistribution {
+                mean: selected_state.mean.xy(),
+                covariance: selected_state
+                    .covariance
+                    .fixed_view::<2, 2>(0, 0)
+                    .into_owned(),
+            }
+        });
         let removed_ball_positions = removed_hypotheses
             .into_iter()
             .filter(|hypothesis| {
@@ -213,6 +223,7 @@ impl BallFilter {
 
         Ok(MainOutputs {
             ball_position: ball_position.into(),
+            ball_position_distribution: ball_position_distribution.into(),
             removed_ball_positions: removed_ball_positions.into(),
             hypothetical_ball_positions: hypothetical_ball_positions.into(),
         })
@@ -1,12 +1,26 @@
 use types::{
-    motion_command::{HeadMotion::Unstiff, MotionCommand},
+    calibration::CalibrationCommand,
+    motion_command::{HeadMotion, ImageRegion, MotionCommand},
     primary_state::PrimaryState,
     world_state::WorldState,
 };
 
-pub fn execute(world_state: &WorldState) -> Option<MotionCommand> {
+pub fn execute(
+    world_state: &WorldState,
+    calibration_command: Option<CalibrationCommand>,
+) -> Option<MotionCommand> {
     match world_state.robot.primary_state {
-        PrimaryState::Calibration => Some(MotionCommand::Stand { head: Unstiff }),
+        PrimaryState::Calibration => {
+            let head = match calibration_command {
+                Some(command) => HeadMotion::LookAt {
+                    target: command.target,
+                    image_region_target: ImageRegion::Center,
+                    camera: Some(command.camera),
+                },
+                None => HeadMotion::Unstiff,
+            };
+            Some(MotionCommand::Stand { head })
+        }
         _ => None,
     }
 }
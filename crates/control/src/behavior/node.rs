@@ -10,6 +10,7 @@ use linear_algebra::{point, Point2};
 use spl_network_messages::{GamePhase, SubState, Team};
 use types::{
     action::Action,
+    calibration::CalibrationCommand,
     cycle_time::CycleTime,
     field_dimensions::FieldDimensions,
     filtered_game_controller_state::FilteredGameControllerState,
@@ -57,6 +58,7 @@ pub struct CycleContext {
     dribble_path_obstacles_output: AdditionalOutput<Vec<PathObstacle>, "dribble_path_obstacles">,
     active_action_output: AdditionalOutput<Action, "active_action">,
 
+    calibration_command: Input<Option<CalibrationCommand>, "calibration_command?">,
     expected_referee_position: Input<Option<Point2<Field>>, "expected_referee_position?">,
     has_ground_contact: Input<bool, "has_ground_contact">,
     world_state: Input<WorldState, "world_state">,
@@ -258,7 +260,9 @@ impl Behavior {
                         *context.intercept_ball_parameters,
                         *context.maximum_step_size,
                     ),
-                    Action::Calibrate => calibrate::execute(world_state),
+                    Action::Calibrate => {
+                        calibrate::execute(world_state, context.calibration_command.copied())
+                    }
                     Action::DefendGoal => defend.goal(&mut context.path_obstacles_output),
                     Action::DefendKickOff => defend.kick_off(&mut context.path_obstacles_output),
                     Action::DefendLeft => defend.left(&mut context.path_obstacles_output),
@@ -0,0 +1,392 @@
+use std::{
+    thread::{self, JoinHandle},
+    time::SystemTime,
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+use log::{error, info};
+use nalgebra::{Rotation3, Vector3};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Builder;
+
+use calibration::{
+    corrections::Corrections,
+    measurement::Measurement,
+    residuals::{calculate_residuals_from_parameters, Residuals},
+    solve,
+};
+use context_attribute::context;
+use framework::{MainOutput, PerceptionInput};
+use hardware::{IdInterface, PathsInterface};
+use parameters::directory::{serialize, Id, Location, Scope};
+use types::{
+    calibration::{CalibrationCommand, CalibrationReport},
+    camera_position::CameraPosition,
+    cycle_time::CycleTime,
+    field_dimensions::FieldDimensions,
+    hardware::{Ids, Paths},
+    parameters::{CalibrationControllerParameters, CameraMatrixParameters},
+    primary_state::PrimaryState,
+};
+
+/// Drives the extrinsic calibration while the robot is in the calibration state
+///
+/// The head is moved through the configured targets. After settling, measurements of the
+/// commanded camera are collected for every target. Once all targets are visited, the corrections
+/// are solved and merged into the camera matrix parameters of the head. Solving and storing is
+/// done on a separate thread to not block the cycle.
+#[derive(Deserialize, Serialize)]
+pub struct CalibrationController {
+    state: State,
+    measurements: Vec<Measurement>,
+    report: Option<CalibrationReport>,
+    #[serde(skip)]
+    solving: Option<JoinHandle<CalibrationReport>>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+enum State {
+    Inactive,
+    Collecting {
+        target_index: usize,
+        since: SystemTime,
+        collected: usize,
+    },
+    Solving,
+    Finished,
+}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    measurements_bottom:
+        PerceptionInput<Option<Measurement>, "VisionBottom", "calibration_measurement?">,
+    measurements_top: PerceptionInput<Option<Measurement>, "VisionTop", "calibration_measurement?">,
+
+    cycle_time: Input<CycleTime, "cycle_time">,
+    primary_state: Input<PrimaryState, "primary_state">,
+
+    bottom_camera_matrix_parameters:
+        Parameter<CameraMatrixParameters, "camera_matrix_parameters.vision_bottom">,
+    field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    parameters: Parameter<CalibrationControllerParameters, "calibration_controller">,
+    robot_rotation_correction:
+        Parameter<Vector3<f32>, "camera_matrix_parameters.robot_rotation_correction">,
+    top_camera_matrix_parameters:
+        Parameter<CameraMatrixParameters, "camera_matrix_parameters.vision_top">,
+
+    hardware_interface: HardwareInterface,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub calibration_command: MainOutput<Option<CalibrationCommand>>,
+    pub calibration_report: MainOutput<Option<CalibrationReport>>,
+}
+
+impl CalibrationController {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {
+            state: State::Inactive,
+            measurements: Vec::new(),
+            report: None,
+            solving: None,
+        })
+    }
+
+    pub fn cycle(
+        &mut self,
+        context: CycleContext<impl IdInterface + PathsInterface>,
+    ) -> Result<MainOutputs> {
+        self.finish_solving();
+
+        let now = context.cycle_time.start_time;
+        if *context.primary_state != PrimaryState::Calibration {
+            self.state = State::Inactive;
+            self.measurements.clear();
+            return Ok(MainOutputs {
+                calibration_command: None.into(),
+                calibration_report: self.report.clone().into(),
+            });
+        }
+
+        if let State::Inactive = self.state {
+            info!("starting calibration");
+            self.state = State::Collecting {
+                target_index: 0,
+                since: now,
+                collected: 0,
+            };
+            self.report = None;
+            self.solving = None;
+        }
+
+        if let State::Collecting {
+            target_index,
+            since,
+            collected,
+        } = self.state
+        {
+            self.state = match context.parameters.targets.get(target_index) {
+                Some(command) => self.collect(&context, *command, target_index, since, collected),
+                None => self.start_solving(&context),
+            };
+        }
+
+        let calibration_command = match self.state {
+            State::Collecting { target_index, .. } => {
+                context.parameters.targets.get(target_index).copied()
+            }
+            State::Inactive | State::Solving | State::Finished => None,
+        };
+        Ok(MainOutputs {
+            calibration_command: calibration_command.into(),
+            calibration_report: self.report.clone().into(),
+        })
+    }
+
+    fn collect(
+        &mut self,
+        context: &CycleContext<impl IdInterface + PathsInterface>,
+        command: CalibrationCommand,
+        target_index: usize,
+        since: SystemTime,
+        collected: usize,
+    ) -> State {
+        let now = context.cycle_time.start_time;
+        let settled_since = since + context.parameters.settle_duration;
+        let measurements = match command.camera {
+            CameraPosition::Top => &context.measurements_top,
+            CameraPosition::Bottom => &context.measurements_bottom,
+        };
+        let new_measurements: Vec<_> = measurements
+            .persistent
+            .range(settled_since..)
+            .flat_map(|(_time, measurements)| measurements.iter().flatten())
+            .map(|measurement| (*measurement).clone())
+            .collect();
+        let collected = collected + new_measurements.len();
+        self.measurements.extend(new_measurements);
+
+        let next_target = State::Collecting {
+            target_index: target_index + 1,
+            since: now,
+            collected: 0,
+        };
+        if collected >= context.parameters.measurements_per_target {
+            next_target
+        } else if now
+            .duration_since(settled_since)
+            .is_ok_and(|duration| duration >= context.parameters.collect_timeout)
+        {
+            error!("skipping calibration target {target_index}, only {collected} measurements");
+            next_target
+        } else {
+            State::Collecting {
+                target_index,
+                since,
+                collected,
+            }
+        }
+    }
+
+    fn start_solving(
+        &mut self,
+        context: &CycleContext<impl IdInterface + PathsInterface>,
+    ) -> State {
+        let measurements = std::mem::take(&mut self.measurements);
+        let field_dimensions = context.field_dimensions.clone();
+        let top = context.top_camera_matrix_parameters.clone();
+        let bottom = context.bottom_camera_matrix_parameters.clone();
+        let robot_rotation_correction = *context.robot_rotation_correction;
+        let paths = context.hardware_interface.get_paths();
+        let ids = context.hardware_interface.get_ids();
+        let solving = thread::Builder::new()
+            .name("calibration".to_string())
+            .spawn(move || {
+                solve_and_store(
+                    measurements,
+                    &field_dimensions,
+                    &top,
+                    &bottom,
+                    robot_rotation_correction,
+                    &paths,
+                    &ids,
+                )
+            });
+        match solving {
+            Ok(solving) => {
+                self.solving = Some(solving);
+                State::Solving
+            }
+            Err(error) => {
+                error!("failed to spawn calibration thread: {error:?}");
+                State::Finished
+            }
+        }
+    }
+
+    fn finish_solving(&mut self) {
+        if !self
+            .solving
+            .as_ref()
+            .is_some_and(|solving| solving.is_finished())
+        {
+            return;
+        }
+        let solving = self
+            .solving
+            .take()
+            .expect("calibration thread should exist if it finished");
+        match solving.join() {
+            Ok(report) => self.report = Some(report),
+            Err(_) => error!("calibration thread panicked"),
+        }
+        if let State::Solving = self.state {
+            self.state = State::Finished;
+        }
+    }
+}
+
+/// Solves the corrections from the measurements and stores them into the parameters of the head
+/// if they improve the residuals
+fn solve_and_store(
+    mut measurements: Vec<Measurement>,
+    field_dimensions: &FieldDimensions,
+    top: &CameraMatrixParameters,
+    bottom: &CameraMatrixParameters,
+    robot_rotation_correction: Vector3<f32>,
+    paths: &Paths,
+    ids: &Ids,
+) -> CalibrationReport {
+    measurements.retain(|measurement| {
+        Residuals::calculate_from(&Corrections::default(), measurement, field_dimensions).is_ok()
+    });
+    let residual_norm = |corrections: &Corrections, measurements: &[Measurement]| {
+        calculate_residuals_from_parameters(corrections, measurements, field_dimensions)
+            .map(|residuals| residuals.norm())
+    };
+    let residual_norm_before = residual_norm(&Corrections::default(), &measurements);
+    let number_of_measurements = measurements.len();
+
+    let (corrections, solver_report) = solve(
+        Corrections::default(),
+        measurements.clone(),
+        field_dimensions.clone(),
+    );
+    let residual_norm_after = residual_norm(&corrections, &measurements);
+
+    let mut report = CalibrationReport {
+        number_of_measurements,
+        residual_norm_before,
+        residual_norm_after,
+        solver_succeeded: solver_report.termination.was_successful(),
+        robot_rotation_correction: corrected_euler_angles(
+            corrections.correction_in_robot,
+            robot_rotation_correction,
+        ),
+        top_extrinsic_rotations: corrected_euler_angles(
+            corrections.correction_in_camera_top,
+            top.extrinsic_rotations,
+        ),
+        bottom_extrinsic_rotations: corrected_euler_angles(
+            corrections.correction_in_camera_bottom,
+            bottom.extrinsic_rotations,
+        ),
+        stored_to_disk: false,
+    };
+
+    let improved = matches!(
+        (residual_norm_before, residual_norm_after),
+        (Some(before), Some(after)) if after < before
+    );
+    if number_of_measurements == 0 || !report.solver_succeeded || !improved {
+        error!("calibration failed: {report:?}");
+        return report;
+    }
+
+    let parameters = StoredParameters {
+        camera_matrix_parameters: StoredCameraMatrixParameters {
+            vision_top: CameraMatrixParameters {
+                extrinsic_rotations: report.top_extrinsic_rotations,
+                ..top.clone()
+            },
+            vision_bottom: CameraMatrixParameters {
+                extrinsic_rotations: report.bottom_extrinsic_rotations,
+                ..bottom.clone()
+            },
+            robot_rotation_correction: report.robot_rotation_correction,
+        },
+    };
+    match store_to_head(&parameters, paths, ids) {
+        Ok(()) => {
+            report.stored_to_disk = true;
+            info!("calibration finished: {report:?}");
+        }
+        Err(error) => error!("failed to store calibration: {error:?}"),
+    }
+    report
+}
+
+/// Parameters changed by the calibration, mirroring the layout of the parameter files
+#[derive(Deserialize, Serialize)]
+struct StoredParameters {
+    camera_matrix_parameters: StoredCameraMatrixParameters,
+}
+
+#[derive(Deserialize, Serialize)]
+struct StoredCameraMatrixParameters {
+    vision_top: CameraMatrixParameters,
+    vision_bottom: CameraMatrixParameters,
+    robot_rotation_correction: Vector3<f32>,
+}
+
+/// Applies the correction onto the rotation given in euler angles in degrees
+fn corrected_euler_angles(correction: Rotation3<f32>, euler_angles: Vector3<f32>) -> Vector3<f32> {
+    let euler_angles = euler_angles.map(|degree| degree.to_radians());
+    let rotation = Rotation3::from_euler_angles(euler_angles.x, euler_angles.y, euler_angles.z);
+    let (roll, pitch, yaw) = (correction * rotation).euler_angles();
+    Vector3::new(roll, pitch, yaw).map(|radian| radian.to_degrees())
+}
+
+fn store_to_head(parameters: &StoredParameters, paths: &Paths, ids: &Ids) -> Result<()> {
+    let runtime = Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .wrap_err("failed to create tokio runtime")?;
+    runtime
+        .block_on(serialize(
+            parameters,
+            Scope {
+                location: Location::All,
+                id: Id::Head,
+            },
+            "camera_matrix_parameters",
+            &paths.parameters,
+            &ids.body_id,
+            &ids.head_id,
+        ))
+        .wrap_err("failed to serialize parameters")
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn corrections_are_applied_onto_euler_angles() {
+        let corrected = corrected_euler_angles(
+            Rotation3::from_euler_angles(0.0, 2.0_f32.to_radians(), 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+        );
+        assert_relative_eq!(corrected, Vector3::new(0.0, 3.0, 0.0), epsilon = 1e-4);
+
+        let unchanged = corrected_euler_angles(Rotation3::identity(), Vector3::new(1.0, -2.0, 3.0));
+        assert_relative_eq!(unchanged, Vector3::new(1.0, -2.0, 3.0), epsilon = 1e-4);
+    }
+}
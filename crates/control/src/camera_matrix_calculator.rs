@@ -2,14 +2,17 @@ use std::f32::consts::FRAC_PI_2;
 
 use color_eyre::Result;
 use nalgebra::UnitQuaternion;
-use projection::{camera_matrices::CameraMatrices, camera_matrix::CameraMatrix, Projection};
+use projection::{
+    camera_matrices::CameraMatrices, camera_matrix::CameraMatrix, distortion::Distortion,
+    Projection,
+};
 use serde::{Deserialize, Serialize};
 
 use context_attribute::context;
 use coordinate_systems::{Camera, Ground, Head, Pixel, Robot};
 use framework::{AdditionalOutput, MainOutput};
 use geometry::line::{Line, Line2};
-use linear_algebra::{point, vector, IntoTransform, Isometry3, Vector3};
+use linear_algebra::{point, vector, IntoTransform, Isometry3, Rotation3, Vector3};
 use types::{
     field_dimensions::FieldDimensions, field_lines::ProjectedFieldLines,
     parameters::CameraMatrixParameters, robot_dimensions::RobotDimensions,
@@ -32,6 +35,8 @@ pub struct CycleContext {
     bottom_camera_matrix_parameters:
         Parameter<CameraMatrixParameters, "camera_matrix_parameters.vision_bottom">,
     field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    robot_rotation_correction:
+        Parameter<nalgebra::Vector3<f32>, "camera_matrix_parameters.robot_rotation_correction">,
     top_camera_matrix_parameters:
         Parameter<CameraMatrixParameters, "camera_matrix_parameters.vision_top">,
 }
@@ -64,7 +69,11 @@ impl CameraMatrixCalculator {
             context.robot_to_ground.inverse(),
             context.robot_kinematics.head.head_to_robot.inverse(),
             head_to_top_camera,
-        );
+        )
+        .with_distortion(Distortion::new(
+            context.top_camera_matrix_parameters.radial_distortion,
+            context.top_camera_matrix_parameters.tangential_distortion,
+        ));
 
         let head_to_bottom_camera = head_to_camera(
             context.bottom_camera_matrix_parameters.extrinsic_rotations,
@@ -81,23 +90,42 @@ impl CameraMatrixCalculator {
             context.robot_to_ground.inverse(),
             context.robot_kinematics.head.head_to_robot.inverse(),
             head_to_bottom_camera,
+        )
+        .with_distortion(Distortion::new(
+            context.bottom_camera_matrix_parameters.radial_distortion,
+            context
+                .bottom_camera_matrix_parameters
+                .tangential_distortion,
+        ));
+
+        let robot_rotation_correction = context
+            .robot_rotation_correction
+            .map(|degree: f32| degree.to_radians());
+        let camera_matrices = CameraMatrices {
+            top: top_camera_matrix,
+            bottom: bottom_camera_matrix,
+        }
+        .to_corrected(
+            Rotation3::from_euler_angles(
+                robot_rotation_correction.x,
+                robot_rotation_correction.y,
+                robot_rotation_correction.z,
+            ),
+            Rotation3::default(),
+            Rotation3::default(),
         );
 
         let field_dimensions = context.field_dimensions;
         context
             .projected_field_lines
             .fill_if_subscribed(|| ProjectedFieldLines {
-                top: project_penalty_area_on_images(field_dimensions, &top_camera_matrix)
+                top: project_penalty_area_on_images(field_dimensions, &camera_matrices.top)
                     .unwrap_or_default(),
-                bottom: project_penalty_area_on_images(field_dimensions, &bottom_camera_matrix)
+                bottom: project_penalty_area_on_images(field_dimensions, &camera_matrices.bottom)
                     .unwrap_or_default(),
             });
         Ok(MainOutputs {
-            camera_matrices: Some(CameraMatrices {
-                top: top_camera_matrix,
-                bottom: bottom_camera_matrix,
-            })
-            .into(),
+            camera_matrices: Some(camera_matrices).into(),
         })
     }
 }
@@ -145,7 +173,7 @@ fn project_penalty_area_on_images(
     ])
 }
 
-fn head_to_camera(
+pub fn head_to_camera(
     extrinsic_rotation: nalgebra::Vector3<f32>,
     camera_pitch: f32,
     head_to_camera: Vector3<Head>,
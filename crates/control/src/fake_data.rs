@@ -10,11 +10,13 @@ use framework::MainOutput;
 use spl_network_messages::HulkMessage;
 use types::{
     ball_position::{BallPosition, HypotheticalBallPosition},
+    calibration::CalibrationCommand,
     cycle_time::CycleTime,
     fall_state::FallState,
     filtered_whistle::FilteredWhistle,
     game_controller_state::GameControllerState,
     joints::head::HeadJoints,
+    multivariate_normal_distribution::MultivariateNormalDistribution,
     obstacles::Obstacle,
     parameters::{BallFilterParameters, CameraMatrixParameters},
     penalty_shot_direction::PenaltyShotDirection,
@@ -31,6 +33,8 @@ pub struct CreationContext {
     maximum_velocity: Parameter<HeadJoints<f32>, "head_motion.maximum_velocity">,
     top_camera_matrix_parameters:
         Parameter<CameraMatrixParameters, "camera_matrix_parameters.vision_top">,
+    bottom_camera_matrix_parameters:
+        Parameter<CameraMatrixParameters, "camera_matrix_parameters.vision_bottom">,
     ball_filter: Parameter<BallFilterParameters, "ball_filter">,
 }
 
@@ -44,6 +48,8 @@ pub struct CycleContext {
 #[derive(Default)]
 pub struct MainOutputs {
     pub ball_position: MainOutput<Option<BallPosition<Ground>>>,
+    pub ball_position_distribution: MainOutput<Option<MultivariateNormalDistribution<2>>>,
+    pub calibration_command: MainOutput<Option<CalibrationCommand>>,
     pub cycle_time: MainOutput<CycleTime>,
     pub fall_state: MainOutput<FallState>,
     pub filtered_whistle: MainOutput<FilteredWhistle>,
@@ -52,6 +58,7 @@ pub struct MainOutputs {
     pub has_ground_contact: MainOutput<bool>,
     pub hulk_messages: MainOutput<Vec<HulkMessage>>,
     pub hypothetical_ball_positions: MainOutput<Vec<HypotheticalBallPosition<Ground>>>,
+    pub localization_confidence: MainOutput<Option<f32>>,
     pub obstacles: MainOutput<Vec<Obstacle>>,
     pub penalty_shot_direction: MainOutput<Option<PenaltyShotDirection>>,
     pub primary_state: MainOutput<PrimaryState>,
@@ -0,0 +1,247 @@
+use std::time::{Duration, SystemTime};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use context_attribute::context;
+use coordinate_systems::{Field, Ground};
+use framework::{AdditionalOutput, PerceptionInput};
+use linear_algebra::{distance, point, Isometry2, Point2};
+use spl_network_messages::{HulkMessage, PlayerNumber};
+use types::{
+    ball_position::BallPosition, cycle_time::CycleTime, messages::IncomingMessage,
+    players::Players, primary_state::PrimaryState,
+};
+
+/// Detects a localization mirrored at the field center by comparing the own ball with the balls
+/// reported by teammates
+///
+/// The field is point symmetric, so lines alone cannot distinguish the true pose from the
+/// mirrored one. Once enough teammates consistently see the ball at the mirrored position of our
+/// own ball, localization is asked to add the mirrored hypothesis. Only cycles in which a new
+/// teammate ball arrived count as detection, the cached teammate messages alone are no
+/// independent confirmation.
+#[derive(Deserialize, Serialize)]
+pub struct FlipDetection {
+    last_teammate_messages: Players<Option<(SystemTime, HulkMessage)>>,
+    consecutive_flip_detections: usize,
+}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    flip_detected: AdditionalOutput<bool, "localization.flip_detected">,
+
+    ball_position: Input<Option<BallPosition<Ground>>, "ball_position?">,
+    cycle_time: Input<CycleTime, "cycle_time">,
+    ground_to_field: Input<Option<Isometry2<Ground, Field>>, "ground_to_field?">,
+    primary_state: Input<PrimaryState, "primary_state">,
+    network_message: PerceptionInput<Option<IncomingMessage>, "SplNetwork", "filtered_message?">,
+
+    maximum_ball_age: Parameter<Duration, "flip_detection.maximum_ball_age">,
+    maximum_ball_distance: Parameter<f32, "flip_detection.maximum_ball_distance">,
+    maximum_teammate_ball_distance: Parameter<f32, "flip_detection.maximum_teammate_ball_distance">,
+    minimum_agreeing_teammates: Parameter<usize, "flip_detection.minimum_agreeing_teammates">,
+    minimum_consecutive_detections:
+        Parameter<usize, "flip_detection.minimum_consecutive_detections">,
+    player_number: Parameter<PlayerNumber, "player_number">,
+
+    mirrored_hypothesis_requested: CyclerState<bool, "mirrored_hypothesis_requested">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {}
+
+impl FlipDetection {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {
+            last_teammate_messages: Default::default(),
+            consecutive_flip_detections: 0,
+        })
+    }
+
+    pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        let mut received_teammate_ball = false;
+        for (time, message) in context
+            .network_message
+            .persistent
+            .iter()
+            .flat_map(|(time, messages)| messages.iter().map(move |message| (*time, message)))
+        {
+            let message = match message {
+                Some(IncomingMessage::Spl(message)) => *message,
+                Some(IncomingMessage::MixedTeam(message)) => HulkMessage::from(*message),
+                _ => continue,
+            };
+            if message.player_number != *context.player_number {
+                self.last_teammate_messages[message.player_number] = Some((time, message));
+                received_teammate_ball |= message.ball_position.is_some();
+            }
+        }
+
+        let now = context.cycle_time.start_time;
+        let own_ball = match (context.ball_position, context.ground_to_field) {
+            (Some(ball), Some(ground_to_field))
+                if *context.primary_state == PrimaryState::Playing
+                    && now.duration_since(ball.last_seen).unwrap_or_default()
+                        <= *context.maximum_ball_age =>
+            {
+                Some(*ground_to_field * ball.position)
+            }
+            _ => None,
+        };
+
+        let mut flip_detected = false;
+        if let Some(own_ball) = own_ball {
+            let teammate_balls = self
+                .last_teammate_messages
+                .iter()
+                .filter_map(|(_, message)| {
+                    let (time_received, message) = message.as_ref()?;
+                    let ball = message.ball_position?;
+                    let age = ball.age + now.duration_since(*time_received).unwrap_or_default();
+                    let is_reliable = !message.fallen
+                        && age <= *context.maximum_ball_age
+                        && distance(message.pose.position(), ball.position)
+                            <= *context.maximum_teammate_ball_distance;
+                    is_reliable.then_some(ball.position)
+                });
+            let votes = count_votes(own_ball, teammate_balls, *context.maximum_ball_distance);
+
+            self.consecutive_flip_detections = update_consecutive_detections(
+                self.consecutive_flip_detections,
+                &votes,
+                received_teammate_ball,
+                *context.minimum_agreeing_teammates,
+            );
+            if self.consecutive_flip_detections >= *context.minimum_consecutive_detections {
+                flip_detected = true;
+                *context.mirrored_hypothesis_requested = true;
+                self.consecutive_flip_detections = 0;
+            }
+        }
+        context.flip_detected.fill_if_subscribed(|| flip_detected);
+
+        Ok(MainOutputs {})
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Votes {
+    flipped: usize,
+    not_flipped: usize,
+}
+
+/// Teammates seeing the ball near our own ball vote against a flip, teammates seeing it near the
+/// mirrored position vote for a flip
+fn count_votes(
+    own_ball: Point2<Field>,
+    teammate_balls: impl Iterator<Item = Point2<Field>>,
+    maximum_ball_distance: f32,
+) -> Votes {
+    let mirrored_own_ball = point![-own_ball.x(), -own_ball.y()];
+    teammate_balls.fold(Votes::default(), |mut votes, teammate_ball| {
+        if distance(own_ball, teammate_ball) <= maximum_ball_distance {
+            votes.not_flipped += 1;
+        } else if distance(mirrored_own_ball, teammate_ball) <= maximum_ball_distance {
+            votes.flipped += 1;
+        }
+        votes
+    })
+}
+
+/// Counts detections only if a new teammate ball arrived, contradicting votes reset the count
+fn update_consecutive_detections(
+    consecutive_detections: usize,
+    votes: &Votes,
+    received_teammate_ball: bool,
+    minimum_agreeing_teammates: usize,
+) -> usize {
+    if votes.flipped >= minimum_agreeing_teammates && votes.not_flipped == 0 {
+        if received_teammate_ball {
+            consecutive_detections + 1
+        } else {
+            consecutive_detections
+        }
+    } else if votes.flipped > 0 || votes.not_flipped > 0 {
+        0
+    } else {
+        consecutive_detections
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn teammates_seeing_mirrored_ball_vote_for_flip() {
+        let own_ball = point![2.0, 1.0];
+
+        let votes = count_votes(
+            own_ball,
+            [point![-2.1, -0.9], point![-1.9, -1.0], point![4.0, 3.0]].into_iter(),
+            0.5,
+        );
+        assert_eq!(
+            votes,
+            Votes {
+                flipped: 2,
+                not_flipped: 0
+            }
+        );
+
+        let votes = count_votes(
+            own_ball,
+            [point![-2.1, -0.9], point![2.1, 1.2]].into_iter(),
+            0.5,
+        );
+        assert_eq!(
+            votes,
+            Votes {
+                flipped: 1,
+                not_flipped: 1
+            }
+        );
+    }
+
+    #[test]
+    fn ball_at_center_is_ambiguous_and_never_votes_for_flip() {
+        let votes = count_votes(point![0.1, 0.0], [point![-0.1, 0.0]].into_iter(), 0.5);
+
+        assert_eq!(
+            votes,
+            Votes {
+                flipped: 0,
+                not_flipped: 1
+            }
+        );
+    }
+
+    #[test]
+    fn detections_are_only_counted_with_new_teammate_balls() {
+        let agreeing = Votes {
+            flipped: 2,
+            not_flipped: 0,
+        };
+
+        assert_eq!(update_consecutive_detections(3, &agreeing, true, 2), 4);
+        assert_eq!(update_consecutive_detections(3, &agreeing, false, 2), 3);
+
+        let contradicting = Votes {
+            flipped: 2,
+            not_flipped: 1,
+        };
+        assert_eq!(
+            update_consecutive_detections(3, &contradicting, false, 2),
+            0
+        );
+        assert_eq!(
+            update_consecutive_detections(3, &Votes::default(), true, 2),
+            3
+        );
+    }
+}
@@ -13,7 +13,7 @@ use spl_network_messages::GameControllerStateMessage;
 use types::{
     audio::{Sound, SpeakerRequest},
     cycle_time::CycleTime,
-    game_controller_state::GameControllerState,
+    game_controller_state::{GameControllerState, JerseyColors},
     messages::IncomingMessage,
 };
 
@@ -113,12 +113,22 @@ impl GameControllerFilter {
         self.game_controller_state = Some(GameControllerState {
             game_state: message.game_state,
             game_phase: message.game_phase,
+            half: message.half,
+            remaining_time_in_half: message.remaining_time_in_half,
             kicking_team: message.kicking_team,
             last_game_state_change: self.last_game_state_change.unwrap(),
             penalties: message.hulks_team.clone().into(),
             remaining_amount_of_messages: message.hulks_team.remaining_amount_of_messages,
             sub_state: message.sub_state,
             hulks_team_is_home_after_coin_toss: message.hulks_team_is_home_after_coin_toss,
+            hulks_jersey_colors: JerseyColors {
+                field_player: message.hulks_team.field_player_color,
+                goal_keeper: message.hulks_team.goal_keeper_color,
+            },
+            opponent_jersey_colors: JerseyColors {
+                field_player: message.opponent_team.field_player_color,
+                goal_keeper: message.opponent_team.goal_keeper_color,
+            },
         });
     }
 
@@ -65,6 +65,8 @@ impl GameControllerStateFilter {
             game_state: game_states.own,
             opponent_game_state: game_states.opponent,
             game_phase: context.game_controller_state.game_phase,
+            half: context.game_controller_state.half,
+            remaining_time_in_half: context.game_controller_state.remaining_time_in_half,
             kicking_team: context.game_controller_state.kicking_team,
             penalties: context.game_controller_state.penalties,
             remaining_number_of_messages: context
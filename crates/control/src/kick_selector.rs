@@ -14,6 +14,7 @@ use geometry::{
 use linear_algebra::{
     distance, point, vector, IntoFramed, Isometry2, Orientation2, Point, Point2, Pose2, Vector2,
 };
+use spl_network_messages::Team;
 use types::{
     field_dimensions::FieldDimensions,
     kick_decision::KickDecision,
@@ -164,6 +165,8 @@ fn generate_obstacle_circles(
 ) -> Vec<Circle<Ground>> {
     obstacles
         .iter()
+        // kicking towards a teammate passes the ball to it instead of losing it
+        .filter(|obstacle| obstacle.team != Team::Hulks)
         .map(|obstacle| {
             let obstacle_radius =
                 obstacle.radius_at_foot_height + ball_radius_for_kick_target_selection;
@@ -4,11 +4,13 @@ pub mod ball_filter;
 pub mod ball_state_composer;
 pub mod behavior;
 pub mod button_filter;
+pub mod calibration_controller;
 pub mod camera_matrix_calculator;
 pub mod center_of_mass_provider;
 pub mod dribble_path_planner;
 pub mod fake_data;
 pub mod fall_state_estimation;
+pub mod flip_detection;
 pub mod foot_bumper_filter;
 pub mod game_controller_filter;
 pub mod game_controller_state_filter;
@@ -18,6 +20,7 @@ pub mod kick_selector;
 pub mod kinematics_provider;
 pub mod led_status;
 pub mod localization;
+pub mod message_budget_manager;
 pub mod motion;
 pub mod obstacle_filter;
 pub mod odometry;
@@ -25,6 +28,7 @@ pub mod orientation_filter;
 pub mod path_planner;
 pub mod penalty_shot_direction_estimation;
 pub mod primary_state_filter;
+pub mod recording_event_detector;
 pub mod referee_pose_detection_filter;
 pub mod referee_position_provider;
 pub mod role_assignment;
@@ -34,6 +38,7 @@ pub mod sensor_data_receiver;
 pub mod sole_pressure_filter;
 pub mod sonar_filter;
 pub mod support_foot_estimation;
+pub mod team_ball_filter;
 pub mod time_to_reach_kick_position;
 pub mod visual_referee_filter;
 pub mod whistle_filter;
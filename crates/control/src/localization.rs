@@ -7,7 +7,8 @@ use std::{
 use approx::assert_relative_eq;
 use color_eyre::{eyre::WrapErr, Result};
 use geometry::line::{Line, Line2};
-use linear_algebra::{distance, point, vector, IntoTransform, Isometry2, Pose2};
+use linear_algebra::{distance, point, vector, IntoTransform, Isometry2, Point2, Pose2};
+use log::warn;
 use nalgebra::{matrix, Matrix, Matrix2, Matrix3, Rotation2, Translation2, Vector2, Vector3};
 use ordered_float::NotNan;
 use serde::{Deserialize, Serialize};
@@ -20,9 +21,13 @@ use spl_network_messages::{GamePhase, Penalty, PlayerNumber, Team};
 use types::{
     cycle_time::CycleTime,
     field_dimensions::FieldDimensions,
-    field_marks::{field_marks_from_field_dimensions, CorrespondencePoints, Direction, FieldMark},
+    field_marks::{
+        field_marks_from_field_dimensions, point_marks_from_field_dimensions, CorrespondencePoints,
+        Direction, FieldMark, PointKind,
+    },
     filtered_game_controller_state::FilteredGameControllerState,
     initial_pose::InitialPose,
+    landmarks::Landmark,
     line_data::LineData,
     localization::{ScoredPose, Update},
     multivariate_normal_distribution::MultivariateNormalDistribution,
@@ -34,12 +39,15 @@ use types::{
 #[derive(Deserialize, Serialize)]
 pub struct Localization {
     field_marks: Vec<FieldMark>,
+    point_marks: Vec<FieldMark>,
     last_primary_state: PrimaryState,
     hypotheses: Vec<ScoredPose>,
     hypotheses_when_entered_playing: Vec<ScoredPose>,
     is_penalized_with_motion_in_set_or_initial: bool,
     was_picked_up_while_penalized_with_motion_in_set_or_initial: bool,
     time_when_penalized_clicked: Option<SystemTime>,
+    fit_error_of_best_hypothesis: Option<f32>,
+    diverged_since: Option<SystemTime>,
 }
 
 #[context]
@@ -66,6 +74,7 @@ pub struct CycleContext {
 
     circle_measurement_noise: Parameter<Vector2<f32>, "localization.circle_measurement_noise">,
     field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    flipped_hypothesis_score_factor: Parameter<f32, "localization.flipped_hypothesis_score_factor">,
     good_matching_threshold: Parameter<f32, "localization.good_matching_threshold">,
     gradient_convergence_threshold: Parameter<f32, "localization.gradient_convergence_threshold">,
     gradient_descent_step_size: Parameter<f32, "localization.gradient_descent_step_size">,
@@ -77,8 +86,11 @@ pub struct CycleContext {
         Parameter<Matrix3<f32>, "localization.initial_hypothesis_covariance">,
     initial_hypothesis_score: Parameter<f32, "localization.initial_hypothesis_score">,
     initial_poses: Parameter<Players<InitialPose>, "localization.initial_poses">,
+    landmark_measurement_noise: Parameter<Vector2<f32>, "localization.landmark_measurement_noise">,
     line_length_acceptance_factor: Parameter<f32, "localization.line_length_acceptance_factor">,
     line_measurement_noise: Parameter<Vector2<f32>, "localization.line_measurement_noise">,
+    maximum_landmark_matching_distance:
+        Parameter<f32, "localization.maximum_landmark_matching_distance">,
     maximum_amount_of_gradient_descent_iterations:
         Parameter<usize, "localization.maximum_amount_of_gradient_descent_iterations">,
     maximum_amount_of_outer_iterations:
@@ -87,10 +99,19 @@ pub struct CycleContext {
     odometry_noise: Parameter<Vector3<f32>, "localization.odometry_noise">,
     player_number: Parameter<PlayerNumber, "player_number">,
     penalized_distance: Parameter<f32, "localization.penalized_distance">,
+    relocalization_duration: Parameter<Duration, "localization.relocalization_duration">,
+    relocalization_fit_error_threshold:
+        Parameter<f32, "localization.relocalization_fit_error_threshold">,
+    relocalization_grid_spacing: Parameter<f32, "localization.relocalization_grid_spacing">,
+    relocalization_hypothesis_covariance:
+        Parameter<Matrix3<f32>, "localization.relocalization_hypothesis_covariance">,
+    relocalization_number_of_orientations:
+        Parameter<usize, "localization.relocalization_number_of_orientations">,
     penalized_hypothesis_covariance:
         Parameter<Matrix3<f32>, "localization.penalized_hypothesis_covariance">,
     score_per_good_match: Parameter<f32, "localization.score_per_good_match">,
     tentative_penalized_duration: Parameter<Duration, "localization.tentative_penalized_duration">,
+    use_landmark_measurements: Parameter<bool, "localization.use_landmark_measurements">,
     use_line_measurements: Parameter<bool, "localization.use_line_measurements">,
     injected_ground_to_field_of_home_after_coin_toss_before_second_half: Parameter<
         Option<Isometry2<Ground, Field>>,
@@ -99,8 +120,11 @@ pub struct CycleContext {
 
     line_data_bottom: PerceptionInput<Option<LineData>, "VisionBottom", "line_data?">,
     line_data_top: PerceptionInput<Option<LineData>, "VisionTop", "line_data?">,
+    landmarks_bottom: PerceptionInput<Option<Vec<Landmark>>, "VisionBottom", "landmarks?">,
+    landmarks_top: PerceptionInput<Option<Vec<Landmark>>, "VisionTop", "landmarks?">,
 
     ground_to_field: CyclerState<Isometry2<Ground, Field>, "ground_to_field">,
+    mirrored_hypothesis_requested: CyclerState<bool, "mirrored_hypothesis_requested">,
     cycle_time: Input<CycleTime, "cycle_time">,
 }
 
@@ -110,6 +134,7 @@ pub struct MainOutputs {
     pub ground_to_field: MainOutput<Option<Isometry2<Ground, Field>>>,
     pub ground_to_field_of_home_after_coin_toss_before_second_half:
         MainOutput<Option<Isometry2<Ground, Field>>>,
+    pub localization_confidence: MainOutput<Option<f32>>,
 }
 
 impl Localization {
@@ -121,12 +146,15 @@ impl Localization {
                     context.field_dimensions,
                 ))
                 .collect(),
+            point_marks: point_marks_from_field_dimensions(context.field_dimensions),
             last_primary_state: PrimaryState::Unstiff,
             hypotheses: vec![],
             hypotheses_when_entered_playing: vec![],
             is_penalized_with_motion_in_set_or_initial: false,
             was_picked_up_while_penalized_with_motion_in_set_or_initial: false,
             time_when_penalized_clicked: None,
+            fit_error_of_best_hypothesis: None,
+            diverged_since: None,
         })
     }
 
@@ -285,6 +313,10 @@ impl Localization {
 
     fn update_state(&mut self, context: &mut CycleContext) -> Result<()> {
         let mut fit_errors_per_measurement = vec![];
+        if take(context.mirrored_hypothesis_requested) {
+            self.add_mirrored_hypothesis(*context.flipped_hypothesis_score_factor);
+        }
+        let mut latest_fit_errors = vec![None; self.hypotheses.len()];
 
         context.measured_lines_in_field.fill_if_subscribed(Vec::new);
         context.correspondence_lines.fill_if_subscribed(Vec::new);
@@ -306,6 +338,14 @@ impl Localization {
             let current_odometry_to_last_odometry = context
                 .current_odometry_to_last_odometry
                 .get(line_data_top_timestamp);
+            // landmarks are detected by the same cycler as the lines and share their timestamps
+            let landmarks: Vec<Landmark> = [&context.landmarks_top, &context.landmarks_bottom]
+                .into_iter()
+                .filter_map(|landmarks| landmarks.persistent.get(line_data_top_timestamp))
+                .flatten()
+                .flatten()
+                .flat_map(|landmarks| landmarks.iter().copied())
+                .collect();
 
             let mut fit_errors_per_hypothesis = vec![];
             for (hypothesis_index, scored_state) in self.hypotheses.iter_mut().enumerate() {
@@ -318,6 +358,71 @@ impl Localization {
                     .wrap_err("failed to predict pose filter")?;
                     scored_state.score *= *context.hypothesis_prediction_score_reduction_factor;
                 }
+                if *context.use_landmark_measurements {
+                    let ground_to_field: Isometry2<Ground, Field> =
+                        scored_state.state.as_isometry().framed_transform();
+                    for landmark in &landmarks {
+                        let Some(field_mark_correspondence) = get_landmark_correspondence(
+                            ground_to_field * landmark.position,
+                            landmark.kind,
+                            &self.point_marks,
+                            *context.maximum_landmark_matching_distance,
+                        ) else {
+                            continue;
+                        };
+                        let update = get_landmark_translation_measurement(
+                            ground_to_field,
+                            field_mark_correspondence,
+                        );
+                        let landmark_distance_to_robot = landmark.position.coords().norm();
+                        let correspondence_points =
+                            field_mark_correspondence.correspondence_points.0;
+                        context
+                            .correspondence_lines
+                            .mutate_if_subscribed(|correspondence_lines| {
+                                if let Some(correspondence_lines) = correspondence_lines {
+                                    correspondence_lines.push(Line(
+                                        correspondence_points.measured,
+                                        correspondence_points.reference,
+                                    ));
+                                }
+                            });
+                        context.updates.mutate_if_subscribed(|updates| {
+                            if let Some(updates) = updates {
+                                updates[hypothesis_index].push(Update {
+                                    ground_to_field: nalgebra::Isometry2::new(
+                                        update,
+                                        ground_to_field.orientation().angle(),
+                                    )
+                                    .framed_transform(),
+                                    line_center_point: correspondence_points.measured,
+                                    fit_error: distance(
+                                        correspondence_points.measured,
+                                        correspondence_points.reference,
+                                    ),
+                                    number_of_measurements_weight: 1.0,
+                                    line_distance_to_robot: landmark_distance_to_robot,
+                                    line_length_weight: 1.0,
+                                });
+                            }
+                        });
+                        // the projection error of a point grows with its distance to the robot
+                        scored_state
+                            .state
+                            .update_with_2d_translation(
+                                update,
+                                Matrix::from_diagonal(context.landmark_measurement_noise)
+                                    * landmark_distance_to_robot.max(1.0),
+                                |state| nalgebra::vector![state.x, state.y],
+                            )
+                            .context("Failed to update pose filter")?;
+                        if field_mark_correspondence.fit_error_sum()
+                            < *context.good_matching_threshold
+                        {
+                            scored_state.score += *context.score_per_good_match;
+                        }
+                    }
+                }
                 if *context.use_line_measurements {
                     let ground_to_field: Isometry2<Ground, Field> =
                         scored_state.state.as_isometry().framed_transform();
@@ -383,6 +488,7 @@ impl Localization {
                         fit_errors_per_hypothesis.push(fit_errors);
                     }
                     let clamped_fit_error = fit_error.max(*context.minimum_fit_error);
+                    latest_fit_errors[hypothesis_index] = Some(clamped_fit_error);
                     let number_of_measurements_weight =
                         1.0 / field_mark_correspondences.len() as f32;
 
@@ -396,6 +502,10 @@ impl Localization {
                                 ground_to_field,
                                 field_mark_correspondence,
                             ),
+                            FieldMark::Point { .. } => get_landmark_translation_measurement(
+                                ground_to_field,
+                                field_mark_correspondence,
+                            ),
                         };
                         let line_length = field_mark_correspondence.measured_line_in_field.length();
                         let line_length_weight = if line_length == 0.0 {
@@ -434,10 +544,12 @@ impl Localization {
                                                     }
                                                 }
                                             }
-                                            FieldMark::Circle { .. } => nalgebra::Isometry2::new(
-                                                update,
-                                                ground_to_field.orientation().angle(),
-                                            ),
+                                            FieldMark::Circle { .. } | FieldMark::Point { .. } => {
+                                                nalgebra::Isometry2::new(
+                                                    update,
+                                                    ground_to_field.orientation().angle(),
+                                                )
+                                            }
                                         }
                                         .framed_transform();
                                     Update {
@@ -472,7 +584,7 @@ impl Localization {
                                     },
                                 )
                                 .context("Failed to update pose filter")?,
-                            FieldMark::Circle { .. } => scored_state
+                            FieldMark::Circle { .. } | FieldMark::Point { .. } => scored_state
                                 .state
                                 .update_with_2d_translation(
                                     update,
@@ -497,14 +609,19 @@ impl Localization {
             }
         }
 
-        let best_hypothesis = self
-            .get_best_hypothesis()
+        let best_hypothesis_index = self
+            .get_best_hypothesis_index()
             .expect("Expected at least one hypothesis");
+        let best_hypothesis = &self.hypotheses[best_hypothesis_index];
         let best_score = best_hypothesis.score;
         let ground_to_field = best_hypothesis.state.as_isometry();
+        if let Some(fit_error) = latest_fit_errors[best_hypothesis_index] {
+            self.fit_error_of_best_hypothesis = Some(fit_error);
+        }
         self.hypotheses.retain(|scored_state| {
             scored_state.score >= *context.hypothesis_retain_factor * best_score
         });
+        self.relocalize_if_diverged(context);
 
         context
             .pose_hypotheses
@@ -548,6 +665,17 @@ impl Localization {
                 self.update_state(&mut context)?;
                 Some(*context.ground_to_field)
             }
+            _ => {
+                self.fit_error_of_best_hypothesis = None;
+                self.diverged_since = None;
+                None
+            }
+        };
+        let localization_confidence = match primary_state {
+            PrimaryState::Initial => Some(1.0),
+            PrimaryState::Ready | PrimaryState::Set | PrimaryState::Playing => {
+                Some(self.confidence(*context.relocalization_fit_error_threshold))
+            }
             _ => None,
         };
         let ground_to_field_of_home_after_coin_toss_before_second_half = context
@@ -574,6 +702,7 @@ impl Localization {
             ground_to_field: ground_to_field.into(),
             ground_to_field_of_home_after_coin_toss_before_second_half:
                 ground_to_field_of_home_after_coin_toss_before_second_half.into(),
+            localization_confidence: localization_confidence.into(),
         })
     }
 
@@ -582,6 +711,92 @@ impl Localization {
             .iter()
             .max_by_key(|scored_filter| NotNan::new(scored_filter.score).unwrap())
     }
+
+    fn get_best_hypothesis_index(&self) -> Option<usize> {
+        self.hypotheses
+            .iter()
+            .enumerate()
+            .max_by_key(|(_index, scored_filter)| NotNan::new(scored_filter.score).unwrap())
+            .map(|(index, _scored_filter)| index)
+    }
+
+    /// Adds the best hypothesis mirrored at the field center, e.g. after teammates reported that
+    /// this robot is localized on the wrong side of the point symmetric field
+    fn add_mirrored_hypothesis(&mut self, flipped_hypothesis_score_factor: f32) {
+        let Some(best_hypothesis_index) = self.get_best_hypothesis_index() else {
+            return;
+        };
+        let best_hypothesis = &mut self.hypotheses[best_hypothesis_index];
+        let mut mirrored_hypothesis = *best_hypothesis;
+        let mean = best_hypothesis.state.mean;
+        mirrored_hypothesis.state.mean =
+            nalgebra::vector![-mean.x, -mean.y, Rotation2::new(mean.z + PI).angle()];
+        best_hypothesis.score *= flipped_hypothesis_score_factor;
+        self.hypotheses.push(mirrored_hypothesis);
+    }
+
+    /// Spreads hypotheses across the whole field if the best hypothesis does not fit the measured
+    /// lines for too long, e.g. after the robot was pushed or carried away
+    fn relocalize_if_diverged(&mut self, context: &CycleContext) {
+        let now = context.cycle_time.start_time;
+        match self.fit_error_of_best_hypothesis {
+            Some(fit_error) if fit_error > *context.relocalization_fit_error_threshold => {
+                let diverged_since = *self.diverged_since.get_or_insert(now);
+                let has_diverged_for_too_long = now
+                    .duration_since(diverged_since)
+                    .expect("time ran backwards")
+                    >= *context.relocalization_duration;
+                if has_diverged_for_too_long {
+                    let grid_spacing = *context.relocalization_grid_spacing;
+                    let number_of_orientations = *context.relocalization_number_of_orientations;
+                    self.diverged_since = None;
+                    let Some(poses) = generate_relocalization_poses(
+                        context.field_dimensions,
+                        grid_spacing,
+                        number_of_orientations,
+                    ) else {
+                        warn!(
+                            "skipping relocalization, grid spacing {grid_spacing} and number of \
+                            orientations {number_of_orientations} have to be positive"
+                        );
+                        return;
+                    };
+                    self.hypotheses.extend(poses.into_iter().map(|pose| {
+                        ScoredPose::from_isometry(
+                            pose,
+                            *context.relocalization_hypothesis_covariance,
+                            *context.initial_hypothesis_score,
+                        )
+                    }));
+                    self.fit_error_of_best_hypothesis = None;
+                }
+            }
+            Some(_) => self.diverged_since = None,
+            None => {}
+        }
+    }
+
+    /// Confidence between 0 and 1 combining how well the best hypothesis fits the measured lines
+    /// and how much it dominates the other hypotheses
+    fn confidence(&self, relocalization_fit_error_threshold: f32) -> f32 {
+        let Some(best_hypothesis) = self.get_best_hypothesis() else {
+            return 0.0;
+        };
+        let total_score: f32 = self
+            .hypotheses
+            .iter()
+            .map(|scored_state| scored_state.score.max(0.0))
+            .sum();
+        let dominance = if total_score > 0.0 {
+            best_hypothesis.score.max(0.0) / total_score
+        } else {
+            0.0
+        };
+        let fit_quality = self.fit_error_of_best_hypothesis.map_or(1.0, |fit_error| {
+            (1.0 - fit_error / relocalization_fit_error_threshold).clamp(0.0, 1.0)
+        });
+        dominance * fit_quality
+    }
 }
 
 pub fn goal_support_structure_line_marks_from_field_dimensions(
@@ -847,6 +1062,7 @@ fn get_field_mark_correspondence(
                     let field_mark_length = match field_mark {
                         FieldMark::Line { line, direction: _ } => line.length(),
                         FieldMark::Circle { center: _, radius } => *radius, // approximation
+                        FieldMark::Point { .. } => return None, // points are not matched to lines
                     };
                     let measured_line_length = transformed_line.length();
                     if measured_line_length <= field_mark_length * line_length_acceptance_factor {
@@ -929,6 +1145,48 @@ fn get_correspondence_points(
         .collect()
 }
 
+/// Matches a measured landmark to the nearest point mark of the same kind
+fn get_landmark_correspondence(
+    measured_point_in_field: Point2<Field>,
+    kind: PointKind,
+    point_marks: &[FieldMark],
+    maximum_matching_distance: f32,
+) -> Option<FieldMarkCorrespondence> {
+    point_marks
+        .iter()
+        .filter_map(|field_mark| match *field_mark {
+            FieldMark::Point {
+                point,
+                kind: mark_kind,
+            } if mark_kind == kind => Some((field_mark, distance(measured_point_in_field, point))),
+            _ => None,
+        })
+        .filter(|(_field_mark, distance)| *distance <= maximum_matching_distance)
+        .min_by(|(_, left_distance), (_, right_distance)| left_distance.total_cmp(right_distance))
+        .map(|(field_mark, _distance)| {
+            let measured_line_in_field = Line(measured_point_in_field, measured_point_in_field);
+            FieldMarkCorrespondence {
+                measured_line_in_field,
+                field_mark: *field_mark,
+                correspondence_points: field_mark
+                    .to_correspondence_points(measured_line_in_field)
+                    .correspondence_points,
+            }
+        })
+}
+
+/// Robot position which would move the measured landmark onto its reference point
+fn get_landmark_translation_measurement(
+    ground_to_field: Isometry2<Ground, Field>,
+    field_mark_correspondence: FieldMarkCorrespondence,
+) -> Vector2<f32> {
+    let correspondence_points = field_mark_correspondence.correspondence_points.0;
+    (ground_to_field.as_pose().position()
+        + (correspondence_points.reference - correspondence_points.measured))
+        .inner
+        .coords
+}
+
 fn get_translation_and_rotation_measurement(
     ground_to_field: Isometry2<Ground, Field>,
     field_mark_correspondence: FieldMarkCorrespondence,
@@ -1033,6 +1291,37 @@ pub fn generate_initial_pose(
     }
 }
 
+/// Poses on a grid covering the field, each position with evenly distributed orientations
+///
+/// Returns `None` if the grid spacing or the number of orientations is not positive.
+fn generate_relocalization_poses(
+    field_dimensions: &FieldDimensions,
+    grid_spacing: f32,
+    number_of_orientations: usize,
+) -> Option<Vec<Pose2<Field>>> {
+    let is_valid = grid_spacing > 0.0 && number_of_orientations > 0;
+    if !is_valid {
+        return None;
+    }
+    let coordinates = |extent: f32| {
+        let number_of_points = (extent / grid_spacing).floor() as usize + 1;
+        let offset = (number_of_points - 1) as f32 * grid_spacing / 2.0;
+        (0..number_of_points).map(move |index| index as f32 * grid_spacing - offset)
+    };
+    let poses = coordinates(field_dimensions.length)
+        .flat_map(|x| coordinates(field_dimensions.width).map(move |y| (x, y)))
+        .flat_map(|(x, y)| {
+            (0..number_of_orientations).map(move |index| {
+                Pose2::new(
+                    vector![x, y],
+                    index as f32 * 2.0 * PI / number_of_orientations as f32,
+                )
+            })
+        })
+        .collect();
+    Some(poses)
+}
+
 fn generate_penalized_poses(
     field_dimensions: &FieldDimensions,
     penalized_distance: f32,
@@ -1740,4 +2029,74 @@ mod tests {
         let update = get_2d_translation_measurement(ground_to_field, field_mark_correspondence);
         assert_relative_eq!(update, nalgebra::vector![0.0, -2.0], epsilon = 0.0001);
     }
+
+    #[test]
+    fn landmarks_are_matched_to_nearest_point_mark_of_same_kind() {
+        let point_marks = [
+            FieldMark::Point {
+                point: point![-3.2, 0.0],
+                kind: PointKind::PenaltyMark,
+            },
+            FieldMark::Point {
+                point: point![-4.5, 0.8],
+                kind: PointKind::GoalPost,
+            },
+            FieldMark::Point {
+                point: point![-4.5, -0.8],
+                kind: PointKind::GoalPost,
+            },
+        ];
+
+        let correspondence =
+            get_landmark_correspondence(point![-4.3, 0.6], PointKind::GoalPost, &point_marks, 0.5)
+                .unwrap();
+        assert_relative_eq!(
+            correspondence.correspondence_points.0.reference,
+            point![-4.5, 0.8]
+        );
+        assert!(get_landmark_correspondence(
+            point![-4.3, 0.6],
+            PointKind::PenaltyMark,
+            &point_marks,
+            0.5
+        )
+        .is_none());
+
+        let ground_to_field = Isometry2::from_parts(vector![-2.0, 0.5], FRAC_PI_4);
+        let update = get_landmark_translation_measurement(ground_to_field, correspondence);
+        assert_relative_eq!(update, nalgebra::vector![-2.2, 0.7], epsilon = 0.0001);
+    }
+
+    #[test]
+    fn relocalization_poses_cover_the_field() {
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            width: 6.0,
+            ..Default::default()
+        };
+
+        let poses = generate_relocalization_poses(&field_dimensions, 1.5, 4).unwrap();
+
+        assert_eq!(poses.len(), 7 * 5 * 4);
+        assert!(poses.iter().all(|pose| {
+            pose.position().x().abs() <= 4.5 + 1e-5 && pose.position().y().abs() <= 3.0 + 1e-5
+        }));
+        assert!(poses
+            .iter()
+            .any(|pose| pose.position().x() == 0.0 && pose.position().y() == 0.0));
+    }
+
+    #[test]
+    fn invalid_relocalization_grids_are_rejected() {
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            width: 6.0,
+            ..Default::default()
+        };
+
+        assert!(generate_relocalization_poses(&field_dimensions, 0.0, 4).is_none());
+        assert!(generate_relocalization_poses(&field_dimensions, -1.5, 4).is_none());
+        assert!(generate_relocalization_poses(&field_dimensions, f32::NAN, 4).is_none());
+        assert!(generate_relocalization_poses(&field_dimensions, 1.5, 0).is_none());
+    }
 }
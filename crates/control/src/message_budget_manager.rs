@@ -0,0 +1,210 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use context_attribute::context;
+use framework::MainOutput;
+use spl_network_messages::{GamePhase, Half};
+use types::{
+    cycle_time::CycleTime, filtered_game_controller_state::FilteredGameControllerState,
+    message_budget::MessageBudget, parameters::SplNetworkParameters,
+};
+
+/// Plans the team message rate such that the message budget of the game controller lasts until
+/// the end of the game
+///
+/// The spendable messages are spread evenly over the remaining game time and all active players.
+/// Messages of higher priority may be sent more often, the plan is recomputed every cycle from the
+/// remaining budget and therefore adapts to messages sent earlier.
+#[derive(Deserialize, Serialize)]
+pub struct MessageBudgetManager {
+    remaining_messages_history: VecDeque<(SystemTime, u16)>,
+}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    cycle_time: Input<CycleTime, "cycle_time">,
+    filtered_game_controller_state:
+        Input<Option<FilteredGameControllerState>, "filtered_game_controller_state?">,
+
+    half_duration: Parameter<Duration, "message_budget_manager.half_duration">,
+    minimum_low_priority_interval:
+        Parameter<Duration, "message_budget_manager.minimum_low_priority_interval">,
+    normal_priority_interval_factor:
+        Parameter<f32, "message_budget_manager.normal_priority_interval_factor">,
+    rate_estimation_window: Parameter<Duration, "message_budget_manager.rate_estimation_window">,
+    spl_network: Parameter<SplNetworkParameters, "spl_network">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub message_budget: MainOutput<MessageBudget>,
+}
+
+impl MessageBudgetManager {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {
+            remaining_messages_history: VecDeque::new(),
+        })
+    }
+
+    pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
+        let Some(game_controller_state) = context.filtered_game_controller_state else {
+            self.remaining_messages_history.clear();
+            return Ok(MainOutputs::default());
+        };
+        let now = context.cycle_time.start_time;
+        let remaining_messages = game_controller_state.remaining_number_of_messages;
+
+        if self
+            .remaining_messages_history
+            .back()
+            .is_none_or(|(_, last_remaining_messages)| {
+                *last_remaining_messages != remaining_messages
+            })
+        {
+            self.remaining_messages_history
+                .push_back((now, remaining_messages));
+        }
+        while self
+            .remaining_messages_history
+            .front()
+            .is_some_and(|(time, _)| {
+                now.duration_since(*time).unwrap_or_default() > *context.rate_estimation_window
+            })
+        {
+            self.remaining_messages_history.pop_front();
+        }
+        let team_messages_per_minute = match self.remaining_messages_history.front() {
+            Some((time, oldest_remaining_messages)) => {
+                let elapsed = now.duration_since(*time).unwrap_or_default();
+                f32::from(oldest_remaining_messages.saturating_sub(remaining_messages))
+                    / elapsed.max(*context.rate_estimation_window).as_secs_f32()
+                    * 60.0
+            }
+            None => 0.0,
+        };
+
+        let remaining_game_time = remaining_game_time(
+            game_controller_state.game_phase,
+            game_controller_state.half,
+            game_controller_state.remaining_time_in_half,
+            *context.half_duration,
+        );
+        let active_players = game_controller_state
+            .penalties
+            .iter()
+            .filter(|(_, penalty)| penalty.is_none())
+            .count()
+            .max(1);
+        let spendable_messages = remaining_messages.saturating_sub(
+            context
+                .spl_network
+                .remaining_amount_of_messages_to_stop_sending,
+        );
+        let planned_message_interval =
+            planned_message_interval(remaining_game_time, active_players, spendable_messages);
+        let high_priority_interval = context.spl_network.silence_interval_between_messages;
+        let low_priority_interval =
+            planned_message_interval.max(*context.minimum_low_priority_interval);
+        let normal_priority_interval = Duration::try_from_secs_f32(
+            low_priority_interval.as_secs_f32() * *context.normal_priority_interval_factor,
+        )
+        .unwrap_or(Duration::MAX)
+        .max(high_priority_interval);
+
+        Ok(MainOutputs {
+            message_budget: MessageBudget {
+                remaining_messages,
+                spendable_messages,
+                remaining_game_time,
+                active_players,
+                planned_message_interval,
+                low_priority_interval,
+                normal_priority_interval,
+                high_priority_interval,
+                team_messages_per_minute,
+                projected_remaining_messages: f32::from(remaining_messages)
+                    - team_messages_per_minute * remaining_game_time.as_secs_f32() / 60.0,
+            }
+            .into(),
+        })
+    }
+}
+
+/// The game controller only reports the time remaining in the current half
+fn remaining_game_time(
+    game_phase: GamePhase,
+    half: Half,
+    remaining_time_in_half: Duration,
+    half_duration: Duration,
+) -> Duration {
+    match (game_phase, half) {
+        (GamePhase::Normal | GamePhase::Timeout, Half::First) => {
+            remaining_time_in_half + half_duration
+        }
+        _ => remaining_time_in_half,
+    }
+}
+
+fn planned_message_interval(
+    remaining_game_time: Duration,
+    active_players: usize,
+    spendable_messages: u16,
+) -> Duration {
+    if spendable_messages == 0 {
+        return Duration::MAX;
+    }
+    remaining_game_time.mul_f32(active_players as f32 / f32::from(spendable_messages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_half_includes_second_half() {
+        let half_duration = Duration::from_secs(600);
+
+        assert_eq!(
+            remaining_game_time(
+                GamePhase::Normal,
+                Half::First,
+                Duration::from_secs(120),
+                half_duration
+            ),
+            Duration::from_secs(720)
+        );
+        assert_eq!(
+            remaining_game_time(
+                GamePhase::Normal,
+                Half::Second,
+                Duration::from_secs(120),
+                half_duration
+            ),
+            Duration::from_secs(120)
+        );
+    }
+
+    #[test]
+    fn budget_is_spread_over_players_and_time() {
+        let interval = planned_message_interval(Duration::from_secs(1200), 7, 1200);
+        assert!((interval.as_secs_f32() - 7.0).abs() < 1e-3);
+
+        let interval = planned_message_interval(Duration::from_secs(600), 5, 100);
+        assert!((interval.as_secs_f32() - 30.0).abs() < 1e-3);
+
+        assert_eq!(
+            planned_message_interval(Duration::from_secs(600), 5, 0),
+            Duration::MAX
+        );
+    }
+}
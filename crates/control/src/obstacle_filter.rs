@@ -9,9 +9,11 @@ use itertools::{chain, iproduct};
 use linear_algebra::{distance, point, IntoFramed, Isometry2, Point2};
 use nalgebra::Matrix2;
 use serde::{Deserialize, Serialize};
+use spl_network_messages::Team;
 use types::{
     cycle_time::CycleTime,
-    detected_feet::DetectedFeet,
+    detected_feet::DetectedFoot,
+    detected_robots::DetectedRobot,
     field_dimensions::FieldDimensions,
     foot_bumper_obstacle::FootBumperObstacle,
     multivariate_normal_distribution::MultivariateNormalDistribution,
@@ -54,8 +56,10 @@ pub struct CycleContext {
         Parameter<f32, "obstacle_filter.robot_obstacle_radius_at_hip_height">,
     unknown_obstacle_radius: Parameter<f32, "obstacle_filter.unknown_obstacle_radius">,
 
-    detected_feet_bottom: PerceptionInput<DetectedFeet, "VisionBottom", "detected_feet">,
-    detected_feet_top: PerceptionInput<DetectedFeet, "VisionTop", "detected_feet">,
+    detected_feet_bottom: PerceptionInput<Vec<DetectedFoot>, "VisionBottom", "detected_feet">,
+    detected_feet_top: PerceptionInput<Vec<DetectedFoot>, "VisionTop", "detected_feet">,
+    detected_robots_bottom: PerceptionInput<Vec<DetectedRobot>, "VisionBottom", "detected_robots">,
+    detected_robots_top: PerceptionInput<Vec<DetectedRobot>, "VisionTop", "detected_robots">,
 }
 
 #[context]
@@ -79,8 +83,11 @@ impl ObstacleFilter {
             .detected_feet_top
             .persistent
             .iter()
-            .zip(context.detected_feet_bottom.persistent.values());
-        for ((detection_time, feet_top), feet_bottom) in measurements {
+            .zip(context.detected_feet_bottom.persistent.values())
+            .zip(context.detected_robots_top.persistent.values())
+            .zip(context.detected_robots_bottom.persistent.values());
+        for ((((detection_time, feet_top), feet_bottom), robots_top), robots_bottom) in measurements
+        {
             let current_odometry_to_last_odometry = context
                 .current_odometry_to_last_odometry
                 .get(detection_time)
@@ -96,10 +103,12 @@ impl ObstacleFilter {
             let goal_posts =
                 calculate_goal_post_positions(current_ground_to_field.copied(), field_dimensions);
 
+            // only teammates send their positions
             for network_robot_obstacle in network_robot_obstacles {
                 self.update_hypotheses_with_measurement(
                     *network_robot_obstacle,
                     ObstacleKind::Robot,
+                    Team::Hulks,
                     *detection_time,
                     context
                         .obstacle_filter_parameters
@@ -112,19 +121,43 @@ impl ObstacleFilter {
                 );
             }
 
+            let detected_robots_in_control_cycle: Vec<_> = if context
+                .obstacle_filter_parameters
+                .use_robot_detection_measurements
+            {
+                robots_top
+                    .iter()
+                    .chain(robots_bottom.iter())
+                    .flat_map(|robots| robots.iter())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
             if context
                 .obstacle_filter_parameters
                 .use_feet_detection_measurements
             {
-                let measured_positions_in_control_cycle = feet_top
+                // feet of detected robots are already measured by the robot detection
+                let detected_feet_in_control_cycle = feet_top
                     .iter()
                     .chain(feet_bottom.iter())
-                    .flat_map(|obstacles| obstacles.positions.iter());
+                    .flat_map(|feet| feet.iter())
+                    .filter(|foot| {
+                        !is_foot_of_detected_robot(
+                            foot.position,
+                            &detected_robots_in_control_cycle,
+                            context
+                                .obstacle_filter_parameters
+                                .feet_to_robot_association_distance,
+                        )
+                    });
 
-                for position in measured_positions_in_control_cycle {
+                for foot in detected_feet_in_control_cycle {
                     self.update_hypotheses_with_measurement(
-                        *position,
+                        foot.position,
                         ObstacleKind::Robot,
+                        foot.team,
                         *detection_time,
                         context
                             .obstacle_filter_parameters
@@ -136,6 +169,21 @@ impl ObstacleFilter {
                 }
             }
 
+            for robot in detected_robots_in_control_cycle {
+                self.update_hypotheses_with_measurement(
+                    robot.position,
+                    ObstacleKind::Robot,
+                    robot.team,
+                    *detection_time,
+                    context
+                        .obstacle_filter_parameters
+                        .robot_detection_measurement_matching_distance,
+                    Matrix2::from_diagonal(
+                        &context.obstacle_filter_parameters.robot_measurement_noise,
+                    ),
+                );
+            }
+
             for sonar_obstacle in context.sonar_obstacles.get(detection_time) {
                 // TODO: Use a clever more intelligent metric
 
@@ -150,6 +198,7 @@ impl ObstacleFilter {
                     self.update_hypotheses_with_measurement(
                         sonar_obstacle.position,
                         ObstacleKind::Unknown,
+                        Team::Uncertain,
                         *detection_time,
                         context
                             .obstacle_filter_parameters
@@ -170,6 +219,7 @@ impl ObstacleFilter {
                     self.update_hypotheses_with_measurement(
                         foot_bumper_obstacle.position,
                         ObstacleKind::Unknown,
+                        Team::Uncertain,
                         *detection_time,
                         context
                             .obstacle_filter_parameters
@@ -219,6 +269,7 @@ impl ObstacleFilter {
                 Obstacle {
                     position: hypothesis.state.mean.framed().as_point(),
                     kind: hypothesis.obstacle_kind,
+                    team: hypothesis.team,
                     radius_at_hip_height,
                     radius_at_foot_height,
                 }
@@ -264,6 +315,7 @@ impl ObstacleFilter {
         &mut self,
         detected_position: Point2<Ground>,
         detected_obstacle_kind: ObstacleKind,
+        detected_team: Team,
         detection_time: SystemTime,
         matching_distance: f32,
         measurement_noise: Matrix2<f32>,
@@ -279,6 +331,7 @@ impl ObstacleFilter {
             self.spawn_hypothesis(
                 detected_position,
                 detected_obstacle_kind,
+                detected_team,
                 detection_time,
                 measurement_noise,
             );
@@ -295,6 +348,7 @@ impl ObstacleFilter {
                 ObstacleKind::Unknown => detected_obstacle_kind,
                 _ => panic!("Unexpected obstacle kind"),
             };
+            hypothesis.team = merge_teams(hypothesis.team, detected_team);
             hypothesis.measurement_count += 1;
             hypothesis.last_update = detection_time;
         });
@@ -304,6 +358,7 @@ impl ObstacleFilter {
         &mut self,
         detected_position: Point2<Ground>,
         obstacle_kind: ObstacleKind,
+        team: Team,
         detection_time: SystemTime,
         initial_covariance: Matrix2<f32>,
     ) {
@@ -314,6 +369,7 @@ impl ObstacleFilter {
                 covariance: initial_covariance,
             },
             obstacle_kind,
+            team,
             measurement_count: 1,
             last_update: detection_time,
         };
@@ -352,6 +408,8 @@ impl ObstacleFilter {
                         ObstacleKind::Unknown => hypothesis.obstacle_kind,
                         _ => panic!("Unexpected obstacle kind"),
                     };
+                    existing_hypothesis.team =
+                        merge_teams(existing_hypothesis.team, hypothesis.team);
                 }
                 None => deduplicated_hypotheses.push(hypothesis),
             }
@@ -360,6 +418,25 @@ impl ObstacleFilter {
     }
 }
 
+/// The latest certain team label wins, uncertain measurements keep the known team
+fn merge_teams(known_team: Team, measured_team: Team) -> Team {
+    match measured_team {
+        Team::Uncertain => known_team,
+        _ => measured_team,
+    }
+}
+
+/// Robot and feet detection both see the feet of a robot, these feet must not be counted twice
+fn is_foot_of_detected_robot(
+    foot: Point2<Ground>,
+    detected_robots: &[&DetectedRobot],
+    association_distance: f32,
+) -> bool {
+    detected_robots
+        .iter()
+        .any(|robot| distance(robot.position, foot) <= association_distance)
+}
+
 fn calculate_goal_post_positions(
     ground_to_field: Option<Isometry2<Ground, Field>>,
     field_dimensions: &FieldDimensions,
@@ -383,3 +460,30 @@ fn calculate_goal_post_positions(
         .flatten()
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use geometry::rectangle::Rectangle;
+
+    use super::*;
+
+    #[test]
+    fn only_feet_near_detected_robots_are_associated() {
+        let robot = DetectedRobot {
+            bounding_box: Rectangle {
+                min: point![300.0, 200.0],
+                max: point![340.0, 280.0],
+            },
+            position: point![2.0, 0.5],
+            team: Team::Opponent,
+        };
+
+        assert!(is_foot_of_detected_robot(point![2.05, 0.4], &[&robot], 0.3));
+        assert!(!is_foot_of_detected_robot(
+            point![1.0, -0.5],
+            &[&robot],
+            0.3
+        ));
+        assert!(!is_foot_of_detected_robot(point![2.05, 0.4], &[], 0.3));
+    }
+}
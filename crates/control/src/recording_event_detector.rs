@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+
+use color_eyre::Result;
+use context_attribute::context;
+use coordinate_systems::{Field, Ground};
+use framework::AdditionalOutput;
+use hardware::RecordingInterface;
+use linear_algebra::{distance, Isometry2, Point2};
+use serde::{Deserialize, Serialize};
+use spl_network_messages::PlayerNumber;
+use types::{
+    ball_position::BallPosition, fall_state::FallState,
+    filtered_game_controller_state::FilteredGameControllerState, filtered_whistle::FilteredWhistle,
+    recording_event::RecordingEvent,
+};
+
+#[derive(Deserialize, Serialize)]
+pub struct RecordingEventDetector {
+    was_falling: bool,
+    was_ball_seen: bool,
+    was_penalized: bool,
+    last_ground_to_field: Option<Isometry2<Ground, Field>>,
+}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    ball_position: Input<Option<BallPosition<Ground>>, "ball_position?">,
+    fall_state: Input<FallState, "fall_state">,
+    filtered_game_controller_state:
+        Input<Option<FilteredGameControllerState>, "filtered_game_controller_state?">,
+    filtered_whistle: Input<FilteredWhistle, "filtered_whistle">,
+    ground_to_field: Input<Option<Isometry2<Ground, Field>>, "ground_to_field?">,
+
+    enabled_events: Parameter<HashSet<RecordingEvent>, "recording_event_detector.enabled_events">,
+    localization_reset_distance:
+        Parameter<f32, "recording_event_detector.localization_reset_distance">,
+    player_number: Parameter<PlayerNumber, "player_number">,
+
+    recording_events: AdditionalOutput<Vec<RecordingEvent>, "recording_events">,
+
+    hardware_interface: HardwareInterface,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {}
+
+impl RecordingEventDetector {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {
+            was_falling: false,
+            was_ball_seen: false,
+            was_penalized: false,
+            last_ground_to_field: None,
+        })
+    }
+
+    pub fn cycle(
+        &mut self,
+        mut context: CycleContext<impl RecordingInterface>,
+    ) -> Result<MainOutputs> {
+        let mut events = Vec::new();
+
+        let is_falling = matches!(context.fall_state, FallState::Falling { .. });
+        if is_falling && !self.was_falling {
+            events.push(RecordingEvent::FallDetected);
+        }
+        self.was_falling = is_falling;
+
+        let is_ball_seen = context.ball_position.is_some();
+        if !is_ball_seen && self.was_ball_seen {
+            events.push(RecordingEvent::BallLost);
+        }
+        self.was_ball_seen = is_ball_seen;
+
+        if context.filtered_whistle.started_this_cycle {
+            events.push(RecordingEvent::WhistleHeard);
+        }
+
+        let is_penalized =
+            context
+                .filtered_game_controller_state
+                .is_some_and(|game_controller_state| {
+                    game_controller_state.penalties[*context.player_number].is_some()
+                });
+        if is_penalized && !self.was_penalized {
+            events.push(RecordingEvent::Penalized);
+        }
+        self.was_penalized = is_penalized;
+
+        if let (Some(last_ground_to_field), Some(ground_to_field)) =
+            (self.last_ground_to_field, context.ground_to_field)
+        {
+            let jump_distance = distance(
+                last_ground_to_field * Point2::origin(),
+                *ground_to_field * Point2::origin(),
+            );
+            if jump_distance > *context.localization_reset_distance {
+                events.push(RecordingEvent::LocalizationReset);
+            }
+        }
+        self.last_ground_to_field = context.ground_to_field.copied();
+
+        events.retain(|event| context.enabled_events.contains(event));
+        if !events.is_empty() {
+            context.hardware_interface.trigger_recording_event();
+        }
+        context.recording_events.fill_if_subscribed(|| events);
+
+        Ok(MainOutputs {})
+    }
+}
@@ -154,7 +154,7 @@ fn unpack_message_tree(
         .iter()
         .flat_map(|(time, messages)| messages.iter().map(|message| (*time, message)))
         .filter_map(|(time, message)| match message {
-            IncomingMessage::GameController(_, _) => None,
+            IncomingMessage::GameController(_, _) | IncomingMessage::MixedTeam(_) => None,
             IncomingMessage::Spl(message) => Some((time, *message)),
         })
         .collect()
@@ -182,6 +182,8 @@ fn send_own_detection_message<T: NetworkInterface>(
         pose: Isometry2::<Ground, Field>::default().as_pose(),
         is_referee_ready_signal_detected: true,
         ball_position: None,
+        ball_covariance: None,
         time_to_reach_kick_position: Some(time_to_reach_kick_position),
+        role_costs: None,
     }))
 }
@@ -4,15 +4,17 @@ use std::{
 };
 
 use color_eyre::{eyre::WrapErr, Result};
+use nalgebra::Matrix2;
 use serde::{Deserialize, Serialize};
 
 use context_attribute::context;
 use coordinate_systems::{Field, Ground};
-use framework::{MainOutput, PerceptionInput};
+use framework::{AdditionalOutput, MainOutput, PerceptionInput};
 use hardware::NetworkInterface;
-use linear_algebra::{Isometry2, Point2, Vector};
+use linear_algebra::{distance, point, Isometry2, Point2};
 use spl_network_messages::{
-    GameControllerReturnMessage, GamePhase, HulkMessage, Penalty, PlayerNumber, SubState, Team,
+    BallCovariance, GameControllerReturnMessage, GamePhase, HulkMessage, MixedTeamIntention,
+    MixedTeamMessage, PlayerNumber, SubState, Team, HULKS_TEAM_NUMBER, NUMBER_OF_AUCTIONED_ROLES,
 };
 use types::{
     ball_position::BallPosition,
@@ -21,8 +23,10 @@ use types::{
     field_dimensions::FieldDimensions,
     filtered_game_controller_state::FilteredGameControllerState,
     initial_pose::InitialPose,
+    message_budget::{MessageBudget, MessagePriority},
     messages::{IncomingMessage, OutgoingMessage},
-    parameters::SplNetworkParameters,
+    multivariate_normal_distribution::MultivariateNormalDistribution,
+    parameters::{RoleAuctionParameters, SplMessageCodec, SplNetworkParameters},
     players::Players,
     primary_state::PrimaryState,
     roles::Role,
@@ -30,14 +34,29 @@ use types::{
 
 use crate::localization::generate_initial_pose;
 
+/// Costs of one robot for every auctioned role in milliseconds
+type RoleCosts = [u16; NUMBER_OF_AUCTIONED_ROLES];
+
+/// Assigns roles with an auction over the role costs of all robots
+///
+/// Every robot broadcasts its costs for all auctioned roles and solves the same assignment from
+/// the latest costs of all unpenalized robots. Since the own costs enter the assignment exactly as
+/// they were sent, all robots agree on the roles as long as they received the same messages.
+///
+/// In mixed team games, robots of other teams do not send role costs. Their costs are derived from
+/// the pose, ball and fallen state of their mixed team messages and the roles they claim by their
+/// intention are left to them. Our own robots send mixed team messages as well and derive their
+/// costs the same way, so the assignment stays consistent.
 #[derive(Deserialize, Serialize)]
 pub struct RoleAssignment {
-    last_received_spl_striker_message: Option<SystemTime>,
     last_system_time_transmitted_game_controller_return_message: Option<SystemTime>,
-    last_transmitted_spl_striker_message: Option<SystemTime>,
+    last_transmitted_spl_message: Option<(SystemTime, HulkMessage)>,
+    assigned_role_at_last_message: Option<Role>,
+    role_costs: Players<Option<(SystemTime, RoleCosts)>>,
+    foreign_role_costs: Players<Option<(SystemTime, RoleCosts)>>,
+    assigned_role: Option<Role>,
     role: Role,
-    role_initialized: bool,
-    team_ball: Option<BallPosition<Field>>,
+    playing_since: Option<SystemTime>,
     last_time_keeper_penalized: Option<SystemTime>,
 }
 
@@ -46,23 +65,31 @@ pub struct CreationContext {}
 
 #[context]
 pub struct CycleContext {
+    assignment: AdditionalOutput<Players<Option<Role>>, "role_assignment.assignment">,
+
     ball_position: Input<Option<BallPosition<Ground>>, "ball_position?">,
+    ball_position_distribution:
+        Input<Option<MultivariateNormalDistribution<2>>, "ball_position_distribution?">,
     fall_state: Input<FallState, "fall_state">,
     filtered_game_controller_state:
         Input<Option<FilteredGameControllerState>, "filtered_game_controller_state?">,
     primary_state: Input<PrimaryState, "primary_state">,
     ground_to_field: Input<Option<Isometry2<Ground, Field>>, "ground_to_field?">,
     cycle_time: Input<CycleTime, "cycle_time">,
+    team_ball: Input<Option<BallPosition<Field>>, "team_ball?">,
     network_message: PerceptionInput<Option<IncomingMessage>, "SplNetwork", "filtered_message?">,
     game_controller_address: Input<Option<SocketAddr>, "game_controller_address?">,
+    message_budget: Input<MessageBudget, "message_budget">,
     time_to_reach_kick_position: CyclerState<Duration, "time_to_reach_kick_position">,
 
     field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
     forced_role: Parameter<Option<Role>, "role_assignment.forced_role?">,
     keeper_replacementkeeper_switch_time:
         Parameter<Duration, "role_assignment.keeper_replacementkeeper_switch_time">,
+    auction: Parameter<RoleAuctionParameters, "role_assignment.auction">,
     initial_poses: Parameter<Players<InitialPose>, "localization.initial_poses">,
     optional_roles: Parameter<Vec<Role>, "behavior.optional_roles">,
+    walking_speed: Parameter<f32, "behavior.path_planning.line_walking_speed">,
     player_number: Parameter<PlayerNumber, "player_number">,
     spl_network: Parameter<SplNetworkParameters, "spl_network">,
 
@@ -72,7 +99,6 @@ pub struct CycleContext {
 #[context]
 #[derive(Default)]
 pub struct MainOutputs {
-    pub team_ball: MainOutput<Option<BallPosition<Field>>>,
     pub network_robot_obstacles: MainOutput<Vec<Point2<Ground>>>,
     pub role: MainOutput<Role>,
 }
@@ -80,20 +106,25 @@ pub struct MainOutputs {
 impl RoleAssignment {
     pub fn new(_context: CreationContext) -> Result<Self> {
         Ok(Self {
-            last_received_spl_striker_message: None,
             last_system_time_transmitted_game_controller_return_message: None,
-            last_transmitted_spl_striker_message: None,
+            last_transmitted_spl_message: None,
+            assigned_role_at_last_message: None,
+            role_costs: Default::default(),
+            foreign_role_costs: Default::default(),
+            assigned_role: None,
             role: Role::Striker,
-            role_initialized: false,
-            team_ball: None,
+            playing_since: None,
             last_time_keeper_penalized: None,
         })
     }
 
-    pub fn cycle(&mut self, context: CycleContext<impl NetworkInterface>) -> Result<MainOutputs> {
+    pub fn cycle(
+        &mut self,
+        mut context: CycleContext<impl NetworkInterface>,
+    ) -> Result<MainOutputs> {
         let cycle_start_time = context.cycle_time.start_time;
         let primary_state = *context.primary_state;
-        let mut role = self.role;
+        let player_number = *context.player_number;
 
         let ground_to_field =
             context
@@ -101,48 +132,13 @@ impl RoleAssignment {
                 .copied()
                 .unwrap_or_else(|| match context.primary_state {
                     PrimaryState::Initial => generate_initial_pose(
-                        &context.initial_poses[*context.player_number],
+                        &context.initial_poses[player_number],
                         context.field_dimensions,
                     )
                     .as_transform(),
                     _ => Default::default(),
                 });
 
-        if !self.role_initialized
-            || primary_state == PrimaryState::Ready
-            || primary_state == PrimaryState::Set
-        {
-            #[allow(clippy::get_first)]
-            let mut player_roles = Players {
-                one: Role::Keeper,
-                two: context.optional_roles.get(0).copied().unwrap_or_default(),
-                three: context.optional_roles.get(1).copied().unwrap_or_default(),
-                four: context.optional_roles.get(2).copied().unwrap_or_default(),
-                five: context.optional_roles.get(3).copied().unwrap_or_default(),
-                six: context.optional_roles.get(4).copied().unwrap_or_default(),
-                seven: Role::Striker,
-            };
-
-            if let Some(game_controller_state) = context.filtered_game_controller_state {
-                if let Some(striker) = [
-                    PlayerNumber::Seven,
-                    PlayerNumber::Six,
-                    PlayerNumber::Five,
-                    PlayerNumber::Four,
-                ]
-                .into_iter()
-                .find(|player| game_controller_state.penalties[*player].is_none())
-                {
-                    player_roles[striker] = Role::Striker;
-                }
-            }
-            role = player_roles[*context.player_number];
-
-            self.role_initialized = true;
-            self.last_received_spl_striker_message = Some(cycle_start_time);
-            self.team_ball = None;
-        }
-
         let send_game_controller_return_message = self
             .last_system_time_transmitted_game_controller_return_message
             .is_none()
@@ -151,27 +147,6 @@ impl RoleAssignment {
                     .unwrap(),
             )? > context.spl_network.game_controller_return_message_interval;
 
-        let mut send_spl_striker_message = self.last_transmitted_spl_striker_message.is_none()
-            || cycle_start_time
-                .duration_since(self.last_transmitted_spl_striker_message.unwrap())?
-                > context.spl_network.spl_striker_message_send_interval;
-
-        let spl_striker_message_timeout = match self.last_received_spl_striker_message {
-            None => false,
-            Some(last_received_spl_striker_message) => {
-                cycle_start_time.duration_since(last_received_spl_striker_message)?
-                    > context.spl_network.spl_striker_message_receive_timeout
-            }
-        };
-
-        let silence_interval_has_passed = match self.last_transmitted_spl_striker_message {
-            Some(last_transmitted_spl_striker_message) => {
-                cycle_start_time.duration_since(last_transmitted_spl_striker_message)?
-                    > context.spl_network.silence_interval_between_messages
-            }
-            None => true,
-        };
-
         if send_game_controller_return_message {
             self.last_system_time_transmitted_game_controller_return_message =
                 Some(cycle_start_time);
@@ -181,7 +156,7 @@ impl RoleAssignment {
                     .write_to_network(OutgoingMessage::GameController(
                         *address,
                         GameControllerReturnMessage {
-                            player_number: *context.player_number,
+                            player_number,
                             fallen: matches!(context.fall_state, FallState::Fallen { .. }),
                             pose: ground_to_field.as_pose(),
                             ball: seen_ball_to_game_controller_ball_position(
@@ -194,431 +169,513 @@ impl RoleAssignment {
             }
         }
 
-        let mut team_ball = self.team_ball;
-
-        if spl_striker_message_timeout {
-            match role {
-                Role::Keeper => {
-                    team_ball = None;
-                }
-                Role::ReplacementKeeper => {
-                    team_ball = None;
-                }
-                Role::Striker => {
-                    send_spl_striker_message = true;
-                    team_ball = None;
-                    role = Role::Loser;
+        let roles = auctioned_roles(context.optional_roles);
+        let message_codec = context.spl_network.message_codec;
+        let mut network_robot_obstacles = vec![];
+        for (time, message) in context
+            .network_message
+            .persistent
+            .iter()
+            .flat_map(|(time, messages)| messages.iter().map(move |message| (*time, message)))
+        {
+            match message {
+                Some(IncomingMessage::Spl(message)) if message.player_number != player_number => {
+                    network_robot_obstacles
+                        .push(ground_to_field.inverse() * message.pose.position());
+                    if let Some(role_costs) = message.role_costs {
+                        self.role_costs[message.player_number] = Some((time, role_costs));
+                    }
                 }
-                _ => {
-                    send_spl_striker_message = false;
-                    team_ball = None;
-                    role = Role::Searcher
+                Some(IncomingMessage::MixedTeam(message))
+                    if message.team_number != HULKS_TEAM_NUMBER
+                        || message.player_number != player_number =>
+                {
+                    network_robot_obstacles
+                        .push(ground_to_field.inverse() * message.pose.position());
+                    if message_codec == SplMessageCodec::MixedTeam {
+                        let costs = mixed_team_role_costs(
+                            &roles,
+                            message,
+                            context.field_dimensions,
+                            *context.walking_speed,
+                            context.auction,
+                        );
+                        // robots of both teams use the same player numbers
+                        let role_costs = if message.team_number == HULKS_TEAM_NUMBER {
+                            &mut self.role_costs
+                        } else {
+                            &mut self.foreign_role_costs
+                        };
+                        role_costs[message.player_number] = Some((time, costs));
+                    }
                 }
+                _ => {}
             }
         }
 
-        let mut network_robot_obstacles = vec![];
-        let mut spl_messages = context
-            .network_message
-            .persistent
-            .into_values()
-            .flatten()
-            .filter_map(|message| match message {
-                Some(IncomingMessage::GameController(..)) | None => None,
-                Some(IncomingMessage::Spl(message)) => Some(message),
-            })
-            .peekable();
-        if spl_messages.peek().is_none() {
-            (role, send_spl_striker_message, team_ball) = process_role_state_machine(
-                role,
-                ground_to_field,
+        if let Some(game_controller_state) = context.filtered_game_controller_state {
+            if game_controller_state.penalties.one.is_some() {
+                self.last_time_keeper_penalized = Some(cycle_start_time);
+            }
+        }
+        let keeper_recently_penalized =
+            self.last_time_keeper_penalized
+                .is_some_and(|last_time_keeper_penalized| {
+                    cycle_start_time
+                        .duration_since(last_time_keeper_penalized)
+                        .expect("Keeper was penalized in the Future")
+                        < *context.keeper_replacementkeeper_switch_time
+                });
+
+        let own_ball = context
+            .ball_position
+            .map(|ball_position| ground_to_field * ball_position.position);
+        let ball = own_ball.or(context.team_ball.map(|team_ball| team_ball.position));
+
+        let mut candidates: Players<Option<RoleCosts>> = Players::default();
+        let mut foreign_candidates: Players<Option<RoleCosts>> = Players::default();
+        if primary_state == PrimaryState::Playing {
+            let playing_since = *self.playing_since.get_or_insert(cycle_start_time);
+            let fallen = matches!(context.fall_state, FallState::Fallen { .. });
+            let ball_position = seen_ball_to_hulks_network_ball_position(
                 context.ball_position,
-                primary_state,
-                None,
-                Some(*context.time_to_reach_kick_position),
-                send_spl_striker_message,
-                team_ball,
+                ground_to_field,
                 cycle_start_time,
-                context.filtered_game_controller_state,
-                *context.player_number,
-                context.spl_network.striker_trusts_team_ball,
-                context.optional_roles,
             );
-        } else {
-            for spl_message in spl_messages {
-                self.last_received_spl_striker_message = Some(cycle_start_time);
-                let sender_position = ground_to_field.inverse() * spl_message.pose.position();
-                if spl_message.player_number != *context.player_number {
-                    network_robot_obstacles.push(sender_position);
-                }
-                (role, send_spl_striker_message, team_ball) = process_role_state_machine(
-                    role,
-                    ground_to_field,
-                    context.ball_position,
-                    primary_state,
-                    Some(spl_message),
-                    Some(*context.time_to_reach_kick_position),
-                    send_spl_striker_message,
-                    team_ball,
-                    cycle_start_time,
-                    context.filtered_game_controller_state,
-                    *context.player_number,
-                    context.spl_network.striker_trusts_team_ball,
-                    context.optional_roles,
-                );
+            let mixed_team_message = MixedTeamMessage {
+                team_number: HULKS_TEAM_NUMBER,
+                player_number,
+                fallen,
+                intention: mixed_team_intention(self.role),
+                pose: ground_to_field.as_pose(),
+                ball_position,
+            };
+            let own_costs = match message_codec {
+                SplMessageCodec::Hulks => role_costs(
+                    &roles,
+                    player_number,
+                    ground_to_field.as_pose().position(),
+                    ball,
+                    *context.time_to_reach_kick_position,
+                    fallen,
+                    self.assigned_role,
+                    context.field_dimensions,
+                    *context.walking_speed,
+                    context.auction,
+                ),
+                SplMessageCodec::MixedTeam => mixed_team_role_costs(
+                    &roles,
+                    &mixed_team_message,
+                    context.field_dimensions,
+                    *context.walking_speed,
+                    context.auction,
+                ),
+            };
+            let message = HulkMessage {
+                player_number,
+                fallen,
+                pose: ground_to_field.as_pose(),
+                is_referee_ready_signal_detected: false,
+                ball_position,
+                ball_covariance: context.ball_position_distribution.map(|distribution| {
+                    covariance_in_field(distribution.covariance, ground_to_field)
+                }),
+                time_to_reach_kick_position: Some(*context.time_to_reach_kick_position),
+                role_costs: Some(own_costs),
+            };
+            let priority = message_priority(
+                self.last_transmitted_spl_message
+                    .as_ref()
+                    .map(|(_, last_message)| last_message),
+                &message,
+                self.assigned_role != self.assigned_role_at_last_message,
+                context.auction.cost_change_threshold,
+            );
+            let time_since_last_message = self
+                .last_transmitted_spl_message
+                .map(|(time, _)| cycle_start_time.duration_since(time))
+                .transpose()?;
+            if context
+                .message_budget
+                .allows(priority, time_since_last_message)
+            {
+                self.last_transmitted_spl_message = Some((cycle_start_time, message));
+                self.assigned_role_at_last_message = self.assigned_role;
+                self.role_costs[player_number] = Some((cycle_start_time, own_costs));
+                context.hardware.write_to_network(match message_codec {
+                    SplMessageCodec::Hulks => OutgoingMessage::Spl(message),
+                    SplMessageCodec::MixedTeam => OutgoingMessage::MixedTeam(mixed_team_message),
+                })?;
             }
-        }
 
-        if let Some(last_time_keeper_penalized) = self.last_time_keeper_penalized {
-            let deny_replacement_keeper_switch = cycle_start_time
-                .duration_since(last_time_keeper_penalized)
-                .expect("Keeper was penalized in the Future")
-                < *context.keeper_replacementkeeper_switch_time;
-            if self.role == Role::ReplacementKeeper
-                && !send_spl_striker_message
-                && deny_replacement_keeper_switch
-            {
-                role = Role::ReplacementKeeper;
+            // teammates send less often if the message budget is tight
+            let role_costs_timeout = context.spl_network.role_costs_timeout.max(
+                context
+                    .message_budget
+                    .low_priority_interval
+                    .saturating_mul(2),
+            );
+            let is_fresh = |time: SystemTime| {
+                cycle_start_time.duration_since(time).unwrap_or_default() <= role_costs_timeout
+            };
+            let in_grace_period = cycle_start_time
+                .duration_since(playing_since)
+                .unwrap_or_default()
+                <= role_costs_timeout;
+            for (player, received_costs) in self.role_costs.iter() {
+                let received_costs = received_costs
+                    .filter(|(time, _)| is_fresh(*time))
+                    .map(|(_, costs)| costs);
+                candidates[player] = match received_costs {
+                    Some(costs) => Some(costs),
+                    None if player == player_number => Some(own_costs),
+                    // teammates which did not send their costs yet keep their lineup role
+                    None if in_grace_period => Some(static_role_costs(player, roles.len())),
+                    None => None,
+                };
+            }
+            for (player, received_costs) in self.foreign_role_costs.iter() {
+                foreign_candidates[player] = received_costs
+                    .filter(|(time, _)| is_fresh(*time))
+                    .map(|(_, costs)| costs);
+            }
+        } else {
+            // the grace period belongs to the game, not to a single penalty of this robot
+            if primary_state != PrimaryState::Penalized {
+                self.playing_since = None;
             }
+            candidates = static_candidates(roles.len());
         }
 
-        if send_spl_striker_message
-            && primary_state == PrimaryState::Playing
-            && silence_interval_has_passed
-        {
-            self.last_transmitted_spl_striker_message = Some(cycle_start_time);
-            self.last_received_spl_striker_message = Some(cycle_start_time);
-            if let Some(game_controller_state) = context.filtered_game_controller_state {
-                if game_controller_state.remaining_number_of_messages
-                    > context
-                        .spl_network
-                        .remaining_amount_of_messages_to_stop_sending
-                {
-                    let ball_position = if context.ball_position.is_none() && team_ball.is_some() {
-                        team_ball_to_network_ball_position(team_ball, cycle_start_time)
-                    } else {
-                        seen_ball_to_hulks_network_ball_position(
-                            context.ball_position,
-                            ground_to_field,
-                            cycle_start_time,
-                        )
-                    };
-                    context
-                        .hardware
-                        .write_to_network(OutgoingMessage::Spl(HulkMessage {
-                            player_number: *context.player_number,
-                            fallen: matches!(context.fall_state, FallState::Fallen { .. }),
-                            pose: ground_to_field.as_pose(),
-                            is_referee_ready_signal_detected: false,
-                            ball_position,
-                            time_to_reach_kick_position: Some(*context.time_to_reach_kick_position),
-                        }))?;
+        if let Some(game_controller_state) = context.filtered_game_controller_state {
+            for (player, penalty) in game_controller_state.penalties.iter() {
+                if penalty.is_some() {
+                    candidates[player] = None;
                 }
             }
         }
-
-        if let Some(forced_role) = context.forced_role {
-            self.role = *forced_role;
-        } else {
-            self.role = role;
+        if keeper_recently_penalized {
+            if let Some(costs) = candidates.one.as_mut() {
+                costs[0] = u16::MAX;
+            }
         }
-        self.team_ball = team_ball;
 
+        let assignment = assign_roles(&roles, &candidates, &foreign_candidates);
+        context.assignment.fill_if_subscribed(|| assignment);
+        self.assigned_role = assignment[player_number];
+
+        let mut role = match assignment[player_number] {
+            Some(Role::Keeper) if player_number != PlayerNumber::One => Role::ReplacementKeeper,
+            Some(role) => role,
+            None => Role::Searcher,
+        };
+        if primary_state == PrimaryState::Playing && ball.is_none() {
+            role = match role {
+                Role::Striker => Role::Loser,
+                Role::Keeper | Role::ReplacementKeeper => role,
+                _ => Role::Searcher,
+            };
+        }
         if let Some(game_controller_state) = context.filtered_game_controller_state {
-            if game_controller_state.penalties.one.is_some() {
-                self.last_time_keeper_penalized = Some(cycle_start_time);
+            match game_controller_state.game_phase {
+                GamePhase::PenaltyShootout {
+                    kicking_team: Team::Hulks,
+                } => role = Role::Striker,
+                GamePhase::PenaltyShootout {
+                    kicking_team: Team::Opponent,
+                } => role = Role::Keeper,
+                _ => {
+                    if let Some(SubState::PenaltyKick) = game_controller_state.sub_state {
+                        role = self.role;
+                    }
+                }
             }
         }
 
+        self.role = context.forced_role.copied().unwrap_or(role);
+
         Ok(MainOutputs {
             role: self.role.into(),
-            team_ball: self.team_ball.into(),
             network_robot_obstacles: network_robot_obstacles.into(),
         })
     }
 }
 
+/// High priority changes require teammates to react, changed role costs may change the assignment
+fn message_priority(
+    last_message: Option<&HulkMessage>,
+    message: &HulkMessage,
+    role_changed: bool,
+    cost_change_threshold: Duration,
+) -> MessagePriority {
+    let Some(last_message) = last_message else {
+        return MessagePriority::High;
+    };
+    let fallen = message.fallen && !last_message.fallen;
+    let ball_found = message.ball_position.is_some() && last_message.ball_position.is_none();
+    if role_changed || fallen || ball_found {
+        return MessagePriority::High;
+    }
+    let costs_changed = match (last_message.role_costs, message.role_costs) {
+        (Some(last_costs), Some(costs)) => last_costs.iter().zip(costs).any(|(last_cost, cost)| {
+            u128::from(last_cost.abs_diff(cost)) > cost_change_threshold.as_millis()
+        }),
+        (last_costs, costs) => last_costs.is_some() != costs.is_some(),
+    };
+    if costs_changed {
+        MessagePriority::Normal
+    } else {
+        MessagePriority::Low
+    }
+}
+
+/// Roles in the order of their importance, only the first ones are assigned if robots are missing
+fn auctioned_roles(optional_roles: &[Role]) -> Vec<Role> {
+    [Role::Keeper, Role::Striker]
+        .into_iter()
+        .chain(optional_roles.iter().copied())
+        .take(NUMBER_OF_AUCTIONED_ROLES)
+        .collect()
+}
+
+fn player_index(player_number: PlayerNumber) -> usize {
+    match player_number {
+        PlayerNumber::One => 1,
+        PlayerNumber::Two => 2,
+        PlayerNumber::Three => 3,
+        PlayerNumber::Four => 4,
+        PlayerNumber::Five => 5,
+        PlayerNumber::Six => 6,
+        PlayerNumber::Seven => 7,
+    }
+}
+
+/// Costs reproducing the lineup by player number: player one is keeper, player seven is striker
+/// and the players in between take the optional roles in order
+///
+/// Replacements for the keeper are preferred from low player numbers, replacements for the
+/// striker from high player numbers.
+fn static_role_costs(player_number: PlayerNumber, number_of_roles: usize) -> RoleCosts {
+    const MISMATCH_COST: u16 = 10_000;
+    const RANK_COST: u16 = 1_000;
+
+    let index = player_index(player_number);
+    let lineup_role_index = match player_number {
+        PlayerNumber::One => 0,
+        PlayerNumber::Seven => 1,
+        _ => index,
+    };
+    let mut costs = [u16::MAX; NUMBER_OF_AUCTIONED_ROLES];
+    for (role_index, cost) in costs.iter_mut().enumerate().take(number_of_roles) {
+        let rank = if role_index == 1 { 7 - index } else { index };
+        *cost = if role_index == lineup_role_index {
+            0
+        } else {
+            MISMATCH_COST + RANK_COST * rank as u16
+        };
+    }
+    costs
+}
+
+fn static_candidates(number_of_roles: usize) -> Players<Option<RoleCosts>> {
+    Players {
+        one: Some(static_role_costs(PlayerNumber::One, number_of_roles)),
+        two: Some(static_role_costs(PlayerNumber::Two, number_of_roles)),
+        three: Some(static_role_costs(PlayerNumber::Three, number_of_roles)),
+        four: Some(static_role_costs(PlayerNumber::Four, number_of_roles)),
+        five: Some(static_role_costs(PlayerNumber::Five, number_of_roles)),
+        six: Some(static_role_costs(PlayerNumber::Six, number_of_roles)),
+        seven: Some(static_role_costs(PlayerNumber::Seven, number_of_roles)),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
-fn process_role_state_machine(
-    current_role: Role,
-    current_pose: Isometry2<Ground, Field>,
-    detected_own_ball: Option<&BallPosition<Ground>>,
-    primary_state: PrimaryState,
-    incoming_message: Option<&HulkMessage>,
-    time_to_reach_kick_position: Option<Duration>,
-    send_spl_striker_message: bool,
-    team_ball: Option<BallPosition<Field>>,
-    cycle_start_time: SystemTime,
-    filtered_game_controller_state: Option<&FilteredGameControllerState>,
+fn role_costs(
+    roles: &[Role],
     player_number: PlayerNumber,
-    striker_trusts_team_ball: Duration,
-    optional_roles: &[Role],
-) -> (Role, bool, Option<BallPosition<Field>>) {
-    if let Some(game_controller_state) = filtered_game_controller_state {
-        match game_controller_state.game_phase {
-            GamePhase::PenaltyShootout {
-                kicking_team: Team::Hulks,
-            } => return (Role::Striker, false, None),
-            GamePhase::PenaltyShootout {
-                kicking_team: Team::Opponent,
-            } => return (Role::Keeper, false, None),
-            _ => {}
+    position: Point2<Field>,
+    ball: Option<Point2<Field>>,
+    time_to_reach_kick_position: Duration,
+    fallen: bool,
+    assigned_role: Option<Role>,
+    field_dimensions: &FieldDimensions,
+    walking_speed: f32,
+    parameters: &RoleAuctionParameters,
+) -> RoleCosts {
+    let walking_time = |target: Point2<Field>| {
+        Duration::try_from_secs_f32(distance(position, target) / walking_speed)
+            .unwrap_or(Duration::MAX)
+    };
+    let own_goal = point![-field_dimensions.length / 2.0, 0.0];
+
+    let mut costs = [u16::MAX; NUMBER_OF_AUCTIONED_ROLES];
+    for (cost, role) in costs.iter_mut().zip(roles) {
+        let is_keeper = *role == Role::Keeper && player_number == PlayerNumber::One;
+        let duration = match role {
+            Role::Keeper if is_keeper => Duration::ZERO,
+            Role::Keeper | Role::ReplacementKeeper => {
+                walking_time(own_goal) + parameters.replacement_keeper_penalty
+            }
+            // already contains the time to stand up
+            Role::Striker if ball.is_some() => time_to_reach_kick_position,
+            Role::Striker => Duration::MAX,
+            Role::DefenderLeft => walking_time(parameters.defender_left_position),
+            Role::DefenderRight => walking_time(parameters.defender_right_position),
+            Role::MidfielderLeft => walking_time(parameters.midfielder_left_position),
+            Role::MidfielderRight => walking_time(parameters.midfielder_right_position),
+            Role::StrikerSupporter => walking_time(ball.map_or(Point2::origin(), |ball| {
+                ball + parameters.striker_supporter_offset_to_ball
+            })),
+            Role::Loser | Role::Searcher => walking_time(Point2::origin()),
         };
-        if let Some(SubState::PenaltyKick) = game_controller_state.sub_state {
-            return (current_role, false, None);
-        }
+        let duration = if fallen && *role != Role::Striker && !is_keeper {
+            duration.saturating_add(parameters.fallen_penalty)
+        } else {
+            duration
+        };
+        let duration = if assigned_role == Some(*role) {
+            duration.saturating_sub(parameters.role_switch_hysteresis)
+        } else {
+            duration
+        };
+        *cost = duration.as_millis().min(u128::from(u16::MAX)) as u16;
     }
+    costs
+}
 
-    if primary_state != PrimaryState::Playing {
-        match detected_own_ball {
-            None => return (current_role, false, team_ball),
-            Some(..) => {
-                return (
-                    current_role,
-                    false,
-                    team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-                )
-            }
-        }
+/// Roles the sender of a mixed team message claims by its intention
+fn claimed_roles(intention: MixedTeamIntention) -> &'static [Role] {
+    match intention {
+        MixedTeamIntention::Keeper => &[Role::Keeper],
+        MixedTeamIntention::Defend => &[Role::DefenderLeft, Role::DefenderRight],
+        MixedTeamIntention::PlayBall => &[Role::Striker],
+        MixedTeamIntention::Nothing | MixedTeamIntention::Lost => &[],
     }
+}
 
-    if let Some(message) = incoming_message {
-        if message.player_number == player_number {
-            return (current_role, false, team_ball);
+fn mixed_team_intention(role: Role) -> MixedTeamIntention {
+    match role {
+        Role::Keeper | Role::ReplacementKeeper => MixedTeamIntention::Keeper,
+        Role::DefenderLeft | Role::DefenderRight => MixedTeamIntention::Defend,
+        Role::Striker => MixedTeamIntention::PlayBall,
+        Role::Loser | Role::Searcher => MixedTeamIntention::Lost,
+        Role::MidfielderLeft | Role::MidfielderRight | Role::StrikerSupporter => {
+            MixedTeamIntention::Nothing
         }
     }
+}
 
-    match (current_role, detected_own_ball, incoming_message) {
-        //Striker maybe lost Ball
-        (Role::Striker, None, None) => match team_ball {
-            None => (Role::Loser, true, None),
-            Some(team_ball) => {
-                if cycle_start_time
-                    .duration_since(team_ball.last_seen)
-                    .unwrap()
-                    > striker_trusts_team_ball
-                {
-                    (Role::Loser, true, None)
-                } else {
-                    (Role::Striker, send_spl_striker_message, Some(team_ball))
-                }
-            }
-        },
-
-        // Striker maybe lost Ball but got a message (edge-case)
-        (Role::Striker, None, Some(spl_message)) => match &spl_message.ball_position {
-            None => {
-                // another Striker became Loser
-                match team_ball {
-                    None => (Role::Loser, true, None),
-                    Some(team_ball) => {
-                        if cycle_start_time
-                            .duration_since(team_ball.last_seen)
-                            .unwrap()
-                            > striker_trusts_team_ball
-                        {
-                            (Role::Loser, true, None)
-                        } else {
-                            (Role::Striker, send_spl_striker_message, Some(team_ball))
-                        }
-                    }
-                }
-            }
-            _ => decide_if_claiming_striker_or_other_role(
-                spl_message,
-                time_to_reach_kick_position,
-                player_number,
-                cycle_start_time,
-                filtered_game_controller_state,
-                optional_roles,
+/// Costs derived from everything a mixed team message contains
+///
+/// Robots of other teams do not take part in the auction and follow their intention regardless of
+/// the assignment, so the roles they claim are free for them and all other roles are excluded. The
+/// intention of our own robots only acts like their assigned role.
+fn mixed_team_role_costs(
+    roles: &[Role],
+    message: &MixedTeamMessage,
+    field_dimensions: &FieldDimensions,
+    walking_speed: f32,
+    parameters: &RoleAuctionParameters,
+) -> RoleCosts {
+    let position = message.pose.position();
+    let ball = message.ball_position.map(|ball| ball.position);
+    let time_to_reach_ball = ball.map_or(Duration::MAX, |ball| {
+        Duration::try_from_secs_f32(distance(position, ball) / walking_speed)
+            .unwrap_or(Duration::MAX)
+    });
+    let claimed_roles = claimed_roles(message.intention);
+    let mut costs = role_costs(
+        roles,
+        message.player_number,
+        position,
+        ball,
+        time_to_reach_ball,
+        message.fallen,
+        None,
+        field_dimensions,
+        walking_speed,
+        parameters,
+    );
+    let is_foreign = message.team_number != HULKS_TEAM_NUMBER;
+    for (cost, role) in costs.iter_mut().zip(roles) {
+        let is_claimed = claimed_roles.contains(role);
+        *cost = match (is_foreign, is_claimed) {
+            (true, true) => 0,
+            (true, false) if !claimed_roles.is_empty() => u16::MAX,
+            (false, true) => cost.saturating_sub(
+                parameters
+                    .role_switch_hysteresis
+                    .as_millis()
+                    .min(u128::from(u16::MAX)) as u16,
             ),
-        },
-
-        //Striker remains Striker, sends message after timeout
-        (Role::Striker, Some(..), None) => (Role::Striker, send_spl_striker_message, team_ball),
-
-        // Striker got a message (either another Player claims Stiker role or Edge-case of a second Striker)
-        (Role::Striker, Some(..), Some(spl_message)) => match &spl_message.ball_position {
-            None => {
-                // another Striker became Loser, so we claim striker since we see a ball
-                (
-                    Role::Striker,
-                    true,
-                    team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-                )
-            }
-            _ => decide_if_claiming_striker_or_other_role(
-                spl_message,
-                time_to_reach_kick_position,
-                player_number,
-                cycle_start_time,
-                filtered_game_controller_state,
-                optional_roles,
-            ),
-        },
-
-        //Loser remains Loser
-        (Role::Loser, None, None) => (Role::Loser, false, team_ball),
+            _ => *cost,
+        };
+    }
+    costs
+}
 
-        (Role::Loser, None, Some(spl_message)) => match &spl_message.ball_position {
-            None => (Role::Loser, false, None), //edge-case, a striker (which should not exist) lost the ball
-            _ => decide_if_claiming_striker_or_other_role(
-                spl_message,
-                time_to_reach_kick_position,
-                player_number,
-                cycle_start_time,
-                filtered_game_controller_state,
-                optional_roles,
-            ),
-        },
-
-        //Loser found ball and becomes Striker
-        (Role::Loser, Some(..), None) => (
-            Role::Striker,
-            true,
-            team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-        ),
-
-        // Edge-case, Loser found Ball at the same time as receiving a message
-        (Role::Loser, Some(..), Some(spl_message)) => match &spl_message.ball_position {
-            None => {
-                // another Striker became Loser, so we claim striker since we see a ball
-                (
-                    Role::Striker,
-                    true,
-                    team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-                )
+/// Assigns the most important roles to the candidates such that the sum of their costs is minimal
+///
+/// The assignment is solved exactly by dynamic programming over the subsets of candidates. Ties
+/// are broken by player number, so every robot computes the same assignment from the same costs.
+fn assign_roles(
+    roles: &[Role],
+    costs: &Players<Option<RoleCosts>>,
+    foreign_costs: &Players<Option<RoleCosts>>,
+) -> Players<Option<Role>> {
+    // robots of other teams take roles away from our robots, but their roles are not returned
+    let candidates: Vec<_> = costs
+        .iter()
+        .filter_map(|(player, costs)| Some((Some(player), costs.as_ref()?)))
+        .chain(
+            foreign_costs
+                .iter()
+                .filter_map(|(_, costs)| Some((None, costs.as_ref()?))),
+        )
+        .collect();
+    let number_of_roles = roles.len().min(candidates.len());
+
+    // indexed by the set of candidates which got the first roles, one bit per candidate
+    let mut best_costs: Vec<Option<u32>> = vec![None; 1 << candidates.len()];
+    let mut last_candidate = vec![0; 1 << candidates.len()];
+    best_costs[0] = Some(0);
+    for assigned in 0..best_costs.len() {
+        let role_index = assigned.count_ones() as usize;
+        let Some(cost) = best_costs[assigned] else {
+            continue;
+        };
+        if role_index >= number_of_roles {
+            continue;
+        }
+        for (candidate, (_, candidate_costs)) in candidates.iter().enumerate() {
+            if assigned & (1 << candidate) != 0 {
+                continue;
             }
-            _ => decide_if_claiming_striker_or_other_role(
-                spl_message,
-                time_to_reach_kick_position,
-                player_number,
-                cycle_start_time,
-                filtered_game_controller_state,
-                optional_roles,
-            ),
-        },
-
-        //Searcher remains Searcher
-        (Role::Searcher, None, None) => (Role::Searcher, false, team_ball),
-
-        (Role::Searcher, None, Some(spl_message)) => match &spl_message.ball_position {
-            None => (Role::Searcher, false, team_ball), //edge-case, a striker (which should not exist) lost the ball
-            _ => decide_if_claiming_striker_or_other_role(
-                spl_message,
-                time_to_reach_kick_position,
-                player_number,
-                cycle_start_time,
-                filtered_game_controller_state,
-                optional_roles,
-            ),
-        },
-
-        //Searcher found ball and becomes Striker
-        (Role::Searcher, Some(..), None) => (
-            Role::Striker,
-            true,
-            team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-        ),
-
-        // TODO: Searcher found Ball at the same time as receiving a message
-        (Role::Searcher, Some(..), Some(spl_message)) => match &spl_message.ball_position {
-            None => (
-                Role::Striker,
-                true,
-                team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-            ),
-            _ => decide_if_claiming_striker_or_other_role(
-                spl_message,
-                time_to_reach_kick_position,
-                player_number,
-                cycle_start_time,
-                filtered_game_controller_state,
-                optional_roles,
-            ),
-        },
-
-        // remain in other_role
-        (other_role, None, None) => (other_role, false, team_ball),
-
-        // Either someone found or lost a ball. if found: do I want to claim striker ?
-        (other_role, None, Some(spl_message)) => match &spl_message.ball_position {
-            None => {
-                if other_role != Role::Keeper && other_role != Role::ReplacementKeeper {
-                    (Role::Searcher, false, None)
-                } else {
-                    (other_role, false, None)
-                }
+            let next = assigned | (1 << candidate);
+            let next_cost = cost + u32::from(candidate_costs[role_index]);
+            if best_costs[next].is_none_or(|best_cost| next_cost < best_cost) {
+                best_costs[next] = Some(next_cost);
+                last_candidate[next] = candidate;
             }
-            _ => decide_if_claiming_striker_or_other_role(
-                spl_message,
-                time_to_reach_kick_position,
-                player_number,
-                cycle_start_time,
-                filtered_game_controller_state,
-                optional_roles,
-            ),
-        },
-
-        // Claim Striker if team-ball position is None
-        (other_role, Some(..), None) => match team_ball {
-            None => (
-                Role::Striker,
-                true,
-                team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-            ),
-            Some(..) => (
-                other_role,
-                false,
-                team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-            ),
-        },
-
-        // if message is Ball-Lost => Striker, claim Striker ? design-decision: which ball to trust ?
-        (_other_role, Some(..), Some(spl_message)) => match &spl_message.ball_position {
-            None => (
-                Role::Striker,
-                true,
-                team_ball_from_seen_ball(detected_own_ball, current_pose, cycle_start_time),
-            ),
-            _ => decide_if_claiming_striker_or_other_role(
-                spl_message,
-                time_to_reach_kick_position,
-                player_number,
-                cycle_start_time,
-                filtered_game_controller_state,
-                optional_roles,
-            ),
-        },
+        }
     }
-}
 
-fn decide_if_claiming_striker_or_other_role(
-    spl_message: &HulkMessage,
-    time_to_reach_kick_position: Option<Duration>,
-    player_number: PlayerNumber,
-    cycle_start_time: SystemTime,
-    filtered_game_controller_state: Option<&FilteredGameControllerState>,
-    optional_roles: &[Role],
-) -> (Role, bool, Option<BallPosition<Field>>) {
-    if time_to_reach_kick_position < spl_message.time_to_reach_kick_position {
-        (
-            Role::Striker,
-            true,
-            team_ball_from_spl_message(cycle_start_time, spl_message),
-        )
-    } else {
-        (
-            generate_role(
-                player_number,
-                filtered_game_controller_state,
-                spl_message.player_number,
-                optional_roles,
-            ),
-            false,
-            team_ball_from_spl_message(cycle_start_time, spl_message),
-        )
+    let mut assignment = Players::default();
+    let best_assignment = (0..best_costs.len())
+        .filter(|assigned| assigned.count_ones() as usize == number_of_roles)
+        .filter_map(|assigned| Some((assigned, best_costs[assigned]?)))
+        .min_by_key(|(_, cost)| *cost);
+    if let Some((mut assigned, _)) = best_assignment {
+        while assigned != 0 {
+            let candidate = last_candidate[assigned];
+            let role_index = assigned.count_ones() as usize - 1;
+            if let Some(player) = candidates[candidate].0 {
+                assignment[player] = Some(roles[role_index]);
+            }
+            assigned &= !(1 << candidate);
+        }
     }
+    assignment
 }
 
 fn seen_ball_to_game_controller_ball_position(
@@ -642,147 +699,280 @@ fn seen_ball_to_hulks_network_ball_position(
     })
 }
 
-fn team_ball_to_network_ball_position(
-    team_ball: Option<BallPosition<Field>>,
-    cycle_start_time: SystemTime,
-) -> Option<spl_network_messages::BallPosition<Field>> {
-    team_ball.map(|team_ball| spl_network_messages::BallPosition {
-        age: cycle_start_time
-            .duration_since(team_ball.last_seen)
-            .unwrap(),
-        position: team_ball.position,
-    })
+fn covariance_in_field(
+    covariance: Matrix2<f32>,
+    ground_to_field: Isometry2<Ground, Field>,
+) -> BallCovariance {
+    let rotation = ground_to_field.inner.rotation.to_rotation_matrix();
+    (rotation.matrix() * covariance * rotation.matrix().transpose()).into()
 }
 
-fn team_ball_from_spl_message(
-    cycle_start_time: SystemTime,
-    spl_message: &HulkMessage,
-) -> Option<BallPosition<Field>> {
-    spl_message
-        .ball_position
-        .as_ref()
-        .map(|ball_position| BallPosition {
-            position: ball_position.position,
-            velocity: Vector::zeros(),
-            last_seen: cycle_start_time - ball_position.age,
-        })
-}
+#[cfg(test)]
+mod tests {
+    use linear_algebra::{vector, Pose2};
+
+    use super::*;
+
+    const OPTIONAL_ROLES: [Role; 5] = [
+        Role::DefenderLeft,
+        Role::DefenderRight,
+        Role::StrikerSupporter,
+        Role::MidfielderRight,
+        Role::MidfielderLeft,
+    ];
+
+    #[test]
+    fn static_costs_reproduce_lineup() {
+        let roles = auctioned_roles(&OPTIONAL_ROLES);
+
+        let assignment = assign_roles(&roles, &static_candidates(roles.len()), &Players::default());
+
+        assert_eq!(
+            assignment,
+            Players {
+                one: Some(Role::Keeper),
+                two: Some(Role::DefenderLeft),
+                three: Some(Role::DefenderRight),
+                four: Some(Role::StrikerSupporter),
+                five: Some(Role::MidfielderRight),
+                six: Some(Role::MidfielderLeft),
+                seven: Some(Role::Striker),
+            }
+        );
+    }
 
-fn team_ball_from_seen_ball(
-    ball: Option<&BallPosition<Ground>>,
-    ground_to_field: Isometry2<Ground, Field>,
-    cycle_start_time: SystemTime,
-) -> Option<BallPosition<Field>> {
-    ball.as_ref().map(|ball| BallPosition {
-        position: (ground_to_field * ball.position),
-        velocity: Vector::zeros(),
-        last_seen: cycle_start_time,
-    })
-}
+    #[test]
+    fn penalized_keeper_and_striker_are_replaced() {
+        let roles = auctioned_roles(&OPTIONAL_ROLES);
+
+        let mut candidates = static_candidates(roles.len());
+        candidates.one = None;
+        candidates.seven = None;
+
+        let assignment = assign_roles(&roles, &candidates, &Players::default());
+
+        // the robots without a role in the short-handed team take over
+        assert_eq!(
+            assignment,
+            Players {
+                one: None,
+                two: Some(Role::DefenderLeft),
+                three: Some(Role::DefenderRight),
+                four: Some(Role::StrikerSupporter),
+                five: Some(Role::Keeper),
+                six: Some(Role::Striker),
+                seven: None,
+            }
+        );
+    }
 
-fn generate_role(
-    own_player_number: PlayerNumber,
-    game_controller_state: Option<&FilteredGameControllerState>,
-    striker_player_number: PlayerNumber,
-    optional_roles: &[Role],
-) -> Role {
-    if let Some(state) = game_controller_state {
-        pick_role_with_penalties(
-            own_player_number,
-            &state.penalties,
-            striker_player_number,
-            optional_roles,
-        )
-    } else {
-        Role::Striker // This case only happens if we don't have a game controller state
+    #[test]
+    fn cheapest_total_assignment_wins() {
+        let roles = [Role::Keeper, Role::Striker, Role::DefenderLeft];
+        let candidates = Players {
+            one: Some([0, 5000, 3000, 0, 0, 0, 0]),
+            two: Some([9000, 1000, 2000, 0, 0, 0, 0]),
+            three: Some([9000, 1500, 8000, 0, 0, 0, 0]),
+            ..Default::default()
+        };
+
+        let assignment = assign_roles(&roles, &candidates, &Players::default());
+
+        assert_eq!(assignment.one, Some(Role::Keeper));
+        // player two is the fastest striker, but player three is much worse at defending
+        assert_eq!(assignment.two, Some(Role::DefenderLeft));
+        assert_eq!(assignment.three, Some(Role::Striker));
     }
-}
 
-fn pick_role_with_penalties(
-    own_player_number: PlayerNumber,
-    penalties: &Players<Option<Penalty>>,
-    striker_player_number: PlayerNumber,
-    optional_roles: &[Role],
-) -> Role {
-    let mut role_assignment: Players<Option<Role>> = Players {
-        one: None,
-        two: None,
-        three: None,
-        four: None,
-        five: None,
-        six: None,
-        seven: None,
-    };
+    #[test]
+    fn equal_costs_are_assigned_by_player_number() {
+        let roles = [Role::Keeper, Role::Striker];
+        let candidates = Players {
+            two: Some([1000; NUMBER_OF_AUCTIONED_ROLES]),
+            four: Some([1000; NUMBER_OF_AUCTIONED_ROLES]),
+            ..Default::default()
+        };
 
-    role_assignment[striker_player_number] = Some(Role::Striker);
-    let mut unassigned_robots = 6;
+        let assignment = assign_roles(&roles, &candidates, &Players::default());
 
-    unassigned_robots -= penalties
-        .iter()
-        .filter(|(_player, &penalty)| penalty.is_some())
-        .count();
+        assert_eq!(assignment.two, Some(Role::Keeper));
+        assert_eq!(assignment.four, Some(Role::Striker));
+    }
+
+    #[test]
+    fn fallen_robots_and_unseen_balls_increase_costs() {
+        let roles = auctioned_roles(&OPTIONAL_ROLES);
+        let parameters = RoleAuctionParameters {
+            fallen_penalty: Duration::from_secs(10),
+            replacement_keeper_penalty: Duration::from_secs(5),
+            role_switch_hysteresis: Duration::from_secs(2),
+            defender_left_position: point![-3.0, 1.0],
+            ..Default::default()
+        };
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            ..Default::default()
+        };
+        let costs = |ball, fallen, assigned_role| {
+            role_costs(
+                &roles,
+                PlayerNumber::Two,
+                point![-3.0, 0.0],
+                ball,
+                Duration::from_secs(4),
+                fallen,
+                assigned_role,
+                &field_dimensions,
+                0.5,
+                &parameters,
+            )
+        };
+
+        let standing = costs(Some(point![0.0, 0.0]), false, None);
+        assert_eq!(standing[0], 8000);
+        assert_eq!(standing[1], 4000);
+        assert_eq!(standing[2], 2000);
+
+        let fallen = costs(Some(point![0.0, 0.0]), true, None);
+        assert_eq!(fallen[1], 4000);
+        assert_eq!(fallen[2], 12000);
 
-    if unassigned_robots > 0 {
-        unassigned_robots =
-            assign_keeper_or_replacement_keeper(unassigned_robots, penalties, &mut role_assignment);
+        let without_ball = costs(None, false, Some(Role::DefenderLeft));
+        assert_eq!(without_ball[1], u16::MAX);
+        assert_eq!(without_ball[2], 0);
     }
 
-    for &optional_role in optional_roles.iter().take(unassigned_robots) {
-        if needs_assignment(PlayerNumber::Two, penalties, &role_assignment) {
-            role_assignment[PlayerNumber::Two] = Some(optional_role);
-        } else if needs_assignment(PlayerNumber::Three, penalties, &role_assignment) {
-            role_assignment[PlayerNumber::Three] = Some(optional_role);
-        } else if needs_assignment(PlayerNumber::Four, penalties, &role_assignment) {
-            role_assignment[PlayerNumber::Four] = Some(optional_role);
-        } else if needs_assignment(PlayerNumber::Five, penalties, &role_assignment) {
-            role_assignment[PlayerNumber::Five] = Some(optional_role);
-        } else if needs_assignment(PlayerNumber::Six, penalties, &role_assignment) {
-            role_assignment[PlayerNumber::Six] = Some(optional_role);
-        } else if needs_assignment(PlayerNumber::Seven, penalties, &role_assignment) {
-            role_assignment[PlayerNumber::Seven] = Some(optional_role);
+    #[test]
+    fn invalid_walking_speeds_result_in_maximum_costs() {
+        let roles = [Role::Striker, Role::DefenderLeft];
+        let parameters = RoleAuctionParameters::default();
+        let field_dimensions = FieldDimensions::default();
+
+        for walking_speed in [0.0, -0.5, f32::NAN] {
+            let costs = role_costs(
+                &roles,
+                PlayerNumber::Two,
+                point![-3.0, 0.0],
+                Some(point![0.0, 0.0]),
+                Duration::from_secs(4),
+                false,
+                None,
+                &field_dimensions,
+                walking_speed,
+                &parameters,
+            );
+            assert_eq!(costs[0], 4000);
+            assert_eq!(costs[1], u16::MAX);
         }
     }
 
-    role_assignment[own_player_number].unwrap_or_default()
-}
+    #[test]
+    fn foreign_teammates_keep_claimed_roles() {
+        let roles = [Role::Keeper, Role::Striker, Role::DefenderLeft];
+        let parameters = RoleAuctionParameters {
+            defender_left_position: point![-3.0, 1.0],
+            ..Default::default()
+        };
+        let field_dimensions = FieldDimensions {
+            length: 9.0,
+            ..Default::default()
+        };
+        let ball = Some(spl_network_messages::BallPosition {
+            position: point![1.0, 0.0],
+            age: Duration::ZERO,
+        });
+        let message = |team_number, player_number, x, intention| MixedTeamMessage {
+            team_number,
+            player_number,
+            intention,
+            pose: Pose2::new(vector![x, 0.0], 0.0),
+            ball_position: ball,
+            ..Default::default()
+        };
+        let costs = |message| {
+            Some(mixed_team_role_costs(
+                &roles,
+                &message,
+                &field_dimensions,
+                0.5,
+                &parameters,
+            ))
+        };
+        let candidates = Players {
+            one: costs(message(
+                HULKS_TEAM_NUMBER,
+                PlayerNumber::One,
+                -4.5,
+                MixedTeamIntention::Keeper,
+            )),
+            two: costs(message(
+                HULKS_TEAM_NUMBER,
+                PlayerNumber::Two,
+                0.5,
+                MixedTeamIntention::Nothing,
+            )),
+            three: costs(message(
+                HULKS_TEAM_NUMBER,
+                PlayerNumber::Three,
+                -3.0,
+                MixedTeamIntention::Nothing,
+            )),
+            ..Default::default()
+        };
+        // the foreign robot shares its player number with one of ours, is far from the ball but
+        // wants to play it
+        let foreign_candidates = Players {
+            two: costs(message(
+                5,
+                PlayerNumber::Two,
+                -3.0,
+                MixedTeamIntention::PlayBall,
+            )),
+            ..Default::default()
+        };
 
-fn needs_assignment(
-    player_number: PlayerNumber,
-    penalties: &Players<Option<Penalty>>,
-    role_assignment: &Players<Option<Role>>,
-) -> bool {
-    role_assignment[player_number].is_none() && penalties[player_number].is_none()
-}
+        let assignment = assign_roles(&roles, &candidates, &foreign_candidates);
 
-fn assign_keeper_or_replacement_keeper(
-    unassigned_robots: usize,
-    penalties: &Players<Option<Penalty>>,
-    role_assignment: &mut Players<Option<Role>>,
-) -> usize {
-    if needs_assignment(PlayerNumber::One, penalties, role_assignment) {
-        role_assignment[PlayerNumber::One] = Some(Role::Keeper);
-        return unassigned_robots - 1;
+        assert_eq!(assignment.one, Some(Role::Keeper));
+        assert_eq!(assignment.two, None);
+        assert_eq!(assignment.three, Some(Role::DefenderLeft));
     }
 
-    if needs_assignment(PlayerNumber::Two, penalties, role_assignment) {
-        role_assignment[PlayerNumber::Two] = Some(Role::ReplacementKeeper);
-        return unassigned_robots - 1;
-    } else if needs_assignment(PlayerNumber::Three, penalties, role_assignment) {
-        role_assignment[PlayerNumber::Three] = Some(Role::ReplacementKeeper);
-        return unassigned_robots - 1;
-    } else if needs_assignment(PlayerNumber::Four, penalties, role_assignment) {
-        role_assignment[PlayerNumber::Four] = Some(Role::ReplacementKeeper);
-        return unassigned_robots - 1;
-    } else if needs_assignment(PlayerNumber::Five, penalties, role_assignment) {
-        role_assignment[PlayerNumber::Five] = Some(Role::ReplacementKeeper);
-        return unassigned_robots - 1;
-    } else if needs_assignment(PlayerNumber::Six, penalties, role_assignment) {
-        role_assignment[PlayerNumber::Six] = Some(Role::ReplacementKeeper);
-        return unassigned_robots - 1;
-    } else if needs_assignment(PlayerNumber::Seven, penalties, role_assignment) {
-        role_assignment[PlayerNumber::Seven] = Some(Role::ReplacementKeeper);
-        return unassigned_robots - 1;
-    }
+    #[test]
+    fn informative_messages_have_higher_priority() {
+        let threshold = Duration::from_secs(3);
+        let last_message = HulkMessage {
+            role_costs: Some([1000; NUMBER_OF_AUCTIONED_ROLES]),
+            ..Default::default()
+        };
 
-    unassigned_robots
+        assert_eq!(
+            message_priority(None, &last_message, false, threshold),
+            MessagePriority::High
+        );
+        assert_eq!(
+            message_priority(Some(&last_message), &last_message, false, threshold),
+            MessagePriority::Low
+        );
+        assert_eq!(
+            message_priority(Some(&last_message), &last_message, true, threshold),
+            MessagePriority::High
+        );
+
+        let fallen = HulkMessage {
+            fallen: true,
+            ..last_message
+        };
+        assert_eq!(
+            message_priority(Some(&last_message), &fallen, false, threshold),
+            MessagePriority::High
+        );
+
+        let mut changed_costs = last_message;
+        changed_costs.role_costs.as_mut().unwrap()[1] = 5000;
+        assert_eq!(
+            message_priority(Some(&last_message), &changed_costs, false, threshold),
+            MessagePriority::Normal
+        );
+    }
 }
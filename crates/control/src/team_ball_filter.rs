@@ -0,0 +1,279 @@
+use std::time::{Duration, SystemTime};
+
+use color_eyre::Result;
+use nalgebra::Matrix2;
+use serde::{Deserialize, Serialize};
+
+use context_attribute::context;
+use coordinate_systems::{Field, Ground};
+use framework::{AdditionalOutput, MainOutput, PerceptionInput};
+use linear_algebra::{Isometry2, Point2, Vector2};
+use spl_network_messages::{HulkMessage, PlayerNumber};
+use types::{
+    ball_position::BallPosition, cycle_time::CycleTime, messages::IncomingMessage,
+    multivariate_normal_distribution::MultivariateNormalDistribution, players::Players,
+};
+
+/// Fuses the own ball with the balls reported by all teammates into one team ball
+///
+/// Every ball is weighted by its inverse covariance, which grows with the age of the ball. Balls
+/// inconsistent with the largest group of agreeing balls are rejected, since they are most likely
+/// reported by a mislocalized robot.
+#[derive(Deserialize, Serialize)]
+pub struct TeamBallFilter {
+    last_teammate_messages: Players<Option<(SystemTime, HulkMessage)>>,
+}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    team_ball_distribution: AdditionalOutput<
+        Option<MultivariateNormalDistribution<2>>,
+        "team_ball_filter.team_ball_distribution",
+    >,
+    rejected_balls: AdditionalOutput<Vec<Point2<Field>>, "team_ball_filter.rejected_balls">,
+
+    ball_position: Input<Option<BallPosition<Ground>>, "ball_position?">,
+    ball_position_distribution:
+        Input<Option<MultivariateNormalDistribution<2>>, "ball_position_distribution?">,
+    cycle_time: Input<CycleTime, "cycle_time">,
+    ground_to_field: Input<Option<Isometry2<Ground, Field>>, "ground_to_field?">,
+    network_message: PerceptionInput<Option<IncomingMessage>, "SplNetwork", "filtered_message?">,
+
+    default_variance: Parameter<f32, "team_ball_filter.default_variance">,
+    maximum_ball_age: Parameter<Duration, "team_ball_filter.maximum_ball_age">,
+    outlier_threshold: Parameter<f32, "team_ball_filter.outlier_threshold">,
+    variance_increase_per_second: Parameter<f32, "team_ball_filter.variance_increase_per_second">,
+    player_number: Parameter<PlayerNumber, "player_number">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub team_ball: MainOutput<Option<BallPosition<Field>>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BallMeasurement {
+    position: Point2<Field>,
+    covariance: Matrix2<f32>,
+    last_seen: SystemTime,
+}
+
+impl TeamBallFilter {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {
+            last_teammate_messages: Default::default(),
+        })
+    }
+
+    pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        for (time, message) in context
+            .network_message
+            .persistent
+            .iter()
+            .flat_map(|(time, messages)| messages.iter().map(move |message| (*time, message)))
+        {
+            let message = match message {
+                Some(IncomingMessage::Spl(message)) => *message,
+                Some(IncomingMessage::MixedTeam(message)) => HulkMessage::from(*message),
+                _ => continue,
+            };
+            if message.player_number != *context.player_number {
+                self.last_teammate_messages[message.player_number] = Some((time, message));
+            }
+        }
+
+        let now = context.cycle_time.start_time;
+        let default_covariance = Matrix2::identity() * *context.default_variance;
+        let own_ball =
+            context
+                .ball_position
+                .zip(context.ground_to_field)
+                .map(|(ball, ground_to_field)| {
+                    let rotation = ground_to_field.inner.rotation.to_rotation_matrix();
+                    let covariance = context.ball_position_distribution.map_or(
+                        default_covariance,
+                        |distribution| {
+                            rotation.matrix()
+                                * distribution.covariance
+                                * rotation.matrix().transpose()
+                        },
+                    );
+                    BallMeasurement {
+                        position: *ground_to_field * ball.position,
+                        covariance,
+                        last_seen: ball.last_seen,
+                    }
+                });
+        let teammate_balls = self
+            .last_teammate_messages
+            .iter()
+            .filter_map(|(_, message)| {
+                let (time_received, message) = message.as_ref()?;
+                if message.fallen {
+                    return None;
+                }
+                let ball = message.ball_position?;
+                Some(BallMeasurement {
+                    position: ball.position,
+                    covariance: message
+                        .ball_covariance
+                        .map_or(default_covariance, Matrix2::from),
+                    last_seen: *time_received - ball.age,
+                })
+            });
+        let measurements: Vec<_> = own_ball
+            .into_iter()
+            .chain(teammate_balls)
+            .filter_map(|measurement| {
+                let age = now
+                    .duration_since(measurement.last_seen)
+                    .unwrap_or_default();
+                (age <= *context.maximum_ball_age).then(|| BallMeasurement {
+                    covariance: measurement.covariance
+                        + Matrix2::identity()
+                            * age.as_secs_f32()
+                            * *context.variance_increase_per_second,
+                    ..measurement
+                })
+            })
+            .collect();
+
+        let (inliers, outliers) = reject_outliers(&measurements, *context.outlier_threshold);
+        context.rejected_balls.fill_if_subscribed(|| {
+            outliers
+                .iter()
+                .map(|measurement| measurement.position)
+                .collect()
+        });
+        let team_ball = fuse(&inliers);
+        context.team_ball_distribution.fill_if_subscribed(|| {
+            team_ball.map(|team_ball| MultivariateNormalDistribution {
+                mean: team_ball.position.inner.coords,
+                covariance: team_ball.covariance,
+            })
+        });
+
+        Ok(MainOutputs {
+            team_ball: team_ball
+                .map(|team_ball| BallPosition {
+                    position: team_ball.position,
+                    velocity: Vector2::zeros(),
+                    last_seen: team_ball.last_seen,
+                })
+                .into(),
+        })
+    }
+}
+
+fn squared_mahalanobis_distance(first: &BallMeasurement, second: &BallMeasurement) -> f32 {
+    let difference = (first.position - second.position).inner;
+    (first.covariance + second.covariance)
+        .try_inverse()
+        .map_or(f32::INFINITY, |information| {
+            difference.dot(&(information * difference))
+        })
+}
+
+/// Keeps the largest group of measurements agreeing with one of them, the first measurement wins
+/// ties so the own ball is preferred over teammate balls
+fn reject_outliers(
+    measurements: &[BallMeasurement],
+    outlier_threshold: f32,
+) -> (Vec<BallMeasurement>, Vec<BallMeasurement>) {
+    let agreeing_measurements = |reference: &BallMeasurement| {
+        measurements
+            .iter()
+            .filter(|measurement| {
+                squared_mahalanobis_distance(reference, measurement) <= outlier_threshold
+            })
+            .count()
+    };
+    let Some(reference) = measurements
+        .iter()
+        .rev()
+        .max_by_key(|measurement| agreeing_measurements(measurement))
+    else {
+        return (Vec::new(), Vec::new());
+    };
+    measurements.iter().partition(|measurement| {
+        squared_mahalanobis_distance(reference, measurement) <= outlier_threshold
+    })
+}
+
+/// Weights every measurement by its inverse covariance
+fn fuse(measurements: &[BallMeasurement]) -> Option<BallMeasurement> {
+    let (information, weighted_position) = measurements.iter().try_fold(
+        (Matrix2::zeros(), nalgebra::Vector2::zeros()),
+        |(information, weighted_position), measurement| {
+            let measurement_information = measurement.covariance.try_inverse()?;
+            Some((
+                information + measurement_information,
+                weighted_position + measurement_information * measurement.position.inner.coords,
+            ))
+        },
+    )?;
+    let covariance = information.try_inverse()?;
+    let last_seen = measurements
+        .iter()
+        .map(|measurement| measurement.last_seen)
+        .max()?;
+    Some(BallMeasurement {
+        position: Point2::wrap((covariance * weighted_position).into()),
+        covariance,
+        last_seen,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::point;
+
+    use super::*;
+
+    fn measurement(x: f32, y: f32, variance: f32) -> BallMeasurement {
+        BallMeasurement {
+            position: point![x, y],
+            covariance: Matrix2::identity() * variance,
+            last_seen: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    #[test]
+    fn certain_measurements_dominate_the_team_ball() {
+        let team_ball = fuse(&[measurement(1.0, 0.0, 0.01), measurement(2.0, 0.0, 0.09)]).unwrap();
+
+        assert!((team_ball.position.x() - 1.1).abs() < 1e-4);
+        assert!(team_ball.position.y().abs() < 1e-4);
+        assert!(team_ball.covariance.m11 < 0.01);
+    }
+
+    #[test]
+    fn ball_of_mislocalized_teammate_is_rejected() {
+        let measurements = [
+            measurement(1.0, 1.0, 0.04),
+            measurement(-1.0, -1.0, 0.04),
+            measurement(1.1, 0.9, 0.04),
+            measurement(0.9, 1.0, 0.04),
+        ];
+
+        let (inliers, outliers) = reject_outliers(&measurements, 9.0);
+
+        assert_eq!(inliers.len(), 3);
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].position, point![-1.0, -1.0]);
+    }
+
+    #[test]
+    fn own_ball_wins_ties() {
+        let measurements = [measurement(1.0, 1.0, 0.04), measurement(-1.0, -1.0, 0.04)];
+
+        let (inliers, _) = reject_outliers(&measurements, 9.0);
+
+        assert_eq!(inliers.len(), 1);
+        assert_eq!(inliers[0].position, point![1.0, 1.0]);
+    }
+}
@@ -8,8 +8,11 @@ mod panic;
 mod parameters;
 mod perception_databases;
 mod perception_input;
+mod recording_event_buffer;
+mod recording_header;
 mod recording_index;
 mod recording_trigger;
+mod recording_writer;
 
 pub use additional_output::{should_be_filled, AdditionalOutput};
 pub use future_queue::{future_queue, Consumer, Item, Producer, Update, Updates};
@@ -18,8 +21,14 @@ pub use historic_input::HistoricInput;
 pub use main_output::MainOutput;
 pub use multiple_buffer::{multiple_buffer_with_slots, Reader, ReaderGuard, Writer, WriterGuard};
 pub use panic::deserialize_not_implemented;
-pub use parameters::Parameters;
+pub use parameters::{CommunicationAuthentication, CommunicationRole, Parameters};
 pub use perception_databases::PerceptionDatabases;
 pub use perception_input::PerceptionInput;
+pub use recording_event_buffer::{RecordingEventBuffer, RecordingEventWindow};
+pub use recording_header::{
+    parameters_hash, RecordingCompression, RecordingHeader, RECORDING_FORMAT_VERSION,
+    RECORDING_MAGIC,
+};
 pub use recording_index::{RecordingFrame, RecordingIndex, Timing};
 pub use recording_trigger::RecordingTrigger;
+pub use recording_writer::RecordingWriter;
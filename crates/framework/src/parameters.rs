@@ -2,10 +2,42 @@ use std::{collections::HashMap, path::PathBuf};
 
 use serde::Deserialize;
 
+use crate::{RecordingCompression, RecordingEventWindow};
+
 #[derive(Clone, Debug, Deserialize)]
 pub struct Parameters {
     pub communication_addresses: Option<String>,
+    pub communication_authentication: Option<CommunicationAuthentication>,
     pub recording_intervals: HashMap<String, usize>,
+    pub recording_compression: RecordingCompression,
+    pub recording_event_window: Option<RecordingEventWindow>,
     pub hardware_parameters: PathBuf,
     pub parameters_directory: PathBuf,
 }
+
+/// Token-based authentication of communication clients, without it every client may write
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CommunicationAuthentication {
+    /// Tokens which clients present when connecting, mapped to the role they are granted
+    pub tokens: HashMap<String, CommunicationRole>,
+    /// Role of clients connecting without a token, `null` rejects them
+    pub anonymous_role: Option<CommunicationRole>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+pub enum CommunicationRole {
+    /// May subscribe to outputs and parameters
+    ReadOnly,
+    /// May additionally change and store parameters and inject outputs
+    Write,
+}
+
+impl CommunicationAuthentication {
+    /// Role granted to a client presenting the token, `None` if the client is rejected
+    pub fn role_of(&self, token: Option<&str>) -> Option<CommunicationRole> {
+        match token {
+            Some(token) => self.tokens.get(token).copied(),
+            None => self.anonymous_role,
+        }
+    }
+}
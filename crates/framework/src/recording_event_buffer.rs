@@ -0,0 +1,160 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+use serde::Deserialize;
+
+use crate::RecordingFrame;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct RecordingEventWindow {
+    pub pre_event: Duration,
+    pub post_event: Duration,
+    pub maximum_buffered_bytes: usize,
+}
+
+/// Keeps the frames of the last `pre_event` duration in memory and releases all frames around
+/// recording events, i.e. from `pre_event` before until `post_event` after the latest event
+///
+/// Outside of event windows, only frames of the recording interval are released once they leave
+/// the buffer. The oldest frames leave the buffer early if it exceeds `maximum_buffered_bytes`.
+pub struct RecordingEventBuffer {
+    window: RecordingEventWindow,
+    frames: VecDeque<(RecordingFrame, bool)>,
+    buffered_bytes: usize,
+    latest_event: Option<SystemTime>,
+}
+
+impl RecordingEventBuffer {
+    pub fn new(window: RecordingEventWindow) -> Self {
+        Self {
+            window,
+            frames: VecDeque::new(),
+            buffered_bytes: 0,
+            latest_event: None,
+        }
+    }
+
+    /// Returns the frames that should be written into the recording
+    pub fn push(
+        &mut self,
+        frame: RecordingFrame,
+        is_in_recording_interval: bool,
+        latest_event: Option<SystemTime>,
+    ) -> Vec<RecordingFrame> {
+        self.latest_event = self.latest_event.max(latest_event);
+        let timestamp = frame.timing.timestamp;
+        self.buffered_bytes += frame.data.len();
+        self.frames.push_back((frame, is_in_recording_interval));
+
+        match self.latest_event {
+            Some(event) if timestamp <= event + self.window.post_event => {
+                let recording_start = event
+                    .checked_sub(self.window.pre_event)
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                self.buffered_bytes = 0;
+                self.frames
+                    .drain(..)
+                    .filter(|(frame, is_in_recording_interval)| {
+                        *is_in_recording_interval || frame.timing.timestamp >= recording_start
+                    })
+                    .map(|(frame, _is_in_recording_interval)| frame)
+                    .collect()
+            }
+            _ => {
+                let mut released_frames = Vec::new();
+                while self.frames.front().is_some_and(|(oldest_frame, _)| {
+                    oldest_frame.timing.timestamp + self.window.pre_event < timestamp
+                        || self.buffered_bytes > self.window.maximum_buffered_bytes
+                }) {
+                    let (oldest_frame, is_in_recording_interval) = self
+                        .frames
+                        .pop_front()
+                        .expect("front frame should exist");
+                    self.buffered_bytes -= oldest_frame.data.len();
+                    if is_in_recording_interval {
+                        released_frames.push(oldest_frame);
+                    }
+                }
+                released_frames
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::UNIX_EPOCH;
+
+    use crate::Timing;
+
+    use super::*;
+
+    fn seconds(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    fn frame(second: u64) -> RecordingFrame {
+        RecordingFrame {
+            timing: Timing {
+                timestamp: seconds(second),
+                duration: Duration::ZERO,
+            },
+            data: vec![second as u8; 10],
+        }
+    }
+
+    fn released_seconds(frames: Vec<RecordingFrame>) -> Vec<u8> {
+        frames.into_iter().map(|frame| frame.data[0]).collect()
+    }
+
+    #[test]
+    fn all_frames_around_events_are_released() {
+        let mut buffer = RecordingEventBuffer::new(RecordingEventWindow {
+            pre_event: Duration::from_secs(2),
+            post_event: Duration::from_secs(1),
+            maximum_buffered_bytes: 1000,
+        });
+
+        for second in 0..10 {
+            assert!(buffer.push(frame(second), false, None).is_empty());
+        }
+        let released = buffer.push(frame(10), false, Some(seconds(10)));
+        assert_eq!(released_seconds(released), vec![8, 9, 10]);
+        assert_eq!(buffer.push(frame(11), false, Some(seconds(10))).len(), 1);
+        assert!(buffer.push(frame(12), false, Some(seconds(10))).is_empty());
+    }
+
+    #[test]
+    fn frames_of_the_recording_interval_are_released_outside_of_events() {
+        let mut buffer = RecordingEventBuffer::new(RecordingEventWindow {
+            pre_event: Duration::from_secs(2),
+            post_event: Duration::from_secs(1),
+            maximum_buffered_bytes: 1000,
+        });
+
+        let released: Vec<_> = (0..10)
+            .flat_map(|second| buffer.push(frame(second), second % 3 == 1, None))
+            .collect();
+        assert_eq!(released_seconds(released), vec![1, 4]);
+        let released = buffer.push(frame(10), false, Some(seconds(10)));
+        assert_eq!(released_seconds(released), vec![7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn oldest_frames_leave_a_full_buffer() {
+        let mut buffer = RecordingEventBuffer::new(RecordingEventWindow {
+            pre_event: Duration::from_secs(5),
+            post_event: Duration::from_secs(1),
+            maximum_buffered_bytes: 30,
+        });
+
+        let released: Vec<_> = (0..6)
+            .flat_map(|second| buffer.push(frame(second), second % 2 == 0, None))
+            .collect();
+        assert_eq!(released_seconds(released), vec![0, 2]);
+        let released = buffer.push(frame(6), false, Some(seconds(6)));
+        assert_eq!(released_seconds(released), vec![3, 4, 5, 6]);
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::{Read, Write};
+
+use bincode::{deserialize_from, serialize_into};
+use color_eyre::eyre::{bail, WrapErr};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub const RECORDING_MAGIC: [u8; 8] = *b"HULKSREC";
+pub const RECORDING_FORMAT_VERSION: u32 = 3;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct RecordingHeader {
+    pub cycler_instance: String,
+    pub schema_hash: u64,
+    pub parameters_hash: u64,
+    pub compression: RecordingCompression,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum RecordingCompression {
+    None,
+    Zstd,
+}
+
+impl RecordingHeader {
+    pub fn write_to(&self, mut writer: impl Write) -> color_eyre::Result<()> {
+        writer
+            .write_all(&RECORDING_MAGIC)
+            .wrap_err("failed to write magic")?;
+        serialize_into(&mut writer, &RECORDING_FORMAT_VERSION)
+            .wrap_err("failed to serialize format version")?;
+        serialize_into(&mut writer, self).wrap_err("failed to serialize header")
+    }
+
+    pub fn read_from(mut reader: impl Read) -> color_eyre::Result<Self> {
+        let mut magic = [0; RECORDING_MAGIC.len()];
+        reader
+            .read_exact(&mut magic)
+            .wrap_err("failed to read magic")?;
+        if magic != RECORDING_MAGIC {
+            bail!("not a recording file or recorded with a format without header");
+        }
+        let format_version: u32 =
+            deserialize_from(&mut reader).wrap_err("failed to deserialize format version")?;
+        if format_version != RECORDING_FORMAT_VERSION {
+            bail!("unsupported recording format version {format_version}, expected {RECORDING_FORMAT_VERSION}");
+        }
+        deserialize_from(&mut reader).wrap_err("failed to deserialize header")
+    }
+}
+
+pub fn parameters_hash(parameters: &impl Serialize) -> color_eyre::Result<u64> {
+    let parameters =
+        serde_json::to_string_pretty(parameters).wrap_err("failed to serialize parameters")?;
+    Ok(hash_to_u64(parameters.as_bytes()))
+}
+
+fn hash_to_u64(data: &[u8]) -> u64 {
+    let digest = Sha256::digest(data);
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn header_survives_round_trip() {
+        let header = RecordingHeader {
+            cycler_instance: "VisionTop".to_string(),
+            schema_hash: 42,
+            parameters_hash: 1337,
+            compression: RecordingCompression::Zstd,
+        };
+        let mut buffer = Vec::new();
+        header.write_to(&mut buffer).unwrap();
+
+        let read_header = RecordingHeader::read_from(Cursor::new(buffer)).unwrap();
+
+        assert_eq!(read_header, header);
+    }
+
+    #[test]
+    fn files_without_magic_are_rejected() {
+        let buffer = vec![0; 64];
+
+        assert!(RecordingHeader::read_from(Cursor::new(buffer)).is_err());
+    }
+}
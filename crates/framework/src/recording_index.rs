@@ -1,91 +1,143 @@
 use std::{
     fs::File,
-    io::{self, Read, Seek, SeekFrom},
+    io::{self, BufReader, Read, Seek, SeekFrom},
     path::Path,
     time::{Duration, SystemTime},
 };
 
 use bincode::{deserialize_from, Error};
-use color_eyre::eyre::WrapErr;
+use color_eyre::eyre::{bail, WrapErr};
+use crc32fast::Hasher;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    recording_header::{RecordingCompression, RecordingHeader},
+    recording_writer::index_file_path,
+};
 
 #[derive(Debug)]
 pub struct RecordingIndex {
     file: File,
+    header: Option<RecordingHeader>,
     frames: Vec<RecordingFrameMetadata>,
 }
 
 impl RecordingIndex {
-    pub fn read_from(recording_file: impl AsRef<Path>) -> color_eyre::Result<Self> {
-        let file = File::open(&recording_file)
+    pub fn read_from(
+        recording_file: impl AsRef<Path>,
+        cycler_instance: &str,
+        schema_hash: u64,
+    ) -> color_eyre::Result<Self> {
+        let mut file = File::open(&recording_file)
             .wrap_err_with(|| format!("failed to open {}", recording_file.as_ref().display()))?;
-        Self::collect_frames(file).wrap_err("failed to collect frames")
+        // the recording thread may not have flushed the header before the robot was turned off
+        if file_length(&mut file)? == 0 {
+            return Ok(Self {
+                file,
+                header: None,
+                frames: Vec::new(),
+            });
+        }
+        let index = match File::open(index_file_path(&recording_file)) {
+            Ok(index_file) => {
+                Self::read_index(file, index_file).wrap_err("failed to read index file")?
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                Self::collect_frames(file).wrap_err("failed to collect frames")?
+            }
+            Err(error) => return Err(error).wrap_err("failed to open index file"),
+        };
+        if let Some(header) = &index.header {
+            if header.cycler_instance != cycler_instance {
+                bail!(
+                    "recording contains cycler instance {}, expected {cycler_instance}",
+                    header.cycler_instance
+                );
+            }
+            if header.schema_hash != schema_hash {
+                bail!("recording of {cycler_instance} is incompatible with this build, it was recorded with different node or output types");
+            }
+        }
+        Ok(index)
     }
 
-    fn collect_frames(mut recording_file: File) -> color_eyre::Result<Self> {
-        let mut frames = Vec::new();
-
-        recording_file
-            .seek(SeekFrom::End(0))
-            .wrap_err("failed to seek to end of file")?;
-        let file_length = recording_file
+    /// Reads the frame locations from the index sidecar instead of scanning the whole recording
+    ///
+    /// The recording and its index are flushed independently, frames beyond the last index entry
+    /// are collected by scanning the rest of the recording.
+    fn read_index(mut recording_file: File, index_file: File) -> color_eyre::Result<Self> {
+        let file_length = file_length(&mut recording_file)?;
+        let header = RecordingHeader::read_from(&mut recording_file)
+            .wrap_err("failed to read recording header")?;
+        let header_end = recording_file
             .stream_position()
-            .wrap_err("failed to get stream position of end of file")?;
-        recording_file.rewind().wrap_err("failed to rewind file")?;
+            .wrap_err("failed to get stream position")?;
 
-        let mut offset = 0;
-        while offset < file_length {
-            let Some(timestamp) =
-                end_of_file_error_as_option(deserialize_from(&mut recording_file))
-                    .wrap_err("failed to deserialize timestamp")?
-            else {
-                eprintln!("unexpected end of file of recording file while deserializing timestamp");
-                break;
-            };
-            let Some(duration) = end_of_file_error_as_option(deserialize_from(&mut recording_file))
-                .wrap_err("failed to deserialize duration")?
+        let mut index_file = BufReader::new(index_file);
+        let mut frames = Vec::new();
+        let mut end_of_indexed_frames = header_end;
+        loop {
+            let Some(frame): Option<RecordingFrameMetadata> =
+                end_of_file_error_as_option(deserialize_from(&mut index_file))
+                    .wrap_err("failed to deserialize index entry")?
             else {
-                eprintln!("unexpected end of file of recording file while deserializing duration");
                 break;
             };
-            let Some(length) = end_of_file_error_as_option(deserialize_from(&mut recording_file))
-                .wrap_err("failed to deserialize data length")?
-            else {
-                eprintln!("unexpected end of file of recording file while deserializing length");
+            let Some(frame_end) = frame_end(frame.data_offset, frame.length, file_length) else {
+                eprintln!("index of recording file references frames beyond the end of file");
                 break;
             };
-            let header_length = recording_file
-                .stream_position()
-                .wrap_err("failed to get stream position")?
-                - offset;
-            recording_file
-                .seek(SeekFrom::Current(length as i64))
-                .wrap_err("failed to seek to end of data")?;
-            if offset + header_length + length as u64 > file_length {
-                eprintln!("unexpected end of file of recording file");
-                break;
-            }
-            frames.push(RecordingFrameMetadata {
-                timing: Timing {
-                    timestamp,
-                    duration,
-                },
-                offset: offset.try_into().unwrap(),
-                header_offset: header_length.try_into().unwrap(),
-                length,
-            });
-            offset = recording_file
-                .stream_position()
-                .wrap_err("failed to get stream position")?;
+            end_of_indexed_frames = frame_end;
+            frames.push(frame);
         }
 
+        recording_file
+            .seek(SeekFrom::Start(end_of_indexed_frames))
+            .wrap_err("failed to seek to end of indexed frames")?;
+        let number_of_indexed_frames = frames.len();
+        collect_frames_until(&mut recording_file, file_length, &mut frames)?;
+        if frames.len() > number_of_indexed_frames {
+            eprintln!(
+                "index of recording file lags behind, found {} frames after the last index entry",
+                frames.len() - number_of_indexed_frames
+            );
+        }
         recording_file.rewind().wrap_err("failed to rewind file")?;
 
         Ok(Self {
             file: recording_file,
+            header: Some(header),
             frames,
         })
     }
 
+    fn collect_frames(mut recording_file: File) -> color_eyre::Result<Self> {
+        let mut frames = Vec::new();
+
+        let file_length = file_length(&mut recording_file)?;
+        let header = RecordingHeader::read_from(&mut recording_file)
+            .wrap_err("failed to read recording header")?;
+        collect_frames_until(&mut recording_file, file_length, &mut frames)?;
+
+        recording_file.rewind().wrap_err("failed to rewind file")?;
+
+        Ok(Self {
+            file: recording_file,
+            header: Some(header),
+            frames,
+        })
+    }
+
+    pub fn header(&self) -> Option<&RecordingHeader> {
+        self.header.as_ref()
+    }
+
+    fn compression(&self) -> RecordingCompression {
+        self.header
+            .as_ref()
+            .map_or(RecordingCompression::None, |header| header.compression)
+    }
+
     pub fn number_of_frames(&self) -> usize {
         self.frames.len()
     }
@@ -94,29 +146,25 @@ impl RecordingIndex {
         &mut self,
         timestamp: SystemTime,
     ) -> color_eyre::Result<Option<RecordingFrame>> {
-        let frame = match self
+        let compression = self.compression();
+        let Some(frame) = self
             .frames
             .iter()
             .rev()
             .find(|frame| frame.timing.timestamp <= timestamp)
-        {
-            Some(frame) => frame,
-            None => return Ok(None),
+        else {
+            return Ok(None);
+        };
+        read_frame(&mut self.file, compression, frame).map(Some)
+    }
+
+    /// Reads the frame at the given index, fails if the frame is damaged
+    pub fn read_frame(&mut self, index: usize) -> color_eyre::Result<Option<RecordingFrame>> {
+        let compression = self.compression();
+        let Some(frame) = self.frames.get(index) else {
+            return Ok(None);
         };
-        self.file
-            .seek(SeekFrom::Start(
-                (frame.offset + frame.header_offset).try_into().unwrap(),
-            ))
-            .wrap_err("failed to seek to frame")?;
-        let mut data = Vec::new();
-        data.resize_with(frame.length, Default::default);
-        self.file
-            .read_exact(&mut data)
-            .wrap_err("failed to read from recording file")?;
-        Ok(Some(RecordingFrame {
-            timing: frame.timing,
-            data,
-        }))
+        read_frame(&mut self.file, compression, frame).map(Some)
     }
 
     pub fn first_timing(&self) -> Option<Timing> {
@@ -132,12 +180,159 @@ impl RecordingIndex {
     }
 }
 
-#[derive(Debug)]
-struct RecordingFrameMetadata {
-    timing: Timing,
-    offset: usize,
-    header_offset: usize,
-    length: usize,
+/// Scans frames from the current position of the recording file up to its end
+fn collect_frames_until(
+    recording_file: &mut File,
+    file_length: u64,
+    frames: &mut Vec<RecordingFrameMetadata>,
+) -> color_eyre::Result<()> {
+    let mut offset = recording_file
+        .stream_position()
+        .wrap_err("failed to get stream position")?;
+    while offset < file_length {
+        let mut frame_header = ChecksumReader::new(&mut *recording_file);
+        let Some(timestamp) = end_of_file_error_as_option(deserialize_from(&mut frame_header))
+            .wrap_err("failed to deserialize timestamp")?
+        else {
+            eprintln!("unexpected end of file of recording file while deserializing timestamp");
+            break;
+        };
+        let Some(duration) = end_of_file_error_as_option(deserialize_from(&mut frame_header))
+            .wrap_err("failed to deserialize duration")?
+        else {
+            eprintln!("unexpected end of file of recording file while deserializing duration");
+            break;
+        };
+        let Some(length) = end_of_file_error_as_option(deserialize_from(&mut frame_header))
+            .wrap_err("failed to deserialize data length")?
+        else {
+            eprintln!("unexpected end of file of recording file while deserializing length");
+            break;
+        };
+        let Some(checksum) = end_of_file_error_as_option(deserialize_from(&mut frame_header))
+            .wrap_err("failed to deserialize checksum")?
+        else {
+            eprintln!("unexpected end of file of recording file while deserializing checksum");
+            break;
+        };
+        let calculated_header_checksum = frame_header.finalize();
+        let Some(header_checksum): Option<u32> =
+            end_of_file_error_as_option(deserialize_from(&mut *recording_file))
+                .wrap_err("failed to deserialize header checksum")?
+        else {
+            eprintln!(
+                "unexpected end of file of recording file while deserializing header checksum"
+            );
+            break;
+        };
+        // the length cannot be trusted to find the following frames if the header is damaged
+        if header_checksum != calculated_header_checksum {
+            eprintln!("frame header at {offset} of recording file is damaged");
+            break;
+        }
+        let data_offset = recording_file
+            .stream_position()
+            .wrap_err("failed to get stream position")?;
+        let Some(frame_end) = frame_end(data_offset, length, file_length) else {
+            eprintln!("unexpected end of file of recording file");
+            break;
+        };
+        recording_file
+            .seek(SeekFrom::Start(frame_end))
+            .wrap_err("failed to seek to end of data")?;
+        frames.push(RecordingFrameMetadata {
+            timing: Timing {
+                timestamp,
+                duration,
+            },
+            data_offset,
+            length,
+            checksum,
+        });
+        offset = recording_file
+            .stream_position()
+            .wrap_err("failed to get stream position")?;
+    }
+    Ok(())
+}
+
+/// End of the frame data, `None` if it lies beyond the end of the file
+fn frame_end(data_offset: u64, length: usize, file_length: u64) -> Option<u64> {
+    data_offset
+        .checked_add(length as u64)
+        .filter(|frame_end| *frame_end <= file_length)
+}
+
+/// Calculates the checksum of all bytes read through it
+struct ChecksumReader<R> {
+    reader: R,
+    hasher: Hasher,
+}
+
+impl<R> ChecksumReader<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            hasher: Hasher::new(),
+        }
+    }
+
+    fn finalize(self) -> u32 {
+        self.hasher.finalize()
+    }
+}
+
+impl<R: Read> Read for ChecksumReader<R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let length = self.reader.read(buffer)?;
+        self.hasher.update(&buffer[..length]);
+        Ok(length)
+    }
+}
+
+fn file_length(file: &mut File) -> color_eyre::Result<u64> {
+    let file_length = file
+        .seek(SeekFrom::End(0))
+        .wrap_err("failed to seek to end of file")?;
+    file.rewind().wrap_err("failed to rewind file")?;
+    Ok(file_length)
+}
+
+fn read_frame(
+    file: &mut File,
+    compression: RecordingCompression,
+    frame: &RecordingFrameMetadata,
+) -> color_eyre::Result<RecordingFrame> {
+    file.seek(SeekFrom::Start(frame.data_offset))
+        .wrap_err("failed to seek to frame")?;
+    let mut data = Vec::new();
+    data.resize_with(frame.length, Default::default);
+    file.read_exact(&mut data)
+        .wrap_err("failed to read from recording file")?;
+    if crc32fast::hash(&data) != frame.checksum {
+        bail!(
+            "frame at {:?} is damaged, checksum does not match",
+            frame.timing.timestamp
+        );
+    }
+    let data = match compression {
+        RecordingCompression::None => data,
+        RecordingCompression::Zstd => {
+            zstd::stream::decode_all(data.as_slice()).wrap_err("failed to decompress frame")?
+        }
+    };
+    Ok(RecordingFrame {
+        timing: frame.timing,
+        data,
+    })
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct RecordingFrameMetadata {
+    pub(crate) timing: Timing,
+    pub(crate) data_offset: u64,
+    pub(crate) length: usize,
+    pub(crate) checksum: u32,
 }
 
 #[derive(Debug)]
@@ -146,7 +341,7 @@ pub struct RecordingFrame {
     pub data: Vec<u8>,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct Timing {
     pub timestamp: SystemTime,
     pub duration: Duration,
@@ -162,3 +357,146 @@ fn end_of_file_error_as_option<T>(result: Result<T, Error>) -> Result<Option<T>,
         Err(error)
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{remove_file, OpenOptions},
+        io::Write,
+        time::UNIX_EPOCH,
+    };
+
+    use tempfile::tempdir;
+
+    use crate::RecordingWriter;
+
+    use super::*;
+
+    fn write_recording(recording_file: &Path, compression: RecordingCompression) -> Vec<Vec<u8>> {
+        let frames: Vec<_> = (0..10u8).map(|frame| vec![frame; 1000]).collect();
+        let mut writer = RecordingWriter::create(
+            recording_file,
+            RecordingHeader {
+                cycler_instance: "Control".to_string(),
+                schema_hash: 42,
+                parameters_hash: 1337,
+                compression,
+            },
+        )
+        .unwrap();
+        for (index, data) in frames.iter().enumerate() {
+            writer
+                .write_frame(
+                    UNIX_EPOCH + Duration::from_millis(index as u64 * 12),
+                    Duration::from_millis(1),
+                    data,
+                )
+                .unwrap();
+        }
+        frames
+    }
+
+    fn read_all_frames(index: &mut RecordingIndex) -> Vec<Vec<u8>> {
+        (0..index.number_of_frames())
+            .map(|frame_index| index.read_frame(frame_index).unwrap().unwrap().data)
+            .collect()
+    }
+
+    #[test]
+    fn compressed_frames_are_read_from_index_and_by_scanning() {
+        let directory = tempdir().unwrap();
+        let recording_file = directory.path().join("Control.bincode");
+        let frames = write_recording(&recording_file, RecordingCompression::Zstd);
+
+        let mut index = RecordingIndex::read_from(&recording_file, "Control", 42).unwrap();
+        assert_eq!(read_all_frames(&mut index), frames);
+
+        remove_file(index_file_path(&recording_file)).unwrap();
+        let mut index = RecordingIndex::read_from(&recording_file, "Control", 42).unwrap();
+        assert_eq!(read_all_frames(&mut index), frames);
+    }
+
+    #[test]
+    fn frames_after_a_lagging_index_are_found_by_scanning() {
+        let directory = tempdir().unwrap();
+        let recording_file = directory.path().join("Control.bincode");
+        let frames = write_recording(&recording_file, RecordingCompression::Zstd);
+        // keep 4 complete index entries and a partially flushed fifth one
+        let index_file = OpenOptions::new()
+            .write(true)
+            .open(index_file_path(&recording_file))
+            .unwrap();
+        let entry_length = index_file.metadata().unwrap().len() / frames.len() as u64;
+        index_file.set_len(4 * entry_length + 3).unwrap();
+
+        let mut index = RecordingIndex::read_from(&recording_file, "Control", 42).unwrap();
+        assert_eq!(read_all_frames(&mut index), frames);
+
+        OpenOptions::new()
+            .write(true)
+            .open(index_file_path(&recording_file))
+            .unwrap()
+            .set_len(0)
+            .unwrap();
+        let mut index = RecordingIndex::read_from(&recording_file, "Control", 42).unwrap();
+        assert_eq!(read_all_frames(&mut index), frames);
+    }
+
+    #[test]
+    fn scanning_stops_at_a_damaged_frame_header() {
+        let directory = tempdir().unwrap();
+        let recording_file = directory.path().join("Control.bincode");
+        let frames = write_recording(&recording_file, RecordingCompression::None);
+        let third_frame_data_offset = RecordingIndex::read_from(&recording_file, "Control", 42)
+            .unwrap()
+            .frames[2]
+            .data_offset;
+        remove_file(index_file_path(&recording_file)).unwrap();
+        // the length is followed by the data and header checksums
+        let length_offset = third_frame_data_offset - 16;
+        let mut recording = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&recording_file)
+            .unwrap();
+        recording.seek(SeekFrom::Start(length_offset)).unwrap();
+        recording.write_all(&[0xff]).unwrap();
+
+        let mut index = RecordingIndex::read_from(&recording_file, "Control", 42).unwrap();
+        assert_eq!(read_all_frames(&mut index), frames[..2]);
+    }
+
+    #[test]
+    fn index_entries_with_overflowing_offsets_are_ignored() {
+        let directory = tempdir().unwrap();
+        let recording_file = directory.path().join("Control.bincode");
+        let frames = write_recording(&recording_file, RecordingCompression::None);
+        let index_file = File::create(index_file_path(&recording_file)).unwrap();
+        bincode::serialize_into(
+            index_file,
+            &RecordingFrameMetadata {
+                timing: Timing {
+                    timestamp: UNIX_EPOCH,
+                    duration: Duration::ZERO,
+                },
+                data_offset: u64::MAX,
+                length: 1000,
+                checksum: 0,
+            },
+        )
+        .unwrap();
+
+        let mut index = RecordingIndex::read_from(&recording_file, "Control", 42).unwrap();
+        assert_eq!(read_all_frames(&mut index), frames);
+    }
+
+    #[test]
+    fn recordings_with_different_schema_are_refused() {
+        let directory = tempdir().unwrap();
+        let recording_file = directory.path().join("Control.bincode");
+        write_recording(&recording_file, RecordingCompression::None);
+
+        assert!(RecordingIndex::read_from(&recording_file, "Control", 43).is_err());
+        assert!(RecordingIndex::read_from(&recording_file, "VisionTop", 42).is_err());
+    }
+}
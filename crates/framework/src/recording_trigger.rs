@@ -1,12 +1,15 @@
 pub struct RecordingTrigger {
     recording_interval: usize,
+    record_every_cycle: bool,
     counter: usize,
 }
 
 impl RecordingTrigger {
-    pub fn new(recording_interval: usize) -> Self {
+    /// With `record_every_cycle`, every cycle is recorded to fill the event buffer
+    pub fn new(recording_interval: usize, record_every_cycle: bool) -> Self {
         Self {
             recording_interval,
+            record_every_cycle,
             counter: 0,
         }
     }
@@ -18,6 +21,10 @@ impl RecordingTrigger {
     }
 
     pub fn should_record(&self) -> bool {
+        self.recording_interval != 0 && (self.record_every_cycle || self.counter == 0)
+    }
+
+    pub fn is_in_recording_interval(&self) -> bool {
         self.recording_interval != 0 && self.counter == 0
     }
 }
@@ -0,0 +1,108 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use bincode::serialize_into;
+use color_eyre::eyre::WrapErr;
+
+use crate::{
+    recording_header::{RecordingCompression, RecordingHeader},
+    recording_index::{RecordingFrameMetadata, Timing},
+};
+
+// favors speed over ratio, the recording thread has to keep up with the vision cyclers on the NAO
+const ZSTD_LEVEL: i32 = 1;
+
+/// Writes frames into a recording file and their locations into the index sidecar next to it
+pub struct RecordingWriter {
+    file: BufWriter<File>,
+    index: BufWriter<File>,
+    compression: RecordingCompression,
+    offset: u64,
+}
+
+impl RecordingWriter {
+    pub fn create(
+        recording_file: impl AsRef<Path>,
+        header: RecordingHeader,
+    ) -> color_eyre::Result<Self> {
+        let mut file =
+            BufWriter::new(File::create(&recording_file).wrap_err_with(|| {
+                format!("failed to create {}", recording_file.as_ref().display())
+            })?);
+        let index_file = index_file_path(&recording_file);
+        let index = BufWriter::new(
+            File::create(&index_file)
+                .wrap_err_with(|| format!("failed to create {}", index_file.display()))?,
+        );
+        let mut header_buffer = Vec::new();
+        header
+            .write_to(&mut header_buffer)
+            .wrap_err("failed to serialize header")?;
+        file.write_all(&header_buffer)
+            .wrap_err("failed to write header")?;
+        Ok(Self {
+            file,
+            index,
+            compression: header.compression,
+            offset: header_buffer.len() as u64,
+        })
+    }
+
+    pub fn write_frame(
+        &mut self,
+        timestamp: SystemTime,
+        duration: Duration,
+        data: &[u8],
+    ) -> color_eyre::Result<()> {
+        let compressed_data;
+        let data = match self.compression {
+            RecordingCompression::None => data,
+            RecordingCompression::Zstd => {
+                compressed_data =
+                    zstd::bulk::compress(data, ZSTD_LEVEL).wrap_err("failed to compress frame")?;
+                &compressed_data
+            }
+        };
+        let checksum = crc32fast::hash(data);
+
+        let mut frame_header = Vec::new();
+        serialize_into(&mut frame_header, &timestamp).wrap_err("failed to serialize timestamp")?;
+        serialize_into(&mut frame_header, &duration).wrap_err("failed to serialize duration")?;
+        serialize_into(&mut frame_header, &data.len())
+            .wrap_err("failed to serialize data length")?;
+        serialize_into(&mut frame_header, &checksum).wrap_err("failed to serialize checksum")?;
+        let header_checksum = crc32fast::hash(&frame_header);
+        serialize_into(&mut frame_header, &header_checksum)
+            .wrap_err("failed to serialize header checksum")?;
+        self.file
+            .write_all(&frame_header)
+            .wrap_err("failed to write frame header")?;
+        self.file
+            .write_all(data)
+            .wrap_err("failed to write frame data")?;
+
+        let data_offset = self.offset + frame_header.len() as u64;
+        self.offset = data_offset + data.len() as u64;
+        serialize_into(
+            &mut self.index,
+            &RecordingFrameMetadata {
+                timing: Timing {
+                    timestamp,
+                    duration,
+                },
+                data_offset,
+                length: data.len(),
+                checksum,
+            },
+        )
+        .wrap_err("failed to write index entry")
+    }
+}
+
+pub(crate) fn index_file_path(recording_file: impl AsRef<Path>) -> PathBuf {
+    recording_file.as_ref().with_extension("index")
+}
@@ -46,6 +46,8 @@ pub trait PathsInterface {
 pub trait RecordingInterface {
     fn should_record(&self) -> bool;
     fn set_whether_to_record(&self, enable: bool);
+    fn trigger_recording_event(&self);
+    fn latest_recording_event(&self) -> Option<SystemTime>;
 }
 
 pub trait SensorInterface {
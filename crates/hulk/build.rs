@@ -1,4 +1,4 @@
-use code_generation::{generate, write_to_file::WriteToFile, ExecutionMode};
+use code_generation::{generate, recording_schema, write_to_file::WriteToFile, ExecutionMode};
 use color_eyre::eyre::{Result, WrapErr};
 use hulk_manifest::collect_hulk_cyclers;
 use source_analyzer::{pretty::to_string_pretty, structs::Structs};
@@ -8,6 +8,9 @@ fn main() -> Result<()> {
     for path in cyclers.watch_paths() {
         println!("cargo:rerun-if-changed={}", path.display());
     }
+    for path in recording_schema::watch_paths(&cyclers) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
 
     println!();
     println!("{}", to_string_pretty(&cyclers)?);
@@ -0,0 +1,157 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use clap::Args;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use serde_json::Value;
+
+use crate::output_frame::{read_output_frames, OutputFrame};
+
+#[derive(Args)]
+pub struct Arguments {
+    /// Output file of the baseline replay
+    pub baseline: PathBuf,
+    /// Output file of the replay to compare against the baseline
+    pub candidate: PathBuf,
+    /// Maximum absolute difference between two numbers that are still considered equal
+    #[arg(long, default_value_t = 0.0)]
+    pub tolerance: f64,
+}
+
+type FrameKey = (String, SystemTime);
+
+pub fn compare(arguments: Arguments) -> Result<()> {
+    let baseline =
+        index_frames(read_output_frames(&arguments.baseline).wrap_err("failed to read baseline")?);
+    let candidate = index_frames(
+        read_output_frames(&arguments.candidate).wrap_err("failed to read candidate")?,
+    );
+
+    let mut missing_frames = BTreeMap::<_, Vec<_>>::new();
+    let mut divergences = BTreeMap::<_, Vec<_>>::new();
+    let keys: BTreeSet<_> = baseline.keys().chain(candidate.keys()).collect();
+    for key @ (cycler_instance, timestamp) in keys {
+        let (Some(baseline_outputs), Some(candidate_outputs)) =
+            (baseline.get(key), candidate.get(key))
+        else {
+            missing_frames
+                .entry(cycler_instance.clone())
+                .or_default()
+                .push(*timestamp);
+            continue;
+        };
+        let paths: BTreeSet<_> = baseline_outputs
+            .keys()
+            .chain(candidate_outputs.keys())
+            .collect();
+        for path in paths {
+            let is_matching = match (baseline_outputs.get(path), candidate_outputs.get(path)) {
+                (Some(baseline_value), Some(candidate_value)) => {
+                    values_match(baseline_value, candidate_value, arguments.tolerance)
+                }
+                _ => false,
+            };
+            if !is_matching {
+                divergences
+                    .entry(format!("{cycler_instance}.{path}"))
+                    .or_default()
+                    .push(*timestamp);
+            }
+        }
+    }
+
+    for (cycler_instance, timestamps) in &missing_frames {
+        println!(
+            "{cycler_instance}: {} frames are only present in one of the files",
+            timestamps.len()
+        );
+        print_timestamps(timestamps);
+    }
+    for (output, timestamps) in &divergences {
+        println!("{output}: diverged in {} frames", timestamps.len());
+        print_timestamps(timestamps);
+    }
+
+    if !missing_frames.is_empty() || !divergences.is_empty() {
+        bail!(
+            "{} outputs diverged, {} cycler instances have missing frames",
+            divergences.len(),
+            missing_frames.len()
+        );
+    }
+    println!("all outputs match");
+    Ok(())
+}
+
+fn index_frames(frames: Vec<OutputFrame>) -> BTreeMap<FrameKey, BTreeMap<String, Value>> {
+    frames
+        .into_iter()
+        .map(|frame| ((frame.cycler_instance, frame.timestamp), frame.outputs))
+        .collect()
+}
+
+fn print_timestamps(timestamps: &[SystemTime]) {
+    for timestamp in timestamps {
+        let seconds = timestamp
+            .duration_since(UNIX_EPOCH)
+            .expect("time ran backwards")
+            .as_secs_f64();
+        println!("    {seconds:.3}");
+    }
+}
+
+fn values_match(baseline: &Value, candidate: &Value, tolerance: f64) -> bool {
+    match (baseline, candidate) {
+        (Value::Number(baseline), Value::Number(candidate)) => {
+            match (baseline.as_f64(), candidate.as_f64()) {
+                (Some(baseline), Some(candidate)) => (baseline - candidate).abs() <= tolerance,
+                _ => baseline == candidate,
+            }
+        }
+        (Value::Array(baseline), Value::Array(candidate)) => {
+            baseline.len() == candidate.len()
+                && baseline
+                    .iter()
+                    .zip(candidate)
+                    .all(|(baseline, candidate)| values_match(baseline, candidate, tolerance))
+        }
+        (Value::Object(baseline), Value::Object(candidate)) => {
+            baseline.len() == candidate.len()
+                && baseline.iter().all(|(key, baseline)| {
+                    candidate
+                        .get(key)
+                        .is_some_and(|candidate| values_match(baseline, candidate, tolerance))
+                })
+        }
+        (baseline, candidate) => baseline == candidate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn numbers_within_tolerance_match() {
+        let baseline = json!({"position": [1.0, 2.0], "is_valid": true});
+        let candidate = json!({"position": [1.05, 2.0], "is_valid": true});
+
+        assert!(values_match(&baseline, &candidate, 0.1));
+        assert!(!values_match(&baseline, &candidate, 0.01));
+    }
+
+    #[test]
+    fn structural_differences_do_not_match() {
+        assert!(!values_match(&json!([1, 2]), &json!([1, 2, 3]), 1.0));
+        assert!(!values_match(&json!({"a": 1}), &json!({"b": 1}), 1.0));
+        assert!(!values_match(&json!(null), &json!(0), 1.0));
+    }
+}
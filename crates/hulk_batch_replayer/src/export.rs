@@ -0,0 +1,245 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::Args;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use image::{codecs::jpeg::JpegEncoder, RgbImage};
+use serde_json::{json, to_vec, value::Serializer, Map, Value};
+
+use crate::{
+    mcap::McapWriter,
+    replay::{create_replayer, group_outputs, parse_output, replay_frames},
+};
+
+const IMAGE_CYCLER_INSTANCES: [&str; 2] = ["VisionTop", "VisionBottom"];
+const JPEG_QUALITY: u8 = 80;
+
+#[derive(Args)]
+pub struct Arguments {
+    /// Directory containing the recording files of one execution of the `hulk` binary
+    pub recording_directory: PathBuf,
+    /// Path of the MCAP file to write
+    pub output_file: PathBuf,
+    /// Parameters used during replay (defaults to the parameters stored next to the recording)
+    #[arg(long)]
+    pub parameters_directory: Option<PathBuf>,
+    /// Outputs to export, e.g. Control.main_outputs.ball_position,Control.main_outputs.robot_to_field
+    #[arg(long, value_delimiter = ',', value_parser = parse_output)]
+    pub outputs: Vec<(String, String)>,
+    /// Export camera images of the vision cyclers as JPEG compressed images
+    #[arg(long)]
+    pub images: bool,
+}
+
+pub fn export(arguments: Arguments) -> Result<()> {
+    if arguments.outputs.is_empty() && !arguments.images {
+        bail!("nothing to export, select outputs or images");
+    }
+    let mut outputs = group_outputs(arguments.outputs);
+    if arguments.images {
+        for cycler_instance in IMAGE_CYCLER_INSTANCES {
+            outputs.entry(cycler_instance.to_string()).or_default();
+        }
+    }
+    let mut replayer = create_replayer(
+        arguments.recording_directory,
+        arguments.parameters_directory,
+    )?;
+
+    let file = File::create(&arguments.output_file)
+        .wrap_err_with(|| format!("failed to create {}", arguments.output_file.display()))?;
+    let mut writer = McapWriter::new(BufWriter::new(file))?;
+
+    let mut output_channels = BTreeMap::new();
+    for (cycler_instance, paths) in &outputs {
+        let fields = replayer.output_fields(cycler_instance)?;
+        for path in paths {
+            if !fields.contains(path) {
+                bail!("unknown output `{cycler_instance}.{path}`");
+            }
+            let topic = format!("{cycler_instance}.{path}");
+            let schema =
+                to_vec(&json_schema(&fields, path)).wrap_err("failed to serialize schema")?;
+            let schema_id = writer.add_schema(&topic, "jsonschema", &schema)?;
+            let channel_id = writer.add_channel(schema_id, &topic, "json")?;
+            output_channels.insert((cycler_instance.clone(), path.clone()), channel_id);
+        }
+    }
+    let mut image_channels = BTreeMap::new();
+    if arguments.images {
+        let schema = to_vec(&compressed_image_schema()).wrap_err("failed to serialize schema")?;
+        let schema_id = writer.add_schema("foxglove.CompressedImage", "jsonschema", &schema)?;
+        for (cycler_instance, reader) in IMAGE_CYCLER_INSTANCES.into_iter().zip([
+            replayer.vision_top_reader(),
+            replayer.vision_bottom_reader(),
+        ]) {
+            let channel_id =
+                writer.add_channel(schema_id, &format!("{cycler_instance}.image"), "json")?;
+            image_channels.insert(cycler_instance, (channel_id, reader));
+        }
+    }
+
+    replay_frames(
+        &mut replayer,
+        &outputs,
+        |replayer, cycler_instance, timestamp| {
+            for path in &outputs[cycler_instance] {
+                let value = replayer
+                    .serialize_output(cycler_instance, path, Serializer)
+                    .wrap_err_with(|| format!("failed to serialize {cycler_instance}.{path}"))?;
+                let message =
+                    to_vec(&json!({ "value": value })).wrap_err("failed to serialize message")?;
+                let channel_id = output_channels[&(cycler_instance.to_string(), path.clone())];
+                writer.write_message(channel_id, timestamp, &message)?;
+            }
+            if let Some((channel_id, reader)) = image_channels.get(cycler_instance) {
+                let image = RgbImage::from(&reader.next().main_outputs.image);
+                let message = to_vec(&compressed_image_message(
+                    &image,
+                    cycler_instance,
+                    timestamp,
+                )?)
+                .wrap_err("failed to serialize message")?;
+                writer.write_message(*channel_id, timestamp, &message)?;
+            }
+            Ok(())
+        },
+    )?;
+
+    writer
+        .finish()?
+        .flush()
+        .wrap_err("failed to flush output file")
+}
+
+/// Derives a JSON schema for the output at `path` from the fields of the cycler database
+///
+/// The field list neither contains leaf types nor optionality, therefore leaves accept any value
+/// and objects are described only by their properties.
+fn json_schema(fields: &BTreeSet<String>, path: &str) -> Value {
+    let prefix = format!("{path}.");
+    let mut root = Map::new();
+    for field in fields
+        .iter()
+        .filter_map(|field| field.strip_prefix(&prefix))
+    {
+        let mut properties = &mut root;
+        for segment in field.split('.') {
+            properties = properties
+                .entry(segment)
+                .or_insert_with(|| json!({ "properties": {} }))["properties"]
+                .as_object_mut()
+                .unwrap();
+        }
+    }
+    remove_empty_objects(&mut root);
+    json!({
+        "type": "object",
+        "properties": {
+            "value": if root.is_empty() { json!({}) } else { json!({ "properties": root }) },
+        },
+    })
+}
+
+fn remove_empty_objects(properties: &mut Map<String, Value>) {
+    for property in properties.values_mut() {
+        let property = property.as_object_mut().unwrap();
+        let nested_properties = property["properties"].as_object_mut().unwrap();
+        if nested_properties.is_empty() {
+            property.clear();
+        } else {
+            remove_empty_objects(nested_properties);
+        }
+    }
+}
+
+fn compressed_image_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "timestamp": {
+                "type": "object",
+                "properties": {
+                    "sec": { "type": "integer" },
+                    "nsec": { "type": "integer" },
+                },
+            },
+            "frame_id": { "type": "string" },
+            "data": { "type": "string", "contentEncoding": "base64" },
+            "format": { "type": "string" },
+        },
+    })
+}
+
+fn compressed_image_message(
+    image: &RgbImage,
+    cycler_instance: &str,
+    timestamp: SystemTime,
+) -> Result<Value> {
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode_image(image)
+        .wrap_err("failed to encode image")?;
+    let since_epoch = timestamp
+        .duration_since(UNIX_EPOCH)
+        .expect("time ran backwards");
+    Ok(json!({
+        "timestamp": {
+            "sec": since_epoch.as_secs(),
+            "nsec": since_epoch.subsec_nanos(),
+        },
+        "frame_id": cycler_instance,
+        "data": STANDARD.encode(jpeg),
+        "format": "jpeg",
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn schema_contains_nested_fields_of_output() {
+        let fields = [
+            "main_outputs.ball_position",
+            "main_outputs.ball_position.position",
+            "main_outputs.ball_position.position.x",
+            "main_outputs.ball_position.position.y",
+            "main_outputs.ball_position.velocity",
+            "main_outputs.primary_state",
+        ]
+        .into_iter()
+        .map(String::from)
+        .collect();
+
+        let schema = json_schema(&fields, "main_outputs.ball_position");
+
+        assert_eq!(
+            schema["properties"]["value"],
+            json!({
+                "properties": {
+                    "position": {
+                        "properties": {
+                            "x": {},
+                            "y": {},
+                        },
+                    },
+                    "velocity": {},
+                },
+            })
+        );
+        assert_eq!(
+            json_schema(&fields, "main_outputs.primary_state")["properties"]["value"],
+            json!({})
+        );
+    }
+}
@@ -0,0 +1,168 @@
+#![recursion_limit = "256"]
+mod compare;
+mod export;
+mod mcap;
+mod output_frame;
+mod replay;
+
+use std::time::SystemTime;
+
+use clap::{Parser, Subcommand};
+use color_eyre::{
+    eyre::{Result, WrapErr},
+    install,
+};
+use hardware::{
+    ActuatorInterface, CameraInterface, IdInterface, MicrophoneInterface, NetworkInterface,
+    PathsInterface, RecordingInterface, SensorInterface, SpeakerInterface, TimeInterface,
+};
+use types::{
+    audio::SpeakerRequest,
+    camera_position::CameraPosition,
+    hardware::{Ids, Paths},
+    joints::Joints,
+    led::Leds,
+    messages::{IncomingMessage, OutgoingMessage},
+    samples::Samples,
+    sensor_data::SensorData,
+    ycbcr422_image::YCbCr422Image,
+};
+
+use compare::{compare, Arguments as CompareArguments};
+use export::{export, Arguments as ExportArguments};
+use replay::{replay, Arguments as ReplayArguments};
+
+pub trait HardwareInterface:
+    ActuatorInterface
+    + CameraInterface
+    + IdInterface
+    + MicrophoneInterface
+    + NetworkInterface
+    + PathsInterface
+    + RecordingInterface
+    + SensorInterface
+    + SpeakerInterface
+    + TimeInterface
+{
+}
+
+include!(concat!(env!("OUT_DIR"), "/generated_code.rs"));
+
+struct BatchReplayerHardwareInterface {
+    ids: Ids,
+}
+
+impl ActuatorInterface for BatchReplayerHardwareInterface {
+    fn write_to_actuators(
+        &self,
+        _positions: Joints<f32>,
+        _stiffnesses: Joints<f32>,
+        _leds: Leds,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl CameraInterface for BatchReplayerHardwareInterface {
+    fn read_from_camera(&self, _camera_position: CameraPosition) -> Result<YCbCr422Image> {
+        panic!("Replayer cannot produce data from hardware")
+    }
+}
+
+impl IdInterface for BatchReplayerHardwareInterface {
+    fn get_ids(&self) -> Ids {
+        self.ids.clone()
+    }
+}
+
+impl MicrophoneInterface for BatchReplayerHardwareInterface {
+    fn read_from_microphones(&self) -> Result<Samples> {
+        panic!("Replayer cannot produce data from hardware")
+    }
+}
+
+impl NetworkInterface for BatchReplayerHardwareInterface {
+    fn read_from_network(&self) -> Result<IncomingMessage> {
+        panic!("Replayer cannot produce data from hardware")
+    }
+
+    fn write_to_network(&self, _message: OutgoingMessage) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl PathsInterface for BatchReplayerHardwareInterface {
+    fn get_paths(&self) -> Paths {
+        Paths {
+            motions: "etc/motions".into(),
+            neural_networks: "etc/neural_networks".into(),
+            parameters: "etc/parameters".into(),
+            sounds: "etc/sounds".into(),
+        }
+    }
+}
+
+impl RecordingInterface for BatchReplayerHardwareInterface {
+    fn should_record(&self) -> bool {
+        false
+    }
+
+    fn set_whether_to_record(&self, _enable: bool) {}
+
+    fn trigger_recording_event(&self) {}
+
+    fn latest_recording_event(&self) -> Option<SystemTime> {
+        None
+    }
+}
+
+impl SensorInterface for BatchReplayerHardwareInterface {
+    fn read_from_sensors(&self) -> Result<SensorData> {
+        panic!("Replayer cannot produce data from hardware")
+    }
+}
+
+impl SpeakerInterface for BatchReplayerHardwareInterface {
+    fn write_to_speakers(&self, _request: SpeakerRequest) {}
+}
+
+impl TimeInterface for BatchReplayerHardwareInterface {
+    fn get_now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+impl HardwareInterface for BatchReplayerHardwareInterface {}
+
+#[derive(Parser)]
+#[clap(name = "batch_replayer")]
+struct Arguments {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Replay all frames of a recording and write selected outputs into a file
+    Replay(ReplayArguments),
+    /// Compare two output files written by `replay` and report diverging outputs
+    Compare(CompareArguments),
+    /// Replay a recording and export selected outputs and camera images into an MCAP file
+    Export(ExportArguments),
+}
+
+fn main() -> Result<()> {
+    install()?;
+
+    match Arguments::parse().command {
+        Command::Replay(arguments) => {
+            replay(arguments).wrap_err("failed to execute replay command")
+        }
+        Command::Compare(arguments) => {
+            compare(arguments).wrap_err("failed to execute compare command")
+        }
+        Command::Export(arguments) => {
+            export(arguments).wrap_err("failed to execute export command")
+        }
+    }
+}
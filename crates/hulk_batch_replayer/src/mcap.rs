@@ -0,0 +1,166 @@
+use std::{
+    io::Write,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+
+const MAGIC: &[u8] = b"\x89MCAP0\r\n";
+
+const OPCODE_HEADER: u8 = 0x01;
+const OPCODE_FOOTER: u8 = 0x02;
+const OPCODE_SCHEMA: u8 = 0x03;
+const OPCODE_CHANNEL: u8 = 0x04;
+const OPCODE_MESSAGE: u8 = 0x05;
+const OPCODE_DATA_END: u8 = 0x0f;
+
+/// Minimal writer for unchunked MCAP files without summary section
+///
+/// Readers like Foxglove fall back to reading the data section sequentially if the summary is
+/// missing, which is fast enough for the size of our recordings.
+pub struct McapWriter<W: Write> {
+    writer: W,
+    next_schema_id: u16,
+    next_channel_id: u16,
+    sequences: Vec<u32>,
+}
+
+impl<W: Write> McapWriter<W> {
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(MAGIC).wrap_err("failed to write magic")?;
+        let mut content = Vec::new();
+        put_string(&mut content, "");
+        put_string(&mut content, "hulk_batch_replayer");
+        write_record(&mut writer, OPCODE_HEADER, &content)?;
+        Ok(Self {
+            writer,
+            next_schema_id: 1,
+            next_channel_id: 0,
+            sequences: Vec::new(),
+        })
+    }
+
+    pub fn add_schema(&mut self, name: &str, encoding: &str, data: &[u8]) -> Result<u16> {
+        let id = self.next_schema_id;
+        self.next_schema_id += 1;
+        let mut content = Vec::new();
+        content.extend_from_slice(&id.to_le_bytes());
+        put_string(&mut content, name);
+        put_string(&mut content, encoding);
+        put_bytes(&mut content, data);
+        write_record(&mut self.writer, OPCODE_SCHEMA, &content)?;
+        Ok(id)
+    }
+
+    pub fn add_channel(
+        &mut self,
+        schema_id: u16,
+        topic: &str,
+        message_encoding: &str,
+    ) -> Result<u16> {
+        let id = self.next_channel_id;
+        self.next_channel_id += 1;
+        self.sequences.push(0);
+        let mut content = Vec::new();
+        content.extend_from_slice(&id.to_le_bytes());
+        content.extend_from_slice(&schema_id.to_le_bytes());
+        put_string(&mut content, topic);
+        put_string(&mut content, message_encoding);
+        // empty metadata map
+        content.extend_from_slice(&0u32.to_le_bytes());
+        write_record(&mut self.writer, OPCODE_CHANNEL, &content)?;
+        Ok(id)
+    }
+
+    pub fn write_message(&mut self, channel_id: u16, time: SystemTime, data: &[u8]) -> Result<()> {
+        let sequence = &mut self.sequences[usize::from(channel_id)];
+        let time = time
+            .duration_since(UNIX_EPOCH)
+            .expect("time ran backwards")
+            .as_nanos() as u64;
+        let mut content = Vec::with_capacity(22 + data.len());
+        content.extend_from_slice(&channel_id.to_le_bytes());
+        content.extend_from_slice(&sequence.to_le_bytes());
+        content.extend_from_slice(&time.to_le_bytes());
+        content.extend_from_slice(&time.to_le_bytes());
+        content.extend_from_slice(data);
+        *sequence += 1;
+        write_record(&mut self.writer, OPCODE_MESSAGE, &content)
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        // a data section CRC of zero means that it was not calculated
+        write_record(&mut self.writer, OPCODE_DATA_END, &0u32.to_le_bytes())?;
+        let mut content = Vec::new();
+        content.extend_from_slice(&0u64.to_le_bytes());
+        content.extend_from_slice(&0u64.to_le_bytes());
+        content.extend_from_slice(&0u32.to_le_bytes());
+        write_record(&mut self.writer, OPCODE_FOOTER, &content)?;
+        self.writer
+            .write_all(MAGIC)
+            .wrap_err("failed to write magic")?;
+        Ok(self.writer)
+    }
+}
+
+fn write_record(writer: &mut impl Write, opcode: u8, content: &[u8]) -> Result<()> {
+    writer
+        .write_all(&[opcode])
+        .and_then(|()| writer.write_all(&(content.len() as u64).to_le_bytes()))
+        .and_then(|()| writer.write_all(content))
+        .wrap_err("failed to write record")
+}
+
+fn put_string(buffer: &mut Vec<u8>, string: &str) {
+    put_bytes(buffer, string.as_bytes());
+}
+
+fn put_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn opcodes_of_records(file: &[u8]) -> Vec<u8> {
+        let mut opcodes = Vec::new();
+        let mut records = &file[MAGIC.len()..file.len() - MAGIC.len()];
+        while !records.is_empty() {
+            let length = u64::from_le_bytes(records[1..9].try_into().unwrap()) as usize;
+            opcodes.push(records[0]);
+            records = &records[9 + length..];
+        }
+        opcodes
+    }
+
+    #[test]
+    fn written_file_consists_of_framed_records() {
+        let mut writer = McapWriter::new(Vec::new()).unwrap();
+        let schema_id = writer
+            .add_schema("ball", "jsonschema", br#"{"type":"object"}"#)
+            .unwrap();
+        let channel_id = writer
+            .add_channel(schema_id, "Control.ball", "json")
+            .unwrap();
+        writer
+            .write_message(channel_id, UNIX_EPOCH, br#"{"value":null}"#)
+            .unwrap();
+        let file = writer.finish().unwrap();
+
+        assert!(file.starts_with(MAGIC));
+        assert!(file.ends_with(MAGIC));
+        assert_eq!(
+            opcodes_of_records(&file),
+            vec![
+                OPCODE_HEADER,
+                OPCODE_SCHEMA,
+                OPCODE_CHANNEL,
+                OPCODE_MESSAGE,
+                OPCODE_DATA_END,
+                OPCODE_FOOTER,
+            ]
+        );
+    }
+}
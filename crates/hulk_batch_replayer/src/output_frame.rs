@@ -0,0 +1,32 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+    time::SystemTime,
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{from_str, Value};
+
+/// One line of an output file: the selected outputs of a cycler instance after replaying a frame
+#[derive(Debug, Deserialize, Serialize)]
+pub struct OutputFrame {
+    pub cycler_instance: String,
+    pub timestamp: SystemTime,
+    pub outputs: BTreeMap<String, Value>,
+}
+
+pub fn read_output_frames(path: impl AsRef<Path>) -> Result<Vec<OutputFrame>> {
+    let file = File::open(&path)
+        .wrap_err_with(|| format!("failed to open {}", path.as_ref().display()))?;
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .map(|(line_index, line)| {
+            let line = line.wrap_err("failed to read line")?;
+            from_str(&line).wrap_err_with(|| format!("failed to parse line {}", line_index + 1))
+        })
+        .collect()
+}
@@ -0,0 +1,175 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    error::Error,
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use clap::Args;
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use serde_json::{to_writer, value::Serializer};
+use types::hardware::Ids;
+
+use crate::{execution::Replayer, output_frame::OutputFrame, BatchReplayerHardwareInterface};
+
+#[derive(Args)]
+pub struct Arguments {
+    /// Directory containing the recording files of one execution of the `hulk` binary
+    pub recording_directory: PathBuf,
+    /// Path of the file the selected outputs are written to (one JSON object per replayed frame)
+    pub output_file: PathBuf,
+    /// Parameters used during replay (defaults to the parameters stored next to the recording)
+    #[arg(long)]
+    pub parameters_directory: Option<PathBuf>,
+    /// Outputs to write, e.g. Control.main_outputs.ball_position,VisionTop.additional_outputs.ball_candidates
+    #[arg(long, required = true, value_delimiter = ',', value_parser = parse_output)]
+    pub outputs: Vec<(String, String)>,
+}
+
+pub fn replay(arguments: Arguments) -> Result<()> {
+    let outputs = group_outputs(arguments.outputs);
+    let mut replayer = create_replayer(
+        arguments.recording_directory,
+        arguments.parameters_directory,
+    )?;
+
+    let file = File::create(&arguments.output_file)
+        .wrap_err_with(|| format!("failed to create {}", arguments.output_file.display()))?;
+    let mut writer = BufWriter::new(file);
+    replay_frames(
+        &mut replayer,
+        &outputs,
+        |replayer, cycler_instance, timestamp| {
+            let outputs = outputs[cycler_instance]
+                .iter()
+                .map(|path| {
+                    let value = replayer
+                        .serialize_output(cycler_instance, path, Serializer)
+                        .wrap_err_with(|| {
+                            format!("failed to serialize {cycler_instance}.{path}")
+                        })?;
+                    Ok((path.clone(), value))
+                })
+                .collect::<Result<_>>()?;
+            to_writer(
+                &mut writer,
+                &OutputFrame {
+                    cycler_instance: cycler_instance.to_string(),
+                    timestamp,
+                    outputs,
+                },
+            )
+            .wrap_err("failed to write output frame")?;
+            writeln!(writer).wrap_err("failed to write output frame")
+        },
+    )?;
+    writer.flush().wrap_err("failed to flush output file")
+}
+
+pub fn create_replayer(
+    recording_directory: PathBuf,
+    parameters_directory: Option<PathBuf>,
+) -> Result<Replayer<BatchReplayerHardwareInterface>> {
+    let id = "replayer".to_string();
+    let parameters_directory = parameters_directory.unwrap_or(recording_directory.clone());
+    Replayer::new(
+        Arc::new(BatchReplayerHardwareInterface {
+            ids: Ids {
+                body_id: id.clone(),
+                head_id: id.clone(),
+            },
+        }),
+        parameters_directory,
+        id.clone(),
+        id,
+        recording_directory,
+    )
+    .wrap_err("failed to create replayer")
+}
+
+/// Replays all frames of the given cycler instances in the order they were recorded and calls
+/// `on_frame` after each replayed frame, damaged frames are reported and skipped
+pub fn replay_frames(
+    replayer: &mut Replayer<BatchReplayerHardwareInterface>,
+    outputs: &BTreeMap<String, Vec<String>>,
+    mut on_frame: impl FnMut(&Replayer<BatchReplayerHardwareInterface>, &str, SystemTime) -> Result<()>,
+) -> Result<()> {
+    let recording_indices = replayer.get_recording_indices();
+    for cycler_instance in outputs.keys() {
+        if !recording_indices.contains_key(cycler_instance) {
+            bail!("unknown cycler instance `{cycler_instance}`");
+        }
+    }
+    // Cycler instances replay independently of each other because all inputs from other cycler
+    // instances are part of the recording, therefore only instances with selected outputs are replayed
+    let mut frames: Vec<_> = recording_indices
+        .iter()
+        .filter(|(cycler_instance, _index)| outputs.contains_key(*cycler_instance))
+        .flat_map(|(cycler_instance, index)| {
+            index.iter().enumerate().map(move |(frame_index, timing)| {
+                (timing.timestamp, cycler_instance.clone(), frame_index)
+            })
+        })
+        .collect();
+    frames.sort();
+
+    for (cycler_instance, paths) in outputs {
+        replayer
+            .set_subscribed_outputs(
+                cycler_instance,
+                paths.iter().cloned().collect::<HashSet<_>>(),
+            )
+            .wrap_err("failed to subscribe outputs")?;
+    }
+
+    let mut number_of_damaged_frames = 0;
+    for (timestamp, cycler_instance, frame_index) in frames {
+        let frame = match replayer
+            .get_recording_indices_mut()
+            .get_mut(&cycler_instance)
+            .expect("cycler instance should have a recording index")
+            .read_frame(frame_index)
+        {
+            Ok(frame) => frame.expect("frame index should be within recording index"),
+            Err(error) => {
+                eprintln!("skipping damaged frame {frame_index} of {cycler_instance}: {error:?}");
+                number_of_damaged_frames += 1;
+                continue;
+            }
+        };
+        replayer
+            .replay(&cycler_instance, timestamp, &frame.data)
+            .wrap_err_with(|| format!("failed to replay {cycler_instance} frame {frame_index}"))?;
+        on_frame(replayer, &cycler_instance, timestamp)?;
+    }
+    if number_of_damaged_frames > 0 {
+        eprintln!("skipped {number_of_damaged_frames} damaged frames");
+    }
+    Ok(())
+}
+
+pub fn group_outputs(outputs: Vec<(String, String)>) -> BTreeMap<String, Vec<String>> {
+    let mut grouped_outputs: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (cycler_instance, path) in outputs {
+        grouped_outputs
+            .entry(cycler_instance)
+            .or_default()
+            .push(path);
+    }
+    grouped_outputs
+}
+
+pub fn parse_output(
+    string: &str,
+) -> Result<(String, String), Box<dyn Error + Send + Sync + 'static>> {
+    let (cycler_instance, path) = string
+        .split_once('.')
+        .ok_or_else(|| format!("invalid CyclerInstance.path: no `.` found in `{string}`"))?;
+    Ok((cycler_instance.to_string(), path.to_string()))
+}
@@ -29,6 +29,7 @@ impl PathsInterface for ImageExtractorHardwareInterface {
         Paths {
             motions: "etc/motions".into(),
             neural_networks: "etc/neural_networks".into(),
+            parameters: "etc/parameters".into(),
             sounds: "etc/sounds".into(),
         }
     }
@@ -90,12 +91,15 @@ fn main() -> Result<()> {
             let frame = replayer
                 .get_recording_indices_mut()
                 .get_mut(instance_name)
-                .map(|index| {
-                    index
-                        .find_latest_frame_up_to(timing.timestamp)
-                        .expect("failed to find latest frame")
-                })
+                .map(|index| index.find_latest_frame_up_to(timing.timestamp))
                 .expect(&unknown_indices_error_message);
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(error) => {
+                    eprintln!("skipping damaged frame of {instance_name}: {error:?}");
+                    continue;
+                }
+            };
 
             if let Some(frame) = frame {
                 replayer
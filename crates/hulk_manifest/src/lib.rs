@@ -14,14 +14,17 @@ pub fn collect_hulk_cyclers() -> Result<Cyclers, Error> {
                 setup_nodes: vec!["vision::image_receiver"],
                 nodes: vec![
                     "vision::ball_detection",
+                    "vision::calibration_measurement_detection",
                     "vision::camera_matrix_extractor",
                     "vision::feet_detection",
                     "vision::field_border_detection",
                     "vision::field_color_detection",
                     "vision::image_segmenter",
+                    "vision::landmark_detection",
                     "vision::limb_projector",
                     "vision::line_detection",
                     "vision::perspective_grid_candidates_provider",
+                    "vision::robot_detection",
                     "vision::segment_filter",
                 ],
             },
@@ -46,9 +49,11 @@ pub fn collect_hulk_cyclers() -> Result<Cyclers, Error> {
                     "control::ball_state_composer",
                     "control::behavior::node",
                     "control::button_filter",
+                    "control::calibration_controller",
                     "control::camera_matrix_calculator",
                     "control::center_of_mass_provider",
                     "control::fall_state_estimation",
+                    "control::flip_detection",
                     "control::foot_bumper_filter",
                     "control::game_controller_filter",
                     "control::game_controller_state_filter",
@@ -58,6 +63,7 @@ pub fn collect_hulk_cyclers() -> Result<Cyclers, Error> {
                     "control::kinematics_provider",
                     "control::led_status",
                     "control::localization",
+                    "control::message_budget_manager",
                     "control::motion::arms_up_squat",
                     "control::motion::command_sender",
                     "control::motion::condition_input_provider",
@@ -84,6 +90,7 @@ pub fn collect_hulk_cyclers() -> Result<Cyclers, Error> {
                     "control::orientation_filter",
                     "control::penalty_shot_direction_estimation",
                     "control::primary_state_filter",
+                    "control::recording_event_detector",
                     "control::role_assignment",
                     "control::rule_obstacle_composer",
                     "control::referee_position_provider",
@@ -92,6 +99,7 @@ pub fn collect_hulk_cyclers() -> Result<Cyclers, Error> {
                     "control::sonar_filter",
                     "control::search_suggestor",
                     "control::support_foot_estimation",
+                    "control::team_ball_filter",
                     "control::time_to_reach_kick_position",
                     "control::visual_referee_filter",
                     "control::whistle_filter",
@@ -62,6 +62,7 @@ pub struct HardwareInterface {
     camera_top: Camera,
     camera_bottom: Camera,
     enable_recording: AtomicBool,
+    latest_recording_event: Mutex<Option<SystemTime>>,
     keep_running: CancellationToken,
 }
 
@@ -105,6 +106,7 @@ impl HardwareInterface {
             .wrap_err("failed to initialize bottom camera")?,
 
             enable_recording: AtomicBool::new(false),
+            latest_recording_event: Mutex::new(None),
             keep_running,
         })
     }
@@ -179,6 +181,14 @@ impl RecordingInterface for HardwareInterface {
     fn set_whether_to_record(&self, enable: bool) {
         self.enable_recording.store(enable, Ordering::SeqCst)
     }
+
+    fn trigger_recording_event(&self) {
+        *self.latest_recording_event.lock() = Some(SystemTime::now());
+    }
+
+    fn latest_recording_event(&self) -> Option<SystemTime> {
+        *self.latest_recording_event.lock()
+    }
 }
 
 impl SensorInterface for HardwareInterface {
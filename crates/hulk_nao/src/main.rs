@@ -86,11 +86,14 @@ fn main() -> Result<()> {
     run(
         Arc::new(hardware_interface),
         framework_parameters.communication_addresses,
+        framework_parameters.communication_authentication,
         framework_parameters.parameters_directory,
         arguments.log_path,
         ids.body_id,
         ids.head_id,
         keep_running,
         framework_parameters.recording_intervals,
+        framework_parameters.recording_compression,
+        framework_parameters.recording_event_window,
     )
 }
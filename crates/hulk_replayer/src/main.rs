@@ -91,6 +91,7 @@ impl PathsInterface for ReplayerHardwareInterface {
         Paths {
             motions: "etc/motions".into(),
             neural_networks: "etc/neural_networks".into(),
+            parameters: "etc/parameters".into(),
             sounds: "etc/sounds".into(),
         }
     }
@@ -102,6 +103,12 @@ impl RecordingInterface for ReplayerHardwareInterface {
     }
 
     fn set_whether_to_record(&self, _enable: bool) {}
+
+    fn trigger_recording_event(&self) {}
+
+    fn latest_recording_event(&self) -> Option<SystemTime> {
+        None
+    }
 }
 
 impl SensorInterface for ReplayerHardwareInterface {
@@ -55,6 +55,7 @@ pub fn replayer() -> Result<()> {
         ids.head_id,
         replay_path,
         framework_parameters.communication_addresses,
+        framework_parameters.communication_authentication,
         keep_running,
     )
     .wrap_err("failed to create replayer")?;
@@ -37,14 +37,15 @@ impl Window {
         let recording_indices = self.replayer.get_recording_indices_mut();
         let frames = recording_indices
             .into_iter()
-            .map(|(name, index)| {
-                (
-                    name,
-                    index
-                        .find_latest_frame_up_to(timestamp.inner())
-                        .expect("failed to find latest frame"),
-                )
-            })
+            .filter_map(
+                |(name, index)| match index.find_latest_frame_up_to(timestamp.inner()) {
+                    Ok(frame) => Some((name, frame)),
+                    Err(error) => {
+                        eprintln!("skipping damaged frame of {name}: {error:?}");
+                        None
+                    }
+                },
+            )
             .collect::<BTreeMap<_, _>>();
         for (name, frame) in frames {
             if let Some(frame) = frame {
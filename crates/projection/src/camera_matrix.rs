@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     camera_projection::{CameraProjection, InverseCameraProjection},
+    distortion::Distortion,
     horizon::Horizon,
     intrinsic::Intrinsic,
 };
@@ -80,6 +81,14 @@ impl CameraMatrix {
         }
     }
 
+    /// Projections of the returned camera matrix account for the lens distortion, the horizon
+    /// stays the one of the undistorted pinhole camera.
+    pub fn with_distortion(mut self, distortion: Distortion) -> Self {
+        self.intrinsics = self.intrinsics.with_distortion(distortion);
+        self.compute_memoized();
+        self
+    }
+
     pub fn compute_memoized(&mut self) {
         self.ground_to_camera = self.head_to_camera * self.robot_to_head * self.ground_to_robot;
         self.ground_to_pixel =
@@ -52,6 +52,7 @@ impl<From> CameraProjection<From> {
 )]
 pub struct InverseCameraProjection<To> {
     back_project: Transform<Pixel, To, nalgebra::Matrix3<f32>>,
+    intrinsic: Intrinsic,
     z: f32,
 }
 
@@ -71,11 +72,13 @@ impl<To> InverseCameraProjection<To> {
 
         Self {
             back_project: Transform::wrap(inverse),
+            intrinsic: forward.intrinsic.clone(),
             z,
         }
     }
 
     pub fn back_project_unchecked(&self, point: Point2<Pixel>) -> Point3<To> {
+        let point = self.intrinsic.undistort_pixel(point);
         let point_to = self.back_project.inner * point.inner.to_homogeneous();
         point![point_to.x / point_to.z, point_to.y / point_to.z, self.z]
     }
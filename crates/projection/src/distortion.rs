@@ -0,0 +1,97 @@
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+use serde::{Deserialize, Serialize};
+
+const UNDISTORTION_ITERATIONS: usize = 10;
+
+/// Lens distortion in the Brown-Conrady model with two radial and two tangential coefficients
+///
+/// Distortion is applied to normalized image coordinates, i.e. camera rays divided by their depth,
+/// before the focal lengths and the optical center are applied.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    PartialEq,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+)]
+pub struct Distortion {
+    /// Coefficients k1 and k2 of the even radial polynomial
+    pub radial: nalgebra::Vector2<f32>,
+    /// Coefficients p1 and p2 of the decentering distortion
+    pub tangential: nalgebra::Vector2<f32>,
+}
+
+impl Distortion {
+    pub fn new(radial: nalgebra::Vector2<f32>, tangential: nalgebra::Vector2<f32>) -> Self {
+        Self { radial, tangential }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.radial == nalgebra::Vector2::zeros() && self.tangential == nalgebra::Vector2::zeros()
+    }
+
+    pub fn distort(&self, normalized: nalgebra::Vector2<f32>) -> nalgebra::Vector2<f32> {
+        normalized * self.radial_factor(normalized) + self.tangential_offset(normalized)
+    }
+
+    /// Inverts [`Distortion::distort`] by fixed-point iteration, which converges for the mild
+    /// distortion of the NAO cameras
+    pub fn undistort(&self, distorted: nalgebra::Vector2<f32>) -> nalgebra::Vector2<f32> {
+        if self.is_zero() {
+            return distorted;
+        }
+        (0..UNDISTORTION_ITERATIONS).fold(distorted, |normalized, _| {
+            (distorted - self.tangential_offset(normalized)) / self.radial_factor(normalized)
+        })
+    }
+
+    fn radial_factor(&self, normalized: nalgebra::Vector2<f32>) -> f32 {
+        let squared_radius = normalized.norm_squared();
+        1.0 + self.radial.x * squared_radius + self.radial.y * squared_radius * squared_radius
+    }
+
+    fn tangential_offset(&self, normalized: nalgebra::Vector2<f32>) -> nalgebra::Vector2<f32> {
+        let (x, y) = (normalized.x, normalized.y);
+        let squared_radius = normalized.norm_squared();
+        let (p1, p2) = (self.tangential.x, self.tangential.y);
+        nalgebra::vector![
+            2.0 * p1 * x * y + p2 * (squared_radius + 2.0 * x * x),
+            p1 * (squared_radius + 2.0 * y * y) + 2.0 * p2 * x * y
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+
+    use super::*;
+
+    #[test]
+    fn undistort_inverts_distort() {
+        let distortion = Distortion::new(
+            nalgebra::vector![-0.05, 0.01],
+            nalgebra::vector![0.001, -0.002],
+        );
+        for normalized in [
+            nalgebra::vector![0.0, 0.0],
+            nalgebra::vector![0.3, -0.2],
+            nalgebra::vector![-0.5, 0.4],
+        ] {
+            let distorted = distortion.distort(normalized);
+            assert_relative_eq!(distortion.undistort(distorted), normalized, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn zero_distortion_is_identity() {
+        let normalized = nalgebra::vector![0.3, -0.2];
+        assert_eq!(Distortion::default().distort(normalized), normalized);
+        assert_eq!(Distortion::default().undistort(normalized), normalized);
+    }
+}
@@ -3,12 +3,15 @@ use linear_algebra::{point, vector, Point2, Vector3};
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use serde::{Deserialize, Serialize};
 
+use crate::distortion::Distortion;
+
 #[derive(
     Clone, Debug, PartialEq, Serialize, Deserialize, PathSerialize, PathDeserialize, PathIntrospect,
 )]
 pub struct Intrinsic {
     focals: nalgebra::Vector2<f32>,
     optical_center: Point2<Pixel>,
+    distortion: Distortion,
 }
 
 impl Default for Intrinsic {
@@ -16,6 +19,7 @@ impl Default for Intrinsic {
         Self {
             focals: nalgebra::vector![1.0, 1.0],
             optical_center: point![0.0, 0.0],
+            distortion: Distortion::default(),
         }
     }
 }
@@ -25,9 +29,18 @@ impl Intrinsic {
         Self {
             focals: focal_length,
             optical_center,
+            distortion: Distortion::default(),
         }
     }
 
+    pub fn with_distortion(self, distortion: Distortion) -> Self {
+        Self { distortion, ..self }
+    }
+
+    pub fn distortion(&self) -> Distortion {
+        self.distortion
+    }
+
     pub fn as_matrix(&self) -> nalgebra::Matrix3x4<f32> {
         nalgebra::matrix![
             self.focals.x, 0.0, self.optical_center.x(), 0.0;
@@ -36,6 +49,7 @@ impl Intrinsic {
         ]
     }
 
+    /// Pinhole transformation of the ray, ignoring the distortion
     pub fn transform(&self, ray: Vector3<Camera>) -> Vector3<NormalizedDeviceCoordinates> {
         let (x, y, z) = (ray.x(), ray.y(), ray.z());
 
@@ -47,15 +61,38 @@ impl Intrinsic {
     }
 
     pub fn project(&self, ray: Vector3<Camera>) -> Point2<Pixel> {
-        let projected = self.transform(ray);
-        point![projected.x() / projected.z(), projected.y() / projected.z()]
+        let normalized = self
+            .distortion
+            .distort(nalgebra::vector![ray.x() / ray.z(), ray.y() / ray.z()]);
+        self.normalized_to_pixel(normalized)
     }
 
     pub fn bearing(&self, pixel: Point2<Pixel>) -> Vector3<Camera> {
-        let x = (pixel.x() - self.optical_center.x()) / self.focals.x;
-        let y = (pixel.y() - self.optical_center.y()) / self.focals.y;
+        let normalized = self.distortion.undistort(self.pixel_to_normalized(pixel));
 
-        vector![x, y, 1.0]
+        vector![normalized.x, normalized.y, 1.0]
+    }
+
+    /// Moves the pixel to where it would be seen by an ideal pinhole camera
+    pub fn undistort_pixel(&self, pixel: Point2<Pixel>) -> Point2<Pixel> {
+        if self.distortion.is_zero() {
+            return pixel;
+        }
+        self.normalized_to_pixel(self.distortion.undistort(self.pixel_to_normalized(pixel)))
+    }
+
+    fn pixel_to_normalized(&self, pixel: Point2<Pixel>) -> nalgebra::Vector2<f32> {
+        nalgebra::vector![
+            (pixel.x() - self.optical_center.x()) / self.focals.x,
+            (pixel.y() - self.optical_center.y()) / self.focals.y
+        ]
+    }
+
+    fn normalized_to_pixel(&self, normalized: nalgebra::Vector2<f32>) -> Point2<Pixel> {
+        point![
+            self.focals.x * normalized.x + self.optical_center.x(),
+            self.focals.y * normalized.y + self.optical_center.y()
+        ]
     }
 }
 
@@ -78,4 +115,23 @@ mod tests {
         let bearing = intrinsic.bearing(pixel);
         assert_eq!(bearing, vector![0.0, 0.0, 1.0]);
     }
+
+    #[test]
+    fn distorted_bearing_inverts_projection() {
+        let intrinsic = Intrinsic::new(nalgebra::vector![500.0, 500.0], point![320.0, 240.0])
+            .with_distortion(Distortion::new(
+                nalgebra::vector![-0.1, 0.02],
+                nalgebra::vector![0.001, 0.001],
+            ));
+        let ray = vector![0.4, -0.3, 1.0];
+        let pixel = intrinsic.project(ray);
+        assert!(
+            (pixel
+                - Intrinsic::new(nalgebra::vector![500.0, 500.0], point![320.0, 240.0])
+                    .project(ray))
+            .norm()
+                > 1.0
+        );
+        assert!((intrinsic.bearing(pixel) - ray).norm() < 1e-4);
+    }
 }
@@ -1,6 +1,7 @@
 pub mod camera_matrices;
 pub mod camera_matrix;
 pub mod camera_projection;
+pub mod distortion;
 pub mod horizon;
 pub mod intrinsic;
 
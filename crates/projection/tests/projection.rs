@@ -4,7 +4,7 @@ use approx::assert_relative_eq;
 
 use coordinate_systems::{Camera, Head, Pixel};
 use linear_algebra::{point, vector, IntoTransform, Isometry3, Vector2, Vector3};
-use projection::{camera_matrix::CameraMatrix, Projection};
+use projection::{camera_matrix::CameraMatrix, distortion::Distortion, Projection};
 
 fn from_normalized_focal_and_center_short(
     focal_length: nalgebra::Vector2<f32>,
@@ -294,3 +294,32 @@ fn get_pixel_radius_pitch_45_degree_down() {
         epsilon = 0.01,
     );
 }
+
+#[test]
+fn distorted_pixel_to_ground_projects_back_to_pixel() {
+    let mut camera_matrix = from_normalized_focal_and_center_short(
+        nalgebra::vector![0.95, 1.27],
+        nalgebra::point![0.5, 0.5],
+        vector![640.0, 480.0],
+    )
+    .with_distortion(Distortion::new(
+        nalgebra::vector![-0.08, 0.01],
+        nalgebra::vector![0.002, -0.001],
+    ));
+    camera_matrix.head_to_camera = head_to_camera(-FRAC_PI_4, vector![0.0, 0.0, 0.5]);
+    camera_matrix.compute_memoized();
+
+    for pixel in [
+        point![320.0, 400.0],
+        point![40.0, 460.0],
+        point![600.0, 300.0],
+    ] {
+        let ground_point = camera_matrix.pixel_to_ground(pixel).unwrap();
+
+        assert_relative_eq!(
+            camera_matrix.ground_to_pixel(ground_point).unwrap(),
+            pixel,
+            epsilon = 0.01
+        );
+    }
+}
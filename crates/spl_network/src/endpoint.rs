@@ -5,6 +5,9 @@ use std::{
 
 use log::warn;
 use serde::Deserialize;
+use spl_network_messages::{
+    is_hulks_message, is_mixed_team_message, HulkMessage, MixedTeamMessage,
+};
 use thiserror::Error;
 use tokio::{net::UdpSocket, select};
 use types::messages::{IncomingMessage, OutgoingMessage};
@@ -65,9 +68,18 @@ impl Endpoint {
                 },
                 result = self.spl_socket.recv_from(&mut spl_buffer) => {
                     let (received_bytes, _address) = result.map_err(Error::ReadError)?;
-                    match bincode::deserialize(&spl_buffer[0..received_bytes]) {
+                    let buffer = &spl_buffer[0..received_bytes];
+                    let parsed_message = if is_hulks_message(buffer) {
+                        HulkMessage::try_from(buffer).map(IncomingMessage::Spl)
+                    } else if is_mixed_team_message(buffer) {
+                        MixedTeamMessage::try_from(buffer).map(IncomingMessage::MixedTeam)
+                    } else {
+                        // other teams share the SPL port
+                        continue;
+                    };
+                    match parsed_message {
                         Ok(parsed_message) => {
-                            break Ok(IncomingMessage::Spl(parsed_message));
+                            break Ok(parsed_message);
                         }
                         Err(error) => {
                             warn!("Failed to parse SPL message (will be discarded): {error:?}");
@@ -86,23 +98,12 @@ impl Endpoint {
                 self.send_game_controller_visual_referee_message(destination, message)
                     .await;
             }
-            OutgoingMessage::Spl(message) => match bincode::serialize(&message) {
-                Ok(message) => {
-                    if let Err(error) = self
-                        .spl_socket
-                        .send_to(
-                            message.as_slice(),
-                            SocketAddr::new(Ipv4Addr::BROADCAST.into(), self.ports.spl),
-                        )
-                        .await
-                    {
-                        warn!("Failed to send UDP datagram via SPL socket: {error:?}")
-                    }
-                }
-                Err(error) => {
-                    warn!("Failed to serialize Hulk Message: {error:?}")
-                }
-            },
+            OutgoingMessage::Spl(message) => {
+                self.send_spl_message(message.into()).await;
+            }
+            OutgoingMessage::MixedTeam(message) => {
+                self.send_spl_message(message.into()).await;
+            }
             OutgoingMessage::VisualReferee(destination, message) => {
                 let message: Vec<u8> = message.into();
                 self.send_game_controller_visual_referee_message(destination, message)
@@ -111,6 +112,19 @@ impl Endpoint {
         };
     }
 
+    async fn send_spl_message(&self, message: Vec<u8>) {
+        if let Err(error) = self
+            .spl_socket
+            .send_to(
+                message.as_slice(),
+                SocketAddr::new(Ipv4Addr::BROADCAST.into(), self.ports.spl),
+            )
+            .await
+        {
+            warn!("Failed to send UDP datagram via SPL socket: {error:?}")
+        }
+    }
+
     async fn send_game_controller_visual_referee_message(
         &self,
         destination: SocketAddr,
@@ -35,6 +35,11 @@ impl MessageFilter {
             IncomingMessage::Spl(message) if message.player_number != *context.player_number => {
                 Some(IncomingMessage::Spl(*message))
             }
+            IncomingMessage::MixedTeam(message)
+                if message.player_number != *context.player_number =>
+            {
+                Some(IncomingMessage::MixedTeam(*message))
+            }
             _ => None,
         };
         Ok(MainOutputs {
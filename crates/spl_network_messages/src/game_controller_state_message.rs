@@ -373,6 +373,7 @@ pub enum SubState {
     Clone,
     Copy,
     Debug,
+    Default,
     Deserialize,
     Eq,
     PartialEq,
@@ -382,6 +383,7 @@ pub enum SubState {
     PathIntrospect,
 )]
 pub enum Half {
+    #[default]
     First,
     Second,
 }
@@ -411,7 +413,18 @@ pub struct TeamState {
     pub players: Vec<Player>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+)]
 pub enum TeamColor {
     Blue,
     Red,
@@ -0,0 +1,469 @@
+//! Bit-packed wire format of [`HulkMessage`]
+//!
+//! Every message starts with a byte aligned header:
+//!
+//! | Field            | Bits | Content                                  |
+//! |------------------|------|------------------------------------------|
+//! | magic            | 8    | [`HULK_MESSAGE_MAGIC`]                   |
+//! | team number      | 8    | [`HULKS_TEAM_NUMBER`]                    |
+//! | protocol version | 8    | [`HULK_MESSAGE_PROTOCOL_VERSION`]        |
+//!
+//! The payload follows without alignment, the last byte is padded with zeros:
+//!
+//! | Field                              | Bits | Encoding                                   |
+//! |------------------------------------|------|--------------------------------------------|
+//! | player number                      | 3    | 1 to 7                                     |
+//! | fallen                             | 1    |                                            |
+//! | referee ready signal detected      | 1    |                                            |
+//! | pose x, y                          | 2x11 | 1 cm steps, saturated at ±10.24 m          |
+//! | pose orientation                   | 10   | 2π / 1024 steps                            |
+//! | ball present                       | 1    | the ball fields follow only if set         |
+//! | ball x, y                          | 2x11 | 1 cm steps, saturated at ±10.24 m          |
+//! | ball age                           | 8    | 50 ms steps, saturated at 12.75 s          |
+//! | ball covariance present            | 1    | the covariance fields follow only if set   |
+//! | ball standard deviation x, y       | 2x8  | 1 cm steps, from 1 cm to 2.55 m            |
+//! | ball correlation                   | 7    | 1 / 63 steps                               |
+//! | time to reach kick position present| 1    | the time follows only if set               |
+//! | time to reach kick position        | 10   | 100 ms steps, the largest value is `MAX`   |
+//! | role costs present                 | 1    | the costs follow only if set               |
+//! | role costs                         | 7x16 | exact milliseconds                         |
+//!
+//! Role costs are transmitted exactly since all robots have to solve the role auction with the
+//! same costs.
+//!
+//! New protocol versions may only append fields starting with a presence bit. Decoders ignore
+//! fields they do not know and treat fields missing in older messages as absent, since the zero
+//! padding reads as an unset presence bit. Incompatible changes have to raise
+//! [`MINIMUM_COMPATIBLE_PROTOCOL_VERSION`].
+
+use std::{f32::consts::PI, time::Duration};
+
+use color_eyre::{
+    eyre::{bail, eyre},
+    Report, Result,
+};
+use linear_algebra::{point, vector, Pose2};
+
+use crate::{
+    BallCovariance, BallPosition, HulkMessage, PlayerNumber, HULKS_TEAM_NUMBER,
+    NUMBER_OF_AUCTIONED_ROLES,
+};
+
+pub const HULK_MESSAGE_MAGIC: u8 = b'H';
+pub const HULK_MESSAGE_PROTOCOL_VERSION: u8 = 1;
+pub const MINIMUM_COMPATIBLE_PROTOCOL_VERSION: u8 = 1;
+
+const HEADER_SIZE: usize = 3;
+const POSITION_BITS: u32 = 11;
+const POSITION_STEP: f32 = 0.01;
+const ORIENTATION_BITS: u32 = 10;
+const BALL_AGE_BITS: u32 = 8;
+const BALL_AGE_STEP: Duration = Duration::from_millis(50);
+const STANDARD_DEVIATION_BITS: u32 = 8;
+const STANDARD_DEVIATION_STEP: f32 = 0.01;
+const CORRELATION_BITS: u32 = 7;
+const TIME_TO_REACH_KICK_POSITION_BITS: u32 = 10;
+const TIME_TO_REACH_KICK_POSITION_STEP: Duration = Duration::from_millis(100);
+const ROLE_COST_BITS: u32 = 16;
+
+/// Whether the buffer carries a message of our own team, messages of other teams are not an error
+pub fn is_hulks_message(buffer: &[u8]) -> bool {
+    buffer.len() >= HEADER_SIZE && buffer[0] == HULK_MESSAGE_MAGIC && buffer[1] == HULKS_TEAM_NUMBER
+}
+
+impl TryFrom<&[u8]> for HulkMessage {
+    type Error = Report;
+
+    fn try_from(buffer: &[u8]) -> Result<Self> {
+        if buffer.len() < HEADER_SIZE {
+            bail!("buffer too small");
+        }
+        if buffer[0] != HULK_MESSAGE_MAGIC {
+            bail!("unexpected magic {}", buffer[0]);
+        }
+        if buffer[1] != HULKS_TEAM_NUMBER {
+            bail!(
+                "unexpected team number {} != {HULKS_TEAM_NUMBER}",
+                buffer[1]
+            );
+        }
+        if buffer[2] < MINIMUM_COMPATIBLE_PROTOCOL_VERSION {
+            bail!(
+                "incompatible protocol version {} < {MINIMUM_COMPATIBLE_PROTOCOL_VERSION}",
+                buffer[2]
+            );
+        }
+
+        let mut reader = BitReader::new(&buffer[HEADER_SIZE..]);
+        let player_number = match reader.read(3)? {
+            1 => PlayerNumber::One,
+            2 => PlayerNumber::Two,
+            3 => PlayerNumber::Three,
+            4 => PlayerNumber::Four,
+            5 => PlayerNumber::Five,
+            6 => PlayerNumber::Six,
+            7 => PlayerNumber::Seven,
+            player_number => bail!("unexpected player number {player_number}"),
+        };
+        let fallen = reader.read_bool()?;
+        let is_referee_ready_signal_detected = reader.read_bool()?;
+        let pose = Pose2::new(
+            vector![
+                reader.read_signed(POSITION_BITS)? as f32 * POSITION_STEP,
+                reader.read_signed(POSITION_BITS)? as f32 * POSITION_STEP
+            ],
+            reader.read_signed(ORIENTATION_BITS)? as f32 * orientation_step(),
+        );
+        let ball_position = reader.read_optional(|reader| {
+            Ok(BallPosition {
+                position: point![
+                    reader.read_signed(POSITION_BITS)? as f32 * POSITION_STEP,
+                    reader.read_signed(POSITION_BITS)? as f32 * POSITION_STEP
+                ],
+                age: BALL_AGE_STEP * reader.read(BALL_AGE_BITS)?,
+            })
+        })?;
+        let ball_covariance = reader.read_optional(|reader| {
+            let standard_deviation_x =
+                reader.read(STANDARD_DEVIATION_BITS)? as f32 * STANDARD_DEVIATION_STEP;
+            let standard_deviation_y =
+                reader.read(STANDARD_DEVIATION_BITS)? as f32 * STANDARD_DEVIATION_STEP;
+            let correlation = reader.read_signed(CORRELATION_BITS)? as f32 * correlation_step();
+            Ok(BallCovariance {
+                xx: standard_deviation_x.powi(2),
+                xy: correlation * standard_deviation_x * standard_deviation_y,
+                yy: standard_deviation_y.powi(2),
+            })
+        })?;
+        let time_to_reach_kick_position = reader.read_optional(|reader| {
+            Ok(match reader.read(TIME_TO_REACH_KICK_POSITION_BITS)? {
+                steps if steps == maximum_unsigned(TIME_TO_REACH_KICK_POSITION_BITS) => {
+                    Duration::MAX
+                }
+                steps => TIME_TO_REACH_KICK_POSITION_STEP * steps,
+            })
+        })?;
+        let role_costs = reader.read_optional(|reader| {
+            let mut role_costs = [0; NUMBER_OF_AUCTIONED_ROLES];
+            for cost in role_costs.iter_mut() {
+                *cost = reader.read(ROLE_COST_BITS)? as u16;
+            }
+            Ok(role_costs)
+        })?;
+
+        Ok(Self {
+            player_number,
+            fallen,
+            pose,
+            is_referee_ready_signal_detected,
+            ball_position,
+            ball_covariance,
+            time_to_reach_kick_position,
+            role_costs,
+        })
+    }
+}
+
+impl From<HulkMessage> for Vec<u8> {
+    fn from(message: HulkMessage) -> Self {
+        let mut writer = BitWriter::default();
+        writer.write(
+            match message.player_number {
+                PlayerNumber::One => 1,
+                PlayerNumber::Two => 2,
+                PlayerNumber::Three => 3,
+                PlayerNumber::Four => 4,
+                PlayerNumber::Five => 5,
+                PlayerNumber::Six => 6,
+                PlayerNumber::Seven => 7,
+            },
+            3,
+        );
+        writer.write_bool(message.fallen);
+        writer.write_bool(message.is_referee_ready_signal_detected);
+        writer.write_quantized(message.pose.position().x(), POSITION_STEP, POSITION_BITS);
+        writer.write_quantized(message.pose.position().y(), POSITION_STEP, POSITION_BITS);
+        writer.write_quantized(
+            message.pose.orientation().angle(),
+            orientation_step(),
+            ORIENTATION_BITS,
+        );
+        writer.write_optional(message.ball_position, |writer, ball| {
+            writer.write_quantized(ball.position.x(), POSITION_STEP, POSITION_BITS);
+            writer.write_quantized(ball.position.y(), POSITION_STEP, POSITION_BITS);
+            writer.write_saturated(
+                ball.age.as_millis() / BALL_AGE_STEP.as_millis(),
+                BALL_AGE_BITS,
+            );
+        });
+        writer.write_optional(message.ball_covariance, |writer, covariance| {
+            let standard_deviation_x = covariance.xx.max(0.0).sqrt();
+            let standard_deviation_y = covariance.yy.max(0.0).sqrt();
+            let correlation = if standard_deviation_x * standard_deviation_y > 0.0 {
+                covariance.xy / (standard_deviation_x * standard_deviation_y)
+            } else {
+                0.0
+            };
+            // a vanishing standard deviation would make the covariance singular
+            writer.write_saturated(
+                ((standard_deviation_x / STANDARD_DEVIATION_STEP).round() as u128).max(1),
+                STANDARD_DEVIATION_BITS,
+            );
+            writer.write_saturated(
+                ((standard_deviation_y / STANDARD_DEVIATION_STEP).round() as u128).max(1),
+                STANDARD_DEVIATION_BITS,
+            );
+            writer.write_quantized(correlation, correlation_step(), CORRELATION_BITS);
+        });
+        writer.write_optional(message.time_to_reach_kick_position, |writer, time| {
+            writer.write_saturated(
+                time.as_millis() / TIME_TO_REACH_KICK_POSITION_STEP.as_millis(),
+                TIME_TO_REACH_KICK_POSITION_BITS,
+            );
+        });
+        writer.write_optional(message.role_costs, |writer, role_costs| {
+            for cost in role_costs {
+                writer.write(cost.into(), ROLE_COST_BITS);
+            }
+        });
+
+        [
+            HULK_MESSAGE_MAGIC,
+            HULKS_TEAM_NUMBER,
+            HULK_MESSAGE_PROTOCOL_VERSION,
+        ]
+        .into_iter()
+        .chain(writer.into_bytes())
+        .collect()
+    }
+}
+
+fn orientation_step() -> f32 {
+    2.0 * PI / (1 << ORIENTATION_BITS) as f32
+}
+
+fn correlation_step() -> f32 {
+    1.0 / maximum_signed(CORRELATION_BITS) as f32
+}
+
+fn maximum_unsigned(bits: u32) -> u32 {
+    (1 << bits) - 1
+}
+
+fn maximum_signed(bits: u32) -> i32 {
+    (1 << (bits - 1)) - 1
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    number_of_bits: usize,
+}
+
+impl BitWriter {
+    /// Writes the lowest bits of the value, most significant bit first
+    fn write(&mut self, value: u32, bits: u32) {
+        for bit in (0..bits).rev() {
+            if self.number_of_bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> bit) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.number_of_bits % 8);
+            }
+            self.number_of_bits += 1;
+        }
+    }
+
+    fn write_bool(&mut self, value: bool) {
+        self.write(value.into(), 1);
+    }
+
+    fn write_saturated(&mut self, value: u128, bits: u32) {
+        self.write(value.min(maximum_unsigned(bits).into()) as u32, bits);
+    }
+
+    /// Writes the value in two's complement after rounding it to the step, values outside of the
+    /// representable range are saturated
+    fn write_quantized(&mut self, value: f32, step: f32, bits: u32) {
+        let maximum = maximum_signed(bits);
+        let steps = ((value / step).round() as i32).clamp(-maximum, maximum);
+        self.write(steps as u32 & maximum_unsigned(bits), bits);
+    }
+
+    fn write_optional<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        self.write_bool(value.is_some());
+        if let Some(value) = value {
+            write(self, value);
+        }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+struct BitReader<'buffer> {
+    bytes: &'buffer [u8],
+    position: usize,
+}
+
+impl<'buffer> BitReader<'buffer> {
+    fn new(bytes: &'buffer [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn remaining_bits(&self) -> usize {
+        self.bytes.len() * 8 - self.position
+    }
+
+    fn read(&mut self, bits: u32) -> Result<u32> {
+        if self.remaining_bits() < bits as usize {
+            return Err(eyre!("message too short"));
+        }
+        let mut value = 0;
+        for _ in 0..bits {
+            let bit = (self.bytes[self.position / 8] >> (7 - self.position % 8)) & 1;
+            value = (value << 1) | u32::from(bit);
+            self.position += 1;
+        }
+        Ok(value)
+    }
+
+    fn read_bool(&mut self) -> Result<bool> {
+        Ok(self.read(1)? == 1)
+    }
+
+    fn read_signed(&mut self, bits: u32) -> Result<i32> {
+        let value = self.read(bits)?;
+        Ok(((value << (32 - bits)) as i32) >> (32 - bits))
+    }
+
+    /// Fields appended by newer protocol versions are absent in older messages
+    fn read_optional<T>(&mut self, read: impl FnOnce(&mut Self) -> Result<T>) -> Result<Option<T>> {
+        if self.remaining_bits() == 0 || !self.read_bool()? {
+            return Ok(None);
+        }
+        read(self).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use linear_algebra::Point;
+
+    use super::*;
+
+    fn full_message() -> HulkMessage {
+        HulkMessage {
+            player_number: PlayerNumber::Four,
+            fallen: true,
+            pose: Pose2::new(vector![-3.21, 2.5], -2.0),
+            is_referee_ready_signal_detected: false,
+            ball_position: Some(BallPosition {
+                position: point![4.2, -0.07],
+                age: Duration::from_millis(1200),
+            }),
+            ball_covariance: Some(BallCovariance {
+                xx: 0.04,
+                xy: -0.01,
+                yy: 0.09,
+            }),
+            time_to_reach_kick_position: Some(Duration::from_millis(4300)),
+            role_costs: Some([0, 1, 2, 3000, 40000, 65534, u16::MAX]),
+        }
+    }
+
+    #[test]
+    fn maximum_hulk_message_size() {
+        let message = HulkMessage {
+            ball_position: Some(BallPosition {
+                position: Point::origin(),
+                age: Duration::MAX,
+            }),
+            time_to_reach_kick_position: Some(Duration::MAX),
+            ..full_message()
+        };
+        assert!(Vec::<u8>::from(message).len() <= 32);
+    }
+
+    #[test]
+    fn quantized_message_survives_round_trip() {
+        let message = full_message();
+
+        let decoded = HulkMessage::try_from(Vec::<u8>::from(message).as_slice()).unwrap();
+
+        assert_eq!(decoded.player_number, message.player_number);
+        assert_eq!(decoded.fallen, message.fallen);
+        assert_relative_eq!(decoded.pose, message.pose, epsilon = orientation_step());
+        let ball = decoded.ball_position.unwrap();
+        assert_relative_eq!(ball.position, point![4.2, -0.07], epsilon = 0.005);
+        assert_eq!(ball.age, Duration::from_millis(1200));
+        let covariance = decoded.ball_covariance.unwrap();
+        assert_relative_eq!(covariance.xx, 0.04, epsilon = 1e-3);
+        assert_relative_eq!(covariance.xy, -0.01, epsilon = 1e-3);
+        assert_relative_eq!(covariance.yy, 0.09, epsilon = 1e-3);
+        assert_eq!(
+            decoded.time_to_reach_kick_position,
+            message.time_to_reach_kick_position
+        );
+        assert_eq!(decoded.role_costs, message.role_costs);
+    }
+
+    #[test]
+    fn out_of_range_values_saturate() {
+        let message = HulkMessage {
+            pose: Pose2::new(vector![42.0, -42.0], 0.0),
+            time_to_reach_kick_position: Some(Duration::MAX),
+            ..full_message()
+        };
+
+        let decoded = HulkMessage::try_from(Vec::<u8>::from(message).as_slice()).unwrap();
+
+        assert_relative_eq!(decoded.pose.position().x(), 10.23, epsilon = 1e-4);
+        assert_relative_eq!(decoded.pose.position().y(), -10.23, epsilon = 1e-4);
+        assert_eq!(decoded.time_to_reach_kick_position, Some(Duration::MAX));
+    }
+
+    #[test]
+    fn fields_of_newer_versions_are_ignored() {
+        let mut buffer = Vec::<u8>::from(full_message());
+        buffer[2] = HULK_MESSAGE_PROTOCOL_VERSION + 1;
+        buffer.extend([0xff; 4]);
+
+        let decoded = HulkMessage::try_from(buffer.as_slice()).unwrap();
+
+        assert_eq!(decoded.role_costs, full_message().role_costs);
+    }
+
+    #[test]
+    fn fields_missing_in_older_versions_are_absent() {
+        let message = HulkMessage {
+            ball_position: None,
+            ball_covariance: None,
+            time_to_reach_kick_position: None,
+            role_costs: None,
+            ..full_message()
+        };
+        let mut buffer = Vec::<u8>::from(message);
+        // an older sender ends the message before the optional fields
+        buffer.truncate(HEADER_SIZE + 5);
+
+        let decoded = HulkMessage::try_from(buffer.as_slice()).unwrap();
+
+        assert!(decoded.ball_position.is_none());
+        assert!(decoded.role_costs.is_none());
+    }
+
+    #[test]
+    fn foreign_packets_are_rejected() {
+        let mut buffer = Vec::<u8>::from(full_message());
+        assert!(is_hulks_message(&buffer));
+
+        buffer[1] = HULKS_TEAM_NUMBER + 1;
+        assert!(!is_hulks_message(&buffer));
+        assert!(HulkMessage::try_from(buffer.as_slice()).is_err());
+
+        assert!(HulkMessage::try_from([HULK_MESSAGE_MAGIC].as_slice()).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 mod bindings;
 mod game_controller_return_message;
 mod game_controller_state_message;
+mod hulk_message_encoding;
+mod mixed_team_message;
 mod visual_referee_message;
 
 use std::{
@@ -10,6 +12,7 @@ use std::{
 
 use coordinate_systems::Field;
 use linear_algebra::{Point2, Pose2};
+use nalgebra::Matrix2;
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use serde::{Deserialize, Serialize};
 
@@ -18,6 +21,14 @@ pub use game_controller_state_message::{
     GameControllerStateMessage, GamePhase, GameState, Half, Penalty, PenaltyShoot, Player,
     SubState, Team, TeamColor, TeamState,
 };
+pub use hulk_message_encoding::{
+    is_hulks_message, HULK_MESSAGE_MAGIC, HULK_MESSAGE_PROTOCOL_VERSION,
+    MINIMUM_COMPATIBLE_PROTOCOL_VERSION,
+};
+pub use mixed_team_message::{
+    is_mixed_team_message, MixedTeamIntention, MixedTeamMessage, MIXED_TEAM_MESSAGE_MAGIC,
+    MIXED_TEAM_MESSAGE_VERSION,
+};
 pub use visual_referee_message::{VisualRefereeDecision, VisualRefereeMessage};
 
 #[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
@@ -27,9 +38,17 @@ pub struct HulkMessage {
     pub pose: Pose2<Field>,
     pub is_referee_ready_signal_detected: bool,
     pub ball_position: Option<BallPosition<Field>>,
+    /// Uncertainty of `ball_position`, `None` if the sender has no covariance for its ball
+    pub ball_covariance: Option<BallCovariance>,
     pub time_to_reach_kick_position: Option<Duration>,
+    /// Costs of the sender for every auctioned role in milliseconds, `None` if the sender does not
+    /// take part in the role auction
+    pub role_costs: Option<[u16; NUMBER_OF_AUCTIONED_ROLES]>,
 }
 
+/// The role auction distributes at most one role per robot of a full team
+pub const NUMBER_OF_AUCTIONED_ROLES: usize = 7;
+
 #[derive(
     Clone,
     Copy,
@@ -46,6 +65,41 @@ pub struct BallPosition<Frame> {
     pub age: Duration,
 }
 
+/// Symmetric 2x2 position covariance in square meters, only the upper triangle is transmitted
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    PartialEq,
+    PathDeserialize,
+    PathIntrospect,
+    PathSerialize,
+    Serialize,
+)]
+pub struct BallCovariance {
+    pub xx: f32,
+    pub xy: f32,
+    pub yy: f32,
+}
+
+impl From<Matrix2<f32>> for BallCovariance {
+    fn from(matrix: Matrix2<f32>) -> Self {
+        Self {
+            xx: matrix.m11,
+            xy: (matrix.m12 + matrix.m21) / 2.0,
+            yy: matrix.m22,
+        }
+    }
+}
+
+impl From<BallCovariance> for Matrix2<f32> {
+    fn from(covariance: BallCovariance) -> Self {
+        Matrix2::new(covariance.xx, covariance.xy, covariance.xy, covariance.yy)
+    }
+}
+
 pub const HULKS_TEAM_NUMBER: u8 = 24;
 
 #[derive(
@@ -88,28 +142,3 @@ impl Display for PlayerNumber {
         write!(formatter, "{number}")
     }
 }
-
-#[cfg(test)]
-mod tests {
-    use std::time::Duration;
-
-    use linear_algebra::{Point, Pose2};
-
-    use crate::{BallPosition, HulkMessage, PlayerNumber};
-
-    #[test]
-    fn maximum_hulk_message_size() {
-        let test_message = HulkMessage {
-            player_number: PlayerNumber::Seven,
-            fallen: false,
-            pose: Pose2::default(),
-            is_referee_ready_signal_detected: false,
-            ball_position: Some(BallPosition {
-                position: Point::origin(),
-                age: Duration::MAX,
-            }),
-            time_to_reach_kick_position: Some(Duration::MAX),
-        };
-        assert!(bincode::serialize(&test_message).unwrap().len() <= 128)
-    }
-}
@@ -0,0 +1,256 @@
+//! Shared message of mixed team and drop-in games
+//!
+//! All teams of a mixed team have to understand each other, so the message only contains what
+//! every team can provide and is laid out byte aligned in little endian:
+//!
+//! | Field          | Bytes | Encoding                                      |
+//! |----------------|-------|-----------------------------------------------|
+//! | magic          | 4     | [`MIXED_TEAM_MESSAGE_MAGIC`]                  |
+//! | version        | 1     | [`MIXED_TEAM_MESSAGE_VERSION`]                |
+//! | team number    | 1     | team number of the sender's own team          |
+//! | player number  | 1     | 1 to 7                                        |
+//! | fallen         | 1     | 0 or 1                                        |
+//! | intention      | 1     | [`MixedTeamIntention`]                        |
+//! | pose x, y      | 2x2   | signed millimeters                            |
+//! | pose angle     | 2     | signed 0.1 milliradians in (-π, π]            |
+//! | ball present   | 1     | 0 or 1, the ball fields are zero if unset     |
+//! | ball x, y      | 2x2   | signed millimeters                            |
+//! | ball age       | 2     | milliseconds, saturated                       |
+//!
+//! Poses and balls are given in the field frame of the mixed team, which is shared by all of its
+//! players.
+
+use std::{f32::consts::PI, time::Duration};
+
+use color_eyre::{eyre::bail, Report, Result};
+use coordinate_systems::Field;
+use linear_algebra::{point, vector, Pose2};
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+use serde::{Deserialize, Serialize};
+
+use crate::{BallPosition, HulkMessage, PlayerNumber};
+
+pub const MIXED_TEAM_MESSAGE_MAGIC: [u8; 4] = *b"MIXT";
+pub const MIXED_TEAM_MESSAGE_VERSION: u8 = 1;
+
+const MIXED_TEAM_MESSAGE_SIZE: usize = 22;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct MixedTeamMessage {
+    pub team_number: u8,
+    pub player_number: PlayerNumber,
+    pub fallen: bool,
+    pub intention: MixedTeamIntention,
+    pub pose: Pose2<Field>,
+    pub ball_position: Option<BallPosition<Field>>,
+}
+
+/// What the sender is about to do, teammates are expected to leave claimed roles to the sender
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    PathDeserialize,
+    PathIntrospect,
+    PathSerialize,
+    Serialize,
+)]
+#[repr(u8)]
+pub enum MixedTeamIntention {
+    #[default]
+    Nothing = 0,
+    Keeper = 1,
+    Defend = 2,
+    PlayBall = 3,
+    Lost = 4,
+}
+
+/// Whether the buffer carries a mixed team message of any team
+pub fn is_mixed_team_message(buffer: &[u8]) -> bool {
+    buffer.len() >= MIXED_TEAM_MESSAGE_MAGIC.len()
+        && buffer[..MIXED_TEAM_MESSAGE_MAGIC.len()] == MIXED_TEAM_MESSAGE_MAGIC
+}
+
+impl TryFrom<&[u8]> for MixedTeamMessage {
+    type Error = Report;
+
+    fn try_from(buffer: &[u8]) -> Result<Self> {
+        if buffer.len() < MIXED_TEAM_MESSAGE_SIZE {
+            bail!(
+                "buffer too small {} < {MIXED_TEAM_MESSAGE_SIZE}",
+                buffer.len()
+            );
+        }
+        if !is_mixed_team_message(buffer) {
+            bail!("unexpected magic {:?}", &buffer[..4]);
+        }
+        if buffer[4] != MIXED_TEAM_MESSAGE_VERSION {
+            bail!(
+                "unexpected version {} != {MIXED_TEAM_MESSAGE_VERSION}",
+                buffer[4]
+            );
+        }
+        let player_number = match buffer[6] {
+            1 => PlayerNumber::One,
+            2 => PlayerNumber::Two,
+            3 => PlayerNumber::Three,
+            4 => PlayerNumber::Four,
+            5 => PlayerNumber::Five,
+            6 => PlayerNumber::Six,
+            7 => PlayerNumber::Seven,
+            player_number => bail!("unexpected player number {player_number}"),
+        };
+        let intention = match buffer[8] {
+            0 => MixedTeamIntention::Nothing,
+            1 => MixedTeamIntention::Keeper,
+            2 => MixedTeamIntention::Defend,
+            3 => MixedTeamIntention::PlayBall,
+            4 => MixedTeamIntention::Lost,
+            intention => bail!("unexpected intention {intention}"),
+        };
+        let millimeters =
+            |index: usize| i16::from_le_bytes([buffer[index], buffer[index + 1]]) as f32 / 1000.0;
+        let pose = Pose2::new(
+            vector![millimeters(9), millimeters(11)],
+            i16::from_le_bytes([buffer[13], buffer[14]]) as f32 / 10_000.0,
+        );
+        let ball_position = (buffer[15] != 0).then(|| BallPosition {
+            position: point![millimeters(16), millimeters(18)],
+            age: Duration::from_millis(u16::from_le_bytes([buffer[20], buffer[21]]).into()),
+        });
+
+        Ok(Self {
+            team_number: buffer[5],
+            player_number,
+            fallen: buffer[7] != 0,
+            intention,
+            pose,
+            ball_position,
+        })
+    }
+}
+
+/// Fills the fields every team provides, HULKs specific fields stay unset
+impl From<MixedTeamMessage> for HulkMessage {
+    fn from(message: MixedTeamMessage) -> Self {
+        Self {
+            player_number: message.player_number,
+            fallen: message.fallen,
+            pose: message.pose,
+            ball_position: message.ball_position,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<MixedTeamMessage> for Vec<u8> {
+    fn from(message: MixedTeamMessage) -> Self {
+        let millimeters = |meters: f32| ((meters * 1000.0).round() as i16).to_le_bytes();
+        let angle = message.pose.angle();
+        let angle = if angle <= -PI {
+            angle + 2.0 * PI
+        } else {
+            angle
+        };
+        let ball = message.ball_position.unwrap_or_default();
+
+        let mut buffer = Vec::with_capacity(MIXED_TEAM_MESSAGE_SIZE);
+        buffer.extend_from_slice(&MIXED_TEAM_MESSAGE_MAGIC);
+        buffer.push(MIXED_TEAM_MESSAGE_VERSION);
+        buffer.push(message.team_number);
+        buffer.push(match message.player_number {
+            PlayerNumber::One => 1,
+            PlayerNumber::Two => 2,
+            PlayerNumber::Three => 3,
+            PlayerNumber::Four => 4,
+            PlayerNumber::Five => 5,
+            PlayerNumber::Six => 6,
+            PlayerNumber::Seven => 7,
+        });
+        buffer.push(message.fallen.into());
+        buffer.push(message.intention as u8);
+        buffer.extend_from_slice(&millimeters(message.pose.position().x()));
+        buffer.extend_from_slice(&millimeters(message.pose.position().y()));
+        buffer.extend_from_slice(&((angle * 10_000.0).round() as i16).to_le_bytes());
+        buffer.push(message.ball_position.is_some().into());
+        buffer.extend_from_slice(&millimeters(ball.position.x()));
+        buffer.extend_from_slice(&millimeters(ball.position.y()));
+        buffer.extend_from_slice(&(ball.age.as_millis().min(u16::MAX.into()) as u16).to_le_bytes());
+        buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixed_team_message_survives_round_trip() {
+        let message = MixedTeamMessage {
+            team_number: 5,
+            player_number: PlayerNumber::Four,
+            fallen: true,
+            intention: MixedTeamIntention::PlayBall,
+            pose: Pose2::new(vector![-3.2, 1.5], -2.0),
+            ball_position: Some(BallPosition {
+                position: point![0.5, -0.25],
+                age: Duration::from_millis(1500),
+            }),
+        };
+
+        let buffer: Vec<u8> = message.into();
+        assert_eq!(buffer.len(), MIXED_TEAM_MESSAGE_SIZE);
+        let decoded = MixedTeamMessage::try_from(buffer.as_slice()).unwrap();
+
+        assert_eq!(decoded.team_number, 5);
+        assert_eq!(decoded.player_number, PlayerNumber::Four);
+        assert!(decoded.fallen);
+        assert_eq!(decoded.intention, MixedTeamIntention::PlayBall);
+        assert!((decoded.pose.position() - message.pose.position()).norm() < 1e-3);
+        assert!((decoded.pose.angle() + 2.0).abs() < 1e-3);
+        let ball = decoded.ball_position.unwrap();
+        assert!((ball.position - point![0.5, -0.25]).norm() < 1e-3);
+        assert_eq!(ball.age, Duration::from_millis(1500));
+    }
+
+    #[test]
+    fn converted_hulk_message_keeps_shared_fields() {
+        let message = HulkMessage::from(MixedTeamMessage {
+            team_number: 5,
+            player_number: PlayerNumber::Two,
+            fallen: false,
+            intention: MixedTeamIntention::Defend,
+            pose: Pose2::new(vector![1.0, -2.0], 0.5),
+            ball_position: Some(BallPosition {
+                position: point![0.5, -0.25],
+                age: Duration::from_millis(300),
+            }),
+        });
+
+        assert_eq!(message.player_number, PlayerNumber::Two);
+        assert!(!message.fallen);
+        assert_eq!(message.pose.position(), point![1.0, -2.0]);
+        assert_eq!(
+            message.ball_position.unwrap().age,
+            Duration::from_millis(300)
+        );
+        assert!(message.ball_covariance.is_none());
+        assert!(message.role_costs.is_none());
+    }
+
+    #[test]
+    fn messages_without_ball_and_foreign_packets() {
+        let buffer: Vec<u8> = MixedTeamMessage::default().into();
+        assert!(MixedTeamMessage::try_from(buffer.as_slice())
+            .unwrap()
+            .ball_position
+            .is_none());
+
+        assert!(!is_mixed_team_message(b"SPL "));
+        assert!(MixedTeamMessage::try_from(&buffer[..10]).is_err());
+    }
+}
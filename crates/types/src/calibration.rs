@@ -0,0 +1,35 @@
+use linear_algebra::Point2;
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+use serde::{Deserialize, Serialize};
+
+use coordinate_systems::Ground;
+
+use crate::camera_position::CameraPosition;
+
+/// Where the head should look at while collecting calibration measurements
+#[derive(
+    Clone, Copy, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
+)]
+pub struct CalibrationCommand {
+    pub target: Point2<Ground>,
+    pub camera: CameraPosition,
+}
+
+/// Outcome of a finished calibration run
+///
+/// All rotations are euler angles in degrees, like the camera matrix parameters they replace.
+#[derive(
+    Clone, Debug, Default, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
+)]
+pub struct CalibrationReport {
+    pub number_of_measurements: usize,
+    /// Norms of the residuals before and after solving, unset if the residuals are not computable
+    pub residual_norm_before: Option<f32>,
+    pub residual_norm_after: Option<f32>,
+    pub solver_succeeded: bool,
+    pub robot_rotation_correction: nalgebra::Vector3<f32>,
+    pub top_extrinsic_rotations: nalgebra::Vector3<f32>,
+    pub bottom_extrinsic_rotations: nalgebra::Vector3<f32>,
+    /// Set once the corrections are written into the parameter file of the head
+    pub stored_to_disk: bool,
+}
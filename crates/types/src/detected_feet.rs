@@ -3,12 +3,15 @@ use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use serde::{Deserialize, Serialize};
 
 use coordinate_systems::{Ground, Pixel};
+use spl_network_messages::Team;
 
 #[derive(
-    Default, Clone, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
+    Clone, Copy, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
 )]
-pub struct DetectedFeet {
-    pub positions: Vec<Point2<Ground>>,
+pub struct DetectedFoot {
+    pub position: Point2<Ground>,
+    /// Team of the robot standing on this foot
+    pub team: Team,
 }
 
 #[derive(
@@ -0,0 +1,17 @@
+use geometry::rectangle::Rectangle;
+use linear_algebra::Point2;
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+use serde::{Deserialize, Serialize};
+
+use coordinate_systems::{Ground, Pixel};
+use spl_network_messages::Team;
+
+#[derive(
+    Clone, Copy, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
+)]
+pub struct DetectedRobot {
+    pub bounding_box: Rectangle<Pixel>,
+    /// Center between the feet of the robot, projected from the bottom of the bounding box
+    pub position: Point2<Ground>,
+    pub team: Team,
+}
@@ -1,5 +1,6 @@
 use geometry::line::{Line, Line2};
 use ordered_float::NotNan;
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use serde::{Deserialize, Serialize};
 
 use coordinate_systems::Field;
@@ -17,6 +18,10 @@ pub enum FieldMark {
         center: Point2<Field>,
         radius: f32,
     },
+    Point {
+        point: Point2<Field>,
+        kind: PointKind,
+    },
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
@@ -25,6 +30,24 @@ pub enum Direction {
     PositiveY,
 }
 
+/// Field marks that are detected as single points instead of lines
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+)]
+pub enum PointKind {
+    PenaltyMark,
+    GoalPost,
+}
+
 impl FieldMark {
     pub fn to_correspondence_points(self, measured_line: Line2<Field>) -> Correspondences {
         match self {
@@ -139,6 +162,24 @@ impl FieldMark {
                     reference_direction,
                 }
             }
+            FieldMark::Point { point, kind: _ } => {
+                // a point has no direction, both directions are chosen to be perfectly aligned
+                let direction = vector![1.0, 0.0];
+                Correspondences {
+                    correspondence_points: (
+                        CorrespondencePoints {
+                            measured: measured_line.0,
+                            reference: point,
+                        },
+                        CorrespondencePoints {
+                            measured: measured_line.1,
+                            reference: point,
+                        },
+                    ),
+                    measured_direction: direction,
+                    reference_direction: direction,
+                }
+            }
         }
     }
 }
@@ -419,3 +460,37 @@ pub fn field_marks_from_field_dimensions(field_dimensions: &FieldDimensions) ->
         },
     ]
 }
+
+/// Penalty marks and goal posts, the goal posts are placed at the center of the post
+pub fn point_marks_from_field_dimensions(field_dimensions: &FieldDimensions) -> Vec<FieldMark> {
+    let penalty_mark_x = field_dimensions.length / 2.0 - field_dimensions.penalty_marker_distance;
+    let goal_post_x = field_dimensions.length / 2.0;
+    let goal_post_y =
+        field_dimensions.goal_inner_width / 2.0 + field_dimensions.goal_post_diameter / 2.0;
+    vec![
+        FieldMark::Point {
+            point: point![-penalty_mark_x, 0.0],
+            kind: PointKind::PenaltyMark,
+        },
+        FieldMark::Point {
+            point: point![penalty_mark_x, 0.0],
+            kind: PointKind::PenaltyMark,
+        },
+        FieldMark::Point {
+            point: point![-goal_post_x, goal_post_y],
+            kind: PointKind::GoalPost,
+        },
+        FieldMark::Point {
+            point: point![-goal_post_x, -goal_post_y],
+            kind: PointKind::GoalPost,
+        },
+        FieldMark::Point {
+            point: point![goal_post_x, goal_post_y],
+            kind: PointKind::GoalPost,
+        },
+        FieldMark::Point {
+            point: point![goal_post_x, -goal_post_y],
+            kind: PointKind::GoalPost,
+        },
+    ]
+}
@@ -1,6 +1,8 @@
+use std::time::Duration;
+
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use serde::{Deserialize, Serialize};
-use spl_network_messages::{GamePhase, Penalty, SubState, Team};
+use spl_network_messages::{GamePhase, Half, Penalty, SubState, Team};
 
 use crate::{filtered_game_state::FilteredGameState, players::Players};
 
@@ -19,6 +21,8 @@ pub struct FilteredGameControllerState {
     pub game_state: FilteredGameState,
     pub opponent_game_state: FilteredGameState,
     pub game_phase: GamePhase,
+    pub half: Half,
+    pub remaining_time_in_half: Duration,
     pub kicking_team: Team,
     pub penalties: Players<Option<Penalty>>,
     pub remaining_number_of_messages: u16,
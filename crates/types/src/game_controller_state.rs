@@ -1,8 +1,8 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use serde::{Deserialize, Serialize};
-use spl_network_messages::{GamePhase, GameState, Penalty, SubState, Team};
+use spl_network_messages::{GamePhase, GameState, Half, Penalty, SubState, Team, TeamColor};
 
 use crate::players::Players;
 
@@ -12,10 +12,22 @@ use crate::players::Players;
 pub struct GameControllerState {
     pub game_state: GameState,
     pub game_phase: GamePhase,
+    pub half: Half,
+    pub remaining_time_in_half: Duration,
     pub kicking_team: Team,
     pub last_game_state_change: SystemTime,
     pub penalties: Players<Option<Penalty>>,
     pub remaining_amount_of_messages: u16,
     pub sub_state: Option<SubState>,
     pub hulks_team_is_home_after_coin_toss: bool,
+    pub hulks_jersey_colors: JerseyColors,
+    pub opponent_jersey_colors: JerseyColors,
+}
+
+#[derive(
+    Clone, Copy, Debug, Serialize, Deserialize, PathSerialize, PathDeserialize, PathIntrospect,
+)]
+pub struct JerseyColors {
+    pub field_player: TeamColor,
+    pub goal_keeper: TeamColor,
 }
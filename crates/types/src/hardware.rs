@@ -12,5 +12,6 @@ pub struct Ids {
 pub struct Paths {
     pub motions: PathBuf,
     pub neural_networks: PathBuf,
+    pub parameters: PathBuf,
     pub sounds: PathBuf,
 }
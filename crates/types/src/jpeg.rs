@@ -1,4 +1,6 @@
-use image::{codecs::jpeg::JpegEncoder, ImageBuffer, ImageError, Luma, RgbImage};
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::thumbnail, ImageBuffer, ImageError, Luma, RgbImage,
+};
 use serde::{Deserialize, Serialize};
 
 use crate::{grayscale_image::GrayscaleImage, ycbcr422_image::YCbCr422Image};
@@ -36,3 +38,15 @@ impl TryFrom<&YCbCr422Image> for JpegImage {
         Ok(Self { data: jpeg_buffer })
     }
 }
+
+impl JpegImage {
+    /// Encodes the image with its width and height divided by `scale` to save bandwidth when
+    /// streaming images
+    pub fn downscaled(image: &RgbImage, scale: u32, quality: u8) -> Result<Self, ImageError> {
+        let preview = thumbnail(image, image.width() / scale, image.height() / scale);
+        let mut jpeg_buffer = vec![];
+        let mut encoder = JpegEncoder::new_with_quality(&mut jpeg_buffer, quality);
+        encoder.encode_image(&preview)?;
+        Ok(Self { data: jpeg_buffer })
+    }
+}
@@ -0,0 +1,17 @@
+use serde::{Deserialize, Serialize};
+
+use coordinate_systems::{Ground, Pixel};
+use linear_algebra::Point2;
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+
+use crate::field_marks::PointKind;
+
+/// A penalty mark or goal post detected in an image
+#[derive(
+    Clone, Copy, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
+)]
+pub struct Landmark {
+    pub position: Point2<Ground>,
+    pub image_location: Point2<Pixel>,
+    pub kind: PointKind,
+}
@@ -6,11 +6,13 @@ pub mod ball_filter;
 pub mod ball_position;
 pub mod bounding_box;
 pub mod buttons;
+pub mod calibration;
 pub mod camera_position;
 pub mod color;
 pub mod condition_input;
 pub mod cycle_time;
 pub mod detected_feet;
+pub mod detected_robots;
 pub mod fall_state;
 pub mod field_border;
 pub mod field_color;
@@ -35,10 +37,12 @@ pub mod joints_velocity;
 pub mod jpeg;
 pub mod kick_decision;
 pub mod kick_target;
+pub mod landmarks;
 pub mod led;
 pub mod limb;
 pub mod line_data;
 pub mod localization;
+pub mod message_budget;
 pub mod message_event;
 pub mod messages;
 pub mod motion_command;
@@ -59,6 +63,7 @@ pub mod point_of_interest;
 pub mod pose_detection;
 pub mod pose_kinds;
 pub mod primary_state;
+pub mod recording_event;
 pub mod robot_dimensions;
 pub mod robot_kinematics;
 pub mod robot_masses;
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+use serde::{Deserialize, Serialize};
+
+/// Information value of a team message, more valuable messages may be sent more often
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+)]
+pub enum MessagePriority {
+    /// Periodic refresh without new information
+    Low,
+    /// Information changed, e.g. role costs
+    Normal,
+    /// Teammates have to react, e.g. a role change, a fall or a ball found after a long absence
+    High,
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    PartialEq,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+)]
+pub struct MessageBudget {
+    pub remaining_messages: u16,
+    /// Messages the team may send before the reserve is reached
+    pub spendable_messages: u16,
+    pub remaining_game_time: Duration,
+    pub active_players: usize,
+    /// Interval between messages of every player which spreads the spendable messages evenly over
+    /// the remaining game time
+    pub planned_message_interval: Duration,
+    pub low_priority_interval: Duration,
+    pub normal_priority_interval: Duration,
+    pub high_priority_interval: Duration,
+    /// Team message rate observed in the game controller message budget
+    pub team_messages_per_minute: f32,
+    /// Messages left at the end of the game if the team keeps the observed rate
+    pub projected_remaining_messages: f32,
+}
+
+impl MessageBudget {
+    pub fn interval(&self, priority: MessagePriority) -> Duration {
+        match priority {
+            MessagePriority::Low => self.low_priority_interval,
+            MessagePriority::Normal => self.normal_priority_interval,
+            MessagePriority::High => self.high_priority_interval,
+        }
+    }
+
+    pub fn allows(
+        &self,
+        priority: MessagePriority,
+        time_since_last_message: Option<Duration>,
+    ) -> bool {
+        self.spendable_messages > 0
+            && time_since_last_message.is_none_or(|time_since_last_message| {
+                time_since_last_message > self.interval(priority)
+            })
+    }
+}
@@ -4,13 +4,15 @@ use serde::{Deserialize, Serialize};
 
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use spl_network_messages::{
-    GameControllerReturnMessage, GameControllerStateMessage, HulkMessage, VisualRefereeMessage,
+    GameControllerReturnMessage, GameControllerStateMessage, HulkMessage, MixedTeamMessage,
+    VisualRefereeMessage,
 };
 
 #[derive(Clone, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect)]
 pub enum IncomingMessage {
     GameController(SocketAddr, GameControllerStateMessage),
     Spl(HulkMessage),
+    MixedTeam(MixedTeamMessage),
 }
 
 impl Default for IncomingMessage {
@@ -23,6 +25,7 @@ impl Default for IncomingMessage {
 pub enum OutgoingMessage {
     GameController(SocketAddr, GameControllerReturnMessage),
     Spl(HulkMessage),
+    MixedTeam(MixedTeamMessage),
     VisualReferee(SocketAddr, VisualRefereeMessage),
 }
 
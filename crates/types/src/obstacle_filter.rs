@@ -1,6 +1,7 @@
 use std::time::SystemTime;
 
 use serde::{Deserialize, Serialize};
+use spl_network_messages::Team;
 
 use crate::{
     multivariate_normal_distribution::MultivariateNormalDistribution, obstacles::ObstacleKind,
@@ -12,4 +13,5 @@ pub struct Hypothesis {
     pub measurement_count: usize,
     pub last_update: SystemTime,
     pub obstacle_kind: ObstacleKind,
+    pub team: Team,
 }
@@ -4,6 +4,7 @@ use linear_algebra::Point2;
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 
 use coordinate_systems::Ground;
+use spl_network_messages::Team;
 
 #[derive(
     Clone,
@@ -29,6 +30,8 @@ pub enum ObstacleKind {
 )]
 pub struct Obstacle {
     pub kind: ObstacleKind,
+    /// Only known for robots whose team could be told apart
+    pub team: Team,
     pub position: Point2<Ground>,
     pub radius_at_foot_height: f32,
     pub radius_at_hip_height: f32,
@@ -38,6 +41,7 @@ impl Obstacle {
     pub fn ball(position: Point2<Ground>, radius: f32) -> Self {
         Self {
             kind: ObstacleKind::Ball,
+            team: Team::Uncertain,
             position,
             radius_at_foot_height: radius,
             radius_at_hip_height: radius,
@@ -51,6 +55,7 @@ impl Obstacle {
     ) -> Self {
         Self {
             kind: ObstacleKind::Robot,
+            team: Team::Uncertain,
             position,
             radius_at_foot_height,
             radius_at_hip_height,
@@ -60,6 +65,7 @@ impl Obstacle {
     pub fn goal_post(position: Point2<Ground>, radius: f32) -> Self {
         Self {
             kind: ObstacleKind::GoalPost,
+            team: Team::Uncertain,
             position,
             radius_at_foot_height: radius,
             radius_at_hip_height: radius,
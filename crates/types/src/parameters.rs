@@ -7,6 +7,7 @@ use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    calibration::CalibrationCommand,
     joints::head::HeadJoints,
     motion_command::{KickVariant, MotionCommand},
     roles::Role,
@@ -251,9 +252,47 @@ pub struct SplNetworkParameters {
     pub game_controller_return_message_interval: Duration,
     pub remaining_amount_of_messages_to_stop_sending: u16,
     pub silence_interval_between_messages: Duration,
-    pub spl_striker_message_receive_timeout: Duration,
-    pub spl_striker_message_send_interval: Duration,
-    pub striker_trusts_team_ball: Duration,
+    pub role_costs_timeout: Duration,
+    pub message_codec: SplMessageCodec,
+}
+
+/// Message format sent to teammates, chosen per game
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    PartialEq,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+)]
+pub enum SplMessageCodec {
+    /// Our own message, only understood by our robots
+    #[default]
+    Hulks,
+    /// The shared message of mixed team and drop-in games, understood by all teams
+    MixedTeam,
+}
+
+#[derive(
+    Clone, Debug, Default, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
+)]
+pub struct RoleAuctionParameters {
+    /// Own role costs are sent early if any of them changed by more than this since the last message
+    pub cost_change_threshold: Duration,
+    pub fallen_penalty: Duration,
+    pub replacement_keeper_penalty: Duration,
+    /// Subtracted from the cost of the currently assigned role to prevent oscillating roles
+    pub role_switch_hysteresis: Duration,
+    pub defender_left_position: Point2<Field>,
+    pub defender_right_position: Point2<Field>,
+    pub midfielder_left_position: Point2<Field>,
+    pub midfielder_right_position: Point2<Field>,
+    pub striker_supporter_offset_to_ball: Vector2<Field>,
 }
 
 #[derive(
@@ -336,6 +375,9 @@ pub struct ObstacleFilterParameters {
     pub network_robot_measurement_matching_distance: f32,
     pub sonar_goal_post_matching_distance: f32,
     pub feet_detection_measurement_matching_distance: f32,
+    pub robot_detection_measurement_matching_distance: f32,
+    /// Detected feet closer to a detected robot are the feet of that robot
+    pub feet_to_robot_association_distance: f32,
     pub goal_post_measurement_matching_distance: f32,
     pub hypothesis_merge_distance: f32,
     pub process_noise: nalgebra::Vector2<f32>,
@@ -346,6 +388,7 @@ pub struct ObstacleFilterParameters {
     pub initial_covariance: nalgebra::Vector2<f32>,
     pub measurement_count_threshold: usize,
     pub use_feet_detection_measurements: bool,
+    pub use_robot_detection_measurements: bool,
     pub use_sonar_measurements: bool,
     pub use_foot_bumper_measurements: bool,
     pub robot_obstacle_radius_at_hip_height: f32,
@@ -354,6 +397,23 @@ pub struct ObstacleFilterParameters {
     pub goal_post_obstacle_radius: f32,
 }
 
+#[derive(
+    Clone, Debug, Default, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
+)]
+pub struct JerseyClassificationParameters {
+    /// Heights of the torso region above the ground
+    pub torso_bottom_height: f32,
+    pub torso_top_height: f32,
+    pub torso_half_width: f32,
+    pub samples_per_axis: usize,
+    /// Samples farther away from all jersey colors are not counted
+    pub maximum_color_distance: f32,
+    /// Luminance differences count less than chroma differences since lighting varies
+    pub luminance_weight: f32,
+    /// Fraction of all samples which have to vote for a team
+    pub minimum_vote_ratio: f32,
+}
+
 #[derive(
     Clone, Debug, Default, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
 )]
@@ -362,6 +422,23 @@ pub struct CameraMatrixParameters {
     pub extrinsic_rotations: nalgebra::Vector3<f32>,
     pub focal_lengths: nalgebra::Vector2<f32>,
     pub cc_optical_center: nalgebra::Point2<f32>,
+    /// Brown-Conrady coefficients k1 and k2
+    pub radial_distortion: nalgebra::Vector2<f32>,
+    /// Brown-Conrady coefficients p1 and p2
+    pub tangential_distortion: nalgebra::Vector2<f32>,
+}
+
+#[derive(
+    Clone, Debug, Default, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect,
+)]
+pub struct CalibrationControllerParameters {
+    /// Head targets visited one after another, each collecting measurements of its camera
+    pub targets: Vec<CalibrationCommand>,
+    /// Time for the head to reach a target before measurements are collected
+    pub settle_duration: Duration,
+    /// A target is skipped if not enough measurements are collected within this time
+    pub collect_timeout: Duration,
+    pub measurements_per_target: usize,
 }
 
 #[derive(
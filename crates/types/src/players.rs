@@ -11,6 +11,7 @@ use spl_network_messages::{Penalty, PlayerNumber, TeamState};
     Default,
     Debug,
     Deserialize,
+    PartialEq,
     Serialize,
     PathSerialize,
     PathIntrospect,
@@ -0,0 +1,23 @@
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Deserialize,
+    Eq,
+    Hash,
+    PartialEq,
+    Serialize,
+    PathSerialize,
+    PathDeserialize,
+    PathIntrospect,
+)]
+pub enum RecordingEvent {
+    FallDetected,
+    LocalizationReset,
+    BallLost,
+    WhistleHeard,
+    Penalized,
+}
@@ -77,4 +77,6 @@ pub struct RobotState {
     pub fall_state: FallState,
     pub has_ground_contact: bool,
     pub player_number: PlayerNumber,
+    /// Confidence of the localization between 0 and 1, `None` while the pose is unknown
+    pub localization_confidence: Option<f32>,
 }
@@ -0,0 +1,171 @@
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use calibration::{lines::Lines, measurement::Measurement};
+use context_attribute::context;
+use coordinate_systems::{Ground, Pixel};
+use framework::MainOutput;
+use geometry::line::{Line, Line2};
+use linear_algebra::point;
+use projection::{camera_matrix::CameraMatrix, Projection};
+use types::{
+    camera_position::CameraPosition, field_dimensions::FieldDimensions, line_data::LineData,
+    primary_state::PrimaryState,
+};
+
+/// Finds the lines of a goal box in the detected lines while the robot is calibrating
+///
+/// The goal line and the front line of the goal box are a pair of parallel lines which are
+/// `goal_box_area_length` apart, the farther one being the goal line. The longest line orthogonal
+/// to both connects them. The lines are projected back into the image to form a measurement for
+/// the extrinsic calibration.
+#[derive(Deserialize, Serialize)]
+pub struct CalibrationMeasurementDetection {}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    camera_position: Parameter<CameraPosition, "image_receiver.$cycler_instance.camera_position">,
+    field_dimensions: Parameter<FieldDimensions, "field_dimensions">,
+    maximum_distance_deviation:
+        Parameter<f32, "calibration_measurement_detection.maximum_distance_deviation">,
+    maximum_orthogonal_angle:
+        Parameter<f32, "calibration_measurement_detection.maximum_orthogonal_angle">,
+    maximum_parallel_angle:
+        Parameter<f32, "calibration_measurement_detection.maximum_parallel_angle">,
+
+    camera_matrix: RequiredInput<Option<CameraMatrix>, "camera_matrix?">,
+    line_data: RequiredInput<Option<LineData>, "line_data?">,
+    primary_state: Input<PrimaryState, "Control", "primary_state">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub calibration_measurement: MainOutput<Option<Measurement>>,
+}
+
+impl CalibrationMeasurementDetection {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {})
+    }
+
+    pub fn cycle(&mut self, context: CycleContext) -> Result<MainOutputs> {
+        if *context.primary_state != PrimaryState::Calibration {
+            return Ok(MainOutputs::default());
+        }
+
+        let calibration_measurement = find_goal_box_lines(
+            &context.line_data.lines,
+            context.field_dimensions.goal_box_area_length,
+            *context.maximum_distance_deviation,
+            *context.maximum_parallel_angle,
+            *context.maximum_orthogonal_angle,
+        )
+        .and_then(|lines| project_to_pixel(&lines, context.camera_matrix))
+        .map(|lines| Measurement {
+            position: *context.camera_position,
+            matrix: context.camera_matrix.clone(),
+            lines,
+        });
+
+        Ok(MainOutputs {
+            calibration_measurement: calibration_measurement.into(),
+        })
+    }
+}
+
+fn find_goal_box_lines(
+    lines: &[Line2<Ground>],
+    goal_box_area_length: f32,
+    maximum_distance_deviation: f32,
+    maximum_parallel_angle: f32,
+    maximum_orthogonal_angle: f32,
+) -> Option<Lines<Ground>> {
+    let origin = point![0.0, 0.0];
+    let (border_line, goal_box_line, _) = lines
+        .iter()
+        .flat_map(|border_line| {
+            lines
+                .iter()
+                .map(move |goal_box_line| (*border_line, *goal_box_line))
+        })
+        .filter(|(border_line, goal_box_line)| {
+            border_line.distance_to_point(origin) > goal_box_line.distance_to_point(origin)
+                && border_line.signed_acute_angle(*goal_box_line).abs() < maximum_parallel_angle
+        })
+        .map(|(border_line, goal_box_line)| {
+            let deviation = (border_line.distance_to_point(goal_box_line.center())
+                - goal_box_area_length)
+                .abs();
+            (border_line, goal_box_line, deviation)
+        })
+        .filter(|(_, _, deviation)| *deviation < maximum_distance_deviation)
+        .min_by(|(_, _, left), (_, _, right)| left.total_cmp(right))?;
+
+    let connecting_line = lines
+        .iter()
+        .filter(|line| {
+            border_line.is_orthogonal(**line, maximum_orthogonal_angle)
+                && goal_box_line.is_orthogonal(**line, maximum_orthogonal_angle)
+        })
+        .max_by(|left, right| left.length().total_cmp(&right.length()))?;
+
+    Some(Lines {
+        border_line,
+        goal_box_line,
+        connecting_line: *connecting_line,
+    })
+}
+
+fn project_to_pixel(lines: &Lines<Ground>, camera_matrix: &CameraMatrix) -> Option<Lines<Pixel>> {
+    let project = |line: Line2<Ground>| -> Option<Line2<Pixel>> {
+        Some(Line(
+            camera_matrix.ground_to_pixel(line.0).ok()?,
+            camera_matrix.ground_to_pixel(line.1).ok()?,
+        ))
+    };
+    Some(Lines {
+        border_line: project(lines.border_line)?,
+        goal_box_line: project(lines.goal_box_line)?,
+        connecting_line: project(lines.connecting_line)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goal_box_lines_are_found_among_other_lines() {
+        let border_line = Line(point![3.0, 1.5], point![3.0, -1.5]);
+        let goal_box_line = Line(point![2.4, 1.1], point![2.4, -1.1]);
+        let connecting_line = Line(point![2.4, 1.1], point![3.0, 1.1]);
+        let lines = [
+            Line(point![0.5, 2.0], point![0.5, -2.0]),
+            goal_box_line,
+            Line(point![2.5, 1.1], point![2.9, 1.1]),
+            border_line,
+            connecting_line,
+        ];
+
+        let found = find_goal_box_lines(&lines, 0.6, 0.1, 0.1, 0.1).unwrap();
+
+        assert_eq!(found.border_line.0, border_line.0);
+        assert_eq!(found.goal_box_line.0, goal_box_line.0);
+        assert_eq!(found.connecting_line.0, connecting_line.0);
+    }
+
+    #[test]
+    fn parallel_lines_at_wrong_distance_are_ignored() {
+        let lines = [
+            Line(point![3.0, 1.5], point![3.0, -1.5]),
+            Line(point![2.0, 1.1], point![2.0, -1.1]),
+            Line(point![2.0, 1.1], point![3.0, 1.1]),
+        ];
+
+        assert!(find_goal_box_lines(&lines, 0.6, 0.1, 0.1, 0.1).is_none());
+    }
+}
@@ -13,12 +13,17 @@ use linear_algebra::{distance, point, Point2};
 use projection::{camera_matrix::CameraMatrix, Projection};
 use types::{
     ball::Ball,
-    detected_feet::{ClusterPoint, CountedCluster, DetectedFeet},
+    detected_feet::{ClusterPoint, CountedCluster, DetectedFoot},
     filtered_segments::FilteredSegments,
+    game_controller_state::GameControllerState,
     image_segments::{EdgeType, ScanLine, Segment},
     line_data::LineData,
+    parameters::JerseyClassificationParameters,
+    ycbcr422_image::YCbCr422Image,
 };
 
+use crate::jersey_classification::classify_jersey;
+
 #[derive(Deserialize, Serialize)]
 pub struct FeetDetection {}
 
@@ -39,17 +44,20 @@ pub struct CycleContext {
         Parameter<f32, "feet_detection.$cycler_instance.minimum_luminance_standard_deviation">,
     minimum_samples_per_cluster:
         Parameter<usize, "feet_detection.$cycler_instance.minimum_samples_per_cluster">,
+    jersey_classification: Parameter<JerseyClassificationParameters, "jersey_classification">,
 
     balls: RequiredInput<Option<Vec<Ball>>, "balls?">,
     camera_matrix: RequiredInput<Option<CameraMatrix>, "camera_matrix?">,
     filtered_segments: Input<FilteredSegments, "filtered_segments">,
+    game_controller_state: Input<Option<GameControllerState>, "Control", "game_controller_state?">,
+    image: Input<YCbCr422Image, "image">,
     line_data: RequiredInput<Option<LineData>, "line_data?">,
 }
 
 #[context]
 #[derive(Default)]
 pub struct MainOutputs {
-    pub detected_feet: MainOutput<DetectedFeet>,
+    pub detected_feet: MainOutput<Vec<DetectedFoot>>,
 }
 
 impl FeetDetection {
@@ -86,12 +94,21 @@ impl FeetDetection {
                 .map(|cluster| cluster.mean)
                 .collect()
         });
-        let positions = clusters_in_ground
+        let detected_feet: Vec<_> = clusters_in_ground
             .into_iter()
-            .map(|cluster| cluster.mean)
+            .map(|cluster| DetectedFoot {
+                position: cluster.mean,
+                team: classify_jersey(
+                    context.image,
+                    context.camera_matrix,
+                    cluster.mean,
+                    context.game_controller_state,
+                    context.jersey_classification,
+                ),
+            })
             .collect();
         Ok(MainOutputs {
-            detected_feet: DetectedFeet { positions }.into(),
+            detected_feet: detected_feet.into(),
         })
     }
 }
@@ -136,7 +153,9 @@ fn extract_segment_cluster_points(
         .collect()
 }
 
-fn find_last_consecutive_cluster(
+/// Finds the lowest run of consecutive non-field segments which neither belong to a line nor to a
+/// ball and ends before the image border
+pub fn find_last_consecutive_cluster(
     scan_line: &ScanLine,
     line_data: &LineData,
     balls: &[Ball],
@@ -0,0 +1,210 @@
+use coordinate_systems::Ground;
+use linear_algebra::{vector, Point2};
+use projection::{camera_matrix::CameraMatrix, Projection};
+use spl_network_messages::{Team, TeamColor};
+use types::{
+    color::YCbCr444, game_controller_state::GameControllerState,
+    parameters::JerseyClassificationParameters, ycbcr422_image::YCbCr422Image,
+};
+
+/// Classifies the team of the robot standing at the given position by the color of its jersey
+///
+/// The torso region above the position is sampled and every sample votes for the team whose
+/// field player or goal keeper jersey color is nearest. The team is uncertain without
+/// GameController since the colors are unknown.
+pub fn classify_jersey(
+    image: &YCbCr422Image,
+    camera_matrix: &CameraMatrix,
+    position: Point2<Ground>,
+    game_controller_state: Option<&GameControllerState>,
+    parameters: &JerseyClassificationParameters,
+) -> Team {
+    let Some(game_controller_state) = game_controller_state else {
+        return Team::Uncertain;
+    };
+    let samples = sample_torso(image, camera_matrix, position, parameters);
+    classify_samples(
+        &samples,
+        &[
+            game_controller_state.hulks_jersey_colors.field_player,
+            game_controller_state.hulks_jersey_colors.goal_keeper,
+        ],
+        &[
+            game_controller_state.opponent_jersey_colors.field_player,
+            game_controller_state.opponent_jersey_colors.goal_keeper,
+        ],
+        parameters,
+    )
+}
+
+fn sample_torso(
+    image: &YCbCr422Image,
+    camera_matrix: &CameraMatrix,
+    position: Point2<Ground>,
+    parameters: &JerseyClassificationParameters,
+) -> Vec<YCbCr444> {
+    let Some(viewing_direction) = position.coords().try_normalize(f32::EPSILON) else {
+        return Vec::new();
+    };
+    let sideways = vector![-viewing_direction.y(), viewing_direction.x()];
+    let steps = parameters.samples_per_axis.max(2);
+    let fraction = |index: usize| index as f32 / (steps - 1) as f32;
+
+    let mut samples = Vec::with_capacity(steps * steps);
+    for row in 0..steps {
+        let height = parameters.torso_bottom_height
+            + fraction(row) * (parameters.torso_top_height - parameters.torso_bottom_height);
+        for column in 0..steps {
+            let offset = (2.0 * fraction(column) - 1.0) * parameters.torso_half_width;
+            let Ok(pixel) =
+                camera_matrix.ground_with_z_to_pixel(position + sideways * offset, height)
+            else {
+                continue;
+            };
+            if pixel.x() < 0.0 || pixel.y() < 0.0 {
+                continue;
+            }
+            if let Some(color) = image.try_at(pixel.x() as u32, pixel.y() as u32) {
+                samples.push(color);
+            }
+        }
+    }
+    samples
+}
+
+fn classify_samples(
+    samples: &[YCbCr444],
+    own_colors: &[TeamColor],
+    opponent_colors: &[TeamColor],
+    parameters: &JerseyClassificationParameters,
+) -> Team {
+    // colors worn by both teams cannot tell them apart
+    let candidates: Vec<_> = own_colors
+        .iter()
+        .filter(|color| !opponent_colors.contains(color))
+        .map(|color| (Team::Hulks, jersey_reference_color(*color)))
+        .chain(
+            opponent_colors
+                .iter()
+                .filter(|color| !own_colors.contains(color))
+                .map(|color| (Team::Opponent, jersey_reference_color(*color))),
+        )
+        .collect();
+
+    let (own_votes, opponent_votes) =
+        samples
+            .iter()
+            .fold((0, 0), |(own_votes, opponent_votes), sample| {
+                let nearest = candidates
+                    .iter()
+                    .map(|(team, reference)| {
+                        (team, color_distance(*sample, *reference, parameters))
+                    })
+                    .filter(|(_, distance)| *distance <= parameters.maximum_color_distance)
+                    .min_by(|(_, left), (_, right)| left.total_cmp(right));
+                match nearest {
+                    Some((Team::Hulks, _)) => (own_votes + 1, opponent_votes),
+                    Some((Team::Opponent, _)) => (own_votes, opponent_votes + 1),
+                    _ => (own_votes, opponent_votes),
+                }
+            });
+
+    let minimum_votes = parameters.minimum_vote_ratio * samples.len() as f32;
+    if samples.is_empty() {
+        Team::Uncertain
+    } else if own_votes as f32 >= minimum_votes && own_votes > opponent_votes {
+        Team::Hulks
+    } else if opponent_votes as f32 >= minimum_votes && opponent_votes > own_votes {
+        Team::Opponent
+    } else {
+        Team::Uncertain
+    }
+}
+
+fn color_distance(
+    sample: YCbCr444,
+    reference: YCbCr444,
+    parameters: &JerseyClassificationParameters,
+) -> f32 {
+    let luminance = (sample.y as f32 - reference.y as f32) * parameters.luminance_weight;
+    let cb = sample.cb as f32 - reference.cb as f32;
+    let cr = sample.cr as f32 - reference.cr as f32;
+    (luminance * luminance + cb * cb + cr * cr).sqrt()
+}
+
+/// Nominal colors of the jerseys converted to YCbCr
+fn jersey_reference_color(team_color: TeamColor) -> YCbCr444 {
+    match team_color {
+        TeamColor::Blue => YCbCr444::new(29, 255, 107),
+        TeamColor::Red => YCbCr444::new(76, 85, 255),
+        TeamColor::Yellow => YCbCr444::new(226, 1, 149),
+        TeamColor::Black => YCbCr444::new(16, 128, 128),
+        TeamColor::White => YCbCr444::new(235, 128, 128),
+        TeamColor::Green => YCbCr444::new(75, 85, 74),
+        TeamColor::Orange => YCbCr444::new(173, 30, 186),
+        TeamColor::Purple => YCbCr444::new(53, 165, 181),
+        TeamColor::Brown => YCbCr444::new(79, 112, 190),
+        TeamColor::Gray => YCbCr444::new(128, 128, 128),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters() -> JerseyClassificationParameters {
+        JerseyClassificationParameters {
+            maximum_color_distance: 60.0,
+            luminance_weight: 0.3,
+            minimum_vote_ratio: 0.3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn jersey_color_decides_team() {
+        let red = YCbCr444::new(90, 100, 220);
+        let blue = YCbCr444::new(50, 220, 110);
+        let white = YCbCr444::new(230, 128, 128);
+        let own_colors = [TeamColor::Red, TeamColor::Black];
+        let opponent_colors = [TeamColor::Blue, TeamColor::Yellow];
+
+        let red_robot = [red, red, white, red, white, white];
+        assert_eq!(
+            classify_samples(&red_robot, &own_colors, &opponent_colors, &parameters()),
+            Team::Hulks
+        );
+
+        let blue_robot = [blue, white, blue, blue, white, white];
+        assert_eq!(
+            classify_samples(&blue_robot, &own_colors, &opponent_colors, &parameters()),
+            Team::Opponent
+        );
+
+        let occluded_robot = [white, white, white, white, white, red];
+        assert_eq!(
+            classify_samples(
+                &occluded_robot,
+                &own_colors,
+                &opponent_colors,
+                &parameters()
+            ),
+            Team::Uncertain
+        );
+    }
+
+    #[test]
+    fn shared_colors_are_ignored() {
+        let black = YCbCr444::new(20, 128, 128);
+
+        assert_eq!(
+            classify_samples(
+                &[black; 4],
+                &[TeamColor::Red, TeamColor::Black],
+                &[TeamColor::Black, TeamColor::Blue],
+                &parameters()
+            ),
+            Team::Uncertain
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use std::ops::Range;
+
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use context_attribute::context;
+use coordinate_systems::{Ground, Pixel};
+use framework::{AdditionalOutput, MainOutput};
+use linear_algebra::{distance, point, Point2};
+use projection::{camera_matrix::CameraMatrix, Projection};
+use types::{
+    field_border::FieldBorder,
+    field_marks::PointKind,
+    filtered_segments::FilteredSegments,
+    image_segments::{EdgeType, ImageSegments},
+    landmarks::Landmark,
+    line_data::LineData,
+};
+
+#[derive(Deserialize, Serialize)]
+pub struct LandmarkDetection {}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    penalty_mark_points:
+        AdditionalOutput<Vec<Point2<Pixel>>, "landmark_detection.penalty_mark_points">,
+    goal_post_points: AdditionalOutput<Vec<Point2<Pixel>>, "landmark_detection.goal_post_points">,
+
+    enable: Parameter<bool, "landmark_detection.$cycler_instance.enable">,
+    maximum_cluster_distance:
+        Parameter<f32, "landmark_detection.$cycler_instance.maximum_cluster_distance">,
+    maximum_distance_to_robot:
+        Parameter<f32, "landmark_detection.$cycler_instance.maximum_distance_to_robot">,
+    allowed_penalty_mark_size:
+        Parameter<Range<f32>, "landmark_detection.$cycler_instance.allowed_penalty_mark_size">,
+    minimum_points_per_penalty_mark:
+        Parameter<usize, "landmark_detection.$cycler_instance.minimum_points_per_penalty_mark">,
+    maximum_goal_post_width:
+        Parameter<f32, "landmark_detection.$cycler_instance.maximum_goal_post_width">,
+    minimum_goal_post_luminance:
+        Parameter<u8, "landmark_detection.$cycler_instance.minimum_goal_post_luminance">,
+    minimum_goal_post_length_in_pixels:
+        Parameter<u16, "landmark_detection.$cycler_instance.minimum_goal_post_length_in_pixels">,
+
+    camera_matrix: RequiredInput<Option<CameraMatrix>, "camera_matrix?">,
+    field_border: Input<Option<FieldBorder>, "field_border?">,
+    filtered_segments: Input<FilteredSegments, "filtered_segments">,
+    image_segments: Input<ImageSegments, "image_segments">,
+    line_data: RequiredInput<Option<LineData>, "line_data?">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub landmarks: MainOutput<Option<Vec<Landmark>>>,
+}
+
+impl LandmarkDetection {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {})
+    }
+
+    pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        if !context.enable {
+            return Ok(MainOutputs::default());
+        }
+
+        let penalty_mark_points = extract_penalty_mark_points(
+            context.filtered_segments,
+            context.line_data,
+            context.camera_matrix,
+            *context.maximum_distance_to_robot,
+        );
+        context.penalty_mark_points.fill_if_subscribed(|| {
+            penalty_mark_points
+                .iter()
+                .map(|point| point.pixel_coordinates)
+                .collect()
+        });
+        let goal_post_points = match context.field_border {
+            Some(field_border) => extract_goal_post_points(
+                context.image_segments,
+                field_border,
+                context.camera_matrix,
+                *context.maximum_distance_to_robot,
+                *context.minimum_goal_post_luminance,
+                *context.minimum_goal_post_length_in_pixels,
+            ),
+            None => Vec::new(),
+        };
+        context.goal_post_points.fill_if_subscribed(|| {
+            goal_post_points
+                .iter()
+                .map(|point| point.pixel_coordinates)
+                .collect()
+        });
+
+        let penalty_marks = cluster_points(penalty_mark_points, *context.maximum_cluster_distance)
+            .into_iter()
+            .filter(|cluster| {
+                cluster.points.len() >= *context.minimum_points_per_penalty_mark
+                    && context
+                        .allowed_penalty_mark_size
+                        .contains(&cluster.extent())
+            })
+            .map(|cluster| cluster.to_landmark(PointKind::PenaltyMark));
+        let goal_posts = cluster_points(goal_post_points, *context.maximum_cluster_distance)
+            .into_iter()
+            .filter(|cluster| cluster.extent() <= *context.maximum_goal_post_width)
+            .map(|cluster| cluster.to_landmark(PointKind::GoalPost));
+
+        Ok(MainOutputs {
+            landmarks: Some(penalty_marks.chain(goal_posts).collect()).into(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct LandmarkPoint {
+    pixel_coordinates: Point2<Pixel>,
+    position_in_ground: Point2<Ground>,
+}
+
+/// Centers of bright segments on the field which are not part of a detected line
+fn extract_penalty_mark_points(
+    filtered_segments: &FilteredSegments,
+    line_data: &LineData,
+    camera_matrix: &CameraMatrix,
+    maximum_distance_to_robot: f32,
+) -> Vec<LandmarkPoint> {
+    filtered_segments
+        .scan_grid
+        .vertical_scan_lines
+        .iter()
+        .flat_map(|scan_line| {
+            scan_line
+                .segments
+                .iter()
+                .filter(|segment| {
+                    segment.start_edge_type == EdgeType::Rising
+                        && segment.end_edge_type == EdgeType::Falling
+                        && !line_data
+                            .used_segments
+                            .contains(&point![scan_line.position, segment.start])
+                })
+                .map(|segment| point![scan_line.position as f32, segment.center() as f32])
+        })
+        .filter_map(|pixel_coordinates| {
+            let position_in_ground = camera_matrix.pixel_to_ground(pixel_coordinates).ok()?;
+            (position_in_ground.coords().norm() <= maximum_distance_to_robot).then_some(
+                LandmarkPoint {
+                    pixel_coordinates,
+                    position_in_ground,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Foot points of bright segments reaching from above the field border into the field
+fn extract_goal_post_points(
+    image_segments: &ImageSegments,
+    field_border: &FieldBorder,
+    camera_matrix: &CameraMatrix,
+    maximum_distance_to_robot: f32,
+    minimum_luminance: u8,
+    minimum_length_in_pixels: u16,
+) -> Vec<LandmarkPoint> {
+    image_segments
+        .scan_grid
+        .vertical_scan_lines
+        .iter()
+        .filter_map(|scan_line| {
+            let segment = scan_line.segments.iter().find(|segment| {
+                let starts_outside_field = !field_border
+                    .is_inside_field(point![scan_line.position as f32, segment.start as f32]);
+                let ends_inside_field = field_border
+                    .is_inside_field(point![scan_line.position as f32, segment.end as f32]);
+                starts_outside_field
+                    && ends_inside_field
+                    && segment.end_edge_type == EdgeType::Falling
+                    && segment.color.y >= minimum_luminance
+                    && segment.length() >= minimum_length_in_pixels
+            })?;
+            let pixel_coordinates = point![scan_line.position as f32, segment.end as f32];
+            let position_in_ground = camera_matrix.pixel_to_ground(pixel_coordinates).ok()?;
+            (position_in_ground.coords().norm() <= maximum_distance_to_robot).then_some(
+                LandmarkPoint {
+                    pixel_coordinates,
+                    position_in_ground,
+                },
+            )
+        })
+        .collect()
+}
+
+struct Cluster {
+    points: Vec<LandmarkPoint>,
+}
+
+impl Cluster {
+    /// Largest distance between two points of the cluster
+    fn extent(&self) -> f32 {
+        self.points
+            .iter()
+            .flat_map(|first| {
+                self.points
+                    .iter()
+                    .map(|second| distance(first.position_in_ground, second.position_in_ground))
+            })
+            .fold(0.0, f32::max)
+    }
+
+    fn to_landmark(&self, kind: PointKind) -> Landmark {
+        let number_of_points = self.points.len() as f32;
+        let position = self.points.iter().fold(Point2::origin(), |sum, point| {
+            sum + point.position_in_ground.coords()
+        }) / number_of_points;
+        let image_location = self.points.iter().fold(Point2::origin(), |sum, point| {
+            sum + point.pixel_coordinates.coords()
+        }) / number_of_points;
+        Landmark {
+            position,
+            image_location,
+            kind,
+        }
+    }
+}
+
+/// Single linkage clustering, points closer than the maximum distance end up in the same cluster
+fn cluster_points(points: Vec<LandmarkPoint>, maximum_cluster_distance: f32) -> Vec<Cluster> {
+    let mut clusters: Vec<Cluster> = Vec::new();
+    for point in points {
+        let (connected, mut unconnected): (Vec<_>, Vec<_>) =
+            clusters.into_iter().partition(|cluster| {
+                cluster.points.iter().any(|other| {
+                    distance(other.position_in_ground, point.position_in_ground)
+                        < maximum_cluster_distance
+                })
+            });
+        let mut merged = Cluster {
+            points: vec![point],
+        };
+        for cluster in connected {
+            merged.points.extend(cluster.points);
+        }
+        unconnected.push(merged);
+        clusters = unconnected;
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn landmark_point(x: f32, y: f32) -> LandmarkPoint {
+        LandmarkPoint {
+            pixel_coordinates: Point2::origin(),
+            position_in_ground: point![x, y],
+        }
+    }
+
+    #[test]
+    fn chained_points_form_one_cluster() {
+        let points = vec![
+            landmark_point(1.0, 0.0),
+            landmark_point(3.0, 0.0),
+            landmark_point(1.15, 0.0),
+            landmark_point(1.3, 0.0),
+        ];
+
+        let clusters = cluster_points(points, 0.2);
+
+        assert_eq!(clusters.len(), 2);
+        let penalty_mark = clusters
+            .iter()
+            .find(|cluster| cluster.points.len() == 3)
+            .unwrap();
+        assert!((penalty_mark.extent() - 0.3).abs() < 1e-5);
+        let landmark = penalty_mark.to_landmark(PointKind::PenaltyMark);
+        assert!(distance(landmark.position, point![1.15, 0.0]) < 1e-5);
+    }
+}
@@ -1,12 +1,16 @@
 pub mod ball_detection;
+pub mod calibration_measurement_detection;
 pub mod camera_matrix_extractor;
 pub mod feet_detection;
 pub mod field_border_detection;
 pub mod field_color_detection;
 pub mod image_receiver;
 pub mod image_segmenter;
+mod jersey_classification;
+pub mod landmark_detection;
 pub mod limb_projector;
 pub mod line_detection;
 pub mod perspective_grid_candidates_provider;
 mod ransac;
+pub mod robot_detection;
 pub mod segment_filter;
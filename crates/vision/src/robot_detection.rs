@@ -0,0 +1,229 @@
+use color_eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use context_attribute::context;
+use coordinate_systems::{Ground, Pixel};
+use framework::{AdditionalOutput, MainOutput};
+use geometry::rectangle::Rectangle;
+use linear_algebra::{distance, point, Point2};
+use projection::{camera_matrix::CameraMatrix, Projection};
+use types::{
+    ball::Ball, detected_robots::DetectedRobot, filtered_segments::FilteredSegments,
+    game_controller_state::GameControllerState, line_data::LineData,
+    parameters::JerseyClassificationParameters, ycbcr422_image::YCbCr422Image,
+};
+
+use crate::{
+    feet_detection::find_last_consecutive_cluster, jersey_classification::classify_jersey,
+};
+
+/// Detects standing robots as groups of neighboring scan lines covered by non-field segments
+///
+/// Every scan line contributes the lowest run of non-field segments. Neighboring runs with nearby
+/// foot points form a bounding box, which is accepted if its projected width and height fit a
+/// robot. The team of every robot is classified by the color of its jersey.
+#[derive(Deserialize, Serialize)]
+pub struct RobotDetection {}
+
+#[context]
+pub struct CreationContext {}
+
+#[context]
+pub struct CycleContext {
+    candidates: AdditionalOutput<Vec<Rectangle<Pixel>>, "robot_detection.candidates">,
+
+    enable: Parameter<bool, "robot_detection.$cycler_instance.enable">,
+    maximum_foot_distance: Parameter<f32, "robot_detection.$cycler_instance.maximum_foot_distance">,
+    maximum_robot_width: Parameter<f32, "robot_detection.$cycler_instance.maximum_robot_width">,
+    minimum_columns: Parameter<usize, "robot_detection.$cycler_instance.minimum_columns">,
+    minimum_consecutive_segments:
+        Parameter<usize, "robot_detection.$cycler_instance.minimum_consecutive_segments">,
+    minimum_robot_height: Parameter<f32, "robot_detection.$cycler_instance.minimum_robot_height">,
+    minimum_robot_width: Parameter<f32, "robot_detection.$cycler_instance.minimum_robot_width">,
+    jersey_classification: Parameter<JerseyClassificationParameters, "jersey_classification">,
+
+    balls: RequiredInput<Option<Vec<Ball>>, "balls?">,
+    camera_matrix: RequiredInput<Option<CameraMatrix>, "camera_matrix?">,
+    filtered_segments: Input<FilteredSegments, "filtered_segments">,
+    game_controller_state: Input<Option<GameControllerState>, "Control", "game_controller_state?">,
+    image: Input<YCbCr422Image, "image">,
+    line_data: RequiredInput<Option<LineData>, "line_data?">,
+}
+
+#[context]
+#[derive(Default)]
+pub struct MainOutputs {
+    pub detected_robots: MainOutput<Vec<DetectedRobot>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct RobotColumn {
+    position: u16,
+    top: u16,
+    bottom: u16,
+    foot: Point2<Ground>,
+}
+
+impl RobotDetection {
+    pub fn new(_context: CreationContext) -> Result<Self> {
+        Ok(Self {})
+    }
+
+    pub fn cycle(&mut self, mut context: CycleContext) -> Result<MainOutputs> {
+        if !context.enable {
+            return Ok(MainOutputs::default());
+        }
+
+        let columns: Vec<_> = context
+            .filtered_segments
+            .scan_grid
+            .vertical_scan_lines
+            .iter()
+            .filter_map(|scan_line| {
+                let cluster = find_last_consecutive_cluster(
+                    scan_line,
+                    context.line_data,
+                    context.balls,
+                    *context.minimum_consecutive_segments,
+                )?;
+                let top = cluster.first()?.start;
+                let bottom = cluster.last()?.end;
+                let foot = context
+                    .camera_matrix
+                    .pixel_to_ground(point![scan_line.position as f32, bottom as f32])
+                    .ok()?;
+                Some(RobotColumn {
+                    position: scan_line.position,
+                    top,
+                    bottom,
+                    foot,
+                })
+            })
+            .collect();
+
+        let groups = group_columns(&columns, *context.maximum_foot_distance);
+        let candidates: Vec<_> = groups
+            .iter()
+            .filter(|group| group.len() >= *context.minimum_columns)
+            .map(|group| bounding_box(group))
+            .collect();
+        context.candidates.fill_if_subscribed(|| candidates.clone());
+
+        let detected_robots: Vec<_> = candidates
+            .into_iter()
+            .filter_map(|bounding_box| {
+                let bottom_left = context
+                    .camera_matrix
+                    .pixel_to_ground(point![bounding_box.min.x(), bounding_box.max.y()])
+                    .ok()?;
+                let bottom_right = context
+                    .camera_matrix
+                    .pixel_to_ground(point![bounding_box.max.x(), bounding_box.max.y()])
+                    .ok()?;
+                let width = distance(bottom_left, bottom_right);
+                if width < *context.minimum_robot_width || width > *context.maximum_robot_width {
+                    return None;
+                }
+                let position = bottom_left + (bottom_right - bottom_left) / 2.0;
+                let minimum_top = context
+                    .camera_matrix
+                    .ground_with_z_to_pixel(position, *context.minimum_robot_height)
+                    .ok()?;
+                if bounding_box.min.y() > minimum_top.y() {
+                    return None;
+                }
+                Some(DetectedRobot {
+                    bounding_box,
+                    position,
+                    team: classify_jersey(
+                        context.image,
+                        context.camera_matrix,
+                        position,
+                        context.game_controller_state,
+                        context.jersey_classification,
+                    ),
+                })
+            })
+            .collect();
+
+        Ok(MainOutputs {
+            detected_robots: detected_robots.into(),
+        })
+    }
+}
+
+/// Splits the columns into groups of neighboring columns which overlap vertically and whose foot
+/// points are close to each other
+fn group_columns(columns: &[RobotColumn], maximum_foot_distance: f32) -> Vec<&[RobotColumn]> {
+    columns
+        .chunk_by(|left, right| {
+            left.top <= right.bottom
+                && right.top <= left.bottom
+                && distance(left.foot, right.foot) <= maximum_foot_distance
+        })
+        .collect()
+}
+
+fn bounding_box(columns: &[RobotColumn]) -> Rectangle<Pixel> {
+    let left = columns
+        .iter()
+        .map(|column| column.position)
+        .min()
+        .unwrap_or(0);
+    let right = columns
+        .iter()
+        .map(|column| column.position)
+        .max()
+        .unwrap_or(0);
+    let top = columns.iter().map(|column| column.top).min().unwrap_or(0);
+    let bottom = columns
+        .iter()
+        .map(|column| column.bottom)
+        .max()
+        .unwrap_or(0);
+    Rectangle {
+        min: point![left as f32, top as f32],
+        max: point![right as f32, bottom as f32],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(position: u16, top: u16, bottom: u16, foot_y: f32) -> RobotColumn {
+        RobotColumn {
+            position,
+            top,
+            bottom,
+            foot: point![2.0, foot_y],
+        }
+    }
+
+    #[test]
+    fn separate_robots_form_separate_groups() {
+        let columns = [
+            column(10, 100, 200, 0.5),
+            column(18, 90, 205, 0.45),
+            column(26, 95, 200, 0.4),
+            // next robot is standing behind the first one
+            column(34, 60, 150, -0.2),
+            column(42, 55, 148, -0.25),
+            // a separate blob without vertical overlap
+            column(50, 300, 320, -0.3),
+        ];
+
+        let groups = group_columns(&columns, 0.15);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].len(), 3);
+        assert_eq!(groups[1].len(), 2);
+        assert_eq!(
+            bounding_box(groups[0]),
+            Rectangle {
+                min: point![10.0, 90.0],
+                max: point![26.0, 205.0],
+            }
+        );
+    }
+}
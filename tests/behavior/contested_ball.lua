@@ -0,0 +1,72 @@
+function spawn_robot(number)
+  table.insert(state.robots, create_robot(number))
+end
+
+spawn_robot(1)
+spawn_robot(2)
+spawn_robot(3)
+spawn_robot(4)
+spawn_robot(5)
+
+state.opponents = {
+  {
+    position = { 1.5, 0.5 },
+    orientation = math.pi,
+    behavior = "ChaseBall",
+  },
+  {
+    position = { 2.0, -1.0 },
+    orientation = math.pi,
+    behavior = "BlockBall",
+  },
+  {
+    position = { -1.5, 1.0 },
+    orientation = 0.0,
+    behavior = "Stand",
+  },
+}
+
+expect_no_robot_leaves_field()
+
+function on_goal()
+  state.finished = true
+end
+
+function on_cycle()
+  if state.cycle_count == 100 then
+    state.filtered_game_controller_state.game_state = {
+      Ready = {
+        kicking_team = "Hulks",
+      },
+    }
+  end
+
+  if state.cycle_count == 1600 then
+    state.filtered_game_controller_state.game_state = "Set"
+    state.ball = {
+      position = { 0.0, 0.0 },
+      velocity = { 0.0, 0.0 },
+    }
+  end
+
+  if state.cycle_count == 1700 then
+    state.filtered_game_controller_state.game_state = {
+      Playing = {
+        ball_is_free = true,
+        kick_off = true
+      },
+    }
+  end
+
+  if state.cycle_count == 3000 then
+    state.opponents[3].behavior = {
+      WalkTo = {
+        target = { -3.5, 0.0 },
+      },
+    }
+  end
+
+  if state.cycle_count == 8000 then
+    state.finished = true
+  end
+end
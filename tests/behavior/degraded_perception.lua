@@ -0,0 +1,62 @@
+function spawn_robot(number)
+  local robot = create_robot(number)
+  robot.perception_noise = {
+    pose_drift_per_meter = 0.05,
+    orientation_drift_per_meter = 0.02,
+    ball_dropout_probability = 0.1,
+    ball_dropout_probability_per_meter = 0.1,
+    ball_dropout_probability_at_image_border = 0.2,
+    ball_position_noise_per_meter = 0.05,
+    message_loss_probability = 0.2,
+    maximum_message_delay = 0.5,
+  }
+  table.insert(state.robots, robot)
+end
+
+spawn_robot(1)
+spawn_robot(2)
+spawn_robot(3)
+spawn_robot(4)
+spawn_robot(5)
+
+function on_goal()
+  state.finished = true
+end
+
+function on_cycle()
+  if state.cycle_count == 100 then
+    state.filtered_game_controller_state.game_state = {
+      Ready = {
+        kicking_team = "Hulks",
+      },
+    }
+  end
+
+  if state.cycle_count == 1600 then
+    state.filtered_game_controller_state.game_state = "Set"
+    state.ball = {
+      position = { 0.0, 0.0 },
+      velocity = { 0.0, 0.0 },
+    }
+  end
+
+  if state.cycle_count == 1700 then
+    state.filtered_game_controller_state.game_state = {
+      Playing = {
+        ball_is_free = true,
+        kick_off = true
+      },
+    }
+  end
+
+  if state.cycle_count == 3000 then
+    -- robot 3 starts hallucinating balls
+    set_perception_noise(3, {
+      false_positive_ball_probability = 0.01,
+    })
+  end
+
+  if state.cycle_count == 6000 then
+    state.finished = true
+  end
+end
@@ -0,0 +1,55 @@
+function spawn_robot(number)
+    table.insert(state.robots, create_robot(number))
+end
+
+spawn_robot(1)
+spawn_robot(7)
+
+local previous_speed = 0.0
+
+function on_goal()
+    state.finished = true
+end
+
+function on_cycle()
+    if state.cycle_count == 100 then
+        state.filtered_game_controller_state.game_state = {
+            Ready = {
+                kicking_team = "Hulks",
+            }
+        }
+    end
+
+    if state.cycle_count == 1600 then
+        state.filtered_game_controller_state.game_state = "Set"
+    end
+
+    if state.cycle_count == 1700 then
+        state.filtered_game_controller_state.game_state = {
+            Playing = {
+                ball_is_free = true,
+                kick_off = false
+            }
+        }
+        expect_goal_within(6000)
+        -- approaching from the side makes turn and side kicks the quickest way to the goal
+        set_robot_pose(7, { -3.2, 3.0 }, -math.pi / 2)
+        state.ball = {
+            position = { 0.0, 0.0 },
+            velocity = { 0.0, 0.0 },
+        }
+    end
+
+    if state.ball ~= nil then
+        local velocity = state.ball.velocity
+        local speed = math.sqrt(velocity[1] ^ 2 + velocity[2] ^ 2)
+        if speed > previous_speed + 0.1 then
+            expect(velocity[1] > 0.0, "ball was kicked towards the own goal")
+        end
+        previous_speed = speed
+    end
+
+    if state.cycle_count == 8000 then
+        state.finished = true
+    end
+end
@@ -25,11 +25,13 @@ fn main() -> Result<()> {
                     "control::referee_position_provider",
                     "control::game_controller_state_filter",
                     "control::kick_selector",
+                    "control::message_budget_manager",
                     "control::motion::look_around",
                     "control::referee_pose_detection_filter",
                     "control::role_assignment",
                     "control::rule_obstacle_composer",
                     "control::search_suggestor",
+                    "control::team_ball_filter",
                     "control::time_to_reach_kick_position",
                     "control::world_state_composer",
                 ],
@@ -41,6 +43,23 @@ fn main() -> Result<()> {
                 setup_nodes: vec!["spl_network::message_receiver"],
                 nodes: vec![],
             },
+            CyclerManifest {
+                name: "Vision",
+                kind: CyclerKind::Perception,
+                instances: vec!["Top", "Bottom"],
+                setup_nodes: vec!["vision::image_receiver"],
+                nodes: vec![
+                    "vision::ball_detection",
+                    "vision::camera_matrix_extractor",
+                    "vision::field_border_detection",
+                    "vision::field_color_detection",
+                    "vision::image_segmenter",
+                    "vision::limb_projector",
+                    "vision::line_detection",
+                    "vision::perspective_grid_candidates_provider",
+                    "vision::segment_filter",
+                ],
+            },
         ],
     };
     let root = "../../crates/";
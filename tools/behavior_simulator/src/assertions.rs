@@ -0,0 +1,340 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use linear_algebra::{distance, Point2};
+use types::{filtered_game_state::FilteredGameState, roles::Role};
+
+use crate::{
+    opponent::ROBOT_RADIUS,
+    robot::from_player_number,
+    state::{Event, State},
+};
+
+/// Distance between a robot and the ball at which the robot has reached the ball
+const BALL_REACHED_DISTANCE: f32 = 0.3;
+
+/// Condition that has to hold in every cycle of a scenario
+#[derive(Clone, Copy, Debug)]
+pub enum Invariant {
+    /// No robot leaves the field including the border strip
+    NoRobotLeavesField,
+    /// The keeper stays in the goal box of our goal while playing
+    KeeperInGoalBox,
+    /// There is at most one keeper and at most one striker while playing
+    UniqueRoles,
+}
+
+impl Invariant {
+    /// Returns a description of the violation if the invariant does not hold
+    fn check(&self, state: &State) -> Option<String> {
+        let is_playing = matches!(
+            state.filtered_game_controller_state.game_state,
+            FilteredGameState::Playing { .. }
+        );
+        let active_robots = state.robots.iter().filter(|(_, robot)| !robot.is_penalized);
+        match self {
+            Invariant::NoRobotLeavesField => active_robots
+                .filter_map(|(player_number, robot)| {
+                    let field_dimensions = &robot.parameters.field_dimensions;
+                    let position = robot
+                        .database
+                        .main_outputs
+                        .ground_to_field
+                        .expect("simulated robots should always have a known pose")
+                        * Point2::origin();
+                    let is_outside = position.x().abs()
+                        > field_dimensions.length / 2.0 + field_dimensions.border_strip_width
+                        || position.y().abs()
+                            > field_dimensions.width / 2.0 + field_dimensions.border_strip_width;
+                    is_outside.then(|| format!("robot {player_number:?} left the field"))
+                })
+                .next(),
+            Invariant::KeeperInGoalBox if is_playing => active_robots
+                .filter(|(_, robot)| robot.database.main_outputs.role == Role::Keeper)
+                .filter_map(|(player_number, robot)| {
+                    let field_dimensions = &robot.parameters.field_dimensions;
+                    let position = robot
+                        .database
+                        .main_outputs
+                        .ground_to_field
+                        .expect("simulated robots should always have a known pose")
+                        * Point2::origin();
+                    let is_inside_goal_box = position.x()
+                        < -field_dimensions.length / 2.0 + field_dimensions.goal_box_area_length
+                        && position.y().abs() < field_dimensions.goal_box_area_width / 2.0;
+                    (!is_inside_goal_box)
+                        .then(|| format!("keeper {player_number:?} left the goal box"))
+                })
+                .next(),
+            Invariant::UniqueRoles if is_playing => [Role::Keeper, Role::Striker]
+                .into_iter()
+                .filter_map(|role| {
+                    let count = state
+                        .robots
+                        .values()
+                        .filter(|robot| {
+                            !robot.is_penalized && robot.database.main_outputs.role == role
+                        })
+                        .count();
+                    (count > 1).then(|| format!("{count} robots have role {role:?}"))
+                })
+                .next(),
+            Invariant::KeeperInGoalBox | Invariant::UniqueRoles => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Failure {
+    pub cycle: usize,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Metrics {
+    pub cycles: usize,
+    pub goals_scored: usize,
+    pub goals_conceded: usize,
+    pub first_goal_cycle: Option<usize>,
+    pub cycles_with_ball_in_own_half: usize,
+    /// Seconds from the start of playing until one of our robots reaches the ball
+    pub time_to_ball: Option<f32>,
+    /// Number of times two robots, including opponents, started to touch each other
+    pub collisions: usize,
+    /// Metrics recorded by the scenario script with `record_metric`
+    pub custom: BTreeMap<String, f64>,
+}
+
+struct MonitoredInvariant {
+    invariant: Invariant,
+    /// Number of consecutive cycles the invariant may be violated before it is reported
+    grace_cycles: usize,
+    consecutive_violations: usize,
+}
+
+struct GoalDeadline {
+    registered_cycle: usize,
+    deadline_cycle: usize,
+}
+
+/// Expectations registered by a scenario script and metrics collected while it runs
+#[derive(Default)]
+pub struct Assertions {
+    invariants: Vec<MonitoredInvariant>,
+    goal_deadlines: Vec<GoalDeadline>,
+    failures: Vec<Failure>,
+    metrics: Metrics,
+    playing_since: Option<Duration>,
+    touching_robots: HashSet<(usize, usize)>,
+}
+
+impl Assertions {
+    pub fn add_invariant(&mut self, invariant: Invariant, grace_cycles: usize) {
+        self.invariants.push(MonitoredInvariant {
+            invariant,
+            grace_cycles,
+            consecutive_violations: 0,
+        });
+    }
+
+    pub fn expect_goal_within(&mut self, current_cycle: usize, cycles: usize) {
+        self.goal_deadlines.push(GoalDeadline {
+            registered_cycle: current_cycle,
+            deadline_cycle: current_cycle + cycles,
+        });
+    }
+
+    pub fn expect(&mut self, cycle: usize, condition: bool, message: String) {
+        if !condition {
+            self.failures.push(Failure { cycle, message });
+        }
+    }
+
+    pub fn record_metric(&mut self, name: String, value: f64) {
+        self.metrics.custom.insert(name, value);
+    }
+
+    /// Checks all expectations against the state after a simulation cycle
+    pub fn check_cycle(&mut self, state: &State, events: &[Event]) {
+        let cycle = state.cycle_count;
+        self.metrics.cycles = cycle;
+        if state
+            .ball
+            .as_ref()
+            .is_some_and(|ball| ball.position.x() < 0.0)
+        {
+            self.metrics.cycles_with_ball_in_own_half += 1;
+        }
+        self.collect_contacts(state);
+
+        for event in events {
+            if let Event::Goal = event {
+                let is_scored_by_us = state
+                    .ball
+                    .as_ref()
+                    .is_some_and(|ball| ball.position.x() > 0.0);
+                if is_scored_by_us {
+                    self.metrics.goals_scored += 1;
+                    self.goal_deadlines.clear();
+                } else {
+                    self.metrics.goals_conceded += 1;
+                }
+                self.metrics.first_goal_cycle.get_or_insert(cycle);
+            }
+        }
+
+        let failures = &mut self.failures;
+        self.goal_deadlines.retain(|deadline| {
+            let is_missed = cycle > deadline.deadline_cycle;
+            if is_missed {
+                failures.push(Failure {
+                    cycle,
+                    message: format!(
+                        "expected a goal between cycles {} and {}",
+                        deadline.registered_cycle, deadline.deadline_cycle
+                    ),
+                });
+            }
+            !is_missed
+        });
+
+        for monitored in &mut self.invariants {
+            match monitored.invariant.check(state) {
+                Some(message) => {
+                    monitored.consecutive_violations += 1;
+                    // report every violation only once, even if it lasts for many cycles
+                    if monitored.consecutive_violations == monitored.grace_cycles + 1 {
+                        failures.push(Failure { cycle, message });
+                    }
+                }
+                None => monitored.consecutive_violations = 0,
+            }
+        }
+    }
+
+    fn collect_contacts(&mut self, state: &State) {
+        let is_playing = matches!(
+            state.filtered_game_controller_state.game_state,
+            FilteredGameState::Playing { .. }
+        );
+        let playing_since = match (is_playing, self.playing_since) {
+            (true, Some(playing_since)) => playing_since,
+            (true, None) => *self.playing_since.insert(state.time_elapsed),
+            (false, _) => return,
+        };
+
+        // own robots are identified by their player number, opponents by their index after them
+        let robots: Vec<_> = state
+            .robots
+            .iter()
+            .filter(|(_, robot)| !robot.is_penalized)
+            .map(|(player_number, robot)| {
+                let ground_to_field = robot
+                    .database
+                    .main_outputs
+                    .ground_to_field
+                    .expect("simulated robots should always have a known pose");
+                (
+                    from_player_number(*player_number),
+                    ground_to_field * Point2::origin(),
+                )
+            })
+            .collect();
+        let opponents = state
+            .opponents
+            .iter()
+            .enumerate()
+            .map(|(index, opponent)| (8 + index, opponent.position));
+
+        if self.metrics.time_to_ball.is_none() {
+            let is_ball_reached = state.ball.as_ref().is_some_and(|ball| {
+                robots
+                    .iter()
+                    .any(|(_, position)| distance(*position, ball.position) < BALL_REACHED_DISTANCE)
+            });
+            if is_ball_reached {
+                self.metrics.time_to_ball =
+                    Some((state.time_elapsed - playing_since).as_secs_f32());
+            }
+        }
+
+        let robots: Vec<_> = robots.into_iter().chain(opponents).collect();
+        let mut touching_robots = HashSet::new();
+        for (index, (first, first_position)) in robots.iter().enumerate() {
+            for (second, second_position) in &robots[index + 1..] {
+                // separated robots end up exactly two radii apart, they still touch each other
+                if distance(*first_position, *second_position) < 2.0 * ROBOT_RADIUS + 0.01 {
+                    touching_robots.insert((*first.min(second), *first.max(second)));
+                }
+            }
+        }
+        self.metrics.collisions += touching_robots.difference(&self.touching_robots).count();
+        self.touching_robots = touching_robots;
+    }
+
+    /// Reports remaining expectations as failed and returns all failures and the metrics
+    pub fn finish(&mut self) -> (Vec<Failure>, Metrics) {
+        let cycle = self.metrics.cycles;
+        for deadline in self.goal_deadlines.drain(..) {
+            self.failures.push(Failure {
+                cycle,
+                message: format!(
+                    "expected a goal between cycles {} and {}, but the scenario ended",
+                    deadline.registered_cycle, deadline.deadline_cycle
+                ),
+            });
+        }
+        (self.failures.clone(), self.metrics.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::point;
+
+    use crate::state::Ball;
+
+    use super::*;
+
+    #[test]
+    fn missed_goal_deadline_fails_once() {
+        let mut assertions = Assertions::default();
+        let mut state = State::default();
+        assertions.expect_goal_within(0, 10);
+
+        for cycle in 0..20 {
+            state.cycle_count = cycle;
+            assertions.check_cycle(&state, &[Event::Cycle]);
+        }
+        let (failures, _metrics) = assertions.finish();
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].cycle, 11);
+    }
+
+    #[test]
+    fn goals_satisfy_deadlines_and_are_counted() {
+        let mut assertions = Assertions::default();
+        let mut state = State {
+            ball: Some(Ball::default()),
+            ..Default::default()
+        };
+        assertions.expect_goal_within(0, 10);
+
+        state.cycle_count = 5;
+        state.ball.as_mut().unwrap().position = point![4.6, 0.0];
+        assertions.check_cycle(&state, &[Event::Cycle, Event::Goal]);
+        state.cycle_count = 30;
+        assertions.check_cycle(&state, &[Event::Cycle]);
+        let (failures, metrics) = assertions.finish();
+
+        assert!(failures.is_empty());
+        assert_eq!(metrics.goals_scored, 1);
+        assert_eq!(metrics.goals_conceded, 0);
+        assert_eq!(metrics.first_goal_cycle, Some(5));
+    }
+}
@@ -6,10 +6,12 @@ use control::{
     ball_state_composer::{self, BallStateComposer},
     behavior::node::{self, Behavior},
     kick_selector::{self, KickSelector},
+    message_budget_manager::{self, MessageBudgetManager},
     motion::look_around::LookAround,
     role_assignment::{self, RoleAssignment},
     rule_obstacle_composer::RuleObstacleComposer,
     search_suggestor::SearchSuggestor,
+    team_ball_filter::{self, TeamBallFilter},
     time_to_reach_kick_position::{self, TimeToReachKickPosition},
     world_state_composer::{self, WorldStateComposer},
 };
@@ -43,8 +45,10 @@ pub struct BehaviorCycler {
     behavior: Behavior,
     kick_selector: KickSelector,
     look_around: LookAround,
+    message_budget_manager: MessageBudgetManager,
     role_assignment: RoleAssignment,
     rule_obstacle_composer: RuleObstacleComposer,
+    team_ball_filter: TeamBallFilter,
     world_state_composer: WorldStateComposer,
     time_to_reach_kick_position: TimeToReachKickPosition,
 }
@@ -79,12 +83,17 @@ impl BehaviorCycler {
             control::motion::look_around::CreationContext::new(),
         )
         .wrap_err("failed to create node `LookAround`")?;
+        let message_budget_manager =
+            MessageBudgetManager::new(message_budget_manager::CreationContext::new())
+                .wrap_err("failed to create node `MessageBudgetManager`")?;
         let role_assignment = RoleAssignment::new(role_assignment::CreationContext::new())
             .wrap_err("failed to create node `RoleAssignment`")?;
         let rule_obstacle_composer = control::rule_obstacle_composer::RuleObstacleComposer::new(
             control::rule_obstacle_composer::CreationContext {},
         )
         .wrap_err("failed to create node `RuleObstacleComposer`")?;
+        let team_ball_filter = TeamBallFilter::new(team_ball_filter::CreationContext::new())
+            .wrap_err("failed to create node `TeamBallFilter`")?;
         let world_state_composer =
             WorldStateComposer::new(world_state_composer::CreationContext::new())
                 .wrap_err("failed to create node `WorldStateComposer`")?;
@@ -99,8 +108,10 @@ impl BehaviorCycler {
             behavior,
             kick_selector,
             look_around,
+            message_budget_manager,
             role_assignment,
             rule_obstacle_composer,
+            team_ball_filter,
             world_state_composer,
         })
     }
@@ -155,11 +166,79 @@ impl BehaviorCycler {
         } else {
             own_database.main_outputs.rule_obstacles = Default::default();
         }
+        {
+            let main_outputs = self
+                .team_ball_filter
+                .cycle(team_ball_filter::CycleContext::new(
+                    AdditionalOutput::new(
+                        false,
+                        &mut own_database
+                            .additional_outputs
+                            .team_ball_filter
+                            .team_ball_distribution,
+                    ),
+                    AdditionalOutput::new(
+                        false,
+                        &mut own_database
+                            .additional_outputs
+                            .team_ball_filter
+                            .rejected_balls,
+                    ),
+                    own_database.main_outputs.ball_position.as_ref(),
+                    own_database
+                        .main_outputs
+                        .ball_position_distribution
+                        .as_ref(),
+                    &own_database.main_outputs.cycle_time,
+                    own_database.main_outputs.ground_to_field.as_ref(),
+                    PerceptionInput {
+                        persistent: incoming_messages.clone(),
+                        temporary: Default::default(),
+                    },
+                    &parameters.team_ball_filter.default_variance,
+                    &parameters.team_ball_filter.maximum_ball_age,
+                    &parameters.team_ball_filter.outlier_threshold,
+                    &parameters.team_ball_filter.variance_increase_per_second,
+                    &parameters.player_number,
+                ))
+                .wrap_err("failed to execute cycle of node `TeamBallFilter`")?;
+            own_database.main_outputs.team_ball = main_outputs.team_ball.value;
+        }
+        {
+            let main_outputs = self
+                .message_budget_manager
+                .cycle(message_budget_manager::CycleContext::new(
+                    &own_database.main_outputs.cycle_time,
+                    own_database
+                        .main_outputs
+                        .filtered_game_controller_state
+                        .as_ref(),
+                    &parameters.message_budget_manager.half_duration,
+                    &parameters
+                        .message_budget_manager
+                        .minimum_low_priority_interval,
+                    &parameters
+                        .message_budget_manager
+                        .normal_priority_interval_factor,
+                    &parameters.message_budget_manager.rate_estimation_window,
+                    &parameters.spl_network,
+                ))
+                .wrap_err("failed to execute cycle of node `MessageBudgetManager`")?;
+            own_database.main_outputs.message_budget = main_outputs.message_budget.value;
+        }
         {
             let main_outputs = self
                 .role_assignment
                 .cycle(role_assignment::CycleContext::new(
+                    AdditionalOutput::new(
+                        false,
+                        &mut own_database.additional_outputs.role_assignment.assignment,
+                    ),
                     own_database.main_outputs.ball_position.as_ref(),
+                    own_database
+                        .main_outputs
+                        .ball_position_distribution
+                        .as_ref(),
                     &own_database.main_outputs.fall_state,
                     own_database
                         .main_outputs
@@ -168,25 +247,28 @@ impl BehaviorCycler {
                     &own_database.main_outputs.primary_state,
                     own_database.main_outputs.ground_to_field.as_ref(),
                     &own_database.main_outputs.cycle_time,
+                    own_database.main_outputs.team_ball.as_ref(),
                     PerceptionInput {
                         persistent: incoming_messages,
                         temporary: Default::default(),
                     },
                     None,
+                    &own_database.main_outputs.message_budget,
                     &mut cycler_state.time_to_reach_kick_position,
                     &parameters.field_dimensions,
                     parameters.role_assignment.forced_role.as_ref(),
                     &parameters
                         .role_assignment
                         .keeper_replacementkeeper_switch_time,
+                    &parameters.role_assignment.auction,
                     &parameters.localization.initial_poses,
                     &parameters.behavior.optional_roles,
+                    &parameters.behavior.path_planning.line_walking_speed,
                     &parameters.player_number,
                     &parameters.spl_network,
                     &self.hardware_interface,
                 ))
                 .wrap_err("failed to execute cycle of node `RoleAssignment`")?;
-            own_database.main_outputs.team_ball = main_outputs.team_ball.value;
             own_database.main_outputs.network_robot_obstacles =
                 main_outputs.network_robot_obstacles.value;
             own_database.main_outputs.role = main_outputs.role.value;
@@ -281,6 +363,7 @@ impl BehaviorCycler {
                         .filtered_game_controller_state
                         .as_ref(),
                     own_database.main_outputs.ground_to_field.as_ref(),
+                    own_database.main_outputs.localization_confidence.as_ref(),
                     own_database.main_outputs.suggested_search_position.as_ref(),
                     own_database.main_outputs.kick_decisions.as_ref(),
                     own_database.main_outputs.instant_kick_decisions.as_ref(),
@@ -309,6 +392,7 @@ impl BehaviorCycler {
                         &mut own_database.additional_outputs.dribble_path_obstacles,
                     ),
                     AdditionalOutput::new(true, &mut own_database.additional_outputs.active_action),
+                    own_database.main_outputs.calibration_command.as_ref(),
                     own_database.main_outputs.expected_referee_position.as_ref(),
                     &true,
                     &own_database.main_outputs.world_state,
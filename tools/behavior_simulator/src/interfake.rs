@@ -1,11 +1,15 @@
 use std::{
     mem::take,
     sync::{Arc, Mutex},
+    time::SystemTime,
 };
 
 use color_eyre::Result;
-use hardware::{NetworkInterface, RecordingInterface};
-use types::messages::{IncomingMessage, OutgoingMessage};
+use hardware::{NetworkInterface, PathsInterface, RecordingInterface};
+use types::{
+    hardware::Paths,
+    messages::{IncomingMessage, OutgoingMessage},
+};
 
 #[derive(Default)]
 pub struct Interfake {
@@ -23,12 +27,29 @@ impl NetworkInterface for Interfake {
     }
 }
 
+impl PathsInterface for Interfake {
+    fn get_paths(&self) -> Paths {
+        Paths {
+            motions: "etc/motions".into(),
+            neural_networks: "etc/neural_networks".into(),
+            parameters: "etc/parameters".into(),
+            sounds: "etc/sounds".into(),
+        }
+    }
+}
+
 impl RecordingInterface for Interfake {
     fn should_record(&self) -> bool {
         false
     }
 
     fn set_whether_to_record(&self, _enable: bool) {}
+
+    fn trigger_recording_event(&self) {}
+
+    fn latest_recording_event(&self) -> Option<SystemTime> {
+        None
+    }
 }
 
 impl Interfake {
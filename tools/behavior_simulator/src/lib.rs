@@ -1,11 +1,18 @@
 use hardware::{NetworkInterface, RecordingInterface, TimeInterface};
 
+pub mod assertions;
 pub mod cycler;
 pub mod interfake;
+pub mod noise;
+pub mod opponent;
+pub mod renderer;
+pub mod report;
 pub mod robot;
 pub mod server;
 pub mod simulator;
 pub mod state;
+pub mod sweep;
+pub mod vision_cycler;
 
 include!(concat!(env!("OUT_DIR"), "/generated_code.rs"));
 
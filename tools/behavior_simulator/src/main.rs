@@ -1,23 +1,45 @@
-use std::{io::stdout, path::PathBuf, time::Instant};
+use std::{
+    fs::File,
+    io::{stdout, BufWriter, Write},
+    ops::Range,
+    path::PathBuf,
+    thread::available_parallelism,
+};
 
 use chrono::Local;
 use clap::Parser;
-use color_eyre::{eyre::Context, install, Result};
+use color_eyre::{
+    eyre::{bail, Context},
+    install, Result,
+};
 use fern::{Dispatch, InitError};
 use log::LevelFilter;
+use serde_json::Value;
 use tokio_util::sync::CancellationToken;
 
-use behavior_simulator::{server, simulator::Simulator};
+use behavior_simulator::{
+    report::{run_scenario, write_report, ReportFormat},
+    server,
+    sweep::{generate_samples, parse_grid, parse_range, run_samples, write_csv, write_summary},
+};
 
 #[derive(Parser)]
 enum Arguments {
     Run(RunArguments),
     Serve(ServeArguments),
+    /// Run a scenario for many parameter combinations and seeds and compare the outcomes
+    Sweep(SweepArguments),
 }
 
 #[derive(Parser)]
 struct RunArguments {
-    scenario_file: PathBuf,
+    #[arg(required = true)]
+    scenario_files: Vec<PathBuf>,
+    /// Write the assertion results and metrics of all scenarios to this file
+    #[arg(long)]
+    report: Option<PathBuf>,
+    #[arg(long, value_enum, default_value_t = ReportFormat::Json)]
+    report_format: ReportFormat,
 }
 
 #[derive(Parser)]
@@ -27,6 +49,29 @@ struct ServeArguments {
     scenario_file: PathBuf,
 }
 
+#[derive(Parser)]
+struct SweepArguments {
+    scenario_file: PathBuf,
+    /// Parameter values to combine, e.g. `behavior.dribbling.hybrid_align_distance=[0.3,0.5]`
+    #[arg(long, value_parser = parse_grid)]
+    grid: Vec<(String, Vec<Value>)>,
+    /// Parameter ranges to sample uniformly, e.g. `behavior.role_positions.keeper_x_offset=0.0..0.5`
+    #[arg(long, value_parser = parse_range)]
+    random: Vec<(String, Range<f64>)>,
+    /// Number of random samples per grid point
+    #[arg(long, default_value_t = 10)]
+    samples: usize,
+    /// Seeds every parameter combination is simulated with
+    #[arg(long, value_delimiter = ',', default_value = "0")]
+    seeds: Vec<u64>,
+    /// Number of simulations to run in parallel (defaults to the number of CPUs)
+    #[arg(long)]
+    jobs: Option<usize>,
+    /// Write the metrics of every simulation to this CSV file
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
 fn setup_logger(is_verbose: bool) -> Result<(), InitError> {
     Dispatch::new()
         .format(|out, message, record| {
@@ -56,18 +101,39 @@ fn main() -> Result<()> {
     match arguments {
         Arguments::Run(arguments) => run(arguments),
         Arguments::Serve(arguments) => serve(arguments),
+        Arguments::Sweep(arguments) => sweep(arguments),
     }
 }
 
 fn run(arguments: RunArguments) -> Result<()> {
-    let mut simulator = Simulator::try_new()?;
-    simulator.execute_script(arguments.scenario_file)?;
+    let mut reports = Vec::new();
+    for scenario_file in &arguments.scenario_files {
+        let report = run_scenario(scenario_file)?;
+        let result = if report.passed() { "passed" } else { "FAILED" };
+        println!(
+            "{}: {result}, took {:.2} seconds",
+            report.name,
+            report.duration.as_secs_f32()
+        );
+        if let Some(error) = &report.error {
+            println!("  {error}");
+        }
+        for failure in &report.failures {
+            println!("  cycle {}: {}", failure.cycle, failure.message);
+        }
+        reports.push(report);
+    }
 
-    let start = Instant::now();
-    simulator.run().wrap_err("failed to run simulation")?;
-    let duration = Instant::now() - start;
-    println!("Took {:.2} seconds", duration.as_secs_f32());
+    if let Some(report_file) = arguments.report {
+        let file = File::create(&report_file)
+            .wrap_err_with(|| format!("failed to create {}", report_file.display()))?;
+        write_report(&mut BufWriter::new(file), &reports, arguments.report_format)?;
+    }
 
+    let number_of_failed_scenarios = reports.iter().filter(|report| !report.passed()).count();
+    if number_of_failed_scenarios > 0 {
+        bail!("{number_of_failed_scenarios} scenarios failed");
+    }
     Ok(())
 }
 
@@ -87,3 +153,29 @@ fn serve(arguments: ServeArguments) -> Result<()> {
         arguments.scenario_file,
     )
 }
+
+fn sweep(arguments: SweepArguments) -> Result<()> {
+    let samples = generate_samples(
+        &arguments.grid,
+        &arguments.random,
+        arguments.samples,
+        &arguments.seeds,
+    );
+    let number_of_jobs = arguments.jobs.unwrap_or_else(|| {
+        available_parallelism()
+            .map(|parallelism| parallelism.get())
+            .unwrap_or(1)
+    });
+    let results = run_samples(&arguments.scenario_file, samples, number_of_jobs)?;
+
+    write_summary(&mut stdout(), &results).wrap_err("failed to write summary")?;
+    if let Some(output) = arguments.output {
+        let file = File::create(&output)
+            .wrap_err_with(|| format!("failed to create {}", output.display()))?;
+        let mut writer = BufWriter::new(file);
+        write_csv(&mut writer, &results)
+            .and_then(|()| writer.flush())
+            .wrap_err("failed to write CSV")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+use coordinate_systems::{Field, Head};
+use linear_algebra::{point, vector, Isometry2, Point2, Vector2};
+
+/// Degradation of the perception of a simulated robot, all probabilities are per cycle
+///
+/// The default is a perfect robot that always knows its pose and sees every ball in its field of
+/// view.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PerceptionNoise {
+    /// Standard deviation of the translational pose error accumulated per meter walked
+    pub pose_drift_per_meter: f32,
+    /// Standard deviation of the rotational pose error (in radians) accumulated per meter walked
+    pub orientation_drift_per_meter: f32,
+    /// Probability of missing a visible ball, independent of where it is seen
+    pub ball_dropout_probability: f32,
+    /// Additional dropout probability per meter distance to the ball
+    pub ball_dropout_probability_per_meter: f32,
+    /// Additional dropout probability for balls at the border of the field of view, decreasing
+    /// linearly towards its center
+    pub ball_dropout_probability_at_image_border: f32,
+    /// Standard deviation of detected ball positions per meter distance to the ball
+    pub ball_position_noise_per_meter: f32,
+    /// Probability of detecting a ball at a random position inside the field of view
+    pub false_positive_ball_probability: f32,
+    /// Probability that a message of a teammate is never received
+    pub message_loss_probability: f32,
+    /// Messages are received after a uniformly distributed delay of up to this many seconds
+    pub maximum_message_delay: f32,
+}
+
+impl PerceptionNoise {
+    /// Pose error accumulated while walking the given distance
+    pub fn drift(
+        &self,
+        random_number_generator: &mut impl Rng,
+        distance: f32,
+    ) -> Isometry2<Field, Field> {
+        let translation = vector![
+            random_number_generator.sample::<f32, _>(StandardNormal),
+            random_number_generator.sample::<f32, _>(StandardNormal)
+        ] * self.pose_drift_per_meter
+            * distance;
+        let angle = random_number_generator.sample::<f32, _>(StandardNormal)
+            * self.orientation_drift_per_meter
+            * distance;
+        Isometry2::from_parts(translation, angle)
+    }
+
+    pub fn is_ball_dropped(
+        &self,
+        random_number_generator: &mut impl Rng,
+        ball_in_head: Point2<Head>,
+        field_of_view: f32,
+    ) -> bool {
+        let distance = ball_in_head.coords().norm();
+        let angle = ball_in_head.coords().angle(Vector2::x_axis());
+        let dropout_probability = self.ball_dropout_probability
+            + self.ball_dropout_probability_per_meter * distance
+            + self.ball_dropout_probability_at_image_border * (angle.abs() / (field_of_view / 2.0));
+        random_number_generator.gen::<f32>() < dropout_probability
+    }
+
+    /// Error of a ball detection at the given distance
+    pub fn ball_detection_error(
+        &self,
+        random_number_generator: &mut impl Rng,
+        distance: f32,
+    ) -> Vector2<Field> {
+        vector![
+            random_number_generator.sample::<f32, _>(StandardNormal),
+            random_number_generator.sample::<f32, _>(StandardNormal)
+        ] * self.ball_position_noise_per_meter
+            * distance
+    }
+
+    pub fn false_positive_ball(
+        &self,
+        random_number_generator: &mut impl Rng,
+        field_of_view: f32,
+        range: f32,
+    ) -> Option<Point2<Head>> {
+        if random_number_generator.gen::<f32>() >= self.false_positive_ball_probability {
+            return None;
+        }
+        let angle = random_number_generator.gen_range(-field_of_view / 2.0..=field_of_view / 2.0);
+        let distance = random_number_generator.gen_range(0.0..=range);
+        Some(point![angle.cos() * distance, angle.sin() * distance])
+    }
+
+    /// Delay after which a message is received, `None` if it is lost
+    pub fn message_delay(&self, random_number_generator: &mut impl Rng) -> Option<Duration> {
+        if random_number_generator.gen::<f32>() < self.message_loss_probability {
+            return None;
+        }
+        let delay = random_number_generator.gen_range(0.0..=self.maximum_message_delay.max(0.0));
+        Some(Duration::from_secs_f32(delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn default_noise_does_not_degrade_perception() {
+        let noise = PerceptionNoise::default();
+        let mut random_number_generator = StdRng::seed_from_u64(0);
+
+        for _ in 0..100 {
+            let drift = noise.drift(&mut random_number_generator, 1.0);
+            assert_eq!(drift.translation(), Point2::origin());
+            assert!(!noise.is_ball_dropped(&mut random_number_generator, point![2.0, 0.5], 1.0));
+            assert!(noise
+                .false_positive_ball(&mut random_number_generator, 1.0, 3.0)
+                .is_none());
+            assert_eq!(
+                noise.message_delay(&mut random_number_generator),
+                Some(Duration::ZERO)
+            );
+        }
+    }
+
+    #[test]
+    fn distant_balls_are_dropped_more_often() {
+        let noise = PerceptionNoise {
+            ball_dropout_probability_per_meter: 0.2,
+            ..Default::default()
+        };
+        let mut random_number_generator = StdRng::seed_from_u64(0);
+
+        let number_of_dropouts = |distance: f32, random_number_generator: &mut StdRng| {
+            (0..1000)
+                .filter(|_| {
+                    noise.is_ball_dropped(random_number_generator, point![distance, 0.0], 1.0)
+                })
+                .count()
+        };
+
+        let near = number_of_dropouts(0.5, &mut random_number_generator);
+        let far = number_of_dropouts(3.0, &mut random_number_generator);
+        assert!(near < 200, "near: {near}");
+        assert!(far > 400, "far: {far}");
+    }
+}
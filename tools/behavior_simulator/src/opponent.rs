@@ -0,0 +1,160 @@
+use std::{f32::consts::FRAC_PI_2, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use coordinate_systems::Field;
+use linear_algebra::{distance, point, Orientation2, Point2, Vector2};
+use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
+
+use crate::state::Ball;
+
+pub const ROBOT_RADIUS: f32 = 0.2;
+const BALL_RADIUS: f32 = 0.05;
+const WALK_SPEED: f32 = 0.25;
+const TURN_SPEED: f32 = FRAC_PI_2;
+const KICK_DISTANCE: f32 = 0.25;
+const KICK_SPEED: f32 = 2.0;
+/// Opponents only kick balls that are nearly at rest, this prevents kicking every cycle
+const MAXIMUM_KICKABLE_BALL_SPEED: f32 = 0.5;
+const BLOCK_DISTANCE: f32 = 1.0;
+/// Center of the goal we defend, i.e. the goal the opponents attack
+const OWN_GOAL: Point2<Field> = point![-4.5, 0.0];
+const OPPONENT_GOAL: Point2<Field> = point![4.5, 0.0];
+
+#[derive(Clone, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect)]
+pub enum OpponentBehavior {
+    /// Stands still, e.g. to block a path
+    Stand,
+    /// Walks to a target set by the scenario script
+    WalkTo { target: Point2<Field> },
+    /// Walks to the ball and kicks it towards our goal
+    ChaseBall,
+    /// Stays between the ball and the goal it defends
+    BlockBall,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PathSerialize, PathDeserialize, PathIntrospect)]
+pub struct Opponent {
+    pub position: Point2<Field>,
+    pub orientation: f32,
+    pub behavior: OpponentBehavior,
+}
+
+impl Opponent {
+    pub fn cycle(&mut self, ball: Option<&mut Ball>, time_step: Duration) {
+        let target = match (&self.behavior, ball.as_deref()) {
+            (OpponentBehavior::Stand, _) => None,
+            (OpponentBehavior::WalkTo { target }, _) => Some(*target),
+            (OpponentBehavior::ChaseBall, Some(ball)) => {
+                // approach from the side facing away from our goal to push the ball towards it
+                let direction_to_goal = (OWN_GOAL - ball.position).normalize();
+                Some(ball.position - direction_to_goal * KICK_DISTANCE * 0.8)
+            }
+            (OpponentBehavior::BlockBall, Some(ball)) => {
+                let direction_to_goal = (OPPONENT_GOAL - ball.position).normalize();
+                Some(ball.position + direction_to_goal * BLOCK_DISTANCE)
+            }
+            (_, None) => None,
+        };
+        if let Some(target) = target {
+            self.walk_to(target, time_step);
+        }
+
+        if let (OpponentBehavior::ChaseBall, Some(ball)) = (&self.behavior, ball) {
+            let is_ball_kickable = distance(self.position, ball.position) < KICK_DISTANCE
+                && ball.velocity.norm() < MAXIMUM_KICKABLE_BALL_SPEED;
+            if is_ball_kickable {
+                ball.velocity = (OWN_GOAL - ball.position).normalize() * KICK_SPEED;
+            }
+        }
+    }
+
+    fn walk_to(&mut self, target: Point2<Field>, time_step: Duration) {
+        let step = (target - self.position).cap_magnitude(WALK_SPEED * time_step.as_secs_f32());
+        if step.norm_squared() < f32::EPSILON {
+            return;
+        }
+        self.position += step;
+        let orientation = Orientation2::new(self.orientation);
+        let turn = orientation
+            .rotation_to(Orientation2::from_vector(step))
+            .angle();
+        let maximum_turn = TURN_SPEED * time_step.as_secs_f32();
+        self.orientation += turn.clamp(-maximum_turn, maximum_turn);
+    }
+}
+
+/// Moves two overlapping robots apart, each by half of the overlap
+pub fn separate(first: &mut Point2<Field>, second: &mut Point2<Field>) {
+    let offset = *second - *first;
+    let overlap = 2.0 * ROBOT_RADIUS - offset.norm();
+    if overlap <= 0.0 {
+        return;
+    }
+    let direction = offset
+        .try_normalize(f32::EPSILON)
+        .unwrap_or(Vector2::x_axis());
+    *first -= direction * overlap / 2.0;
+    *second += direction * overlap / 2.0;
+}
+
+/// Pushes the ball out of a robot and reflects the part of its velocity towards the robot
+pub fn collide_ball(ball: &mut Ball, robot: Point2<Field>) {
+    let offset = ball.position - robot;
+    let minimum_distance = ROBOT_RADIUS + BALL_RADIUS;
+    if offset.norm() >= minimum_distance {
+        return;
+    }
+    let normal = offset
+        .try_normalize(f32::EPSILON)
+        .unwrap_or(Vector2::x_axis());
+    ball.position = robot + normal * minimum_distance;
+    let velocity_towards_robot = ball.velocity.dot(normal);
+    if velocity_towards_robot < 0.0 {
+        // robots are soft, only half of the velocity is reflected
+        ball.velocity -= normal * velocity_towards_robot * 1.5;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use linear_algebra::vector;
+
+    use super::*;
+
+    #[test]
+    fn chasing_opponent_kicks_ball_towards_our_goal() {
+        let mut opponent = Opponent {
+            position: point![1.0, 0.0],
+            orientation: 0.0,
+            behavior: OpponentBehavior::ChaseBall,
+        };
+        let mut ball = Ball {
+            position: point![0.0, 0.0],
+            velocity: vector![0.0, 0.0],
+        };
+
+        for _ in 0..1000 {
+            opponent.cycle(Some(&mut ball), Duration::from_millis(12));
+            if ball.velocity.norm() > 0.0 {
+                break;
+            }
+        }
+
+        assert!(ball.velocity.x() < -1.0);
+        assert!(distance(opponent.position, ball.position) < KICK_DISTANCE);
+    }
+
+    #[test]
+    fn ball_bounces_off_robots() {
+        let mut ball = Ball {
+            position: point![0.1, 0.0],
+            velocity: vector![-1.0, 0.0],
+        };
+
+        collide_ball(&mut ball, point![0.0, 0.0]);
+
+        assert!(distance(ball.position, point![0.0, 0.0]) >= ROBOT_RADIUS + BALL_RADIUS - 1e-6);
+        assert!(ball.velocity.x() > 0.0);
+    }
+}
@@ -0,0 +1,356 @@
+use std::f32::consts::TAU;
+
+use control::camera_matrix_calculator::head_to_camera;
+use coordinate_systems::{Field, Ground, Pixel};
+use kinematics::forward::{head_to_neck, neck_to_robot};
+use linear_algebra::{distance, point, vector, Isometry2, Isometry3, Point2, Point3};
+use projection::{
+    camera_matrices::CameraMatrices, camera_matrix::CameraMatrix, distortion::Distortion,
+    Projection,
+};
+use types::{
+    color::YCbCr444,
+    field_dimensions::FieldDimensions,
+    field_marks::{field_marks_from_field_dimensions, FieldMark},
+    joints::head::HeadJoints,
+    parameters::CameraMatrixParameters,
+    robot_dimensions::RobotDimensions,
+    ycbcr422_image::YCbCr422Image,
+};
+
+const BACKGROUND: YCbCr444 = YCbCr444 {
+    y: 100,
+    cb: 128,
+    cr: 128,
+};
+const FIELD_GREEN: YCbCr444 = YCbCr444 {
+    y: 86,
+    cb: 101,
+    cr: 95,
+};
+const LINE_WHITE: YCbCr444 = YCbCr444 {
+    y: 220,
+    cb: 128,
+    cr: 128,
+};
+const BALL_PATCH_BLACK: YCbCr444 = YCbCr444 {
+    y: 30,
+    cb: 128,
+    cr: 128,
+};
+const ROBOT_GRAY: YCbCr444 = YCbCr444 {
+    y: 190,
+    cb: 126,
+    cr: 130,
+};
+
+const ROBOT_HEIGHT: f32 = 0.58;
+const ROBOT_WIDTH: f32 = 0.3;
+
+/// Camera matrices of a robot standing with stretched legs on flat ground
+pub fn camera_matrices(
+    head: &HeadJoints<f32>,
+    top_parameters: &CameraMatrixParameters,
+    bottom_parameters: &CameraMatrixParameters,
+) -> CameraMatrices {
+    let leg_length = -(RobotDimensions::LEFT_HIP_TO_LEFT_KNEE.z()
+        + RobotDimensions::LEFT_KNEE_TO_LEFT_ANKLE.z()
+        + RobotDimensions::LEFT_ANKLE_TO_LEFT_SOLE.z());
+    let ground_to_robot = Isometry3::from_translation(0.0, 0.0, -leg_length);
+    let robot_to_head = (neck_to_robot(head) * head_to_neck(head)).inverse();
+    let camera_matrix = |parameters: &CameraMatrixParameters, head_to_camera_translation| {
+        CameraMatrix::from_normalized_focal_and_center(
+            parameters.focal_lengths,
+            parameters.cc_optical_center,
+            vector![640.0, 480.0],
+            ground_to_robot,
+            robot_to_head,
+            head_to_camera(
+                parameters.extrinsic_rotations,
+                parameters.camera_pitch.to_radians(),
+                head_to_camera_translation,
+            ),
+        )
+        .with_distortion(Distortion::new(
+            parameters.radial_distortion,
+            parameters.tangential_distortion,
+        ))
+    };
+
+    CameraMatrices {
+        top: camera_matrix(top_parameters, RobotDimensions::HEAD_TO_TOP_CAMERA),
+        bottom: camera_matrix(bottom_parameters, RobotDimensions::HEAD_TO_BOTTOM_CAMERA),
+    }
+}
+
+/// Renders an approximate camera image of the simulated world
+///
+/// The image only contains what the vision pipeline looks for: green carpet, white field lines,
+/// a white ball with black patches and robots as gray boxes. Everything above the horizon or
+/// outside of the carpet is a uniform background.
+pub fn render_image(
+    camera_matrix: &CameraMatrix,
+    ground_to_field: Isometry2<Ground, Field>,
+    field_dimensions: &FieldDimensions,
+    ball: Option<Point2<Field>>,
+    robots: &[Point2<Field>],
+) -> YCbCr422Image {
+    let width = camera_matrix.image_size.x() as usize;
+    let height = camera_matrix.image_size.y() as usize;
+    let field_marks = field_marks_from_field_dimensions(field_dimensions);
+
+    // rays through the pixels are linear in the pixel coordinates, intersecting them with the
+    // ground by hand is a lot faster than projecting every pixel with the camera matrix
+    let camera_to_ground = camera_matrix.ground_to_camera.inverse();
+    let camera_position = camera_to_ground * Point3::origin();
+    let ray_at_origin = camera_to_ground * camera_matrix.intrinsics.bearing(Point2::origin());
+    let ray_step_x = camera_to_ground * vector![1.0 / camera_matrix.focal_length.x, 0.0, 0.0];
+    let ray_step_y = camera_to_ground * vector![0.0, 1.0 / camera_matrix.focal_length.y, 0.0];
+    let mut pixels: Vec<_> = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x as f32, y as f32)))
+        .map(|(x, y)| {
+            let ray = ray_at_origin + ray_step_x * x + ray_step_y * y;
+            if ray.z() >= 0.0 {
+                return BACKGROUND;
+            }
+            let ground = camera_position.xy() + ray.xy() * (-camera_position.z() / ray.z());
+            ground_color(ground_to_field * ground, field_dimensions, &field_marks)
+        })
+        .collect();
+
+    let field_to_ground = ground_to_field.inverse();
+    let mut shapes: Vec<_> = robots
+        .iter()
+        .filter_map(|robot| project_robot(camera_matrix, field_to_ground * *robot))
+        .chain(ball.and_then(|ball| {
+            project_ball(
+                camera_matrix,
+                field_to_ground * ball,
+                field_dimensions.ball_radius,
+            )
+        }))
+        .collect();
+    // painter's algorithm: far shapes first, so that near shapes occlude them
+    shapes.sort_by(|(depth, _), (other_depth, _)| other_depth.total_cmp(depth));
+    for (_depth, shape) in shapes {
+        shape.draw(&mut pixels, width, height);
+    }
+
+    let buffer = pixels
+        .chunks_exact(2)
+        .map(|pair| [pair[0], pair[1]].into())
+        .collect();
+    YCbCr422Image::from_ycbcr_buffer(width as u32 / 2, height as u32, buffer)
+}
+
+fn ground_color(
+    position: Point2<Field>,
+    field_dimensions: &FieldDimensions,
+    field_marks: &[FieldMark],
+) -> YCbCr444 {
+    let is_on_carpet = position.x().abs()
+        <= field_dimensions.length / 2.0 + field_dimensions.border_strip_width
+        && position.y().abs() <= field_dimensions.width / 2.0 + field_dimensions.border_strip_width;
+    if !is_on_carpet {
+        return BACKGROUND;
+    }
+
+    let half_line_width = field_dimensions.line_width / 2.0;
+    // every pixel is checked against every field mark, the bounding box rejects most of them
+    // before the more expensive exact check
+    let is_in_box = |minimum: Point2<Field>, maximum: Point2<Field>| {
+        (minimum.x() - half_line_width..=maximum.x() + half_line_width).contains(&position.x())
+            && (minimum.y() - half_line_width..=maximum.y() + half_line_width)
+                .contains(&position.y())
+    };
+    let is_on_field_mark = field_marks.iter().any(|field_mark| match *field_mark {
+        FieldMark::Line { line, .. } => {
+            is_in_box(
+                point![line.0.x().min(line.1.x()), line.0.y().min(line.1.y())],
+                point![line.0.x().max(line.1.x()), line.0.y().max(line.1.y())],
+            ) && line.squared_distance_to_segment(position) <= half_line_width.powi(2)
+        }
+        FieldMark::Circle { center, radius } => {
+            is_in_box(
+                center - vector![radius, radius],
+                center + vector![radius, radius],
+            ) && (distance(center, position) - radius).abs() <= half_line_width
+        }
+        FieldMark::Point { .. } => false,
+    });
+    if is_on_field_mark {
+        LINE_WHITE
+    } else {
+        FIELD_GREEN
+    }
+}
+
+enum Shape {
+    Ball {
+        center: Point2<Pixel>,
+        radius: f32,
+    },
+    Robot {
+        top_left: Point2<Pixel>,
+        bottom_right: Point2<Pixel>,
+    },
+}
+
+fn project_ball(
+    camera_matrix: &CameraMatrix,
+    position: Point2<Ground>,
+    radius: f32,
+) -> Option<(f32, Shape)> {
+    let center = camera_matrix
+        .ground_with_z_to_pixel(position, radius)
+        .ok()?;
+    let depth = (camera_matrix.ground_to_camera * point![position.x(), position.y(), radius]).z();
+    Some((
+        depth,
+        Shape::Ball {
+            center,
+            radius: radius * camera_matrix.focal_length.x / depth,
+        },
+    ))
+}
+
+fn project_robot(camera_matrix: &CameraMatrix, position: Point2<Ground>) -> Option<(f32, Shape)> {
+    let bottom = camera_matrix.ground_to_pixel(position).ok()?;
+    let top = camera_matrix
+        .ground_with_z_to_pixel(position, ROBOT_HEIGHT)
+        .ok()?;
+    let depth = (camera_matrix.ground_to_camera
+        * point![position.x(), position.y(), ROBOT_HEIGHT / 2.0])
+    .z();
+    let half_width = ROBOT_WIDTH / 2.0 * camera_matrix.focal_length.x / depth;
+    Some((
+        depth,
+        Shape::Robot {
+            top_left: point![bottom.x() - half_width, top.y()],
+            bottom_right: point![bottom.x() + half_width, bottom.y()],
+        },
+    ))
+}
+
+impl Shape {
+    fn draw(&self, pixels: &mut [YCbCr444], width: usize, height: usize) {
+        let (top_left, bottom_right) = match *self {
+            Shape::Ball { center, radius } => (
+                point![center.x() - radius, center.y() - radius],
+                point![center.x() + radius, center.y() + radius],
+            ),
+            Shape::Robot {
+                top_left,
+                bottom_right,
+            } => (top_left, bottom_right),
+        };
+        let columns =
+            top_left.x().max(0.0) as usize..(bottom_right.x().max(0.0) as usize).min(width);
+        let rows = top_left.y().max(0.0) as usize..(bottom_right.y().max(0.0) as usize).min(height);
+        for y in rows {
+            for x in columns.clone() {
+                if let Some(color) = self.color_at(point![x as f32, y as f32]) {
+                    pixels[y * width + x] = color;
+                }
+            }
+        }
+    }
+
+    fn color_at(&self, pixel: Point2<Pixel>) -> Option<YCbCr444> {
+        match *self {
+            Shape::Ball { center, radius } => {
+                let offset = (pixel - center) / radius;
+                if offset.norm() > 1.0 {
+                    return None;
+                }
+                // one patch in the center surrounded by five patches, like the pentagons of a
+                // real ball seen from the side
+                let is_on_patch = offset.norm() < 0.3
+                    || (0..5).any(|index| {
+                        let angle = index as f32 * TAU / 5.0;
+                        (offset - vector![angle.cos(), angle.sin()] * 0.75).norm() < 0.2
+                    });
+                Some(if is_on_patch {
+                    BALL_PATCH_BLACK
+                } else {
+                    LINE_WHITE
+                })
+            }
+            Shape::Robot { .. } => Some(ROBOT_GRAY),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use types::color::YCbCr422;
+
+    use super::*;
+
+    fn color_at(image: &YCbCr422Image, pixel: Point2<Pixel>) -> YCbCr444 {
+        image.at(pixel.x() as u32, pixel.y() as u32)
+    }
+
+    #[test]
+    fn rendered_image_contains_field_lines_and_ball() {
+        let field_dimensions = FieldDimensions {
+            ball_radius: 0.05,
+            length: 9.0,
+            width: 6.0,
+            line_width: 0.05,
+            penalty_marker_size: 0.1,
+            goal_box_area_length: 0.6,
+            goal_box_area_width: 2.2,
+            penalty_area_length: 1.65,
+            penalty_area_width: 4.0,
+            penalty_marker_distance: 1.3,
+            center_circle_diameter: 1.5,
+            border_strip_width: 0.7,
+            goal_inner_width: 1.5,
+            goal_post_diameter: 0.1,
+            goal_depth: 0.5,
+        };
+        let camera_parameters = CameraMatrixParameters {
+            camera_pitch: 1.2,
+            extrinsic_rotations: Default::default(),
+            focal_lengths: nalgebra::vector![0.95, 1.27],
+            cc_optical_center: nalgebra::point![0.5, 0.5],
+            radial_distortion: Default::default(),
+            tangential_distortion: Default::default(),
+        };
+        let camera_matrices = camera_matrices(
+            &HeadJoints {
+                yaw: 0.0,
+                pitch: 0.3,
+            },
+            &camera_parameters,
+            &camera_parameters,
+        );
+        let camera_matrix = &camera_matrices.top;
+        // robot stands in its own half looking at the center circle
+        let ground_to_field = Isometry2::from_parts(vector![-1.5, 0.0], 0.0);
+
+        let image = render_image(
+            camera_matrix,
+            ground_to_field,
+            &field_dimensions,
+            Some(point![0.5, 0.3]),
+            &[],
+        );
+
+        assert_eq!(image.width(), 640);
+        assert_eq!(image.height(), 480);
+        let halfway_line = camera_matrix.ground_to_pixel(point![1.5, -0.2]).unwrap();
+        assert_eq!(color_at(&image, halfway_line), LINE_WHITE);
+        let carpet = camera_matrix.ground_to_pixel(point![2.0, -0.4]).unwrap();
+        assert_eq!(color_at(&image, carpet), FIELD_GREEN);
+        let ball = camera_matrix
+            .ground_with_z_to_pixel(point![2.0, 0.3], field_dimensions.ball_radius)
+            .unwrap();
+        assert_eq!(color_at(&image, ball), BALL_PATCH_BLACK);
+        assert_eq!(
+            image.buffer()[0],
+            YCbCr422::new(BACKGROUND.y, BACKGROUND.cb, BACKGROUND.y, BACKGROUND.cr)
+        );
+    }
+}
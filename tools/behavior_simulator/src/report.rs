@@ -0,0 +1,200 @@
+use std::{
+    io::Write,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use color_eyre::{eyre::WrapErr, Result};
+use serde::Serialize;
+
+use crate::{
+    assertions::{Failure, Metrics},
+    simulator::Simulator,
+};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScenarioReport {
+    pub name: String,
+    pub duration: Duration,
+    /// Error that aborted the scenario, e.g. a failed `error(...)` call in the script
+    pub error: Option<String>,
+    pub failures: Vec<Failure>,
+    pub metrics: Metrics,
+}
+
+impl ScenarioReport {
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.failures.is_empty()
+    }
+}
+
+/// Runs a scenario to its end and collects the results of its assertions
+pub fn run_scenario(scenario_file: impl AsRef<Path>) -> Result<ScenarioReport> {
+    run_scenario_with_parameters(scenario_file, 0, Vec::new())
+}
+
+/// Runs a scenario with the given seed and overwrites the parameters of all its robots
+pub fn run_scenario_with_parameters(
+    scenario_file: impl AsRef<Path>,
+    seed: u64,
+    parameter_overrides: Vec<(String, serde_json::Value)>,
+) -> Result<ScenarioReport> {
+    let scenario_file = scenario_file.as_ref();
+    let mut simulator = Simulator::try_new()?;
+    simulator.record_frames = false;
+    simulator.set_seed(seed)?;
+    simulator.state.lock().parameter_overrides = parameter_overrides;
+
+    let start = Instant::now();
+    let result = simulator
+        .execute_script(scenario_file)
+        .and_then(|()| simulator.run().wrap_err("failed to run simulation"));
+    let duration = start.elapsed();
+
+    let (failures, metrics) = simulator.assertions.lock().finish();
+    Ok(ScenarioReport {
+        name: scenario_file
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        duration,
+        error: result.err().map(|error| format!("{error:?}")),
+        failures,
+        metrics,
+    })
+}
+
+pub fn write_report(
+    writer: &mut impl Write,
+    reports: &[ScenarioReport],
+    format: ReportFormat,
+) -> Result<()> {
+    match format {
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *writer, reports).wrap_err("failed to write JSON")?
+        }
+        ReportFormat::Junit => {
+            write_junit(writer, reports).wrap_err("failed to write JUnit XML")?
+        }
+    }
+    writeln!(writer).wrap_err("failed to write report")
+}
+
+fn write_junit(writer: &mut impl Write, reports: &[ScenarioReport]) -> std::io::Result<()> {
+    let number_of_failures = reports
+        .iter()
+        .filter(|report| report.error.is_none() && !report.failures.is_empty())
+        .count();
+    let number_of_errors = reports
+        .iter()
+        .filter(|report| report.error.is_some())
+        .count();
+    let duration: Duration = reports.iter().map(|report| report.duration).sum();
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<testsuite name="behavior_simulator" tests="{}" failures="{number_of_failures}" errors="{number_of_errors}" time="{:.3}">"#,
+        reports.len(),
+        duration.as_secs_f32(),
+    )?;
+    for report in reports {
+        writeln!(
+            writer,
+            r#"  <testcase classname="behavior_simulator" name="{}" time="{:.3}">"#,
+            escape(&report.name),
+            report.duration.as_secs_f32(),
+        )?;
+        if let Some(error) = &report.error {
+            writeln!(
+                writer,
+                r#"    <error message="scenario aborted">{}</error>"#,
+                escape(error)
+            )?;
+        }
+        for failure in &report.failures {
+            writeln!(
+                writer,
+                r#"    <failure message="{}">cycle {}</failure>"#,
+                escape(&failure.message),
+                failure.cycle,
+            )?;
+        }
+        // JUnit has no notion of metrics, they are attached as properties instead
+        writeln!(writer, "    <properties>")?;
+        let metrics = serde_json::to_value(&report.metrics)?;
+        for (name, value) in metrics.as_object().into_iter().flatten() {
+            match value.as_object() {
+                Some(custom_metrics) => {
+                    for (name, value) in custom_metrics {
+                        write_property(writer, name, value)?;
+                    }
+                }
+                None => write_property(writer, name, value)?,
+            }
+        }
+        writeln!(writer, "    </properties>")?;
+        writeln!(writer, "  </testcase>")?;
+    }
+    writeln!(writer, "</testsuite>")
+}
+
+fn write_property(
+    writer: &mut impl Write,
+    name: &str,
+    value: &serde_json::Value,
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        r#"      <property name="{}" value="{}"/>"#,
+        escape(name),
+        escape(&value.to_string()),
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn junit_report_contains_failures_and_metrics() {
+        let reports = [ScenarioReport {
+            name: "golden_goal".to_string(),
+            duration: Duration::from_secs(2),
+            error: None,
+            failures: vec![Failure {
+                cycle: 42,
+                message: "keeper \"One\" left the goal box".to_string(),
+            }],
+            metrics: Metrics {
+                goals_scored: 1,
+                ..Default::default()
+            },
+        }];
+
+        let mut report = Vec::new();
+        write_report(&mut report, &reports, ReportFormat::Junit).unwrap();
+        let report = String::from_utf8(report).unwrap();
+
+        assert!(report.contains(r#"tests="1" failures="1" errors="0""#));
+        assert!(report.contains(
+            r#"<failure message="keeper &quot;One&quot; left the goal box">cycle 42</failure>"#
+        ));
+        assert!(report.contains(r#"<property name="goals_scored" value="1"/>"#));
+    }
+}
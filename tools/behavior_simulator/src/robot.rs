@@ -7,8 +7,11 @@ use std::{
 
 use color_eyre::{eyre::WrapErr, Result};
 
+use rand::{rngs::StdRng, SeedableRng};
+
 use control::localization::generate_initial_pose;
-use linear_algebra::vector;
+use coordinate_systems::Field;
+use linear_algebra::{vector, Isometry2, Point2, Vector2};
 use parameters::directory::deserialize;
 use projection::camera_matrix::CameraMatrix;
 use spl_network_messages::PlayerNumber;
@@ -17,7 +20,9 @@ use types::messages::IncomingMessage;
 use crate::{
     cycler::{BehaviorCycler, Database},
     interfake::Interfake,
+    noise::PerceptionNoise,
     structs::{control::CyclerState, Parameters},
+    vision_cycler::Vision,
 };
 
 pub struct Robot {
@@ -29,6 +34,17 @@ pub struct Robot {
     pub is_penalized: bool,
     pub last_kick_time: Duration,
     pub ball_last_seen: Option<SystemTime>,
+    /// Only created once images are rendered, because loading the neural networks takes time
+    pub vision: Option<Vision>,
+    pub perception_noise: PerceptionNoise,
+    /// Seeded with the seed of the scenario and the player number to keep scenarios reproducible
+    pub random_number_generator: StdRng,
+    /// Transforms the true pose of the robot into the pose it believes to be at
+    pub pose_error: Isometry2<Field, Field>,
+    pub ball_detection_error: Vector2<Field>,
+    pub false_positive_ball: Option<Point2<Field>>,
+    /// Messages of teammates which are received once their time has come
+    pub pending_messages: Vec<(SystemTime, IncomingMessage)>,
 }
 
 impl Robot {
@@ -61,6 +77,7 @@ impl Robot {
             )
             .as_transform(),
         );
+        database.main_outputs.localization_confidence = Some(1.0);
         database.main_outputs.has_ground_contact = true;
 
         let cycler_state = Default::default();
@@ -74,6 +91,13 @@ impl Robot {
             is_penalized: false,
             last_kick_time: Duration::default(),
             ball_last_seen: None,
+            vision: None,
+            perception_noise: PerceptionNoise::default(),
+            random_number_generator: random_number_generator(0, player_number),
+            pose_error: Isometry2::identity(),
+            ball_detection_error: Vector2::zeros(),
+            false_positive_ball: None,
+            pending_messages: Vec::new(),
         })
     }
 
@@ -89,6 +113,10 @@ impl Robot {
         )
     }
 
+    pub fn seed_random_number_generator(&mut self, seed: u64) {
+        self.random_number_generator = random_number_generator(seed, self.parameters.player_number);
+    }
+
     pub fn field_of_view(&self) -> f32 {
         let image_size = vector![640.0, 480.0];
         let focal_lengths = self
@@ -103,6 +131,10 @@ impl Robot {
     }
 }
 
+fn random_number_generator(seed: u64, player_number: PlayerNumber) -> StdRng {
+    StdRng::seed_from_u64(seed.wrapping_mul(7) + from_player_number(player_number) as u64)
+}
+
 pub fn to_player_number(value: usize) -> Result<PlayerNumber, String> {
     let number = match value {
         1 => PlayerNumber::One,
@@ -6,6 +6,7 @@ use std::{
 
 use crate::{
     cycler::Database,
+    opponent::Opponent,
     robot::to_player_number,
     simulator::{Frame, Simulator},
     state::Ball,
@@ -30,6 +31,7 @@ struct MainOutputs {
     frame_count: usize,
     ball: Option<Ball>,
     databases: Players<Option<Database>>,
+    opponents: Vec<Opponent>,
 }
 
 #[derive(Clone, Default, Serialize, PathSerialize, PathIntrospect)]
@@ -70,6 +72,7 @@ async fn timeline_server(
             let frame = &frames[parameters.selected_frame];
             outputs.main_outputs.ball.clone_from(&frame.ball);
             outputs.main_outputs.databases = frame.robots.clone();
+            outputs.main_outputs.opponents.clone_from(&frame.opponents);
         }
         outputs_changed.notify_waiters();
 
@@ -94,6 +97,7 @@ pub fn run(
     let parameter_slots = 3; // 2 for communication writer + 1 reader for timeline_server
     let communication_server = communication::server::Runtime::<Parameters>::start(
         addresses,
+        None,
         "tools/behavior_simulator",
         "behavior_simulator".to_string(),
         "behavior_simulator".to_string(),
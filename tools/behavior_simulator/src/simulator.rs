@@ -12,7 +12,10 @@ use linear_algebra::{Isometry2, Point2, Vector2};
 use types::{obstacles::Obstacle, players::Players};
 
 use crate::{
+    assertions::{Assertions, Invariant},
     cycler::Database,
+    noise::PerceptionNoise,
+    opponent::Opponent,
     robot::to_player_number,
     robot::Robot,
     state::Ball,
@@ -24,11 +27,15 @@ const SERIALIZE_OPTIONS: SerializeOptions = SerializeOptions::new().serialize_no
 pub struct Frame {
     pub ball: Option<Ball>,
     pub robots: Players<Option<Database>>,
+    pub opponents: Vec<Opponent>,
 }
 
 pub struct Simulator {
     pub state: Arc<Mutex<State>>,
+    pub assertions: Arc<Mutex<Assertions>>,
     pub frames: Vec<Frame>,
+    /// Frames are only needed to inspect a scenario, skipping them saves memory in long runs
+    pub record_frames: bool,
     lua: Lua,
 }
 
@@ -55,14 +62,27 @@ impl Simulator {
         lua.globals()
             .set("error", error)
             .wrap_err("failed to insert create_robot")?;
+        let assertions = Arc::new(Mutex::new(Assertions::default()));
+        register_assertion_functions(&lua, &state, &assertions)?;
 
         Ok(Self {
             state,
+            assertions,
             lua,
             frames: Vec::new(),
+            record_frames: true,
         })
     }
 
+    /// Seeds the random numbers of the robots and of the scenario script
+    pub fn set_seed(&mut self, seed: u64) -> Result<()> {
+        self.state.lock().seed = seed;
+        self.lua
+            .load(format!("math.randomseed({seed})"))
+            .exec()
+            .wrap_err("failed to seed lua random numbers")
+    }
+
     pub fn execute_script(&mut self, file_name: impl AsRef<Path>) -> Result<()> {
         self.serialze_state()?;
 
@@ -87,14 +107,17 @@ impl Simulator {
             self.cycle()?;
 
             let state = self.state.lock();
-            let mut robots = Players::<Option<Database>>::default();
-            for (player_number, robot) in &state.robots {
-                robots[*player_number] = Some(robot.database.clone())
+            if self.record_frames {
+                let mut robots = Players::<Option<Database>>::default();
+                for (player_number, robot) in &state.robots {
+                    robots[*player_number] = Some(robot.database.clone())
+                }
+                self.frames.push(Frame {
+                    robots,
+                    ball: state.ball.clone(),
+                    opponents: state.opponents.clone(),
+                });
             }
-            self.frames.push(Frame {
-                robots,
-                ball: state.ball.clone(),
-            });
 
             if state.finished {
                 break;
@@ -107,7 +130,9 @@ impl Simulator {
     pub fn cycle(&mut self) -> Result<()> {
         let events = {
             let mut state = self.state.lock();
-            state.cycle(Duration::from_millis(12))?
+            let events = state.cycle(Duration::from_millis(12))?;
+            self.assertions.lock().check_cycle(&state, &events);
+            events
         };
 
         self.serialze_state()?;
@@ -224,6 +249,24 @@ impl Simulator {
                 })?,
             )?;
 
+            self.lua.globals().set(
+                "set_perception_noise",
+                scope.create_function(|lua, (player_number, noise): (usize, Value)| {
+                    let player_number =
+                        to_player_number(player_number).map_err(LuaError::external)?;
+                    let noise: PerceptionNoise = lua.from_value(noise)?;
+
+                    self.state
+                        .lock()
+                        .robots
+                        .get_mut(&player_number)
+                        .unwrap()
+                        .perception_noise = noise;
+
+                    Ok(())
+                })?,
+            )?;
+
             for event in events {
                 match event {
                     Event::Cycle => self.execute_event_callback("on_cycle")?,
@@ -273,3 +316,73 @@ impl Simulator {
             .wrap_err("failed to load lua state")
     }
 }
+
+fn register_assertion_functions(
+    lua: &Lua,
+    state: &Arc<Mutex<State>>,
+    assertions: &Arc<Mutex<Assertions>>,
+) -> Result<()> {
+    let globals = lua.globals();
+
+    for (name, invariant) in [
+        (
+            "expect_no_robot_leaves_field",
+            Invariant::NoRobotLeavesField,
+        ),
+        ("expect_keeper_in_goal_box", Invariant::KeeperInGoalBox),
+        ("expect_unique_roles", Invariant::UniqueRoles),
+    ] {
+        let assertions = assertions.clone();
+        let function = lua
+            .create_function(move |_, grace_cycles: Option<usize>| {
+                assertions
+                    .lock()
+                    .add_invariant(invariant, grace_cycles.unwrap_or_default());
+                Ok(())
+            })
+            .wrap_err_with(|| format!("failed to create function {name}"))?;
+        globals
+            .set(name, function)
+            .wrap_err_with(|| format!("failed to insert {name}"))?;
+    }
+
+    let expect_goal_within = {
+        let state = state.clone();
+        let assertions = assertions.clone();
+        lua.create_function(move |_, cycles: usize| {
+            let cycle = state.lock().cycle_count;
+            assertions.lock().expect_goal_within(cycle, cycles);
+            Ok(())
+        })
+        .wrap_err("failed to create function expect_goal_within")?
+    };
+    globals
+        .set("expect_goal_within", expect_goal_within)
+        .wrap_err("failed to insert expect_goal_within")?;
+
+    let expect = {
+        let state = state.clone();
+        let assertions = assertions.clone();
+        lua.create_function(move |_, (condition, message): (bool, String)| {
+            let cycle = state.lock().cycle_count;
+            assertions.lock().expect(cycle, condition, message);
+            Ok(())
+        })
+        .wrap_err("failed to create function expect")?
+    };
+    globals
+        .set("expect", expect)
+        .wrap_err("failed to insert expect")?;
+
+    let record_metric = {
+        let assertions = assertions.clone();
+        lua.create_function(move |_, (name, value): (String, f64)| {
+            assertions.lock().record_metric(name, value);
+            Ok(())
+        })
+        .wrap_err("failed to create function record_metric")?
+    };
+    globals
+        .set("record_metric", record_metric)
+        .wrap_err("failed to insert record_metric")
+}
@@ -5,20 +5,21 @@ use std::{
     time::{Duration, UNIX_EPOCH},
 };
 
-use color_eyre::Result;
+use color_eyre::{eyre::WrapErr, Result};
 use serde::{Deserialize, Serialize};
 
 use coordinate_systems::{Field, Head};
 use geometry::line_segment::LineSegment;
 use linear_algebra::{vector, Isometry2, Orientation2, Point2, Rotation2, Vector2};
 use path_serde::{PathDeserialize, PathIntrospect, PathSerialize};
-use spl_network_messages::{GamePhase, HulkMessage, PlayerNumber, Team};
+use spl_network_messages::{GamePhase, Half, PlayerNumber, Team};
 use types::{
     ball_position::BallPosition,
     filtered_game_controller_state::FilteredGameControllerState,
     filtered_game_state::FilteredGameState,
     messages::{IncomingMessage, OutgoingMessage},
     motion_command::{HeadMotion, KickVariant, MotionCommand, OrientationMode},
+    obstacles::Obstacle,
     planned_path::PathSegment,
     players::Players,
     primary_state::PrimaryState,
@@ -27,10 +28,18 @@ use types::{
 
 use crate::{
     cycler::Database,
+    noise::PerceptionNoise,
+    opponent::{collide_ball, separate, Opponent, ROBOT_RADIUS},
     robot::Robot,
     structs::{control::AdditionalOutputs, Parameters},
+    vision_cycler::Vision,
 };
 
+/// Cameras run at 30 Hz while the simulation cycles every 12 ms
+const VISION_CYCLE_INTERVAL: usize = 3;
+const OPPONENT_DETECTION_RANGE: f32 = 3.0;
+const BALL_DETECTION_RANGE: f32 = 3.0;
+
 pub enum Event {
     Cycle,
     Goal,
@@ -48,10 +57,18 @@ pub struct State {
     pub time_elapsed: Duration,
     pub cycle_count: usize,
     pub robots: HashMap<PlayerNumber, Robot>,
+    pub opponents: Vec<Opponent>,
     pub ball: Option<Ball>,
-    pub messages: Vec<(PlayerNumber, HulkMessage)>,
+    pub messages: Vec<(PlayerNumber, IncomingMessage)>,
     pub finished: bool,
     pub filtered_game_controller_state: FilteredGameControllerState,
+    /// Robots perceive the ball by running the vision cyclers on rendered camera images instead
+    /// of a field of view check
+    pub render_images: bool,
+    /// Seed of the random numbers of the robots, e.g. for their perception noise
+    pub seed: u64,
+    /// Parameters overwritten in all robots created by the scenario, e.g. during sweeps
+    pub parameter_overrides: Vec<(String, serde_json::Value)>,
 }
 
 impl State {
@@ -61,8 +78,11 @@ impl State {
         let mut events = vec![Event::Cycle];
 
         self.move_robots(time_step);
+        self.move_opponents(time_step);
+        self.separate_robots();
         self.cycle_robots(now)?;
         events.extend(self.move_ball(time_step));
+        self.run_game_clock(time_step);
 
         self.time_elapsed += time_step;
         self.cycle_count += 1;
@@ -70,6 +90,17 @@ impl State {
         Ok(events)
     }
 
+    fn run_game_clock(&mut self, time_step: Duration) {
+        if matches!(
+            self.filtered_game_controller_state.game_state,
+            FilteredGameState::Playing { .. }
+        ) {
+            let remaining_time_in_half =
+                &mut self.filtered_game_controller_state.remaining_time_in_half;
+            *remaining_time_in_half = remaining_time_in_half.saturating_sub(time_step);
+        }
+    }
+
     fn move_robots(&mut self, time_step: Duration) {
         for robot in self.robots.values_mut() {
             let ground_to_field = robot
@@ -116,6 +147,10 @@ impl State {
                                 FRAC_PI_4 * time_step.as_secs_f32(),
                             ),
                     );
+                    robot.pose_error = robot
+                        .perception_noise
+                        .drift(&mut robot.random_number_generator, step.norm())
+                        * robot.pose_error;
 
                     for obstacle in robot.database.main_outputs.obstacles.iter_mut() {
                         obstacle.position = ground_to_field.inverse()
@@ -141,10 +176,12 @@ impl State {
                         // TODO: Check if ball is even in range
                         // let kick_location = ground_to_field * ();
                         if (self.time_elapsed - robot.last_kick_time).as_secs_f32() > 1.0 {
+                            // as planned by the kick poses in `in_walk_kicks`: a left turn kick
+                            // sends the ball to the right, a left side kick to the left
                             let direction = match kick {
                                 KickVariant::Forward => vector![1.0, 0.0],
-                                KickVariant::Turn => vector![0.707, 0.707 * side],
-                                KickVariant::Side => vector![0.0, 1.0 * -side],
+                                KickVariant::Turn => vector![0.707, 0.707 * -side],
+                                KickVariant::Side => vector![0.0, 1.0 * side],
                             };
                             ball.velocity += *ground_to_field * direction * *strength * 2.5;
                             robot.last_kick_time = self.time_elapsed;
@@ -182,23 +219,90 @@ impl State {
         }
     }
 
+    fn move_opponents(&mut self, time_step: Duration) {
+        for opponent in &mut self.opponents {
+            opponent.cycle(self.ball.as_mut(), time_step);
+        }
+    }
+
+    /// Pushes overlapping opponents out of each other and out of our robots
+    fn separate_robots(&mut self) {
+        for index in 0..self.opponents.len() {
+            let (opponent, others) = self.opponents[index..]
+                .split_first_mut()
+                .expect("index should be within opponents");
+            for other in others {
+                separate(&mut opponent.position, &mut other.position);
+            }
+            for robot in self.robots.values_mut() {
+                let ground_to_field = robot
+                    .database
+                    .main_outputs
+                    .ground_to_field
+                    .as_mut()
+                    .expect("simulated robots should always have a known pose");
+                let previous_ground_to_field = *ground_to_field;
+                let mut robot_position = *ground_to_field * Point2::origin();
+                separate(&mut robot_position, &mut opponent.position);
+                *ground_to_field = Isometry2::from_parts(
+                    robot_position.coords(),
+                    ground_to_field.orientation().angle(),
+                );
+
+                for obstacle in robot.database.main_outputs.obstacles.iter_mut() {
+                    obstacle.position =
+                        ground_to_field.inverse() * previous_ground_to_field * obstacle.position;
+                }
+            }
+        }
+    }
+
     fn cycle_robots(&mut self, now: std::time::SystemTime) -> Result<()> {
         let incoming_messages = take(&mut self.messages);
+        let robot_positions: Vec<_> = self
+            .robots
+            .iter()
+            .map(|(player_number, robot)| {
+                let ground_to_field = robot
+                    .database
+                    .main_outputs
+                    .ground_to_field
+                    .expect("simulated robots should always have a known pose");
+                (*player_number, ground_to_field * Point2::origin())
+            })
+            .collect();
+        let opponent_positions: Vec<_> = self
+            .opponents
+            .iter()
+            .map(|opponent| opponent.position)
+            .collect();
+
+        for (sender, message) in &incoming_messages {
+            for (player_number, robot) in self.robots.iter_mut() {
+                if sender == player_number {
+                    continue;
+                }
+                if let Some(delay) = robot
+                    .perception_noise
+                    .message_delay(&mut robot.random_number_generator)
+                {
+                    robot.pending_messages.push((now + delay, message.clone()));
+                }
+            }
+        }
 
         for (player_number, robot) in self.robots.iter_mut() {
-            let incoming_messages: Vec<_> = incoming_messages
-                .iter()
-                .map(|(sender, message)| {
-                    (sender != player_number).then_some(IncomingMessage::Spl(*message))
-                })
+            let (received_messages, pending_messages): (Vec<_>, Vec<_>) =
+                take(&mut robot.pending_messages)
+                    .into_iter()
+                    .partition(|(receive_time, _)| *receive_time <= now);
+            robot.pending_messages = pending_messages;
+            let incoming_messages: Vec<_> = received_messages
+                .into_iter()
+                .map(|(_, message)| message)
                 .collect();
-            let messages_with_time = BTreeMap::from_iter([(
-                now,
-                incoming_messages
-                    .iter()
-                    .map(|message| message.as_ref())
-                    .collect(),
-            )]);
+            let messages_with_time =
+                BTreeMap::from_iter([(now, incoming_messages.iter().map(Some).collect())]);
 
             robot.database.main_outputs.cycle_time.start_time = now;
 
@@ -207,29 +311,93 @@ impl State {
                 .main_outputs
                 .ground_to_field
                 .expect("simulated robots should always have a known pose");
-            let ball_visible = self.ball.as_ref().is_some_and(|ball| {
-                let ball_in_ground = ground_to_field.inverse() * ball.position;
-                let head_to_ground =
-                    Rotation2::new(robot.database.main_outputs.sensor_data.positions.head.yaw);
-                let ball_in_head: Point2<Head> = head_to_ground.inverse() * ball_in_ground;
-                let field_of_view = robot.field_of_view();
-                let angle_to_ball = ball_in_head.coords().angle(Vector2::x_axis());
-
-                angle_to_ball.abs() < field_of_view / 2.0 && ball_in_head.coords().norm() < 3.0
+            let head_to_ground =
+                Rotation2::new(robot.database.main_outputs.sensor_data.positions.head.yaw);
+            let field_of_view = robot.field_of_view();
+            let ball_in_head: Option<Point2<Head>> = self
+                .ball
+                .as_ref()
+                .map(|ball| head_to_ground.inverse() * (ground_to_field.inverse() * ball.position));
+            let ball_visible = if self.render_images {
+                if robot.vision.is_none() {
+                    robot.vision = Some(
+                        Vision::try_new(robot.interface.clone(), &robot.parameters)
+                            .wrap_err("failed to create vision")?,
+                    );
+                }
+                let other_robots: Vec<_> = robot_positions
+                    .iter()
+                    .filter(|(other_player_number, _)| other_player_number != player_number)
+                    .map(|(_, position)| *position)
+                    .chain(opponent_positions.iter().copied())
+                    .collect();
+                self.cycle_count.is_multiple_of(VISION_CYCLE_INTERVAL)
+                    && robot.vision.as_mut().unwrap().cycle(
+                        &robot.parameters,
+                        ground_to_field,
+                        &robot.database.main_outputs.sensor_data.positions.head,
+                        self.ball.as_ref().map(|ball| ball.position),
+                        &other_robots,
+                    )?
+            } else {
+                ball_in_head.is_some_and(|ball_in_head| {
+                    let angle_to_ball = ball_in_head.coords().angle(Vector2::x_axis());
+
+                    angle_to_ball.abs() < field_of_view / 2.0
+                        && ball_in_head.coords().norm() < BALL_DETECTION_RANGE
+                })
+            };
+            let ball_in_head = ball_in_head.filter(|ball_in_head| {
+                ball_visible
+                    && !robot.perception_noise.is_ball_dropped(
+                        &mut robot.random_number_generator,
+                        *ball_in_head,
+                        field_of_view,
+                    )
             });
-            if ball_visible {
+            if let Some(ball_in_head) = ball_in_head {
+                robot.ball_last_seen = Some(now);
+                robot.false_positive_ball = None;
+                robot.ball_detection_error = robot.perception_noise.ball_detection_error(
+                    &mut robot.random_number_generator,
+                    ball_in_head.coords().norm(),
+                );
+            }
+            if let Some(false_positive_ball) = robot.perception_noise.false_positive_ball(
+                &mut robot.random_number_generator,
+                field_of_view,
+                BALL_DETECTION_RANGE,
+            ) {
                 robot.ball_last_seen = Some(now);
+                robot.false_positive_ball =
+                    Some(ground_to_field * (head_to_ground * false_positive_ball));
             }
             robot.database.main_outputs.ball_position =
                 if robot.ball_last_seen.is_some_and(|last_seen| {
                     now.duration_since(last_seen).expect("time ran backwards")
                         < robot.parameters.ball_filter.hypothesis_timeout
                 }) {
-                    self.ball.as_ref().map(|ball| BallPosition {
-                        position: ground_to_field.inverse() * ball.position,
-                        velocity: ground_to_field.inverse() * ball.velocity,
-                        last_seen: now,
-                    })
+                    if let Some(false_positive_ball) = robot.false_positive_ball {
+                        Some(BallPosition {
+                            position: ground_to_field.inverse() * false_positive_ball,
+                            velocity: Vector2::zeros(),
+                            last_seen: now,
+                        })
+                    } else {
+                        let detected_ball = robot
+                            .vision
+                            .as_ref()
+                            .and_then(|vision| vision.last_detected_ball)
+                            .filter(|_| self.render_images);
+                        self.ball.as_ref().map(|ball| BallPosition {
+                            position: ground_to_field.inverse()
+                                * (detected_ball.unwrap_or(ball.position)
+                                    + robot.ball_detection_error),
+                            // without a ball filter, detections do not provide a velocity
+                            velocity: ground_to_field.inverse() * ball.velocity,
+                            last_seen: now,
+                        })
+                    }
                 } else {
                     None
                 };
@@ -246,14 +414,39 @@ impl State {
             };
             robot.database.main_outputs.filtered_game_controller_state =
                 Some(self.filtered_game_controller_state);
+
+            // opponents are perceived as obstacles only during this cycle, scripted obstacles persist
+            let number_of_scripted_obstacles = robot.database.main_outputs.obstacles.len();
+            robot.database.main_outputs.obstacles.extend(
+                opponent_positions
+                    .iter()
+                    .map(|position| ground_to_field.inverse() * *position)
+                    .filter(|position| position.coords().norm() < OPPONENT_DETECTION_RANGE)
+                    .map(|position| Obstacle {
+                        team: Team::Opponent,
+                        ..Obstacle::robot(position, ROBOT_RADIUS, ROBOT_RADIUS)
+                    }),
+            );
+            // the robot only knows the pose it believes to be at, the simulation continues with the
+            // true pose
+            robot.database.main_outputs.ground_to_field = Some(robot.pose_error * ground_to_field);
             robot.cycle(messages_with_time)?;
+            robot.database.main_outputs.ground_to_field = Some(ground_to_field);
+            robot
+                .database
+                .main_outputs
+                .obstacles
+                .truncate(number_of_scripted_obstacles);
 
             for message in robot.interface.take_outgoing_messages() {
-                if let OutgoingMessage::Spl(message) = message {
-                    self.messages.push((*player_number, message));
-                    self.filtered_game_controller_state
-                        .remaining_number_of_messages -= 1
-                }
+                let message = match message {
+                    OutgoingMessage::Spl(message) => IncomingMessage::Spl(message),
+                    OutgoingMessage::MixedTeam(message) => IncomingMessage::MixedTeam(message),
+                    _ => continue,
+                };
+                self.messages.push((*player_number, message));
+                self.filtered_game_controller_state
+                    .remaining_number_of_messages -= 1
             }
         }
 
@@ -265,6 +458,9 @@ impl State {
         if let Some(ball) = self.ball.as_mut() {
             ball.position += ball.velocity * time_step.as_secs_f32();
             ball.velocity *= 0.98;
+            for opponent in &self.opponents {
+                collide_ball(ball, opponent.position);
+            }
 
             if ball.position.x().abs() > 4.5 && ball.position.y() < 0.75 {
                 events.push(Event::Goal);
@@ -280,16 +476,19 @@ impl State {
             // TODO: Expose robot data to lua again
             // robots: self.robots.iter().map(LuaRobot::new).collect(),
             robots: Default::default(),
+            opponents: self.opponents.clone(),
             ball: self.ball.clone(),
             messages: self.messages.clone(),
 
             finished: self.finished,
 
             filtered_game_controller_state: self.filtered_game_controller_state,
+            render_images: self.render_images,
         }
     }
 
     pub fn load_lua_state(&mut self, lua_state: LuaState) -> Result<()> {
+        self.opponents = lua_state.opponents;
         self.ball = lua_state.ball;
         self.cycle_count = lua_state.cycle_count;
         for lua_robot in lua_state.robots {
@@ -297,12 +496,21 @@ impl State {
                 .expect("Creating dummy robot should never fail");
             robot.database = lua_robot.database;
             robot.parameters = lua_robot.parameters;
+            robot.perception_noise = lua_robot.perception_noise;
+            robot.seed_random_number_generator(self.seed);
+            for (path, value) in &self.parameter_overrides {
+                robot
+                    .parameters
+                    .deserialize_path(path, value.clone())
+                    .wrap_err_with(|| format!("failed to override parameter {path}"))?;
+            }
             self.robots.insert(robot.parameters.player_number, robot);
         }
 
         self.finished = lua_state.finished;
 
         self.filtered_game_controller_state = lua_state.filtered_game_controller_state;
+        self.render_images = lua_state.render_images;
 
         Ok(())
     }
@@ -315,6 +523,8 @@ impl Default for State {
             game_state: FilteredGameState::Initial,
             opponent_game_state: FilteredGameState::Initial,
             game_phase: GamePhase::Normal,
+            half: Half::First,
+            remaining_time_in_half: Duration::from_secs(600),
             kicking_team: Team::Hulks,
             penalties: Players {
                 one: None,
@@ -334,10 +544,14 @@ impl Default for State {
             time_elapsed: Duration::ZERO,
             cycle_count: 0,
             robots,
+            opponents: Vec::new(),
             ball: None,
             messages: Vec::new(),
             finished: false,
             filtered_game_controller_state,
+            render_images: false,
+            seed: 0,
+            parameter_overrides: Vec::new(),
         }
     }
 }
@@ -347,16 +561,19 @@ pub struct LuaState {
     pub time_elapsed: f32,
     pub cycle_count: usize,
     pub robots: Vec<LuaRobot>,
+    pub opponents: Vec<Opponent>,
     pub ball: Option<Ball>,
-    pub messages: Vec<(PlayerNumber, HulkMessage)>,
+    pub messages: Vec<(PlayerNumber, IncomingMessage)>,
     pub finished: bool,
     pub filtered_game_controller_state: FilteredGameControllerState,
+    pub render_images: bool,
 }
 
 #[derive(Clone, Deserialize, Serialize)]
 pub struct LuaRobot {
     database: Database,
     parameters: Parameters,
+    perception_noise: PerceptionNoise,
 }
 
 impl LuaRobot {
@@ -364,6 +581,7 @@ impl LuaRobot {
         Self {
             database: robot.database.clone(),
             parameters: robot.parameters.clone(),
+            perception_noise: robot.perception_noise.clone(),
         }
     }
 }
@@ -0,0 +1,310 @@
+use std::{
+    error::Error,
+    io::Write,
+    ops::Range,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+};
+
+use color_eyre::{eyre::WrapErr, Result};
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde_json::Value;
+
+use crate::report::{run_scenario_with_parameters, ScenarioReport};
+
+/// One simulation of a sweep
+#[derive(Clone, Debug)]
+pub struct Sample {
+    /// Index of the parameter combination, samples with the same index only differ in their seed
+    pub parameter_set: usize,
+    pub parameters: Vec<(String, Value)>,
+    pub seed: u64,
+}
+
+/// Combines every point of the grid with every random sample and every seed
+///
+/// Random values only depend on the index of the random sample, so all seeds are evaluated with
+/// the same parameters.
+pub fn generate_samples(
+    grid: &[(String, Vec<Value>)],
+    random_ranges: &[(String, Range<f64>)],
+    number_of_random_samples: usize,
+    seeds: &[u64],
+) -> Vec<Sample> {
+    let mut grid_points = vec![Vec::new()];
+    for (path, values) in grid {
+        grid_points = grid_points
+            .into_iter()
+            .flat_map(|point| {
+                values.iter().map(move |value| {
+                    let mut point = point.clone();
+                    point.push((path.clone(), value.clone()));
+                    point
+                })
+            })
+            .collect();
+    }
+
+    let number_of_random_samples = if random_ranges.is_empty() {
+        1
+    } else {
+        number_of_random_samples
+    };
+    let parameter_sets = grid_points.into_iter().flat_map(|point| {
+        (0..number_of_random_samples).map(move |sample_index| {
+            let mut random_number_generator = StdRng::seed_from_u64(sample_index as u64);
+            let mut parameters = point.clone();
+            parameters.extend(random_ranges.iter().map(|(path, range)| {
+                let value = random_number_generator.gen_range(range.clone());
+                (path.clone(), Value::from(value))
+            }));
+            parameters
+        })
+    });
+
+    parameter_sets
+        .enumerate()
+        .flat_map(|(parameter_set, parameters)| {
+            seeds.iter().map(move |seed| Sample {
+                parameter_set,
+                parameters: parameters.clone(),
+                seed: *seed,
+            })
+        })
+        .collect()
+}
+
+/// Runs all samples distributed over the given number of threads
+pub fn run_samples(
+    scenario_file: &Path,
+    samples: Vec<Sample>,
+    number_of_jobs: usize,
+) -> Result<Vec<(Sample, ScenarioReport)>> {
+    let next_sample = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..number_of_jobs.max(1))
+            .map(|_| {
+                scope.spawn(|| -> Result<()> {
+                    while let Some(sample) = samples.get(next_sample.fetch_add(1, Ordering::SeqCst))
+                    {
+                        let report = run_scenario_with_parameters(
+                            scenario_file,
+                            sample.seed,
+                            sample.parameters.clone(),
+                        )
+                        .wrap_err_with(|| format!("failed to run sample {sample:?}"))?;
+                        eprintln!(
+                            "parameter set {}, seed {}: {}",
+                            sample.parameter_set,
+                            sample.seed,
+                            if report.passed() { "passed" } else { "FAILED" }
+                        );
+                        results.lock().push((sample.clone(), report));
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().expect("sweep worker panicked"))
+    })?;
+
+    let mut results = results.into_inner();
+    results.sort_by_key(|(sample, _)| (sample.parameter_set, sample.seed));
+    Ok(results)
+}
+
+/// Writes one CSV row per simulation
+pub fn write_csv(
+    writer: &mut impl Write,
+    results: &[(Sample, ScenarioReport)],
+) -> std::io::Result<()> {
+    let Some((first_sample, _)) = results.first() else {
+        return Ok(());
+    };
+    write!(writer, "parameter_set,seed")?;
+    for (path, _) in &first_sample.parameters {
+        write!(writer, ",{path}")?;
+    }
+    writeln!(
+        writer,
+        ",passed,goals_scored,goals_conceded,time_to_ball,collisions,cycles"
+    )?;
+    for (sample, report) in results {
+        write!(writer, "{},{}", sample.parameter_set, sample.seed)?;
+        for (_, value) in &sample.parameters {
+            write!(writer, ",{}", csv_field(&value.to_string()))?;
+        }
+        let metrics = &report.metrics;
+        writeln!(
+            writer,
+            ",{},{},{},{},{},{}",
+            report.passed(),
+            metrics.goals_scored,
+            metrics.goals_conceded,
+            metrics
+                .time_to_ball
+                .map(|time| time.to_string())
+                .unwrap_or_default(),
+            metrics.collisions,
+            metrics.cycles,
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes a table with the metrics of every parameter set averaged over all seeds
+pub fn write_summary(
+    writer: &mut impl Write,
+    results: &[(Sample, ScenarioReport)],
+) -> std::io::Result<()> {
+    let mut rows = vec![vec![
+        "set".to_string(),
+        "parameters".to_string(),
+        "passed".to_string(),
+        "goals scored".to_string(),
+        "goals conceded".to_string(),
+        "time to ball".to_string(),
+        "collisions".to_string(),
+    ]];
+    for parameter_set in
+        results.chunk_by(|(first, _), (second, _)| first.parameter_set == second.parameter_set)
+    {
+        let (sample, _) = &parameter_set[0];
+        let reports: Vec<_> = parameter_set.iter().map(|(_, report)| report).collect();
+        let mean = |metric: fn(&ScenarioReport) -> Option<f32>| {
+            let values: Vec<_> = reports.iter().filter_map(|report| metric(report)).collect();
+            if values.is_empty() {
+                "-".to_string()
+            } else {
+                format!("{:.2}", values.iter().sum::<f32>() / values.len() as f32)
+            }
+        };
+        rows.push(vec![
+            sample.parameter_set.to_string(),
+            sample
+                .parameters
+                .iter()
+                .map(|(path, value)| format!("{path}={value}"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            format!(
+                "{}/{}",
+                reports.iter().filter(|report| report.passed()).count(),
+                reports.len()
+            ),
+            mean(|report| Some(report.metrics.goals_scored as f32)),
+            mean(|report| Some(report.metrics.goals_conceded as f32)),
+            mean(|report| report.metrics.time_to_ball),
+            mean(|report| Some(report.metrics.collisions as f32)),
+        ]);
+    }
+
+    let widths: Vec<_> = (0..rows[0].len())
+        .map(|column| rows.iter().map(|row| row[column].len()).max().unwrap_or(0))
+        .collect();
+    for row in rows {
+        let line: Vec<_> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        writeln!(writer, "{}", line.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Parses `path=[value, ...]` with JSON values
+pub fn parse_grid(
+    string: &str,
+) -> Result<(String, Vec<Value>), Box<dyn Error + Send + Sync + 'static>> {
+    let (path, values) = string
+        .split_once('=')
+        .ok_or_else(|| format!("invalid path=[values]: no `=` found in `{string}`"))?;
+    let values: Vec<Value> = serde_json::from_str(values)
+        .map_err(|error| format!("invalid values `{values}`, expected a JSON array: {error}"))?;
+    Ok((path.to_string(), values))
+}
+
+/// Parses `path=minimum..maximum`
+pub fn parse_range(
+    string: &str,
+) -> Result<(String, Range<f64>), Box<dyn Error + Send + Sync + 'static>> {
+    let (path, range) = string
+        .split_once('=')
+        .ok_or_else(|| format!("invalid path=minimum..maximum: no `=` found in `{string}`"))?;
+    let (minimum, maximum) = range
+        .split_once("..")
+        .ok_or_else(|| format!("invalid range `{range}`: no `..` found"))?;
+    let minimum: f64 = minimum.trim().parse()?;
+    let maximum: f64 = maximum.trim().parse()?;
+    if minimum >= maximum {
+        return Err(format!("invalid range `{range}`: minimum is not below maximum").into());
+    }
+    Ok((path.to_string(), minimum..maximum))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn samples_cover_grid_random_samples_and_seeds() {
+        let grid = [
+            ("a".to_string(), vec![json!(1), json!(2)]),
+            (
+                "b".to_string(),
+                vec![json!(true), json!(false), json!(null)],
+            ),
+        ];
+        let random_ranges = [("c".to_string(), 0.0..1.0)];
+
+        let samples = generate_samples(&grid, &random_ranges, 4, &[0, 1]);
+
+        assert_eq!(samples.len(), 2 * 3 * 4 * 2);
+        assert_eq!(samples.last().unwrap().parameter_set, 2 * 3 * 4 - 1);
+        for pair in samples.chunks(2) {
+            assert_eq!(pair[0].parameters, pair[1].parameters);
+            assert_ne!(pair[0].seed, pair[1].seed);
+        }
+        assert!(samples.iter().all(|sample| {
+            let value = sample.parameters[2].1.as_f64().unwrap();
+            (0.0..1.0).contains(&value)
+        }));
+    }
+
+    #[test]
+    fn grid_and_range_arguments_are_parsed() {
+        assert_eq!(
+            parse_grid("behavior.dribbling.hybrid_align_distance=[0.1, 0.2]").unwrap(),
+            (
+                "behavior.dribbling.hybrid_align_distance".to_string(),
+                vec![json!(0.1), json!(0.2)]
+            )
+        );
+        assert_eq!(
+            parse_range("behavior.role_positions.striker_supporter_distance_to_ball=0.5..1.5")
+                .unwrap(),
+            (
+                "behavior.role_positions.striker_supporter_distance_to_ball".to_string(),
+                0.5..1.5
+            )
+        );
+        assert!(parse_range("a=1..0").is_err());
+        assert!(parse_grid("a=0.1").is_err());
+    }
+}
@@ -0,0 +1,357 @@
+use std::sync::Arc;
+
+use color_eyre::{eyre::WrapErr, Result};
+use coordinate_systems::{Field, Ground};
+use framework::AdditionalOutput;
+use linear_algebra::{Isometry2, Point2};
+use path_serde::{PathIntrospect, PathSerialize};
+use projection::camera_matrix::CameraMatrix;
+use serde::{Deserialize, Serialize};
+use types::{
+    camera_position::CameraPosition, joints::head::HeadJoints, ycbcr422_image::YCbCr422Image,
+};
+use vision::{
+    ball_detection::{self, BallDetection},
+    field_border_detection::{self, FieldBorderDetection},
+    field_color_detection::{self, FieldColorDetection},
+    image_segmenter::{self, ImageSegmenter},
+    line_detection::{self, LineDetection},
+    perspective_grid_candidates_provider::{self, PerspectiveGridCandidatesProvider},
+    segment_filter::{self, SegmentFilter},
+};
+
+use crate::{
+    interfake::Interfake,
+    renderer::{camera_matrices, render_image},
+    structs::{
+        vision::{AdditionalOutputs, MainOutputs},
+        Parameters,
+    },
+};
+
+#[derive(Clone, Default, Deserialize, Serialize, PathSerialize, PathIntrospect)]
+pub struct VisionDatabase {
+    pub main_outputs: MainOutputs,
+    pub additional_outputs: AdditionalOutputs,
+}
+
+/// Runs the nodes of one vision cycler instance on rendered images
+///
+/// The camera matrix is known exactly in simulation, therefore neither the image receiver nor
+/// the camera matrix extractor or limb projector are executed.
+pub struct VisionCycler {
+    camera_position: CameraPosition,
+    ball_detection: BallDetection,
+    field_border_detection: FieldBorderDetection,
+    field_color_detection: FieldColorDetection,
+    image_segmenter: ImageSegmenter,
+    line_detection: LineDetection,
+    perspective_grid_candidates_provider: PerspectiveGridCandidatesProvider,
+    segment_filter: SegmentFilter,
+}
+
+/// Expands `$body` with `$instance` bound to the parameter field name of the cycler instance
+macro_rules! with_instance {
+    ($camera_position:expr, $instance:ident => $body:expr) => {
+        match $camera_position {
+            CameraPosition::Top => {
+                macro_rules! $instance {
+                    ($parameters:expr) => {
+                        $parameters.vision_top
+                    };
+                }
+                $body
+            }
+            CameraPosition::Bottom => {
+                macro_rules! $instance {
+                    ($parameters:expr) => {
+                        $parameters.vision_bottom
+                    };
+                }
+                $body
+            }
+        }
+    };
+}
+
+impl VisionCycler {
+    pub fn new(
+        hardware_interface: Arc<Interfake>,
+        parameters: &Parameters,
+        camera_position: CameraPosition,
+    ) -> Result<Self> {
+        let ball_detection_parameters = match camera_position {
+            CameraPosition::Top => &parameters.ball_detection.vision_top,
+            CameraPosition::Bottom => &parameters.ball_detection.vision_bottom,
+        };
+        let ball_detection = BallDetection::new(ball_detection::CreationContext::new(
+            &hardware_interface,
+            ball_detection_parameters,
+        ))
+        .wrap_err("failed to create node `BallDetection`")?;
+        let field_border_detection =
+            FieldBorderDetection::new(field_border_detection::CreationContext::new())
+                .wrap_err("failed to create node `FieldBorderDetection`")?;
+        let field_color_detection =
+            FieldColorDetection::new(field_color_detection::CreationContext::new())
+                .wrap_err("failed to create node `FieldColorDetection`")?;
+        let image_segmenter = ImageSegmenter::new(image_segmenter::CreationContext::new())
+            .wrap_err("failed to create node `ImageSegmenter`")?;
+        let line_detection = LineDetection::new(line_detection::CreationContext::new())
+            .wrap_err("failed to create node `LineDetection`")?;
+        let perspective_grid_candidates_provider = PerspectiveGridCandidatesProvider::new(
+            perspective_grid_candidates_provider::CreationContext::new(),
+        )
+        .wrap_err("failed to create node `PerspectiveGridCandidatesProvider`")?;
+        let segment_filter = SegmentFilter::new(segment_filter::CreationContext::new())
+            .wrap_err("failed to create node `SegmentFilter`")?;
+
+        Ok(Self {
+            camera_position,
+            ball_detection,
+            field_border_detection,
+            field_color_detection,
+            image_segmenter,
+            line_detection,
+            perspective_grid_candidates_provider,
+            segment_filter,
+        })
+    }
+
+    pub fn cycle(
+        &mut self,
+        own_database: &mut VisionDatabase,
+        parameters: &Parameters,
+        image: YCbCr422Image,
+        camera_matrix: CameraMatrix,
+        ground_to_field: Option<Isometry2<Ground, Field>>,
+    ) -> Result<()> {
+        own_database.main_outputs.image = image;
+        own_database.main_outputs.camera_matrix = Some(camera_matrix);
+        own_database.main_outputs.projected_limbs = None;
+
+        with_instance!(self.camera_position, instance => {
+            {
+                let main_outputs = self
+                    .field_color_detection
+                    .cycle(field_color_detection::CycleContext::new(
+                        &instance!(parameters.field_color_detection).blue_chromaticity_threshold,
+                        &instance!(parameters.field_color_detection).green_luminance_threshold,
+                        &instance!(parameters.field_color_detection).red_chromaticity_threshold,
+                        &instance!(parameters.field_color_detection).green_chromaticity_threshold,
+                        &instance!(parameters.field_color_detection).luminance_threshold,
+                        ground_to_field.as_ref(),
+                    ))
+                    .wrap_err("failed to execute cycle of node `FieldColorDetection`")?;
+                own_database.main_outputs.field_color = main_outputs.field_color.value;
+            }
+            {
+                let main_outputs = self
+                    .image_segmenter
+                    .cycle(image_segmenter::CycleContext::new(
+                        AdditionalOutput::new(
+                            false,
+                            &mut own_database.additional_outputs.image_segmenter_cycle_time,
+                        ),
+                        &own_database.main_outputs.image,
+                        own_database.main_outputs.camera_matrix.as_ref(),
+                        ground_to_field.as_ref(),
+                        &own_database.main_outputs.field_color,
+                        own_database.main_outputs.projected_limbs.as_ref(),
+                        &instance!(parameters.image_segmenter).horizontal_stride,
+                        &instance!(parameters.image_segmenter).vertical_stride,
+                        &instance!(parameters.image_segmenter).vertical_edge_detection_source,
+                        &instance!(parameters.image_segmenter).vertical_edge_threshold,
+                        &instance!(parameters.image_segmenter).vertical_median_mode,
+                    ))
+                    .wrap_err("failed to execute cycle of node `ImageSegmenter`")?;
+                own_database.main_outputs.image_segments = main_outputs.image_segments.value;
+            }
+            {
+                let main_outputs = self
+                    .field_border_detection
+                    .cycle(field_border_detection::CycleContext::new(
+                        AdditionalOutput::new(
+                            true,
+                            &mut own_database.additional_outputs.field_border_points,
+                        ),
+                        &instance!(parameters.field_border_detection).enable,
+                        &instance!(parameters.field_border_detection).angle_threshold,
+                        &instance!(parameters.field_border_detection)
+                            .first_line_association_distance,
+                        &instance!(parameters.field_border_detection).min_points_per_line,
+                        &instance!(parameters.field_border_detection)
+                            .second_line_association_distance,
+                        own_database.main_outputs.camera_matrix.as_ref().unwrap(),
+                        &own_database.main_outputs.image_segments,
+                    ))
+                    .wrap_err("failed to execute cycle of node `FieldBorderDetection`")?;
+                own_database.main_outputs.field_border = main_outputs.field_border.value;
+            }
+            {
+                let main_outputs = self
+                    .segment_filter
+                    .cycle(segment_filter::CycleContext::new(
+                        own_database.main_outputs.field_border.as_ref(),
+                        &own_database.main_outputs.image_segments,
+                    ))
+                    .wrap_err("failed to execute cycle of node `SegmentFilter`")?;
+                own_database.main_outputs.filtered_segments = main_outputs.filtered_segments.value;
+            }
+            {
+                let main_outputs = self
+                    .line_detection
+                    .cycle(line_detection::CycleContext::new(
+                        AdditionalOutput::new(
+                            true,
+                            &mut own_database.additional_outputs.lines_in_image,
+                        ),
+                        AdditionalOutput::new(
+                            true,
+                            &mut own_database.additional_outputs.discarded_lines,
+                        ),
+                        AdditionalOutput::new(
+                            false,
+                            &mut own_database.additional_outputs.ransac_input,
+                        ),
+                        &instance!(parameters.line_detection).allowed_line_length_in_field,
+                        &instance!(parameters.line_detection).check_edge_gradient,
+                        &instance!(parameters.line_detection).check_line_distance,
+                        &instance!(parameters.line_detection).check_line_length,
+                        &instance!(parameters.line_detection).check_line_segments_projection,
+                        &instance!(parameters.line_detection).gradient_alignment,
+                        &instance!(parameters.line_detection).margin_for_point_inclusion,
+                        &instance!(parameters.line_detection).maximum_distance_to_robot,
+                        &instance!(parameters.line_detection).maximum_fit_distance_in_ground,
+                        &instance!(parameters.line_detection).maximum_gap_on_line,
+                        &instance!(parameters.line_detection).maximum_merge_gap_in_pixels,
+                        &instance!(parameters.line_detection).maximum_number_of_lines,
+                        &instance!(parameters.line_detection).allowed_projected_segment_length,
+                        &instance!(parameters.line_detection).minimum_number_of_points_on_line,
+                        &instance!(parameters.line_detection).ransac_iterations,
+                        own_database.main_outputs.camera_matrix.as_ref().unwrap(),
+                        &own_database.main_outputs.filtered_segments,
+                        &own_database.main_outputs.image,
+                    ))
+                    .wrap_err("failed to execute cycle of node `LineDetection`")?;
+                own_database.main_outputs.line_data = main_outputs.line_data.value;
+            }
+            if let Some(line_data) = own_database.main_outputs.line_data.as_ref() {
+                let main_outputs = self
+                    .perspective_grid_candidates_provider
+                    .cycle(perspective_grid_candidates_provider::CycleContext::new(
+                        own_database.main_outputs.camera_matrix.as_ref().unwrap(),
+                        &own_database.main_outputs.filtered_segments,
+                        line_data,
+                        &own_database.main_outputs.image,
+                        &parameters.field_dimensions.ball_radius,
+                        &instance!(parameters.perspective_grid_candidates_provider)
+                            .minimum_radius,
+                        AdditionalOutput::new(
+                            false,
+                            &mut own_database.additional_outputs.perspective_grid_ball_sizes,
+                        ),
+                    ))
+                    .wrap_err("failed to execute cycle of node `PerspectiveGridCandidatesProvider`")?;
+                own_database.main_outputs.perspective_grid_candidates =
+                    main_outputs.perspective_grid_candidates.value;
+            } else {
+                own_database.main_outputs.perspective_grid_candidates = Default::default();
+            }
+            if let Some(perspective_grid_candidates) =
+                own_database.main_outputs.perspective_grid_candidates.as_ref()
+            {
+                let main_outputs = self
+                    .ball_detection
+                    .cycle(ball_detection::CycleContext::new(
+                        AdditionalOutput::new(
+                            true,
+                            &mut own_database.additional_outputs.ball_candidates,
+                        ),
+                        own_database.main_outputs.camera_matrix.as_ref().unwrap(),
+                        perspective_grid_candidates,
+                        &own_database.main_outputs.image,
+                        &instance!(parameters.ball_detection),
+                        &parameters.field_dimensions.ball_radius,
+                    ))
+                    .wrap_err("failed to execute cycle of node `BallDetection`")?;
+                own_database.main_outputs.balls = main_outputs.balls.value;
+            } else {
+                own_database.main_outputs.balls = Default::default();
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Vision of a simulated robot, both cameras see rendered images of the simulated world
+pub struct Vision {
+    top: VisionCycler,
+    bottom: VisionCycler,
+    pub top_database: VisionDatabase,
+    pub bottom_database: VisionDatabase,
+    pub last_detected_ball: Option<Point2<Field>>,
+}
+
+impl Vision {
+    pub fn try_new(hardware_interface: Arc<Interfake>, parameters: &Parameters) -> Result<Self> {
+        Ok(Self {
+            top: VisionCycler::new(hardware_interface.clone(), parameters, CameraPosition::Top)
+                .wrap_err("failed to create top vision cycler")?,
+            bottom: VisionCycler::new(hardware_interface, parameters, CameraPosition::Bottom)
+                .wrap_err("failed to create bottom vision cycler")?,
+            top_database: Default::default(),
+            bottom_database: Default::default(),
+            last_detected_ball: None,
+        })
+    }
+
+    /// Returns whether a ball was detected by any of the cameras
+    pub fn cycle(
+        &mut self,
+        parameters: &Parameters,
+        ground_to_field: Isometry2<Ground, Field>,
+        head: &HeadJoints<f32>,
+        ball: Option<Point2<Field>>,
+        robots: &[Point2<Field>],
+    ) -> Result<bool> {
+        let camera_matrices = camera_matrices(
+            head,
+            &parameters.camera_matrix_parameters.vision_top,
+            &parameters.camera_matrix_parameters.vision_bottom,
+        );
+        for (cycler, database, camera_matrix) in [
+            (&mut self.top, &mut self.top_database, camera_matrices.top),
+            (
+                &mut self.bottom,
+                &mut self.bottom_database,
+                camera_matrices.bottom,
+            ),
+        ] {
+            let image = render_image(
+                &camera_matrix,
+                ground_to_field,
+                &parameters.field_dimensions,
+                ball,
+                robots,
+            );
+            cycler.cycle(
+                database,
+                parameters,
+                image,
+                camera_matrix,
+                Some(ground_to_field),
+            )?;
+        }
+        let detected_ball = [&self.top_database, &self.bottom_database]
+            .into_iter()
+            .flat_map(|database| database.main_outputs.balls.iter().flatten())
+            .map(|ball| ground_to_field * ball.position)
+            .next();
+        if detected_ball.is_some() {
+            self.last_detected_ball = detected_ball;
+        }
+        Ok(detected_ball.is_some())
+    }
+}
@@ -1,17 +1,22 @@
-use std::{path::Path, time::Instant};
+use std::path::Path;
 
-use color_eyre::{eyre::Context, Result};
+use color_eyre::{eyre::bail, Result};
 
-use behavior_simulator::simulator::Simulator;
+use behavior_simulator::report::run_scenario;
 
 fn test_scenario(path: impl AsRef<Path>) -> Result<()> {
-    let mut simulator = Simulator::try_new()?;
-    simulator.execute_script(path)?;
+    let report = run_scenario(path)?;
+    eprintln!("Took {:.2} seconds", report.duration.as_secs_f32());
 
-    let start = Instant::now();
-    simulator.run().wrap_err("failed to run simulation")?;
-    let duration = Instant::now() - start;
-    eprintln!("Took {:.2} seconds", duration.as_secs_f32());
+    if let Some(error) = report.error {
+        bail!(error);
+    }
+    for failure in &report.failures {
+        eprintln!("cycle {}: {}", failure.cycle, failure.message);
+    }
+    if !report.failures.is_empty() {
+        bail!("{} assertions failed", report.failures.len());
+    }
 
     Ok(())
 }
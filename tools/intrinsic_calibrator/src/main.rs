@@ -0,0 +1,126 @@
+use std::{fs::write, path::PathBuf};
+
+use calibration::intrinsic::{
+    calibrate,
+    checkerboard::{detect_corners, Checkerboard},
+    IntrinsicCalibration,
+};
+use clap::{Parser, ValueEnum};
+use color_eyre::{
+    eyre::{bail, WrapErr},
+    Result,
+};
+use nalgebra::vector;
+use serde_json::{json, to_string_pretty, Value};
+use types::ycbcr422_image::YCbCr422Image;
+
+/// Estimates focal lengths, optical center and lens distortion of a camera from recorded images
+/// of a checkerboard and emits a patch for the camera matrix parameters of the head
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Arguments {
+    /// Camera which recorded the images
+    #[clap(long, value_enum)]
+    camera: Camera,
+    /// Number of inner corners along the vertical axis of the checkerboard
+    #[clap(long)]
+    rows: usize,
+    /// Number of inner corners along the horizontal axis of the checkerboard
+    #[clap(long)]
+    columns: usize,
+    /// Edge length of a checkerboard square in meters
+    #[clap(long, default_value_t = 0.03)]
+    square_size: f32,
+    /// Load images as RGB instead of YCbCr 4:4:4 PNGs as written by the image recorder
+    #[clap(long)]
+    rgb: bool,
+    /// Write the parameter patch to this file instead of stdout
+    #[clap(long)]
+    output: Option<PathBuf>,
+    /// Recorded images showing the checkerboard in different poses
+    #[clap(required = true)]
+    images: Vec<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Camera {
+    Top,
+    Bottom,
+}
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    let arguments = Arguments::parse();
+    let board = Checkerboard {
+        rows: arguments.rows,
+        columns: arguments.columns,
+        square_size: arguments.square_size,
+    };
+
+    let mut image_size = None;
+    let mut views = Vec::new();
+    for path in &arguments.images {
+        let image = if arguments.rgb {
+            YCbCr422Image::load_from_rgb_file(path)
+        } else {
+            YCbCr422Image::load_from_444_png(path)
+        }
+        .wrap_err_with(|| format!("failed to load {}", path.display()))?;
+        let size = (image.width(), image.height());
+        if *image_size.get_or_insert(size) != size {
+            bail!(
+                "{} has a different resolution than previous images",
+                path.display()
+            );
+        }
+        match detect_corners(&image, &board) {
+            Some(corners) => views.push(corners),
+            None => eprintln!("skipping {}, checkerboard not found", path.display()),
+        }
+    }
+    let Some((width, height)) = image_size else {
+        bail!("no images given");
+    };
+    eprintln!(
+        "found the checkerboard in {} of {} images",
+        views.len(),
+        arguments.images.len()
+    );
+
+    let (calibration, report) =
+        calibrate(&board, &views).wrap_err("failed to calibrate intrinsics")?;
+    eprintln!("{report:?}");
+    eprintln!("{calibration:#?}");
+    if !report.termination.was_successful() {
+        bail!("refinement did not converge: {:?}", report.termination);
+    }
+
+    let patch = to_patch(arguments.camera, &calibration, width as f32, height as f32);
+    let patch = to_string_pretty(&patch).wrap_err("failed to serialize parameter patch")?;
+    match arguments.output {
+        Some(output) => write(&output, patch)
+            .wrap_err_with(|| format!("failed to write {}", output.display()))?,
+        None => println!("{patch}"),
+    }
+    Ok(())
+}
+
+/// Parameters to be merged into the `head.{id}.json` of the calibrated robot, focal lengths and
+/// optical center are normalized by the image size like in the parameter files
+fn to_patch(camera: Camera, calibration: &IntrinsicCalibration, width: f32, height: f32) -> Value {
+    let cycler_instance = match camera {
+        Camera::Top => "vision_top",
+        Camera::Bottom => "vision_bottom",
+    };
+    let size = vector![width, height];
+    json!({
+        "camera_matrix_parameters": {
+            cycler_instance: {
+                "focal_lengths": calibration.focal_lengths.component_div(&size),
+                "cc_optical_center": calibration.optical_center.coords.component_div(&size),
+                "radial_distortion": calibration.distortion.radial,
+                "tangential_distortion": calibration.distortion.tangential,
+            }
+        }
+    })
+}
@@ -1,6 +1,6 @@
 use communication::{
     client::{Communication, CyclerOutput, SubscriberMessage},
-    messages::Format,
+    messages::{Format, SubscriptionOptions},
 };
 use log::error;
 use serde_json::Value;
@@ -131,7 +131,7 @@ impl ChangeBuffer {
         let (command_sender, command_receiver) = mpsc::channel(10);
         spawn(async move {
             let (uuid, receiver) = communication
-                .subscribe_output(output, Format::Textual)
+                .subscribe_output(output, Format::Textual, SubscriptionOptions::default())
                 .await;
 
             change_buffer(receiver, command_receiver).await;
@@ -53,6 +53,7 @@ mod players_value_buffer;
 mod repository_parameters;
 mod selectable_panel_macro;
 mod twix_painter;
+mod update_rate;
 mod value_buffer;
 mod visuals;
 
@@ -61,6 +62,10 @@ struct Arguments {
     /// Nao address to connect to (overrides the address saved in the configuration file)
     pub nao_address: Option<NaoAddress>,
 
+    /// Token to authenticate with if the robot requires communication authentication
+    #[arg(long)]
+    pub token: Option<String>,
+
     /// Delete the current panel setup
     #[arg(long)]
     pub clear: bool,
@@ -189,7 +194,11 @@ impl TwixApp {
             })
             .unwrap_or(false);
 
-        let nao = Arc::new(Nao::new(ip_address.clone(), connection_intent));
+        let nao = Arc::new(Nao::new(
+            ip_address.clone(),
+            connection_intent,
+            arguments.token,
+        ));
 
         let dock_state: Option<DockState<Value>> = if arguments.clear {
             None
@@ -2,7 +2,7 @@ use std::{collections::BTreeSet, sync::Mutex};
 
 use communication::{
     client::{Communication, ConnectionStatus, CyclerOutput},
-    messages::{Fields, Path},
+    messages::{Fields, Path, SubscriptionOptions},
 };
 
 use serde_json::Value;
@@ -18,17 +18,18 @@ pub struct Nao {
     communication: Communication,
     runtime: Runtime,
     address: Mutex<Option<String>>,
+    token: Option<String>,
     connection_status_receiver: watch::Receiver<ConnectionStatus>,
 }
 
 impl Nao {
-    pub fn new(address: Option<String>, connect: bool) -> Self {
+    pub fn new(address: Option<String>, connect: bool, token: Option<String>) -> Self {
         let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
         let _guard = runtime.enter();
         let communication = Communication::new(
             address
                 .as_ref()
-                .map(|ip_address| ip_address_to_communication_url(ip_address)),
+                .map(|ip_address| ip_address_to_communication_url(ip_address, token.as_deref())),
             connect,
         );
         let connection_status_receiver = communication.subscribe_connection_status_updates();
@@ -37,6 +38,7 @@ impl Nao {
             communication,
             runtime,
             address: Mutex::new(address),
+            token,
             connection_status_receiver,
         }
     }
@@ -53,18 +55,33 @@ impl Nao {
         }
         self.runtime.block_on(
             self.communication
-                .set_address(ip_address_to_communication_url(address)),
+                .set_address(ip_address_to_communication_url(
+                    address,
+                    self.token.as_deref(),
+                )),
         );
     }
 
     pub fn subscribe_output(&self, output: CyclerOutput) -> ValueBuffer {
+        self.subscribe_output_with_options(output, SubscriptionOptions::default())
+    }
+
+    pub fn subscribe_output_with_options(
+        &self,
+        output: CyclerOutput,
+        options: SubscriptionOptions,
+    ) -> ValueBuffer {
         let _guard = self.runtime.enter();
-        ValueBuffer::output(self.communication.clone(), output)
+        ValueBuffer::output(self.communication.clone(), output, options)
     }
 
-    pub fn subscribe_image(&self, output: CyclerOutput) -> ImageBuffer {
+    pub fn subscribe_image(
+        &self,
+        output: CyclerOutput,
+        options: SubscriptionOptions,
+    ) -> ImageBuffer {
         let _guard = self.runtime.enter();
-        ImageBuffer::new(self.communication.clone(), output)
+        ImageBuffer::new(self.communication.clone(), output, options)
     }
 
     pub fn subscribe_parameter(&self, path: &str) -> ValueBuffer {
@@ -96,6 +113,16 @@ impl Nao {
             .block_on(self.communication.update_parameter_value(path, value));
     }
 
+    pub fn set_output_injection(&self, output: CyclerOutput, value: Value) {
+        self.runtime
+            .block_on(self.communication.set_output_injection(output, value));
+    }
+
+    pub fn unset_output_injection(&self, output: CyclerOutput) {
+        self.runtime
+            .block_on(self.communication.unset_output_injection(output));
+    }
+
     pub fn connection_status(&self) -> ConnectionStatus {
         self.connection_status_receiver.borrow().clone()
     }
@@ -116,6 +143,9 @@ impl Nao {
     }
 }
 
-fn ip_address_to_communication_url(ip_address: &str) -> String {
-    format!("ws://{ip_address}:1337")
+fn ip_address_to_communication_url(ip_address: &str, token: Option<&str>) -> String {
+    match token {
+        Some(token) => format!("ws://{ip_address}:1337/?token={token}"),
+        None => format!("ws://{ip_address}:1337"),
+    }
 }
@@ -1,4 +1,4 @@
-use std::{str::FromStr, sync::Arc};
+use std::{num::NonZeroU32, str::FromStr, sync::Arc};
 
 use color_eyre::{eyre::eyre, Result};
 use eframe::{
@@ -10,7 +10,10 @@ use nalgebra::Similarity2;
 use serde::{Deserialize, Serialize};
 use serde_json::{from_value, json, Value};
 
-use communication::client::{Cycler, CyclerOutput, Output};
+use communication::{
+    client::{Cycler, CyclerOutput, Output},
+    messages::{JpegPreview, SubscriptionOptions},
+};
 use linear_algebra::vector;
 
 use crate::{
@@ -18,6 +21,7 @@ use crate::{
     nao::Nao,
     panel::Panel,
     twix_painter::{CoordinateSystem, TwixPainter},
+    update_rate::UpdateRate,
 };
 
 use self::{cycler_selector::VisionCyclerSelector, overlay::Overlays};
@@ -29,6 +33,7 @@ mod overlays;
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
 enum ImageKind {
     YCbCr422,
+    YCbCr422Preview,
     Luminance,
 }
 
@@ -38,11 +43,28 @@ impl ImageKind {
             ImageKind::YCbCr422 => Output::Main {
                 path: "image.jpeg".to_string(),
             },
+            ImageKind::YCbCr422Preview => Output::Main {
+                path: "image".to_string(),
+            },
             ImageKind::Luminance => Output::Additional {
                 path: "robot_detection.luminance_image.jpeg".to_string(),
             },
         }
     }
+
+    fn subscription_options(&self, update_rate: UpdateRate) -> SubscriptionOptions {
+        let options = update_rate.subscription_options();
+        match self {
+            ImageKind::YCbCr422Preview => SubscriptionOptions {
+                jpeg_preview: Some(JpegPreview {
+                    scale: NonZeroU32::new(2).unwrap(),
+                    quality: 20,
+                }),
+                ..options
+            },
+            ImageKind::YCbCr422 | ImageKind::Luminance => options,
+        }
+    }
 }
 
 pub struct ImagePanel {
@@ -51,6 +73,7 @@ pub struct ImagePanel {
     cycler_selector: VisionCyclerSelector,
     overlays: Overlays,
     image_kind: ImageKind,
+    update_rate: UpdateRate,
 }
 
 impl Panel for ImagePanel {
@@ -77,11 +100,16 @@ impl Panel for ImagePanel {
             .and_then(|value| value.get("image_kind"))
             .and_then(|value| from_value(value.clone()).ok())
             .unwrap_or(ImageKind::YCbCr422);
+        let update_rate: UpdateRate = value
+            .and_then(|value| value.get("update_rate"))
+            .and_then(|value| from_value(value.clone()).ok())
+            .unwrap_or_default();
         let output = CyclerOutput {
             cycler,
             output: image_kind.as_output(),
         };
-        let image_buffer = nao.subscribe_image(output);
+        let image_buffer =
+            nao.subscribe_image(output, image_kind.subscription_options(update_rate));
         let cycler_selector = VisionCyclerSelector::new(cycler);
         let overlays = Overlays::new(
             nao.clone(),
@@ -94,6 +122,7 @@ impl Panel for ImagePanel {
             cycler_selector,
             overlays,
             image_kind,
+            update_rate,
         }
     }
 
@@ -106,6 +135,7 @@ impl Panel for ImagePanel {
             "cycler": cycler.to_string(),
             "overlays": overlays,
             "image_kind": image_kind,
+            "update_rate": self.update_rate,
         })
     }
 }
@@ -118,7 +148,10 @@ impl Widget for &mut ImagePanel {
                     cycler: self.cycler_selector.selected_cycler(),
                     output: self.image_kind.as_output(),
                 };
-                self.image_buffer = self.nao.subscribe_image(output);
+                self.image_buffer = self.nao.subscribe_image(
+                    output,
+                    self.image_kind.subscription_options(self.update_rate),
+                );
                 self.overlays
                     .update_cycler(self.cycler_selector.selected_cycler());
             }
@@ -132,6 +165,16 @@ impl Widget for &mut ImagePanel {
                     {
                         image_selection_changed = true;
                     };
+                    if ui
+                        .selectable_value(
+                            &mut self.image_kind,
+                            ImageKind::YCbCr422Preview,
+                            "YCbCr422Preview",
+                        )
+                        .changed()
+                    {
+                        image_selection_changed = true;
+                    };
                     if ui
                         .selectable_value(&mut self.image_kind, ImageKind::Luminance, "Luminance")
                         .changed()
@@ -139,12 +182,18 @@ impl Widget for &mut ImagePanel {
                         image_selection_changed = true;
                     }
                 });
+            if ui.add(&mut self.update_rate).changed() {
+                image_selection_changed = true;
+            }
             if image_selection_changed {
                 let output = CyclerOutput {
                     cycler: self.cycler_selector.selected_cycler(),
                     output: self.image_kind.as_output(),
                 };
-                self.image_buffer = self.nao.subscribe_image(output);
+                self.image_buffer = self.nao.subscribe_image(
+                    output,
+                    self.image_kind.subscription_options(self.update_rate),
+                );
                 self.overlays
                     .update_cycler(self.cycler_selector.selected_cycler());
             }
@@ -2,10 +2,11 @@ use std::{str::FromStr, sync::Arc};
 
 use color_eyre::Result;
 use eframe::epaint::{Color32, Stroke};
+use serde::Deserialize;
 
 use communication::client::CyclerOutput;
 use coordinate_systems::{Field, Ground};
-use linear_algebra::{IntoFramed, Isometry2, Point2};
+use linear_algebra::{IntoFramed, Isometry2, Point2, Pose2};
 use types::{field_dimensions::FieldDimensions, motion_command::MotionCommand};
 
 use crate::{
@@ -15,12 +16,20 @@ use crate::{
 
 const TRANSPARENT_BLUE: Color32 = Color32::from_rgba_premultiplied(0, 0, 202, 150);
 const TRANSPARENT_LIGHT_BLUE: Color32 = Color32::from_rgba_premultiplied(136, 170, 182, 150);
+const TRANSPARENT_RED: Color32 = Color32::from_rgba_premultiplied(202, 0, 0, 150);
+
+#[derive(Deserialize)]
+struct Opponent {
+    position: Point2<Field>,
+    orientation: f32,
+}
 
 pub struct BehaviorSimulator {
     ground_to_field: PlayersValueBuffer,
     motion_command: PlayersValueBuffer,
     head_yaw: PlayersValueBuffer,
     ball: ValueBuffer,
+    opponents: ValueBuffer,
 }
 
 impl Layer<Field> for BehaviorSimulator {
@@ -48,11 +57,15 @@ impl Layer<Field> for BehaviorSimulator {
         let ball = nao.subscribe_output(
             CyclerOutput::from_str("BehaviorSimulator.main_outputs.ball.position").unwrap(),
         );
+        let opponents = nao.subscribe_output(
+            CyclerOutput::from_str("BehaviorSimulator.main_outputs.opponents").unwrap(),
+        );
         Self {
             ground_to_field,
             motion_command,
             head_yaw: sensor_data,
             ball,
+            opponents,
         }
     }
 
@@ -112,6 +125,21 @@ impl Layer<Field> for BehaviorSimulator {
             );
         }
 
+        if let Ok(opponents) = self.opponents.parse_latest::<Vec<Opponent>>() {
+            for opponent in opponents {
+                painter.pose(
+                    Pose2::new(opponent.position.coords(), opponent.orientation),
+                    0.15,
+                    0.25,
+                    TRANSPARENT_RED,
+                    Stroke {
+                        width: 0.02,
+                        color: Color32::BLACK,
+                    },
+                );
+            }
+        }
+
         if let Ok(ball_position) = self.ball.parse_latest::<Point2<Field>>() {
             painter.ball(ball_position, 0.05);
         }
@@ -1,16 +1,22 @@
 use std::{str::FromStr, sync::Arc};
 
-use communication::client::CyclerOutput;
-use eframe::egui::{Label, ScrollArea, Sense, Widget};
+use communication::client::{CyclerOutput, Output};
+use eframe::egui::{Label, ScrollArea, Sense, TextEdit, Widget};
 use log::error;
-use serde_json::{json, Value};
+use serde_json::{from_value, json, Value};
 
-use crate::{completion_edit::CompletionEdit, nao::Nao, panel::Panel, value_buffer::ValueBuffer};
+use crate::{
+    completion_edit::CompletionEdit, nao::Nao, panel::Panel, update_rate::UpdateRate,
+    value_buffer::ValueBuffer,
+};
 
 pub struct TextPanel {
     nao: Arc<Nao>,
     output: String,
+    update_rate: UpdateRate,
     values: Option<ValueBuffer>,
+    injection_value: String,
+    injected_output: Option<CyclerOutput>,
 }
 
 impl Panel for TextPanel {
@@ -21,10 +27,16 @@ impl Panel for TextPanel {
             Some(Value::String(string)) => string.to_string(),
             _ => String::new(),
         };
+        let update_rate: UpdateRate = value
+            .and_then(|value| value.get("update_rate"))
+            .and_then(|value| from_value(value.clone()).ok())
+            .unwrap_or_default();
         let values = if !output.is_empty() {
             let output = CyclerOutput::from_str(&output);
             match output {
-                Ok(output) => Some(nao.subscribe_output(output)),
+                Ok(output) => Some(
+                    nao.subscribe_output_with_options(output, update_rate.subscription_options()),
+                ),
                 Err(error) => {
                     error!("Failed to subscribe: {error:?}");
                     None
@@ -36,13 +48,17 @@ impl Panel for TextPanel {
         Self {
             nao,
             output,
+            update_rate,
             values,
+            injection_value: String::new(),
+            injected_output: None,
         }
     }
 
     fn save(&self) -> Value {
         json!({
-            "subscribe_key": self.output.clone()
+            "subscribe_key": self.output.clone(),
+            "update_rate": self.update_rate,
         })
     }
 }
@@ -50,16 +66,52 @@ impl Panel for TextPanel {
 impl Widget for &mut TextPanel {
     fn ui(self, ui: &mut eframe::egui::Ui) -> eframe::egui::Response {
         let edit_response = ui.add(CompletionEdit::outputs(&mut self.output, self.nao.as_ref()));
+        let rate_response = ui.add(&mut self.update_rate);
         if edit_response.changed() {
+            self.unset_injection();
+        }
+        if edit_response.changed() || rate_response.changed() {
             match CyclerOutput::from_str(&self.output) {
                 Ok(output) => {
-                    self.values = Some(self.nao.subscribe_output(output));
+                    self.values = Some(self.nao.subscribe_output_with_options(
+                        output,
+                        self.update_rate.subscription_options(),
+                    ));
                 }
                 Err(error) => {
                     error!("Failed to subscribe: {error:#?}");
                 }
             }
         }
+        ui.horizontal(|ui| {
+            let output = CyclerOutput::from_str(&self.output)
+                .ok()
+                .filter(|output| matches!(output.output, Output::Main { .. }));
+            ui.add(
+                TextEdit::singleline(&mut self.injection_value)
+                    .code_editor()
+                    .hint_text("value to inject"),
+            );
+            let injectable = output.is_some() && !self.injection_value.is_empty();
+            ui.add_enabled_ui(injectable, |ui| {
+                if ui.button("Inject").clicked() {
+                    match serde_json::from_str(&self.injection_value) {
+                        Ok(value) => {
+                            if let Some(output) = output {
+                                self.nao.set_output_injection(output.clone(), value);
+                                self.injected_output = Some(output);
+                            }
+                        }
+                        Err(error) => error!("Failed to deserialize injection value: {error:#?}"),
+                    }
+                }
+            });
+            ui.add_enabled_ui(self.injected_output.is_some(), |ui| {
+                if ui.button("Unset").clicked() {
+                    self.unset_injection();
+                }
+            });
+        });
         let scroll_area = ScrollArea::vertical()
             .auto_shrink([false, false])
             .show(ui, |ui| {
@@ -89,3 +141,17 @@ impl Widget for &mut TextPanel {
         }
     }
 }
+
+impl TextPanel {
+    fn unset_injection(&mut self) {
+        if let Some(output) = self.injected_output.take() {
+            self.nao.unset_output_injection(output);
+        }
+    }
+}
+
+impl Drop for TextPanel {
+    fn drop(&mut self) {
+        self.unset_injection();
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use communication::messages::SubscriptionOptions;
+use eframe::egui::{ComboBox, Response, Ui, Widget};
+use serde::{Deserialize, Serialize};
+
+/// How often a panel wants to receive updates of its subscribed outputs
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum UpdateRate {
+    #[default]
+    EveryCycle,
+    OnChange,
+    Hertz30,
+    Hertz10,
+    Hertz1,
+}
+
+impl UpdateRate {
+    const ALL: [UpdateRate; 5] = [
+        UpdateRate::EveryCycle,
+        UpdateRate::OnChange,
+        UpdateRate::Hertz30,
+        UpdateRate::Hertz10,
+        UpdateRate::Hertz1,
+    ];
+
+    pub fn subscription_options(self) -> SubscriptionOptions {
+        let minimum_interval = |hertz: f32| SubscriptionOptions {
+            minimum_interval: Some(Duration::from_secs_f32(1.0 / hertz)),
+            ..Default::default()
+        };
+        match self {
+            UpdateRate::EveryCycle => SubscriptionOptions::default(),
+            UpdateRate::OnChange => SubscriptionOptions {
+                send_on_change: true,
+                ..Default::default()
+            },
+            UpdateRate::Hertz30 => minimum_interval(30.0),
+            UpdateRate::Hertz10 => minimum_interval(10.0),
+            UpdateRate::Hertz1 => minimum_interval(1.0),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            UpdateRate::EveryCycle => "Every cycle",
+            UpdateRate::OnChange => "On change",
+            UpdateRate::Hertz30 => "30 Hz",
+            UpdateRate::Hertz10 => "10 Hz",
+            UpdateRate::Hertz1 => "1 Hz",
+        }
+    }
+}
+
+impl Widget for &mut UpdateRate {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let mut rate_selection_changed = false;
+        let mut combo_box = ComboBox::from_label("Rate")
+            .selected_text(self.label())
+            .show_ui(ui, |ui| {
+                for update_rate in UpdateRate::ALL {
+                    if ui
+                        .selectable_value(self, update_rate, update_rate.label())
+                        .clicked()
+                    {
+                        rate_selection_changed = true;
+                    }
+                }
+            });
+        if rate_selection_changed {
+            combo_box.response.mark_changed()
+        }
+        combo_box.response
+    }
+}